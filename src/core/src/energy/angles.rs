@@ -0,0 +1,297 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Bonded potentials using the full geometry of the three particles in an
+//! angle, like Urey-Bradley or class-II cross terms.
+use types::{Matrix3, Vector3D, Zero};
+
+/// Potential for interactions depending on the full geometry of the three
+/// particles `i-j-k` in a molecular angle.
+///
+/// Contrary to [`AnglePotential`](trait.AnglePotential.html), which only gets
+/// the value of the angle, these potentials get the two vectors `r1 = r_i -
+/// r_j` and `r2 = r_k - r_j` going from the central particle to the outer
+/// particles. They can be used to describe 1-3 distance terms or couplings
+/// between the bonds lengths and the angle value.
+///
+/// The two outer particles are not equivalent: when this potential is added
+/// to the angle `A-B-C`, `r1` always points to the `A` particle, and `r2` to
+/// the `C` particle.
+///
+/// # Example
+///
+/// ```
+/// use lumol::types::{Vector3D, Zero};
+/// use lumol::energy::AngleGeometryPotential;
+///
+/// // A potential depending on the distance between the outer particles
+/// #[derive(Clone)]
+/// struct Distance13;
+///
+/// impl AngleGeometryPotential for Distance13 {
+///     fn energy(&self, r1: &Vector3D, r2: &Vector3D) -> f64 {
+///         (r2 - r1).norm()
+///     }
+///
+///     fn forces(&self, r1: &Vector3D, r2: &Vector3D) -> (Vector3D, Vector3D, Vector3D) {
+///         let d = (r2 - r1).normalized();
+///         (d, Vector3D::zero(), -d)
+///     }
+/// }
+///
+/// let potential = Distance13;
+/// let r1 = Vector3D::new(1.0, 0.0, 0.0);
+/// let r2 = Vector3D::new(0.0, 1.0, 0.0);
+/// assert_eq!(potential.energy(&r1, &r2), f64::sqrt(2.0));
+/// ```
+pub trait AngleGeometryPotential: Sync + Send + BoxCloneAngleGeometry {
+    /// Get the energy of the angle with vectors `r1 = r_i - r_j` and `r2 =
+    /// r_k - r_j`.
+    fn energy(&self, r1: &Vector3D, r2: &Vector3D) -> f64;
+
+    /// Get the forces acting on the particles `i`, `j` and `k` of the angle
+    /// with vectors `r1 = r_i - r_j` and `r2 = r_k - r_j`.
+    fn forces(&self, r1: &Vector3D, r2: &Vector3D) -> (Vector3D, Vector3D, Vector3D);
+
+    /// Get the virial contribution of the angle with vectors `r1 = r_i - r_j`
+    /// and `r2 = r_k - r_j`. The default implementation uses the forces, and
+    /// assume that their sum is zero.
+    fn virial(&self, r1: &Vector3D, r2: &Vector3D) -> Matrix3 {
+        let (force_i, _, force_k) = self.forces(r1, r2);
+        force_i.tensorial(r1) + force_k.tensorial(r2)
+    }
+}
+impl_box_clone!(AngleGeometryPotential, BoxCloneAngleGeometry, box_clone_angle_geometry);
+
+/// Get the angle between the vectors `r1` and `r2`, and the derivatives of
+/// this angle with respect to `r1` and `r2`.
+fn angle_and_derivatives(r1: &Vector3D, r2: &Vector3D) -> (f64, Vector3D, Vector3D) {
+    let r1_norm = r1.norm();
+    let r2_norm = r2.norm();
+    let r1n = r1 / r1_norm;
+    let r2n = r2 / r2_norm;
+
+    let cos = r1n * r2n;
+    let sin_inv = 1.0 / f64::sqrt(1.0 - cos * cos);
+
+    let d1 = sin_inv * (cos * r1n - r2n) / r1_norm;
+    let d2 = sin_inv * (cos * r2n - r1n) / r2_norm;
+    return (f64::acos(cos), d1, d2);
+}
+
+/// Urey-Bradley potential.
+///
+/// This is an harmonic potential acting on the distance between the two outer
+/// particles of an angle, used in CHARMM force fields. The following potential
+/// expression is used: `V(r13) = 1/2 * k * (r13 - r0)^2` where `r13` is the
+/// distance between the particles `i` and `k` in the angle `i-j-k`.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::types::Vector3D;
+/// use lumol::energy::{AngleGeometryPotential, UreyBradley};
+///
+/// let potential = UreyBradley{k: 100.0, r0: 2.0};
+/// let r1 = Vector3D::new(1.0, 0.0, 0.0);
+/// let r2 = Vector3D::new(-2.0, 0.0, 0.0);
+/// assert_eq!(potential.energy(&r1, &r2), 50.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct UreyBradley {
+    /// Spring constant
+    pub k: f64,
+    /// Equilibrium distance between the outer particles
+    pub r0: f64,
+}
+
+impl AngleGeometryPotential for UreyBradley {
+    fn energy(&self, r1: &Vector3D, r2: &Vector3D) -> f64 {
+        let dr = (r2 - r1).norm() - self.r0;
+        0.5 * self.k * dr * dr
+    }
+
+    fn forces(&self, r1: &Vector3D, r2: &Vector3D) -> (Vector3D, Vector3D, Vector3D) {
+        let r13 = r2 - r1;
+        let r = r13.norm();
+        let force_k = self.k * (self.r0 - r) / r * r13;
+        (-force_k, Vector3D::zero(), force_k)
+    }
+}
+
+/// Bond-bond cross term potential.
+///
+/// This potential couples the lengths of the two bonds in an angle, and is
+/// used in class-II force fields. The following potential expression is used:
+/// `V(r1, r2) = k * (r1 - r10) * (r2 - r20)` where `r1` is the `i-j` distance,
+/// and `r2` the `k-j` distance in the angle `i-j-k`.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::types::Vector3D;
+/// use lumol::energy::{AngleGeometryPotential, BondBond};
+///
+/// let potential = BondBond{k: 100.0, r1: 1.0, r2: 1.5};
+/// let r1 = Vector3D::new(2.0, 0.0, 0.0);
+/// let r2 = Vector3D::new(0.0, 2.0, 0.0);
+/// assert_eq!(potential.energy(&r1, &r2), 50.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct BondBond {
+    /// Force constant
+    pub k: f64,
+    /// Equilibrium length of the `i-j` bond
+    pub r1: f64,
+    /// Equilibrium length of the `k-j` bond
+    pub r2: f64,
+}
+
+impl AngleGeometryPotential for BondBond {
+    fn energy(&self, r1: &Vector3D, r2: &Vector3D) -> f64 {
+        self.k * (r1.norm() - self.r1) * (r2.norm() - self.r2)
+    }
+
+    fn forces(&self, r1: &Vector3D, r2: &Vector3D) -> (Vector3D, Vector3D, Vector3D) {
+        let dr1 = r1.norm() - self.r1;
+        let dr2 = r2.norm() - self.r2;
+        let force_i = - self.k * dr2 * r1.normalized();
+        let force_k = - self.k * dr1 * r2.normalized();
+        (force_i, - (force_i + force_k), force_k)
+    }
+}
+
+/// Bond-angle cross term potential.
+///
+/// This potential couples the lengths of the two bonds in an angle with the
+/// angle value, and is used in class-II force fields. The following potential
+/// expression is used: `V(r1, r2, theta) = (theta - theta0) * (k1 * (r1 - r10)
+/// + k2 * (r2 - r20))` where `r1` is the `i-j` distance, `r2` the `k-j`
+/// distance, and `theta` the value of the angle `i-j-k`.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::types::Vector3D;
+/// use lumol::energy::{AngleGeometryPotential, BondAngle};
+/// use std::f64::consts::PI;
+///
+/// let potential = BondAngle{k1: 10.0, k2: 20.0, r1: 1.0, r2: 1.0, theta0: PI / 4.0};
+/// let r1 = Vector3D::new(2.0, 0.0, 0.0);
+/// let r2 = Vector3D::new(0.0, 1.0, 0.0);
+/// assert_eq!(potential.energy(&r1, &r2), 10.0 * PI / 4.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct BondAngle {
+    /// Force constant for the `i-j` bond
+    pub k1: f64,
+    /// Force constant for the `k-j` bond
+    pub k2: f64,
+    /// Equilibrium length of the `i-j` bond
+    pub r1: f64,
+    /// Equilibrium length of the `k-j` bond
+    pub r2: f64,
+    /// Equilibrium value of the angle
+    pub theta0: f64,
+}
+
+impl AngleGeometryPotential for BondAngle {
+    fn energy(&self, r1: &Vector3D, r2: &Vector3D) -> f64 {
+        let r1n = r1.normalized();
+        let r2n = r2.normalized();
+        let theta = f64::acos(r1n * r2n);
+        let bonds = self.k1 * (r1.norm() - self.r1) + self.k2 * (r2.norm() - self.r2);
+        (theta - self.theta0) * bonds
+    }
+
+    fn forces(&self, r1: &Vector3D, r2: &Vector3D) -> (Vector3D, Vector3D, Vector3D) {
+        let (theta, d1, d2) = angle_and_derivatives(r1, r2);
+        let dtheta = theta - self.theta0;
+        let bonds = self.k1 * (r1.norm() - self.r1) + self.k2 * (r2.norm() - self.r2);
+
+        let force_i = - (dtheta * self.k1 * r1.normalized() + bonds * d1);
+        let force_k = - (dtheta * self.k2 * r2.normalized() + bonds * d2);
+        (force_i, - (force_i + force_k), force_k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Matrix3, Vector3D, Zero};
+
+    const EPS: f64 = 1e-7;
+
+    /// Check the forces of `potential` against finite differences of the
+    /// energy
+    fn check_forces(potential: &AngleGeometryPotential, r1: Vector3D, r2: Vector3D) {
+        let (force_i, force_j, force_k) = potential.forces(&r1, &r2);
+        assert_relative_eq!((force_i + force_j + force_k).norm(), 0.0, epsilon = 1e-12);
+
+        let energy = potential.energy(&r1, &r2);
+        for axis in 0..3 {
+            let mut delta = Vector3D::zero();
+            delta[axis] = EPS;
+
+            // Moving the particle i only changes r1
+            let fd = - (potential.energy(&(r1 + delta), &r2) - energy) / EPS;
+            assert_relative_eq!(force_i[axis], fd, epsilon = 1e-5);
+
+            // Moving the particle k only changes r2
+            let fd = - (potential.energy(&r1, &(r2 + delta)) - energy) / EPS;
+            assert_relative_eq!(force_k[axis], fd, epsilon = 1e-5);
+        }
+    }
+
+    /// Check that the trace of the virial is the derivative of the energy
+    /// with respect to an isotropic scaling of the positions
+    fn check_virial(potential: &AngleGeometryPotential, r1: Vector3D, r2: Vector3D) {
+        let virial: Matrix3 = potential.virial(&r1, &r2);
+        let energy = potential.energy(&r1, &r2);
+        let scaled = potential.energy(&((1.0 + EPS) * r1), &((1.0 + EPS) * r2));
+        assert_relative_eq!(virial.trace(), - (scaled - energy) / EPS, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn urey_bradley() {
+        let potential = UreyBradley{k: 50.0, r0: 2.5};
+        let r1 = Vector3D::new(1.0, 0.0, 0.0);
+        let r2 = Vector3D::new(-0.5, 1.2, 0.3);
+
+        let r13 = (r2 - r1).norm();
+        assert_relative_eq!(potential.energy(&r1, &r2), 25.0 * (r13 - 2.5) * (r13 - 2.5));
+
+        let (_, force_j, _) = potential.forces(&r1, &r2);
+        assert_eq!(force_j, Vector3D::zero());
+
+        check_forces(&potential, r1, r2);
+        check_virial(&potential, r1, r2);
+    }
+
+    #[test]
+    fn bond_bond() {
+        let potential = BondBond{k: 30.0, r1: 1.1, r2: 0.9};
+        let r1 = Vector3D::new(1.0, 0.2, 0.0);
+        let r2 = Vector3D::new(-0.5, 1.2, 0.3);
+
+        let energy = 30.0 * (r1.norm() - 1.1) * (r2.norm() - 0.9);
+        assert_relative_eq!(potential.energy(&r1, &r2), energy);
+
+        check_forces(&potential, r1, r2);
+        check_virial(&potential, r1, r2);
+    }
+
+    #[test]
+    fn bond_angle() {
+        let potential = BondAngle{k1: 30.0, k2: 12.0, r1: 1.1, r2: 0.9, theta0: 1.9};
+        let r1 = Vector3D::new(1.0, 0.2, 0.0);
+        let r2 = Vector3D::new(-0.5, 1.2, 0.3);
+
+        let theta = f64::acos(r1.normalized() * r2.normalized());
+        let energy = (theta - 1.9) * (30.0 * (r1.norm() - 1.1) + 12.0 * (r2.norm() - 0.9));
+        assert_relative_eq!(potential.energy(&r1, &r2), energy);
+
+        check_forces(&potential, r1, r2);
+        check_virial(&potential, r1, r2);
+    }
+}
@@ -0,0 +1,275 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! External fields acting on the particles: uniform electric field and
+//! constant body forces.
+//!
+//! With periodic boundary conditions, the potential energy associated with a
+//! uniform field is not defined, as it depends on the arbitrary choice of the
+//! periodic image used for each particle. These potentials then only add
+//! forces to the system, and their energy is always zero. In an infinite
+//! cell, the energy is `V = - sum_i F_i * r_i` with `F_i` the force acting on
+//! the particle `i`.
+//!
+//! The virial contribution of external fields is always zero, as they do not
+//! depend on the volume of the system.
+use sys::Configuration;
+use types::{Matrix3, Vector3D, Zero};
+
+use energy::{GlobalPotential, GlobalCache};
+
+/// Compute the energy of an external field producing the force `force(i)` on
+/// the particle `i`, using `position(i)` as the position of this particle.
+fn field_energy<F>(configuration: &Configuration, particles: &[usize], position: &Fn(usize) -> Vector3D, force: F) -> f64
+    where F: Fn(usize) -> Vector3D {
+    if !configuration.cell.is_infinite() {
+        return 0.0;
+    }
+    let mut energy = 0.0;
+    for &i in particles {
+        energy -= force(i) * position(i);
+    }
+    return energy;
+}
+
+/// Uniform external electric field, acting on the charges of the particles.
+///
+/// The force acting on a particle is `F = q E`, where `q` is the charge of the
+/// particle and `E` the electric field.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, ElectricField};
+/// use lumol::sys::{System, Particle, UnitCell};
+/// use lumol::types::{Vector3D, Zero};
+///
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// let mut particle = Particle::new("Na");
+/// particle.charge = 1.0;
+/// system.add_particle(particle);
+///
+/// let field = ElectricField{field: Vector3D::new(0.0, 0.0, 0.5)};
+///
+/// let mut forces = vec![Vector3D::zero()];
+/// field.forces(&system, &mut forces);
+/// assert_eq!(forces[0], Vector3D::new(0.0, 0.0, 0.5));
+///
+/// // No energy with periodic boundary conditions
+/// assert_eq!(field.energy(&system), 0.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ElectricField {
+    /// The electric field vector
+    pub field: Vector3D,
+}
+
+impl ElectricField {
+    fn energy_with(&self, configuration: &Configuration, particles: &[usize], position: &Fn(usize) -> Vector3D) -> f64 {
+        field_energy(configuration, particles, position, |i| {
+            configuration.particle(i).charge * self.field
+        })
+    }
+}
+
+impl GlobalPotential for ElectricField {
+    fn name(&self) -> &str {
+        "ElectricField"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let particles = (0..configuration.size()).collect::<Vec<_>>();
+        self.energy_with(configuration, &particles, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        for (i, particle) in configuration.particles().enumerate() {
+            forces[i] += particle.charge * self.field;
+        }
+    }
+
+    fn virial(&self, _: &Configuration) -> Matrix3 {
+        Matrix3::zero()
+    }
+}
+
+impl GlobalCache for ElectricField {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let old = self.energy_with(configuration, idxes, &|i| configuration.particle(i).position);
+        let new = self.energy_with(configuration, idxes, &|i| {
+            let idx = idxes.iter().position(|&moved| moved == i).expect("missing moved particle");
+            newpos[idx]
+        });
+        return new - old;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+/// Constant force acting on all the particles with a given name.
+///
+/// This can be used to create body forces, such as gravity or pressure
+/// gradients in flow simulations.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, ConstantForce};
+/// use lumol::sys::{System, Particle};
+/// use lumol::types::{Vector3D, Zero};
+///
+/// let mut system = System::new();
+/// system.add_particle(Particle::new("Ar"));
+/// system.add_particle(Particle::new("He"));
+/// system.particle_mut(0).position = Vector3D::new(0.0, 0.0, 2.0);
+///
+/// let force = ConstantForce{atoms: String::from("Ar"), force: Vector3D::new(0.0, 0.0, -0.1)};
+///
+/// let mut forces = vec![Vector3D::zero(); 2];
+/// force.forces(&system, &mut forces);
+/// assert_eq!(forces[0], Vector3D::new(0.0, 0.0, -0.1));
+/// assert_eq!(forces[1], Vector3D::zero());
+///
+/// // This system uses an infinite cell, so the energy is defined
+/// assert!((force.energy(&system) - 0.2).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConstantForce {
+    /// Name of the particles on which the force acts
+    pub atoms: String,
+    /// The force acting on each of these particles
+    pub force: Vector3D,
+}
+
+impl ConstantForce {
+    fn is_affected(&self, configuration: &Configuration, i: usize) -> bool {
+        configuration.particle(i).name() == self.atoms
+    }
+
+    fn energy_with(&self, configuration: &Configuration, particles: &[usize], position: &Fn(usize) -> Vector3D) -> f64 {
+        let particles = particles.iter()
+                                 .cloned()
+                                 .filter(|&i| self.is_affected(configuration, i))
+                                 .collect::<Vec<_>>();
+        field_energy(configuration, &particles, position, |_| self.force)
+    }
+}
+
+impl GlobalPotential for ConstantForce {
+    fn name(&self) -> &str {
+        "ConstantForce"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let particles = (0..configuration.size()).collect::<Vec<_>>();
+        self.energy_with(configuration, &particles, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        for i in 0..configuration.size() {
+            if self.is_affected(configuration, i) {
+                forces[i] += self.force;
+            }
+        }
+    }
+
+    fn virial(&self, _: &Configuration) -> Matrix3 {
+        Matrix3::zero()
+    }
+}
+
+impl GlobalCache for ConstantForce {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let old = self.energy_with(configuration, idxes, &|i| configuration.particle(i).position);
+        let new = self.energy_with(configuration, idxes, &|i| {
+            let idx = idxes.iter().position(|&moved| moved == i).expect("missing moved particle");
+            newpos[idx]
+        });
+        return new - old;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::{System, Particle, UnitCell};
+    use energy::GlobalPotential;
+
+    fn testing_system(cell: UnitCell) -> System {
+        let mut system = System::with_cell(cell);
+        let mut particle = Particle::new("Na");
+        particle.charge = 1.0;
+        particle.position = Vector3D::new(1.0, 2.0, 3.0);
+        system.add_particle(particle);
+
+        let mut particle = Particle::new("Cl");
+        particle.charge = -1.0;
+        particle.position = Vector3D::new(-1.0, 0.5, 0.0);
+        system.add_particle(particle);
+        return system;
+    }
+
+    #[test]
+    fn electric_field() {
+        let field = ElectricField{field: Vector3D::new(0.3, -0.2, 0.1)};
+
+        let system = testing_system(UnitCell::new());
+        // - sum_i q_i E * r_i
+        let expected = - (0.3 - 0.4 + 0.3) + (-0.3 - 0.1);
+        assert_ulps_eq!(field.energy(&system), expected);
+
+        let mut forces = vec![Vector3D::zero(); 2];
+        field.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::new(0.3, -0.2, 0.1));
+        assert_eq!(forces[1], Vector3D::new(-0.3, 0.2, -0.1));
+        assert_eq!(field.virial(&system), Matrix3::zero());
+
+        let newpos = [Vector3D::new(2.0, 2.0, 3.0)];
+        assert_ulps_eq!(field.move_particles_cost(&system, &[0], &newpos), -0.3);
+
+        let system = testing_system(UnitCell::cubic(10.0));
+        assert_eq!(field.energy(&system), 0.0);
+        assert_eq!(field.move_particles_cost(&system, &[0], &newpos), 0.0);
+
+        let mut forces = vec![Vector3D::zero(); 2];
+        field.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::new(0.3, -0.2, 0.1));
+        assert_eq!(forces[1], Vector3D::new(-0.3, 0.2, -0.1));
+    }
+
+    #[test]
+    fn constant_force() {
+        let force = ConstantForce{atoms: String::from("Cl"), force: Vector3D::new(0.0, 0.0, -0.5)};
+
+        let system = testing_system(UnitCell::new());
+        assert_eq!(force.energy(&system), 0.0);
+
+        let mut forces = vec![Vector3D::zero(); 2];
+        force.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::zero());
+        assert_eq!(forces[1], Vector3D::new(0.0, 0.0, -0.5));
+
+        let newpos = [Vector3D::new(2.0, 2.0, 2.0), Vector3D::new(0.0, 0.0, 2.0)];
+        assert_eq!(force.move_particles_cost(&system, &[0, 1], &newpos), 1.0);
+
+        let system = testing_system(UnitCell::cubic(10.0));
+        assert_eq!(force.energy(&system), 0.0);
+        assert_eq!(force.move_particles_cost(&system, &[0, 1], &newpos), 0.0);
+    }
+}
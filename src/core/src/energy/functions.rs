@@ -1,6 +1,8 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 
+use std::f64;
+
 use energy::Potential;
 use energy::{PairPotential, BondPotential, AnglePotential, DihedralPotential};
 
@@ -94,6 +96,183 @@ impl PairPotential for LennardJones {
     }
 }
 
+/// Mie potential.
+///
+/// The Mie potential is a generalization of the Lennard-Jones potential with
+/// arbitrary repulsive and attractive exponents. The following expression is
+/// used: `V(r) = C * epsilon * ((sigma/r)^n - (sigma/r)^m)` where `sigma` is
+/// the distance constant, `epsilon` the energetic constant, `n` and `m` the
+/// repulsive and attractive exponents, and `C = n / (n - m) * (n / m)^(m / (n
+/// - m))` a normalization constant such that the minimum of the potential is
+/// `-epsilon`.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::Mie;
+///
+/// let potential = Mie::new(/* sigma */ 2.0, /* epsilon */ 10.0, /* n */ 14.0, /* m */ 6.0);
+/// assert_eq!(potential.energy(2.0), 0.0);
+/// assert_eq!(potential.energy(3.0), -2.7873283784590632);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Mie {
+    /// Distance constant
+    sigma: f64,
+    /// Repulsive exponent
+    n: f64,
+    /// Attractive exponent
+    m: f64,
+    /// Energetic constant multiplied by the normalization constant
+    prefactor: f64,
+}
+
+impl Mie {
+    /// Create a new `Mie` potential with the given `sigma` and `epsilon`
+    /// constants, and the `n` and `m` exponents. The attractive exponent `m`
+    /// must be bigger than 3 for the tail corrections to be defined, and the
+    /// repulsive exponent `n` must be bigger than `m`.
+    pub fn new(sigma: f64, epsilon: f64, n: f64, m: f64) -> Mie {
+        assert!(m > 3.0, "the attractive exponent must be bigger than 3 in Mie potential");
+        assert!(n > m, "the repulsive exponent must be bigger than the attractive one in Mie potential");
+        let normalization = n / (n - m) * f64::powf(n / m, m / (n - m));
+        Mie {
+            sigma: sigma,
+            n: n,
+            m: m,
+            prefactor: normalization * epsilon,
+        }
+    }
+}
+
+impl Potential for Mie {
+    fn energy(&self, r: f64) -> f64 {
+        let sr = self.sigma / r;
+        self.prefactor * (f64::powf(sr, self.n) - f64::powf(sr, self.m))
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        let sr = self.sigma / r;
+        self.prefactor * (self.n * f64::powf(sr, self.n) - self.m * f64::powf(sr, self.m)) / r
+    }
+}
+
+impl PairPotential for Mie {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        let sr = self.sigma / cutoff;
+        let rc3 = cutoff * cutoff * cutoff;
+        let repulsive = f64::powf(sr, self.n) / (self.n - 3.0);
+        let attractive = f64::powf(sr, self.m) / (self.m - 3.0);
+        self.prefactor * rc3 * (repulsive - attractive)
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        let sr = self.sigma / cutoff;
+        let rc3 = cutoff * cutoff * cutoff;
+        let repulsive = self.n * f64::powf(sr, self.n) / (self.n - 3.0);
+        let attractive = self.m * f64::powf(sr, self.m) / (self.m - 3.0);
+        self.prefactor * rc3 * (repulsive - attractive)
+    }
+}
+
+/// Soft-core Lennard-Jones potential.
+///
+/// This potential removes the singularity of the Lennard-Jones potential at
+/// `r = 0` when the coupling parameter `lambda` is smaller than 1, and is used
+/// in alchemical free energy computations. The following expression, from
+/// Beutler et al. (Chem. Phys. Lett. 222, 1994) is used: `V(r) = 4 * lambda *
+/// epsilon * (1 / s^2 - 1 / s)` with `s = alpha * (1 - lambda)^2 + (r /
+/// sigma)^6`, where `sigma` and `epsilon` are the Lennard-Jones constants, and
+/// `alpha` the soft-core parameter. With `lambda = 1`, this is the usual
+/// Lennard-Jones potential, and with `lambda = 0` the interaction vanishes.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::SoftCoreLennardJones;
+///
+/// let potential = SoftCoreLennardJones{sigma: 2.0, epsilon: 10.0, lambda: 0.5, alpha: 0.5};
+/// assert_eq!(potential.energy(0.0), 1120.0);
+/// assert_eq!(potential.energy(3.0), -1.585952070166007);
+/// ```
+#[derive(Clone, Copy)]
+pub struct SoftCoreLennardJones {
+    /// Distance constant of the Lennard-Jones potential
+    pub sigma: f64,
+    /// Energy constant of the Lennard-Jones potential
+    pub epsilon: f64,
+    /// Coupling parameter, between 0 and 1
+    pub lambda: f64,
+    /// Soft-core parameter
+    pub alpha: f64,
+}
+
+impl SoftCoreLennardJones {
+    /// Get the soft-core shift `alpha * (1 - lambda)^2`
+    fn shift(&self) -> f64 {
+        self.alpha * (1.0 - self.lambda) * (1.0 - self.lambda)
+    }
+
+    /// Check if the tail corrections can be computed with the given
+    /// `cutoff`. The series used for these corrections only converges if
+    /// `alpha * (1 - lambda)^2 * (sigma / cutoff)^6 < 1`.
+    pub fn tail_converges(&self, cutoff: f64) -> bool {
+        self.shift() * f64::powi(self.sigma / cutoff, 6) < 1.0
+    }
+}
+
+impl Potential for SoftCoreLennardJones {
+    fn energy(&self, r: f64) -> f64 {
+        let s = self.shift() + f64::powi(r / self.sigma, 6);
+        4.0 * self.lambda * self.epsilon * (1.0 / (s * s) - 1.0 / s)
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        let s = self.shift() + f64::powi(r / self.sigma, 6);
+        let ds_dr = 6.0 * f64::powi(r, 5) / f64::powi(self.sigma, 6);
+        4.0 * self.lambda * self.epsilon * (2.0 / (s * s * s) - 1.0 / (s * s)) * ds_dr
+    }
+}
+
+impl PairPotential for SoftCoreLennardJones {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        // Using the series expansion of 1/s and 1/s^2 in powers of `u =
+        // (sigma / r)^6`, which converges for `shift * u < 1`:
+        //
+        //     1/s   = sum_k (-shift)^k u^(k + 1)
+        //     1/s^2 = sum_k (k + 1) (-shift)^k u^(k + 2)
+        //
+        // and integrating each term with the integral of r^2 u^p from
+        // `cutoff` to infinity being `cutoff^3 uc^p / (6p - 3)`.
+        let shift = self.shift();
+        let uc = f64::powi(self.sigma / cutoff, 6);
+        assert!(self.tail_converges(cutoff), "cutoff is too small for soft-core Lennard-Jones tail corrections");
+
+        let mut sum = 0.0;
+        let mut term = 1.0;
+        for k in 0..100 {
+            let p = k as f64 + 1.0;
+            let attractive = term * uc / (6.0 * p - 3.0);
+            let repulsive = (k as f64 + 1.0) * term * uc * uc / (6.0 * p + 3.0);
+            sum += repulsive - attractive;
+            if attractive.abs() < 1e-16 * sum.abs() {
+                break;
+            }
+            term *= -shift * uc;
+        }
+        let rc3 = cutoff * cutoff * cutoff;
+        4.0 * self.lambda * self.epsilon * rc3 * sum
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        // Integrating by parts the integral of r^3 f(r) = - r^3 dV/dr
+        let rc3 = cutoff * cutoff * cutoff;
+        rc3 * self.energy(cutoff) + 3.0 * self.tail_energy(cutoff)
+    }
+}
+
 /// Harmonic potential.
 ///
 /// The following energy expression is used: `V(x) = 1/2 * k * (x - x0)^2` where
@@ -239,6 +418,282 @@ impl Potential for Torsion {
 
 impl DihedralPotential for Torsion {}
 
+/// Ryckaert-Bellemans potential.
+///
+/// This potential is a polynomial in the cosine of the dihedral angle, used in
+/// OPLS or GROMOS force fields. The following potential expression is used:
+/// `V(x) = \sum_{n=0}^5 C_n * cos(x - pi)^n`. The `x - pi` shift follows the
+/// polymer convention, where the `trans` conformation corresponds to an angle
+/// of 0.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::RyckaertBellemans;
+/// use std::f64::consts::PI;
+///
+/// let potential = RyckaertBellemans{c: [9.28, 12.16, -13.12, -3.06, 26.24, 0.0]};
+/// assert_eq!(potential.energy(PI), 9.28 + 12.16 - 13.12 - 3.06 + 26.24);
+/// assert!(potential.force(PI).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RyckaertBellemans {
+    /// Coefficients `C_0` to `C_5` of the polynomial
+    pub c: [f64; 6],
+}
+
+impl Potential for RyckaertBellemans {
+    fn energy(&self, phi: f64) -> f64 {
+        let cos = -f64::cos(phi);
+        let mut energy = 0.0;
+        let mut cos_n = 1.0;
+        for c in &self.c {
+            energy += c * cos_n;
+            cos_n *= cos;
+        }
+        return energy;
+    }
+
+    fn force(&self, phi: f64) -> f64 {
+        // d cos(phi - pi)^n / dphi = n cos(phi - pi)^(n - 1) sin(phi)
+        let cos = -f64::cos(phi);
+        let sin = f64::sin(phi);
+        let mut derivative = 0.0;
+        let mut cos_n = 1.0;
+        for (n, c) in self.c.iter().enumerate().skip(1) {
+            derivative += (n as f64) * c * cos_n;
+            cos_n *= cos;
+        }
+        return - derivative * sin;
+    }
+}
+
+impl DihedralPotential for RyckaertBellemans {}
+
+/// Multi-term Fourier potential.
+///
+/// This potential is used for dihedral angles in OPLS-AA or TraPPE force
+/// fields. The following potential expression is used: `V(x) = 1/2 *
+/// \sum_{n=1}^N k_n * (1 + (-1)^(n + 1) cos(n * x))`; where the `k_n` are the
+/// Fourier coefficients. With four coefficients, this is the usual OPLS
+/// dihedral potential.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::Fourier;
+/// use std::f64::consts::PI;
+///
+/// let potential = Fourier::new(vec![1.74, -0.157, 0.279]);
+/// assert!(potential.energy(PI).abs() < 1e-12);
+/// assert_eq!(potential.energy(0.0), 1.74 + 0.279);
+/// ```
+#[derive(Clone)]
+pub struct Fourier {
+    /// Fourier coefficients, `k[n - 1]` is the coefficient for the `n`-th term
+    k: Vec<f64>,
+}
+
+impl Fourier {
+    /// Create a new `Fourier` potential with the coefficients `k`. The first
+    /// value in `k` is the coefficient for `cos(x)`, the second one for
+    /// `cos(2 x)`, and so on.
+    pub fn new(k: Vec<f64>) -> Fourier {
+        assert!(!k.is_empty(), "Fourier potential needs at least one coefficient");
+        Fourier{k: k}
+    }
+}
+
+impl Potential for Fourier {
+    fn energy(&self, phi: f64) -> f64 {
+        let mut energy = 0.0;
+        for (i, k) in self.k.iter().enumerate() {
+            let n = (i + 1) as f64;
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            energy += k * (1.0 + sign * f64::cos(n * phi));
+        }
+        return 0.5 * energy;
+    }
+
+    fn force(&self, phi: f64) -> f64 {
+        let mut force = 0.0;
+        for (i, k) in self.k.iter().enumerate() {
+            let n = (i + 1) as f64;
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            force += sign * k * n * f64::sin(n * phi);
+        }
+        return 0.5 * force;
+    }
+}
+
+impl DihedralPotential for Fourier {}
+
+/// Finitely extensible nonlinear elastic (FENE) potential.
+///
+/// This potential is used for bonds in bead-spring polymer models, where it
+/// prevents the chains from crossing each other. The following potential
+/// expression is used: `V(r) = - 1/2 * k * r_max^2 * ln(1 - (r / r_max)^2)`
+/// where `k` is the spring constant, and `r_max` the maximal extension of the
+/// bond. The energy is infinite for `r >= r_max`.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::Fene;
+///
+/// let potential = Fene{k: 30.0, r_max: 1.5};
+/// assert_eq!(potential.energy(0.0), 0.0);
+/// assert_eq!(potential.energy(1.5), std::f64::INFINITY);
+///
+/// assert!((potential.force(1.0) + 54.0).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Fene {
+    /// Spring constant
+    pub k: f64,
+    /// Maximal extension of the bond
+    pub r_max: f64,
+}
+
+impl Potential for Fene {
+    fn energy(&self, r: f64) -> f64 {
+        if r >= self.r_max {
+            return f64::INFINITY;
+        }
+        let x = r / self.r_max;
+        - 0.5 * self.k * self.r_max * self.r_max * f64::ln(1.0 - x * x)
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        if r >= self.r_max {
+            return f64::NEG_INFINITY;
+        }
+        let x = r / self.r_max;
+        - self.k * r / (1.0 - x * x)
+    }
+}
+
+impl BondPotential for Fene {
+    fn max_length(&self) -> Option<f64> {
+        Some(self.r_max)
+    }
+}
+
+/// Weeks-Chandler-Andersen (WCA) potential.
+///
+/// This is the repulsive part of the Lennard-Jones potential, truncated at
+/// its minimum and shifted up to zero. It is often used together with the
+/// [FENE](struct.Fene.html) potential for bead-spring polymer models. The
+/// following potential expression is used: `V(r) = 4 * epsilon * ((sigma/r)^12
+/// - (sigma/r)^6) + epsilon` for `r < 2^(1/6) sigma`, and `V(r) = 0` for
+/// larger distances.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::WCA;
+///
+/// let potential = WCA{sigma: 1.0, epsilon: 2.0};
+/// assert_eq!(potential.energy(1.0), 2.0);
+/// assert_eq!(potential.energy(1.5), 0.0);
+///
+/// assert_eq!(potential.force(1.0), 48.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct WCA {
+    /// Distance constant of the underlying Lennard-Jones potential
+    pub sigma: f64,
+    /// Energy constant of the underlying Lennard-Jones potential
+    pub epsilon: f64,
+}
+
+impl WCA {
+    /// Get the distance at which this potential goes to zero, at the minimum
+    /// of the Lennard-Jones potential.
+    fn cutoff(&self) -> f64 {
+        f64::powf(2.0, 1.0 / 6.0) * self.sigma
+    }
+}
+
+impl Potential for WCA {
+    fn energy(&self, r: f64) -> f64 {
+        if r >= self.cutoff() {
+            return 0.0;
+        }
+        let s6 = f64::powi(self.sigma / r, 6);
+        4.0 * self.epsilon * (f64::powi(s6, 2) - s6) + self.epsilon
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        if r >= self.cutoff() {
+            return 0.0;
+        }
+        let s6 = f64::powi(self.sigma / r, 6);
+        -24.0 * self.epsilon * (s6 - 2.0 * f64::powi(s6, 2)) / r
+    }
+}
+
+impl PairPotential for WCA {
+    // The potential is exactly zero after its cutoff
+    fn tail_energy(&self, _: f64) -> f64 {0.0}
+    fn tail_virial(&self, _: f64) -> f64 {0.0}
+}
+
+impl BondPotential for WCA {}
+
+/// Quartic potential.
+///
+/// This potential is used for bonds in class-II force fields, or as an
+/// anharmonic bond in polymer models. The following potential expression is
+/// used: `V(x) = k2 * (x - x0)^2 + k3 * (x - x0)^3 + k4 * (x - x0)^4` where
+/// `x0` is the equilibrium value and `k2`, `k3` and `k4` the force constants.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::Quartic;
+///
+/// let potential = Quartic{k2: 10.0, k3: -5.0, k4: 2.0, x0: 1.0};
+/// assert_eq!(potential.energy(1.0), 0.0);
+/// assert_eq!(potential.energy(2.0), 7.0);
+///
+/// assert_eq!(potential.force(1.0), 0.0);
+/// assert_eq!(potential.force(2.0), -13.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Quartic {
+    /// Quadratic force constant
+    pub k2: f64,
+    /// Cubic force constant
+    pub k3: f64,
+    /// Quartic force constant
+    pub k4: f64,
+    /// Equilibrium value
+    pub x0: f64,
+}
+
+impl Potential for Quartic {
+    fn energy(&self, x: f64) -> f64 {
+        let dx = x - self.x0;
+        let dx2 = dx * dx;
+        self.k2 * dx2 + self.k3 * dx2 * dx + self.k4 * dx2 * dx2
+    }
+
+    fn force(&self, x: f64) -> f64 {
+        let dx = x - self.x0;
+        let dx2 = dx * dx;
+        - (2.0 * self.k2 * dx + 3.0 * self.k3 * dx2 + 4.0 * self.k4 * dx2 * dx)
+    }
+}
+
+impl BondPotential for Quartic {}
+impl AnglePotential for Quartic {}
+
 /// Buckingham potential.
 ///
 /// The following potential expression is used: `V(x) = A * exp((sigma - r) /
@@ -411,10 +866,173 @@ impl BondPotential for MorsePotential {}
 impl AnglePotential for MorsePotential {}
 impl DihedralPotential for MorsePotential {}
 
+/// Tabulated potential.
+///
+/// This potential is defined by tables of values for the energy `U(x)` and the
+/// force `F(x) = - dU/dx` at increasing values of `x`. The energy is
+/// interpolated between the points with cubic Hermite splines, and the force
+/// is the derivative of the interpolated energy. Before the first point, the
+/// energy is linearly extrapolated using the first force value. After the last
+/// point, the energy and the force are zero.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::Potential;
+/// use lumol::energy::TablePotential;
+///
+/// // Harmonic potential with x0 = 1 and k = 2
+/// let x = vec![0.0, 0.5, 1.0, 1.5, 2.0];
+/// let energy = x.iter().map(|x| (x - 1.0) * (x - 1.0)).collect();
+/// let force = x.iter().map(|x| -2.0 * (x - 1.0)).collect();
+///
+/// let potential = TablePotential::new(x, energy, force);
+/// assert_eq!(potential.energy(1.5), 0.25);
+/// assert_eq!(potential.force(1.5), -1.0);
+///
+/// // The cubic interpolation is exact for a quadratic potential
+/// assert!(f64::abs(potential.energy(1.2) - 0.04) < 1e-15);
+///
+/// assert_eq!(potential.energy(2.5), 0.0);
+/// ```
+#[derive(Clone)]
+pub struct TablePotential {
+    /// Points where the potential is tabulated
+    x: Vec<f64>,
+    /// Energy at the tabulated points
+    energy: Vec<f64>,
+    /// Force at the tabulated points
+    force: Vec<f64>,
+}
+
+impl TablePotential {
+    /// Create a new `TablePotential` using the `energy` and `force` values
+    /// tabulated at the `x` points. The `x` values must be strictly
+    /// increasing, and all the vectors must have the same size, with at least
+    /// two points.
+    pub fn new(x: Vec<f64>, energy: Vec<f64>, force: Vec<f64>) -> TablePotential {
+        assert!(x.len() >= 2, "tabulated potential needs at least two points");
+        assert_eq!(x.len(), energy.len(), "energy and x must have the same size in tabulated potential");
+        assert_eq!(x.len(), force.len(), "force and x must have the same size in tabulated potential");
+        assert!(
+            x.windows(2).all(|w| w[0] < w[1]),
+            "x values must be strictly increasing in tabulated potential"
+        );
+        TablePotential {
+            x: x,
+            energy: energy,
+            force: force,
+        }
+    }
+
+    /// Get the index of the segment of the table containing `x`, assuming `x`
+    /// is inside the table.
+    fn segment(&self, x: f64) -> usize {
+        let last = self.x.len() - 2;
+        match self.x.binary_search_by(|value| value.partial_cmp(&x).expect("got a NaN value")) {
+            Ok(i) => usize::min(i, last),
+            Err(i) => usize::min(i - 1, last),
+        }
+    }
+
+    /// Integrate `x^power U(x)` if `virial` is false, and `x^(power + 1)
+    /// F(x)` if `virial` is true, between `start` and the end of the table.
+    fn integrate(&self, start: f64, power: i32, virial: bool) -> f64 {
+        // Three points Gauss-Legendre quadrature, which is exact for the
+        // polynomials of degree 5 we are integrating here.
+        const POINTS: [f64; 3] = [-0.7745966692414834, 0.0, 0.7745966692414834];
+        const WEIGHTS: [f64; 3] = [0.5555555555555556, 0.8888888888888888, 0.5555555555555556];
+        let function = |x: f64| {
+            if virial {
+                x.powi(power + 1) * self.force(x)
+            } else {
+                x.powi(power) * self.energy(x)
+            }
+        };
+
+        let mut bounds = vec![start];
+        bounds.extend(self.x.iter().cloned().filter(|&x| x > start));
+
+        let mut integral = 0.0;
+        for window in bounds.windows(2) {
+            let (a, b) = (window[0], window[1]);
+            let center = 0.5 * (a + b);
+            let half = 0.5 * (b - a);
+            for (point, weight) in POINTS.iter().zip(WEIGHTS.iter()) {
+                integral += half * weight * function(center + half * point);
+            }
+        }
+        return integral;
+    }
+}
+
+impl Potential for TablePotential {
+    fn energy(&self, x: f64) -> f64 {
+        let n = self.x.len();
+        if x < self.x[0] {
+            return self.energy[0] - self.force[0] * (x - self.x[0]);
+        } else if x > self.x[n - 1] {
+            return 0.0;
+        }
+
+        let i = self.segment(x);
+        let h = self.x[i + 1] - self.x[i];
+        let t = (x - self.x[i]) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        // Hermite basis functions, using dU/dx = -F
+        return (2.0 * t3 - 3.0 * t2 + 1.0) * self.energy[i]
+             - (t3 - 2.0 * t2 + t) * h * self.force[i]
+             + (-2.0 * t3 + 3.0 * t2) * self.energy[i + 1]
+             - (t3 - t2) * h * self.force[i + 1];
+    }
+
+    fn force(&self, x: f64) -> f64 {
+        let n = self.x.len();
+        if x < self.x[0] {
+            return self.force[0];
+        } else if x > self.x[n - 1] {
+            return 0.0;
+        }
+
+        let i = self.segment(x);
+        let h = self.x[i + 1] - self.x[i];
+        let t = (x - self.x[i]) / h;
+        let t2 = t * t;
+        // Derivatives of the Hermite basis functions
+        let derivative = (6.0 * t2 - 6.0 * t) * self.energy[i] / h
+                       - (3.0 * t2 - 4.0 * t + 1.0) * self.force[i]
+                       + (-6.0 * t2 + 6.0 * t) * self.energy[i + 1] / h
+                       - (3.0 * t2 - 2.0 * t) * self.force[i + 1];
+        return -derivative;
+    }
+}
+
+impl PairPotential for TablePotential {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        if cutoff >= self.x[self.x.len() - 1] {
+            return 0.0;
+        }
+        return self.integrate(cutoff, 2, false);
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        if cutoff >= self.x[self.x.len() - 1] {
+            return 0.0;
+        }
+        return self.integrate(cutoff, 2, true);
+    }
+}
+
+impl BondPotential for TablePotential {}
+impl AnglePotential for TablePotential {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use energy::{Potential, PairPotential};
+    use energy::{Potential, PairPotential, BondPotential};
+    use std::f64;
+    use std::f64::consts::PI;
     const EPS: f64 = 1e-9;
 
     #[test]
@@ -456,6 +1074,94 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, lj.force(4.0), epsilon=1e-6);
     }
 
+    #[test]
+    fn mie() {
+        // Mie 12-6 is the same as Lennard-Jones
+        let mie = Mie::new(2.0, 0.8, 12.0, 6.0);
+        let lj = LennardJones{epsilon: 0.8, sigma: 2.0};
+        assert_eq!(mie.energy(2.0), 0.0);
+        assert_ulps_eq!(mie.energy(2.5), lj.energy(2.5));
+        assert_ulps_eq!(mie.force(2.5), lj.force(2.5));
+        assert_ulps_eq!(mie.tail_energy(14.42), lj.tail_energy(14.42));
+        assert_ulps_eq!(mie.tail_virial(14.42), lj.tail_virial(14.42));
+
+        let mie = Mie::new(2.0, 0.8, 14.0, 6.0);
+        // The minimum is at -epsilon
+        let r_min = 2.0 * f64::powf(14.0 / 6.0, 1.0 / 8.0);
+        assert_ulps_eq!(mie.energy(r_min), -0.8);
+        assert!(mie.force(r_min).abs() < 1e-15);
+
+        let e0 = mie.energy(4.0);
+        let e1 = mie.energy(4.0 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, mie.force(4.0), epsilon=1e-6);
+
+        // Check tail corrections with a numerical integration
+        let (energy, virial) = integrate_tail(&mie, 5.0);
+        assert_relative_eq!(mie.tail_energy(5.0), energy, max_relative=1e-6);
+        assert_relative_eq!(mie.tail_virial(5.0), virial, max_relative=1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mie_exponents() {
+        let _ = Mie::new(2.0, 0.8, 6.0, 12.0);
+    }
+
+    #[test]
+    fn soft_core_lj() {
+        let lj = LennardJones{epsilon: 0.8, sigma: 2.0};
+        // lambda = 1 is the same as Lennard-Jones
+        let soft = SoftCoreLennardJones{epsilon: 0.8, sigma: 2.0, lambda: 1.0, alpha: 0.5};
+        assert_ulps_eq!(soft.energy(2.5), lj.energy(2.5));
+        assert_ulps_eq!(soft.force(2.5), lj.force(2.5));
+        assert_ulps_eq!(soft.tail_energy(14.42), lj.tail_energy(14.42));
+        assert_relative_eq!(soft.tail_virial(14.42), lj.tail_virial(14.42), max_relative=1e-12);
+
+        // lambda = 0 removes the interaction
+        let soft = SoftCoreLennardJones{epsilon: 0.8, sigma: 2.0, lambda: 0.0, alpha: 0.5};
+        assert_eq!(soft.energy(2.5), 0.0);
+        assert_eq!(soft.force(2.5), 0.0);
+        assert_eq!(soft.tail_energy(5.0), 0.0);
+
+        let soft = SoftCoreLennardJones{epsilon: 0.8, sigma: 2.0, lambda: 0.3, alpha: 0.5};
+        // No singularity at r = 0
+        assert!(soft.energy(0.0).is_finite());
+        assert_eq!(soft.force(0.0), 0.0);
+
+        for &r in &[0.5, 2.0, 4.0] {
+            let eps = 1e-6;
+            let finite_difference = (soft.energy(r - eps) - soft.energy(r + eps)) / (2.0 * eps);
+            assert_relative_eq!(finite_difference, soft.force(r), max_relative=1e-8);
+        }
+
+        let (energy, virial) = integrate_tail(&soft, 2.5);
+        assert_relative_eq!(soft.tail_energy(2.5), energy, max_relative=1e-6);
+        assert_relative_eq!(soft.tail_virial(2.5), virial, max_relative=1e-6);
+
+        let soft = SoftCoreLennardJones{epsilon: 0.8, sigma: 2.0, lambda: 0.0, alpha: 2.0};
+        assert!(soft.tail_converges(2.5));
+        assert!(!soft.tail_converges(2.0));
+    }
+
+    /// Numerically integrate r^2 V(r) and r^3 f(r) from `cutoff` to infinity,
+    /// using the trapezoidal rule with the `r = cutoff / t` substitution.
+    fn integrate_tail(potential: &PairPotential, cutoff: f64) -> (f64, f64) {
+        let n = 100000;
+        let dt = 1.0 / n as f64;
+        let mut energy = 0.0;
+        let mut virial = 0.0;
+        for i in 1..(n + 1) {
+            let t = i as f64 * dt;
+            let weight = if i == n {0.5} else {1.0};
+            let r = cutoff / t;
+            // dr = cutoff / t^2 dt
+            let jacobian = cutoff / (t * t);
+            energy += weight * r * r * potential.energy(r) * jacobian * dt;
+            virial += weight * r * r * r * potential.force(r) * jacobian * dt;
+        }
+        return (energy, virial);
+    }
+
     #[test]
     fn harmonic() {
         let harmonic = Harmonic{k: 50.0, x0: 2.0};
@@ -503,6 +1209,110 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, torsion.force(4.0), epsilon=1e-6);
     }
 
+    #[test]
+    fn ryckaert_bellemans() {
+        let potential = RyckaertBellemans{c: [9.28, 12.16, -13.12, -3.06, 26.24, 0.0]};
+        assert_relative_eq!(potential.energy(PI), 31.5, epsilon=1e-12);
+        assert_relative_eq!(potential.energy(0.0), 13.3, epsilon=1e-12);
+        assert_relative_eq!(potential.force(PI), 0.0, epsilon=1e-12);
+
+        let e0 = potential.energy(2.0);
+        let e1 = potential.energy(2.0 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, potential.force(2.0), epsilon=1e-5);
+    }
+
+    #[test]
+    fn fourier() {
+        let potential = Fourier::new(vec![1.74, -0.157, 0.279, 0.3]);
+        assert_relative_eq!(potential.energy(PI), 0.0, epsilon=1e-12);
+        assert_relative_eq!(potential.energy(PI / 2.0), 0.5 * (1.74 - 2.0 * 0.157 + 0.279), epsilon=1e-12);
+        assert_relative_eq!(potential.force(PI), 0.0, epsilon=1e-12);
+
+        let e0 = potential.energy(1.2);
+        let e1 = potential.energy(1.2 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, potential.force(1.2), epsilon=1e-6);
+    }
+
+    #[test]
+    fn trappe_butane() {
+        // TraPPE-UA torsion for CHx-CH2-CH2-CHy, with the reference energies
+        // at cis, gauche and trans conformations. The parameters are given in
+        // Kelvin (energy / kB).
+        let (c1, c2, c3) = (355.03, -68.19, 791.32);
+        let trappe = |phi: f64| {
+            c1 * (1.0 + f64::cos(phi)) + c2 * (1.0 - f64::cos(2.0 * phi)) + c3 * (1.0 + f64::cos(3.0 * phi))
+        };
+
+        let fourier = Fourier::new(vec![2.0 * c1, 2.0 * c2, 2.0 * c3]);
+        let ryckaert_bellemans = RyckaertBellemans{
+            c: [c1 + 2.0 * c2 + c3, -c1 + 3.0 * c3, -2.0 * c2, -4.0 * c3, 0.0, 0.0]
+        };
+
+        // trans
+        assert_relative_eq!(fourier.energy(PI), 0.0, epsilon=1e-10);
+        assert_relative_eq!(ryckaert_bellemans.energy(PI), 0.0, epsilon=1e-10);
+        // gauche
+        assert_relative_eq!(fourier.energy(PI / 3.0), 430.26, epsilon=1e-10);
+        assert_relative_eq!(ryckaert_bellemans.energy(PI / 3.0), 430.26, epsilon=1e-10);
+        // cis
+        assert_relative_eq!(fourier.energy(0.0), 2292.7, epsilon=1e-10);
+        assert_relative_eq!(ryckaert_bellemans.energy(0.0), 2292.7, epsilon=1e-10);
+
+        for &phi in &[-2.5, -1.0, 0.3, 1.1, 2.0, 2.9] {
+            assert_relative_eq!(fourier.energy(phi), trappe(phi), epsilon=1e-9);
+            assert_relative_eq!(ryckaert_bellemans.energy(phi), trappe(phi), epsilon=1e-9);
+            assert_relative_eq!(fourier.force(phi), ryckaert_bellemans.force(phi), epsilon=1e-9);
+        }
+    }
+
+    #[test]
+    fn fene() {
+        let fene = Fene{k: 30.0, r_max: 1.5};
+        assert_eq!(fene.energy(0.0), 0.0);
+        assert_relative_eq!(fene.energy(1.0), -0.5 * 30.0 * 2.25 * f64::ln(5.0 / 9.0), epsilon=1e-12);
+        assert_eq!(fene.energy(1.5), f64::INFINITY);
+        assert_eq!(fene.energy(2.0), f64::INFINITY);
+        assert_eq!(fene.force(2.0), f64::NEG_INFINITY);
+        assert_eq!(fene.max_length(), Some(1.5));
+
+        let e0 = fene.energy(1.2);
+        let e1 = fene.energy(1.2 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, fene.force(1.2), epsilon=1e-5);
+    }
+
+    #[test]
+    fn wca() {
+        let wca = WCA{sigma: 2.0, epsilon: 3.0};
+        let lj = LennardJones{sigma: 2.0, epsilon: 3.0};
+        let rc = f64::powf(2.0, 1.0 / 6.0) * 2.0;
+
+        assert_relative_eq!(wca.energy(rc - 1e-12), 0.0, epsilon=1e-9);
+        assert_eq!(wca.energy(rc), 0.0);
+        assert_eq!(wca.force(rc + 1.0), 0.0);
+        assert_ulps_eq!(wca.energy(1.9), lj.energy(1.9) + 3.0);
+        assert_eq!(wca.force(1.9), lj.force(1.9));
+
+        assert_eq!(wca.tail_energy(rc), 0.0);
+        assert_eq!(wca.tail_virial(rc), 0.0);
+        assert_eq!(BondPotential::max_length(&wca), None);
+
+        let e0 = wca.energy(2.1);
+        let e1 = wca.energy(2.1 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, wca.force(2.1), epsilon=1e-5);
+    }
+
+    #[test]
+    fn quartic() {
+        let quartic = Quartic{k2: 10.0, k3: -5.0, k4: 2.0, x0: 1.0};
+        assert_eq!(quartic.energy(1.0), 0.0);
+        assert_eq!(quartic.energy(0.5), 10.0 * 0.25 + 5.0 * 0.125 + 2.0 * 0.0625);
+        assert_eq!(quartic.force(1.0), 0.0);
+
+        let e0 = quartic.energy(1.3);
+        let e1 = quartic.energy(1.3 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, quartic.force(1.3), epsilon=1e-5);
+    }
+
     #[test]
     fn buckingham() {
         let buckingham = Buckingham{a: 2.0, c: 1.0, rho: 2.0};
@@ -550,4 +1360,40 @@ mod tests {
         let e1 = morse.energy(1.3 + EPS);
         assert_relative_eq!((e0 - e1) / EPS, morse.force(1.3), epsilon=1e-6);
     }
+
+    #[test]
+    fn table() {
+        let lj = LennardJones{epsilon: 0.8, sigma: 2.0};
+        let x = (0..1000).map(|i| 1.8 + 0.01 * i as f64).collect::<Vec<_>>();
+        let energy = x.iter().map(|&x| lj.energy(x)).collect();
+        let force = x.iter().map(|&x| lj.force(x)).collect();
+        let table = TablePotential::new(x, energy, force);
+        let end = 1.8 + 0.01 * 999.0;
+
+        assert_ulps_eq!(table.energy(2.5), lj.energy(2.5));
+        assert_ulps_eq!(table.force(2.5), lj.force(2.5));
+        assert_relative_eq!(table.energy(2.505), lj.energy(2.505), max_relative=1e-6);
+        assert_relative_eq!(table.force(2.505), lj.force(2.505), max_relative=1e-4);
+
+        // Linear extrapolation before the first point
+        assert_ulps_eq!(table.force(1.5), lj.force(1.8));
+        assert_ulps_eq!(table.energy(1.5), lj.energy(1.8) + 0.3 * lj.force(1.8));
+        // Zero after the last point
+        assert_eq!(table.energy(12.0), 0.0);
+        assert_eq!(table.force(12.0), 0.0);
+
+        for &x in &[1.5, 2.0, 2.505, 3.333, 8.0] {
+            let e0 = table.energy(x);
+            let e1 = table.energy(x + EPS);
+            assert_relative_eq!((e0 - e1) / EPS, table.force(x), epsilon=1e-5);
+        }
+
+        let expected = lj.tail_energy(5.0) - lj.tail_energy(end);
+        assert_relative_eq!(table.tail_energy(5.0), expected, max_relative=1e-6);
+        let expected = lj.tail_virial(5.0) - lj.tail_virial(end);
+        assert_relative_eq!(table.tail_virial(5.0), expected, max_relative=1e-6);
+
+        assert_eq!(table.tail_energy(12.0), 0.0);
+        assert_eq!(table.tail_virial(12.0), 0.0);
+    }
 }
@@ -0,0 +1,419 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use special::Error;
+use std::f64::consts::{PI, FRAC_2_SQRT_PI};
+
+use sys::{Configuration, NeighborList, GroupMasks};
+use types::{Matrix3, Vector3D, Zero};
+use consts::ELCC;
+use energy::{PairRestriction, RestrictionInfo};
+
+use super::{GlobalPotential, CoulombicPotential, GlobalCache};
+
+/// Damped shifted force summation for coulombic interactions.
+///
+/// This is a direct, pairwise summation for coulombic potential
+/// [Fennell2006], similar to the [Wolf][Wolf] summation. The potential is
+/// damped by the `erfc(α r)` function, and shifted so that both the energy
+/// and the force go smoothly to zero at the cutoff distance. This gives a
+/// better energy conservation than the Wolf summation in molecular dynamics.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::DampedShiftedForce;
+///
+/// let dsf = DampedShiftedForce::new(/* cutoff */ 12.0, /* alpha */ 0.2);
+///
+/// use lumol::sys::System;
+/// use lumol::sys::Particle;
+/// use lumol::sys::UnitCell;
+/// use lumol::types::Vector3D;
+///
+/// // Setup a system containing a NaCl pair
+/// let mut system = System::with_cell(UnitCell::cubic(30.0));
+///
+/// let mut na = Particle::new("Na");
+/// na.charge = 1.0;
+/// na.position = Vector3D::new(0.0, 0.0, 0.0);
+///
+/// let mut cl = Particle::new("Cl");
+/// cl.charge = -1.0;
+/// cl.position = Vector3D::new(2.0, 0.0, 0.0);
+///
+/// system.add_particle(na);
+/// system.add_particle(cl);
+///
+/// // Use the damped shifted force for electrostatic interactions
+/// system.set_coulomb_potential(Box::new(dsf));
+///
+/// assert_eq!(system.potential_energy(), -0.07097365177652684);
+/// ```
+///
+/// [Wolf]: struct.Wolf.html
+/// [Fennell2006]: Fennell, C. J. & Gezelter, J. D. J. Chem. Phys. 124, 234104 (2006).
+#[derive(Clone, Debug)]
+pub struct DampedShiftedForce {
+    /// Damping parameter
+    alpha: f64,
+    /// Cutoff radius
+    cutoff: f64,
+    /// Energy shift `erfc(α rc) / rc`
+    energy_cst: f64,
+    /// Force shift `erfc(α rc) / rc^2 + 2 α / √π exp(-α^2 rc^2) / rc`
+    force_cst: f64,
+    /// Restriction scheme
+    restriction: PairRestriction,
+    /// Neighbor list for the pairs computations
+    neighbors: NeighborList,
+}
+
+impl DampedShiftedForce {
+    /// Create a new damped shifted force summation, using a cutoff of
+    /// `cutoff` and a damping parameter `alpha`. [Fennell2006] recommend
+    /// using `alpha = 0.2 A^-1` with cutoffs between 9 and 15 A.
+    ///
+    /// [Fennell2006]: Fennell, C. J. & Gezelter, J. D. J. Chem. Phys. 124, 234104 (2006).
+    pub fn new(cutoff: f64, alpha: f64) -> DampedShiftedForce {
+        assert!(cutoff > 0.0, "Got a negative cutoff in damped shifted force summation");
+        assert!(alpha >= 0.0, "Got a negative damping parameter in damped shifted force summation");
+        let energy_cst = f64::erfc(alpha * cutoff) / cutoff;
+        let force_cst = f64::erfc(alpha * cutoff) / (cutoff * cutoff)
+                      + alpha * FRAC_2_SQRT_PI * f64::exp(-alpha * alpha * cutoff * cutoff) / cutoff;
+        DampedShiftedForce {
+            alpha: alpha,
+            cutoff: cutoff,
+            energy_cst: energy_cst,
+            force_cst: force_cst,
+            restriction: PairRestriction::None,
+            neighbors: NeighborList::new(),
+        }
+    }
+
+    /// Compute the energy for the pair of particles with charge `qi` and `qj`,
+    /// at the distance of `r`, with restriction information in `info`.
+    #[inline]
+    fn energy_pair(&self, info: RestrictionInfo, qi: f64, qj: f64, r: f64) -> f64 {
+        if r > self.cutoff || info.excluded {
+            return 0.0;
+        }
+        let energy = f64::erfc(self.alpha * r) / r - self.energy_cst + self.force_cst * (r - self.cutoff);
+        return info.scaling * qi * qj * energy / ELCC;
+    }
+
+    /// Compute the energy for self interaction of a particle with charge `qi`
+    #[inline]
+    fn energy_self(&self, qi: f64) -> f64 {
+        qi * qi * (self.energy_cst / 2.0 + self.alpha / f64::sqrt(PI)) / ELCC
+    }
+
+    /// Compute the force for the pair of particles with charge `qi` and `qj`,
+    /// at the distance of `rij`, with restriction information in `info`.
+    #[inline]
+    fn force_pair(&self, info: RestrictionInfo, qi: f64, qj: f64, rij: &Vector3D) -> Vector3D {
+        let r = rij.norm();
+        if r > self.cutoff || info.excluded {
+            return Vector3D::zero();
+        }
+        let factor = f64::erfc(self.alpha * r) / (r * r)
+                   + self.alpha * FRAC_2_SQRT_PI * f64::exp(-self.alpha * self.alpha * r * r) / r
+                   - self.force_cst;
+        return info.scaling * qi * qj * factor / r * rij / ELCC;
+    }
+}
+
+impl GlobalCache for DampedShiftedForce {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut e_old = 0.0;
+        let mut e_new = 0.0;
+
+        // Iterate over all interactions between a moved particle and a
+        // particle not moved
+        for (idx, &i) in idxes.iter().enumerate() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+
+            let old_position = configuration.particle(i).position;
+            for j in neighbors.around(&old_position) {
+                if idxes.contains(&j) {continue;}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r_old = configuration.distance(i, j);
+                e_old += self.energy_pair(info, qi, qj, r_old);
+            }
+
+            for j in neighbors.around(&newpos[idx]) {
+                if idxes.contains(&j) {continue;}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r_new = configuration.cell.distance(&newpos[idx], &configuration.particle(j).position);
+                e_new += self.energy_pair(info, qi, qj, r_new);
+            }
+        }
+
+        // Iterate over all interactions between two moved particles
+        for (idx, &i) in idxes.iter().enumerate() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for (jdx, &j) in idxes.iter().enumerate().skip(idx + 1) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let r_old = configuration.distance(i, j);
+                let r_new = configuration.cell.distance(&newpos[idx], &newpos[jdx]);
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                e_old += self.energy_pair(info, qi, qj, r_old);
+                e_new += self.energy_pair(info, qi, qj, r_new);
+            }
+        }
+
+        return e_new - e_old;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+impl GlobalPotential for DampedShiftedForce {
+    fn name(&self) -> &str {
+        "DSF"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.cutoff)
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut energy = 0.0;
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let r = configuration.distance(i, j);
+                energy += self.energy_pair(info, qi, qj, r);
+            }
+        }
+
+        for particle in configuration.particles() {
+            energy -= self.energy_self(particle.charge);
+        }
+        return energy;
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let rij = configuration.nearest_image(i, j);
+                let force = self.force_pair(info, qi, qj, &rij);
+                forces[i] += force;
+                forces[j] -= force;
+            }
+        }
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut virial = Matrix3::zero();
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let rij = configuration.nearest_image(i, j);
+                let force = self.force_pair(info, qi, qj, &rij);
+                virial += force.tensorial(&rij);
+            }
+        }
+        return virial;
+    }
+}
+
+impl CoulombicPotential for DampedShiftedForce {
+    fn set_restriction(&mut self, restriction: PairRestriction) {
+        self.restriction = restriction;
+    }
+
+    fn group_energy(&self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        let masks = GroupMasks::new(configuration.size(), first, second);
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut energy = 0.0;
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 || !masks.in_any(i) {continue}
+            for &j in neighbors.of(i) {
+                if !masks.contains(i, j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                energy += self.energy_pair(info, qi, qj, configuration.distance(i, j));
+            }
+        }
+        return energy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    pub use super::*;
+    use sys::System;
+    use energy::{GlobalPotential, Ewald, SharedEwald};
+    use utils::system_from_xyz;
+
+    pub fn nacl_pair() -> System {
+        let mut system = system_from_xyz("2
+        cell: 20.0
+        Cl 0.0 0.0 0.0
+        Na 1.5 0.0 0.0
+        ");
+        system.particle_mut(0).charge = -1.0;
+        system.particle_mut(1).charge = 1.0;
+        return system;
+    }
+
+    pub fn water() -> System {
+        let mut system = system_from_xyz("6
+        bonds cell: 20.0
+        O  0.0  0.0  0.0
+        H -0.7 -0.7  0.3
+        H  0.3 -0.3 -0.8
+        O  2.0  2.0  0.0
+        H  1.3  1.3  0.3
+        H  2.3  1.7 -0.8
+        ");
+        assert!(system.molecules().len() == 2);
+
+        for particle in system.particles_mut() {
+            if particle.name() == "O" {
+                particle.charge = -0.8476;
+            } else if particle.name() == "H" {
+                particle.charge = 0.4238;
+            }
+        }
+        return system;
+    }
+
+    #[test]
+    fn smooth_cutoff() {
+        let dsf = DampedShiftedForce::new(8.0, 0.2);
+        let info = PairRestriction::None.information(-1);
+
+        assert_ulps_eq!(dsf.energy_pair(info, 1.0, -1.0, 8.0), 0.0);
+        let force = dsf.force_pair(info, 1.0, -1.0, &Vector3D::new(8.0, 0.0, 0.0));
+        assert_ulps_eq!(force.norm(), 0.0);
+    }
+
+    #[test]
+    fn energy() {
+        let dsf = DampedShiftedForce::new(9.5, 0.2);
+        let ewald = SharedEwald::new(Ewald::new(9.5, 12));
+
+        let system = nacl_pair();
+        assert_relative_eq!(dsf.energy(&system), ewald.energy(&system), max_relative=5e-3);
+
+        let system = water();
+        assert_relative_eq!(dsf.energy(&system), ewald.energy(&system), max_relative=5e-3);
+    }
+
+    #[test]
+    fn forces() {
+        let system = water();
+        let dsf = DampedShiftedForce::new(9.5, 0.2);
+        let ewald = SharedEwald::new(Ewald::new(9.5, 12));
+
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        dsf.forces(&system, &mut forces);
+        let mut expected = vec![Vector3D::zero(); system.size()];
+        ewald.forces(&system, &mut expected);
+        for (force, expected) in forces.iter().zip(&expected) {
+            assert!((*force - *expected).norm() < 5e-2 * expected.norm());
+        }
+
+        let total = forces.iter().fold(Vector3D::zero(), |acc, &force| acc + force);
+        assert!(total.norm() < 1e-12);
+
+        // Finite difference computation of the forces
+        let mut system = system;
+        let e = dsf.energy(&system);
+        let eps = 1e-9;
+        for i in 0..3 {
+            system.particle_mut(0).position[i] += eps;
+            let e1 = dsf.energy(&system);
+            system.particle_mut(0).position[i] -= eps;
+            assert_relative_eq!((e - e1) / eps, forces[0][i], epsilon=1e-5);
+        }
+    }
+
+    #[test]
+    fn virial() {
+        let system = nacl_pair();
+        let dsf = DampedShiftedForce::new(8.0, 0.2);
+
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        dsf.forces(&system, &mut forces);
+        let virial = dsf.virial(&system);
+        let expected = forces[0].tensorial(&Vector3D::new(-1.5, 0.0, 0.0));
+        assert_ulps_eq!(virial, expected);
+    }
+
+    mod cache {
+        use super::*;
+        use types::Vector3D;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential, GlobalCache};
+
+        #[test]
+        fn move_atoms() {
+            let mut system = water();
+            let mut dsf = DampedShiftedForce::new(8.0, 0.2);
+            dsf.set_restriction(PairRestriction::InterMolecular);
+
+            let check = dsf.clone();
+
+            let old_e = check.energy(&system);
+            let idxes = &[0, 1];
+            let newpos = &[Vector3D::new(0.0, 0.0, 0.5), Vector3D::new(-0.7, 0.2, 1.5)];
+
+            let cost = dsf.move_particles_cost(&system, idxes, newpos);
+
+            system.particle_mut(0).position = newpos[0];
+            system.particle_mut(1).position = newpos[1];
+            let new_e = check.energy(&system);
+            assert_ulps_eq!(cost, new_e - old_e);
+        }
+    }
+}
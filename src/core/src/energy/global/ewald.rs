@@ -9,7 +9,7 @@ use std::f64;
 
 use ndarray::Zip;
 
-use sys::{Configuration, UnitCell, CellShape};
+use sys::{Configuration, UnitCell, CellShape, GroupMasks};
 use types::{Matrix3, Vector3D, Array3, Complex, Zero};
 use consts::ELCC;
 use energy::{PairRestriction, RestrictionInfo};
@@ -26,6 +26,13 @@ use super::{GlobalPotential, CoulombicPotential, GlobalCache};
 /// one in real space, and the other in Fourier or k-space. For more information
 /// about this algorithm see [FS2002].
 ///
+/// When the unit cell is not periodic along the z axis (see
+/// [`UnitCell::slab`](../sys/struct.UnitCell.html#method.slab)), the
+/// Yeh-Berkowitz correction [YB1999] is added to the energy, forces and virial
+/// to remove the interactions between the periodic images of the system along
+/// z. The cell should then contain enough vacuum along z, typically with a `c`
+/// length of two to three times the thickness of the system.
+///
 /// # Examples
 ///
 /// ```
@@ -62,6 +69,9 @@ use super::{GlobalPotential, CoulombicPotential, GlobalCache};
 /// ```
 ///
 /// [FS2002] Frenkel, D. & Smith, B. Understanding molecular simulation. (Academic press, 2002).
+///
+/// [YB1999] Yeh, I.-C. & Berkowitz, M. L. Ewald summation for systems with
+/// slab geometry. J. Chem. Phys. 111, 3155 (1999).
 #[derive(Clone, Debug)]
 pub struct Ewald {
     /// Splitting parameter between k-space and real space
@@ -85,6 +95,12 @@ pub struct Ewald {
     delta_rho: Array3<Complex>,
     /// Guard for cache invalidation of `expfactors`
     previous_cell: Option<UnitCell>,
+    /// Target accuracy for the automatic selection of `alpha` and `kmax`,
+    /// until the parameters are selected
+    accuracy: Option<f64>,
+    /// Was `alpha` set by the user, or should it be selected from the
+    /// accuracy?
+    fixed_alpha: bool,
 }
 
 impl Ewald {
@@ -104,17 +120,103 @@ impl Ewald {
             rho: rho.clone(),
             delta_rho: rho,
             previous_cell: None,
+            accuracy: None,
+            fixed_alpha: false,
         }
     }
 
+    /// Create an Ewald summation using the given `cutoff` radius in real
+    /// space, and selecting the alpha parameter and the number of points in
+    /// k-space to reach the given relative `accuracy` on the forces.
+    ///
+    /// The parameters are selected using the error estimates from
+    /// [Kolafa1992] for the unit cell and charges of the system at the first
+    /// energy evaluation, and then kept for the whole simulation to keep the
+    /// energy continuous when the cell changes. The `accuracy` is relative to
+    /// the force between two unit charges separated by 1 A.
+    ///
+    /// [Kolafa1992] Kolafa, J. & Perram, J. W. Mol. Simul. 9, 351 (1992).
+    pub fn with_accuracy(cutoff: f64, accuracy: f64) -> Ewald {
+        assert!(accuracy > 0.0, "Ewald accuracy must be positive");
+        let mut ewald = Ewald::new(cutoff, 1);
+        ewald.accuracy = Some(accuracy);
+        return ewald;
+    }
+
     /// Set the value of the alpha parameter for ewald computation. The default is to use
-    /// `alpha = 3 * π / (4 * rc)`.
+    /// `alpha = 3 * π / (4 * rc)`. If this Ewald summation was created with a
+    /// target accuracy, only `kmax` is then selected from the accuracy, using
+    /// this value of alpha.
     pub fn set_alpha(&mut self, alpha: f64) {
         assert!(alpha > 0.0, "Ewald parameter alpha must be positive");
         self.alpha = alpha;
+        self.fixed_alpha = true;
+        self.previous_cell = None;
     }
 
-    fn precompute(&mut self, cell: &UnitCell) {
+    /// Select the value of alpha and kmax reaching the requested accuracy for
+    /// the `configuration`, if an accuracy was requested and the parameters
+    /// were not already selected.
+    fn select_parameters(&mut self, configuration: &Configuration) {
+        let accuracy = match self.accuracy {
+            Some(accuracy) => accuracy,
+            None => return,
+        };
+
+        let natoms = configuration.size();
+        let q2 = configuration.particles().map(|p| p.charge * p.charge).sum::<f64>();
+        if natoms == 0 || q2 == 0.0 || configuration.cell.is_infinite() {
+            // Wait for a configuration with charges to select the parameters
+            return;
+        }
+        self.accuracy = None;
+        // Force the re-computation of the k-space factors
+        self.previous_cell = None;
+
+        let rc = self.rc;
+        let volume = configuration.cell.volume();
+        let lenghts = configuration.cell.lengths();
+        let max_lenght = f64::max(f64::max(lenghts[0], lenghts[1]), lenghts[2]);
+
+        // Real space error estimate: 2 Q^2 exp(-alpha^2 rc^2) / sqrt(N rc V)
+        let real_error = |alpha: f64| {
+            2.0 * q2 * f64::exp(-alpha * alpha * rc * rc) / f64::sqrt(natoms as f64 * rc * volume)
+        };
+        // k-space error estimate: 2 Q^2 alpha / L sqrt(1 / (π kmax N))
+        // exp(-(π kmax / (alpha L))^2)
+        let kspace_error = |alpha: f64, kmax: usize| {
+            let kmax = kmax as f64;
+            let exponent = PI * kmax / (alpha * max_lenght);
+            2.0 * q2 * alpha / max_lenght * f64::sqrt(1.0 / (PI * kmax * natoms as f64)) * f64::exp(-exponent * exponent)
+        };
+
+        if !self.fixed_alpha {
+            let log = f64::ln(2.0 * q2 / (accuracy * f64::sqrt(natoms as f64 * rc * volume)));
+            // If the real space error is always smaller than the accuracy, use
+            // a small value of alpha to reduce the number of k-space vectors.
+            self.alpha = f64::sqrt(f64::max(log, 1.0)) / rc;
+        }
+
+        let mut kmax = 1;
+        while kspace_error(self.alpha, kmax) > accuracy {
+            kmax += 1;
+        }
+
+        if kmax != self.kmax {
+            self.kmax = kmax;
+            self.expfactors = Array3::zeros((kmax, kmax, kmax));
+            self.rho = Array3::zeros((kmax, kmax, kmax));
+            self.delta_rho = Array3::zeros((kmax, kmax, kmax));
+        }
+
+        info!("Ewald parameters for an accuracy of {:e}: alpha = {} A^-1, kmax = {}", accuracy, self.alpha, self.kmax);
+        info!("  Estimated real space error: {:e}", real_error(self.alpha));
+        info!("  Estimated k-space error: {:e}", kspace_error(self.alpha, self.kmax));
+    }
+
+    fn precompute(&mut self, configuration: &Configuration) {
+        self.select_parameters(configuration);
+        let cell = &configuration.cell;
         if let Some(ref prev_cell) = self.previous_cell {
             if cell == prev_cell {
                 // Do not recompute
@@ -328,6 +430,25 @@ impl Ewald {
 impl Ewald {
     /// Compute the Fourier transform of the electrostatic density
     fn density_fft(&mut self, configuration: &Configuration) {
+        self.compute_fourier_phases(configuration);
+        let natoms = configuration.size();
+        for ikx in 0..self.kmax {
+            for iky in 0..self.kmax {
+                for ikz in 0..self.kmax {
+                    let mut rho = Complex::polar(0.0, 0.0);
+                    for j in 0..natoms {
+                        let phi = self.fourier_phases[(ikx, j, 0)] * self.fourier_phases[(iky, j, 1)] * self.fourier_phases[(ikz, j, 2)];
+                        rho = rho + configuration.particle(j).charge * phi;
+                    }
+                    self.rho[(ikx, iky, ikz)] = rho;
+                }
+            }
+        }
+    }
+
+    /// Compute the Fourier phases `exp(-2 i pi k r)` of all the particles in
+    /// the `configuration`
+    fn compute_fourier_phases(&mut self, configuration: &Configuration) {
         let natoms = configuration.size();
         self.fourier_phases.resize_if_different((self.kmax, natoms, 3));
 
@@ -349,26 +470,38 @@ impl Ewald {
                 }
             }
         }
+    }
 
+    /// Compute the k-space energy of the given `charges`, using the Fourier
+    /// phases from the last call to `compute_fourier_phases` and a cell with
+    /// the given `volume`. This overwrites the stored density.
+    fn kspace_energy_charges(&mut self, charges: &[f64], volume: f64) -> f64 {
         for ikx in 0..self.kmax {
             for iky in 0..self.kmax {
                 for ikz in 0..self.kmax {
                     let mut rho = Complex::polar(0.0, 0.0);
-                    for j in 0..natoms {
+                    for (j, &charge) in charges.iter().enumerate() {
+                        if charge == 0.0 {continue}
                         let phi = self.fourier_phases[(ikx, j, 0)] * self.fourier_phases[(iky, j, 1)] * self.fourier_phases[(ikz, j, 2)];
-                        rho = rho + configuration.particle(j).charge * phi;
+                        rho = rho + charge * phi;
                     }
                     self.rho[(ikx, iky, ikz)] = rho;
                 }
             }
         }
+        return self.kspace_energy_density(volume);
     }
 
     /// k-space contribution to the energy
     fn kspace_energy(&mut self, configuration: &Configuration) -> f64 {
         self.density_fft(configuration);
-        let mut energy = 0.0;
+        return self.kspace_energy_density(configuration.cell.volume());
+    }
 
+    /// k-space energy of the current density, in a cell with the given
+    /// `volume`
+    fn kspace_energy_density(&self, volume: f64) -> f64 {
+        let mut energy = 0.0;
         for ikx in 0..self.kmax {
             for iky in 0..self.kmax {
                 for ikz in 0..self.kmax {
@@ -380,7 +513,7 @@ impl Ewald {
                 }
             }
         }
-        energy *= 2.0 * PI / (configuration.cell.volume() * ELCC);
+        energy *= 2.0 * PI / (volume * ELCC);
         return energy;
     }
 
@@ -702,6 +835,143 @@ impl Ewald {
     }
 }
 
+/// Slab correction for systems periodic in two dimensions only [YB1999]
+///
+/// [YB1999] Yeh, I.-C. & Berkowitz, M. L. Ewald summation for systems with
+/// slab geometry. J. Chem. Phys. 111, 3155 (1999).
+impl Ewald {
+    /// Get the total dipole moment of the configuration along the z axis
+    fn slab_dipole(configuration: &Configuration) -> f64 {
+        configuration.particles().map(|particle| particle.charge * particle.position[2]).sum()
+    }
+
+    /// Get the energy of the slab correction for a dipole moment `dipole`
+    fn slab_energy_dipole(configuration: &Configuration, dipole: f64) -> f64 {
+        2.0 * PI * dipole * dipole / (ELCC * configuration.cell.volume())
+    }
+
+    /// Slab correction contribution to the energy
+    fn slab_energy(&self, configuration: &Configuration) -> f64 {
+        if configuration.cell.is_periodic_z() {
+            return 0.0;
+        }
+        let dipole = Ewald::slab_dipole(configuration);
+        return Ewald::slab_energy_dipole(configuration, dipole);
+    }
+
+    /// Slab correction contribution to the forces
+    fn slab_forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        if configuration.cell.is_periodic_z() {
+            return;
+        }
+        let dipole = Ewald::slab_dipole(configuration);
+        let factor = 4.0 * PI * dipole / (ELCC * configuration.cell.volume());
+        for (i, particle) in configuration.particles().enumerate() {
+            forces[i][2] -= factor * particle.charge;
+        }
+    }
+
+    /// Slab correction contribution to the virial
+    fn slab_virial(&self, configuration: &Configuration) -> Matrix3 {
+        let energy = self.slab_energy(configuration);
+        let mut virial = Matrix3::zero();
+        virial[0][0] = energy;
+        virial[1][1] = energy;
+        virial[2][2] = -energy;
+        return virial;
+    }
+
+    fn slab_move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        if configuration.cell.is_periodic_z() {
+            return 0.0;
+        }
+        let old_dipole = Ewald::slab_dipole(configuration);
+        let mut new_dipole = old_dipole;
+        for (idx, &i) in idxes.iter().enumerate() {
+            let particle = configuration.particle(i);
+            new_dipole += particle.charge * (newpos[idx][2] - particle.position[2]);
+        }
+        let e_old = Ewald::slab_energy_dipole(configuration, old_dipole);
+        let e_new = Ewald::slab_energy_dipole(configuration, new_dipole);
+        return e_new - e_old;
+    }
+}
+
+/// Interaction energy between groups of particles
+impl Ewald {
+    /// Get the interaction energy between the particles in the `first` and
+    /// `second` groups.
+    ///
+    /// The real space, molecular correction and slab correction parts are
+    /// pairwise decomposable, and are directly computed for each pair. The
+    /// k-space part is recovered from k-space energies computed with only the
+    /// charges of some groups. It includes the interactions with all the
+    /// periodic images of the particles, but not the self-interaction terms.
+    fn group_energy(&mut self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        self.precompute(configuration);
+
+        let masks = GroupMasks::new(configuration.size(), first, second);
+        let natoms = configuration.size();
+        let mut energy = 0.0;
+        for i in 0..natoms {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 || !masks.in_any(i) {continue}
+            for j in i+1..natoms {
+                if !masks.contains(i, j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let info = self.restriction.information(configuration.bond_distance(i, j));
+                let r = configuration.distance(i, j);
+                if info.excluded {
+                    energy += self.molcorrect_energy_pair(info, qi, qj, r);
+                } else {
+                    energy += self.real_space_energy_pair(info, qi, qj, r);
+                }
+
+                if !configuration.cell.is_periodic_z() {
+                    let zi = configuration.particle(i).position[2];
+                    let zj = configuration.particle(j).position[2];
+                    energy += 4.0 * PI * qi * zi * qj * zj / (ELCC * configuration.cell.volume());
+                }
+            }
+        }
+
+        // The k-space energy of a set of charges contains all the pair
+        // interactions in this set, and the self-interaction of all the
+        // charges. We remove the pairs inside only one of the groups, and the
+        // self-interactions of the particles in both groups. The Fourier
+        // phases are shared by all these energies, and only the charges
+        // change.
+        self.compute_fourier_phases(configuration);
+        let volume = configuration.cell.volume();
+        let mut charges = vec![0.0; natoms];
+        let mask_charges = |charges: &mut [f64], charged: &Fn(usize) -> bool| {
+            for (i, charge) in charges.iter_mut().enumerate() {
+                *charge = if charged(i) {configuration.particle(i).charge} else {0.0};
+            }
+        };
+
+        mask_charges(&mut charges, &|i| masks.in_any(i));
+        energy += self.kspace_energy_charges(&charges, volume);
+        mask_charges(&mut charges, &|i| masks.in_first(i) && !masks.in_second(i));
+        energy -= self.kspace_energy_charges(&charges, volume);
+        mask_charges(&mut charges, &|i| masks.in_second(i) && !masks.in_first(i));
+        energy -= self.kspace_energy_charges(&charges, volume);
+
+        let mut q2 = 0.0;
+        for i in 0..natoms {
+            if masks.in_first(i) && masks.in_second(i) {
+                q2 += configuration.particle(i).charge * configuration.particle(i).charge;
+            }
+        }
+        let self_factor = self.expfactors.iter().sum::<f64>() * 2.0 * PI / (volume * ELCC);
+        energy -= self_factor * q2;
+
+        return energy;
+    }
+}
+
 /// Thread-sade wrapper around Ewald implementing `CoulombicPotential`.
 ///
 /// This wrapper allow to share a Ewald solver between threads (make it `Send
@@ -744,39 +1014,46 @@ impl Clone for SharedEwald {
 }
 
 impl GlobalPotential for SharedEwald {
+    fn name(&self) -> &str {
+        "Ewald"
+    }
+
     fn cutoff(&self) -> Option<f64> {
         Some(self.read().rc)
     }
 
     fn energy(&self, configuration: &Configuration) -> f64 {
         let mut ewald = self.write();
-        ewald.precompute(&configuration.cell);
+        ewald.precompute(configuration);
         let real = ewald.real_space_energy(configuration);
         let self_e = ewald.self_energy(configuration);
         let kspace = ewald.kspace_energy(configuration);
         let molecular = ewald.molcorrect_energy(configuration);
-        return real + self_e + kspace + molecular;
+        let slab = ewald.slab_energy(configuration);
+        return real + self_e + kspace + molecular + slab;
     }
 
     fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D])  {
         assert_eq!(forces.len(), configuration.size());
         let mut ewald = self.write();
-        ewald.precompute(&configuration.cell);
+        ewald.precompute(configuration);
 
         ewald.real_space_forces(configuration, forces);
         /* No self force */
         ewald.kspace_forces(configuration, forces);
         ewald.molcorrect_forces(configuration, forces);
+        ewald.slab_forces(configuration, forces);
     }
 
     fn virial(&self, configuration: &Configuration) -> Matrix3 {
         let mut ewald = self.write();
-        ewald.precompute(&configuration.cell);
+        ewald.precompute(configuration);
         let real = ewald.real_space_virial(configuration);
         /* No self virial */
         let kspace = ewald.kspace_virial(configuration);
         let molecular = ewald.molcorrect_virial(configuration);
-        return real + kspace + molecular;
+        let slab = ewald.slab_virial(configuration);
+        return real + kspace + molecular + slab;
     }
 }
 
@@ -784,17 +1061,25 @@ impl CoulombicPotential for SharedEwald {
     fn set_restriction(&mut self, restriction: PairRestriction) {
         self.write().restriction = restriction;
     }
+
+    fn group_energy(&self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        // Work on a copy of the solver to keep the cached charge density used
+        // by Monte Carlo simulations untouched.
+        let mut ewald = self.read().clone();
+        return ewald.group_energy(configuration, first, second);
+    }
 }
 
 impl GlobalCache for SharedEwald {
     fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
         let mut ewald = self.write();
-        ewald.precompute(&configuration.cell);
+        ewald.precompute(configuration);
         let real = ewald.real_space_move_particles_cost(configuration, idxes, newpos);
         /* No self cost */
         let kspace = ewald.kspace_move_particles_cost(configuration, idxes, newpos);
         let molecular = ewald.molcorrect_move_particles_cost(configuration, idxes, newpos);
-        return real + kspace + molecular;
+        let slab = ewald.slab_move_particles_cost(configuration, idxes, newpos);
+        return real + kspace + molecular + slab;
     }
 
     fn update(&self) {
@@ -878,6 +1163,73 @@ mod tests {
         }
     }
 
+    mod accuracy {
+        use super::*;
+        use energy::GlobalPotential;
+        use sys::UnitCell;
+
+        #[test]
+        #[should_panic]
+        fn negative_accuracy() {
+            let _ = Ewald::with_accuracy(8.0, -1e-5);
+        }
+
+        #[test]
+        fn parameters() {
+            let system = nacl_pair();
+            let mut ewald = Ewald::with_accuracy(8.0, 1e-5);
+            ewald.select_parameters(&system);
+            let kmax = ewald.kmax;
+            assert_eq!(kmax, 6);
+            assert_relative_eq!(ewald.alpha, 0.3311751579281546, epsilon=1e-12);
+
+            // Better accuracy means more k-space vectors
+            let mut ewald = Ewald::with_accuracy(8.0, 1e-8);
+            ewald.select_parameters(&system);
+            assert!(ewald.kmax > kmax);
+
+            // Setting alpha only selects kmax
+            let mut ewald = Ewald::with_accuracy(8.0, 1e-5);
+            ewald.set_alpha(0.3);
+            ewald.select_parameters(&system);
+            assert_eq!(ewald.kmax, 6);
+            assert_eq!(ewald.alpha, 0.3);
+        }
+
+        #[test]
+        fn selected_once() {
+            let mut system = nacl_pair();
+            let mut ewald = Ewald::with_accuracy(8.0, 1e-5);
+            // No parameters are selected without charges
+            system.particle_mut(0).charge = 0.0;
+            system.particle_mut(1).charge = 0.0;
+            ewald.select_parameters(&system);
+            assert_eq!(ewald.kmax, 1);
+
+            system.particle_mut(0).charge = -1.0;
+            system.particle_mut(1).charge = 1.0;
+            ewald.select_parameters(&system);
+            let (alpha, kmax) = (ewald.alpha, ewald.kmax);
+            assert_eq!(kmax, 6);
+
+            // Changing the cell does not change the parameters
+            system.cell = UnitCell::cubic(35.0);
+            ewald.select_parameters(&system);
+            assert_eq!(ewald.kmax, kmax);
+            assert_eq!(ewald.alpha, alpha);
+        }
+
+        #[test]
+        fn energy() {
+            let system = nacl_pair();
+            let ewald = SharedEwald::new(Ewald::with_accuracy(8.0, 1e-6));
+
+            let energy = ewald.energy(&system);
+            let energy_brute_force = -0.09262397663346732;
+            assert_ulps_eq!(energy, energy_brute_force, epsilon=1e-4);
+        }
+    }
+
     mod pairs {
         use super::*;
         use energy::GlobalPotential;
@@ -1059,6 +1411,166 @@ mod tests {
         }
     }
 
+    mod slab {
+        use super::*;
+        use sys::UnitCell;
+        use types::{Vector3D, Zero};
+        use energy::{GlobalPotential, GlobalCache};
+
+        fn slab_pair() -> System {
+            let mut system = nacl_pair();
+            system.cell = UnitCell::slab(20.0, 20.0, 30.0);
+            system.particle_mut(1).position = Vector3D::new(0.5, 0.3, 1.5);
+            return system;
+        }
+
+        #[test]
+        fn energy() {
+            let system = slab_pair();
+            let ewald = Ewald::new(8.0, 10);
+            let dipole = system.particle(0).charge * system.particle(0).position[2]
+                       + system.particle(1).charge * system.particle(1).position[2];
+            let expected = 2.0 * PI * dipole * dipole / (ELCC * 20.0 * 20.0 * 30.0);
+            assert_ulps_eq!(ewald.slab_energy(&system), expected);
+
+            // Only the slab correction differs from a periodic cell
+            let mut periodic = system.clone();
+            periodic.cell = UnitCell::ortho(20.0, 20.0, 30.0);
+            assert_eq!(ewald.slab_energy(&periodic), 0.0);
+
+            let ewald = SharedEwald::new(Ewald::new(8.0, 10));
+            let delta = ewald.energy(&system) - ewald.energy(&periodic);
+            assert_relative_eq!(delta, expected, epsilon = 1e-12);
+        }
+
+        #[test]
+        fn forces() {
+            let mut system = slab_pair();
+            let ewald = Ewald::new(8.0, 10);
+
+            let mut forces = vec![Vector3D::zero(); 2];
+            ewald.slab_forces(&system, &mut forces);
+            assert_eq!(forces[0][0], 0.0);
+            assert_eq!(forces[0][1], 0.0);
+
+            let eps = 1e-6;
+            let energy = ewald.slab_energy(&system);
+            system.particle_mut(0).position[2] += eps;
+            let finite = -(ewald.slab_energy(&system) - energy) / eps;
+            assert_relative_eq!(forces[0][2], finite, max_relative = 1e-4);
+        }
+
+        #[test]
+        fn virial() {
+            let system = slab_pair();
+            let ewald = Ewald::new(8.0, 10);
+            let virial = ewald.slab_virial(&system);
+
+            // The virial is minus the derivative of the energy with respect
+            // to a scaling of the cell and positions along each axis
+            let eps = 1e-6;
+            for axis in 0..3 {
+                let mut scaled = system.clone();
+                let mut scaling = Matrix3::zero();
+                for k in 0..3 {
+                    scaling[k][k] = 1.0;
+                }
+                scaling[axis][axis] = 1.0 + eps;
+                scaled.cell = scaled.cell.scale(scaling);
+                for particle in scaled.particles_mut() {
+                    particle.position[axis] *= 1.0 + eps;
+                }
+                let finite = -(ewald.slab_energy(&scaled) - ewald.slab_energy(&system)) / eps;
+                assert_relative_eq!(virial[axis][axis], finite, max_relative = 1e-4);
+            }
+        }
+
+        #[test]
+        fn move_particles_cost() {
+            let mut system = slab_pair();
+            let ewald = SharedEwald::new(Ewald::new(8.0, 10));
+            let ewald_check = ewald.clone();
+
+            let old_e = ewald_check.read().slab_energy(&system);
+            let idxes = &[1];
+            let newpos = &[Vector3D::new(0.5, 0.3, 3.5)];
+            let cost = ewald.read().slab_move_particles_cost(&system, idxes, newpos);
+
+            system.particle_mut(1).position = newpos[0];
+            let new_e = ewald_check.read().slab_energy(&system);
+            assert_ulps_eq!(cost, new_e - old_e);
+
+            let mut system = slab_pair();
+            let old_e = ewald_check.energy(&system);
+            let cost = ewald.move_particles_cost(&system, idxes, newpos);
+            system.particle_mut(1).position = newpos[0];
+            let new_e = ewald_check.energy(&system);
+            assert_relative_eq!(cost, new_e - old_e, epsilon = 1e-12);
+        }
+    }
+
+    mod group {
+        use super::*;
+        use sys::UnitCell;
+        use types::Vector3D;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential};
+
+        /// Energy of the particle `i` alone in the configuration, from its
+        /// self-interaction with all the periodic images.
+        fn single_energy(ewald: &SharedEwald, system: &System, i: usize) -> f64 {
+            let mut single = Configuration::clone(system);
+            for (j, particle) in single.particles_mut().enumerate() {
+                if i != j {
+                    particle.charge = 0.0;
+                }
+            }
+            ewald.energy(&single)
+        }
+
+        #[test]
+        fn pair() {
+            let system = nacl_pair();
+            let ewald = SharedEwald::new(Ewald::new(8.0, 10));
+
+            let total = ewald.energy(&system);
+            let group = ewald.group_energy(&system, &[0], &[1]);
+            let singles = single_energy(&ewald, &system, 0) + single_energy(&ewald, &system, 1);
+            assert_relative_eq!(total, group + singles, epsilon = 1e-12);
+            assert_ulps_eq!(group, ewald.group_energy(&system, &[1], &[0]));
+            assert_eq!(ewald.group_energy(&system, &[0], &[0]), 0.0);
+        }
+
+        #[test]
+        fn molecule() {
+            let system = water();
+            let mut ewald = SharedEwald::new(Ewald::new(8.0, 10));
+            ewald.set_restriction(PairRestriction::InterMolecular);
+
+            let total = ewald.energy(&system);
+            let group = ewald.group_energy(&system, &[0, 1, 2], &[0, 1, 2]);
+            let singles = (0..3).map(|i| single_energy(&ewald, &system, i)).sum::<f64>();
+            assert_relative_eq!(total, group + singles, epsilon = 1e-12);
+
+            // Overlapping groups
+            let overlap = ewald.group_energy(&system, &[0, 1], &[1, 2]);
+            let disjoint = ewald.group_energy(&system, &[0], &[1, 2]) + ewald.group_energy(&system, &[1], &[2]);
+            assert_relative_eq!(overlap, disjoint, epsilon = 1e-12);
+        }
+
+        #[test]
+        fn slab() {
+            let mut system = nacl_pair();
+            system.cell = UnitCell::slab(20.0, 20.0, 30.0);
+            system.particle_mut(1).position = Vector3D::new(0.5, 0.3, 1.5);
+            let ewald = SharedEwald::new(Ewald::new(8.0, 10));
+
+            let total = ewald.energy(&system);
+            let group = ewald.group_energy(&system, &[0], &[1]);
+            let singles = single_energy(&ewald, &system, 0) + single_energy(&ewald, &system, 1);
+            assert_relative_eq!(total, group + singles, epsilon = 1e-12);
+        }
+    }
+
     mod cache {
         use super::*;
         use sys::System;
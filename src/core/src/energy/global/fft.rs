@@ -0,0 +1,236 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Fast Fourier transforms of complex data, for mesh based electrostatic
+//! solvers.
+use std::f64::consts::PI;
+
+use types::{Array3, Complex, Zero};
+
+/// Pre-computed data for fast Fourier transforms of a given size.
+///
+/// This uses a mixed-radix Cooley-Tukey algorithm, which works for any size,
+/// but is faster when the size only contains small prime factors.
+#[derive(Clone, Debug)]
+pub struct FFT {
+    /// Size of the transform
+    size: usize,
+    /// Twiddle factors `exp(-2 i π k / size)` for k in `0..size`
+    twiddles: Vec<Complex>,
+}
+
+impl FFT {
+    /// Create a new `FFT` for data of the given `size`
+    pub fn new(size: usize) -> FFT {
+        assert!(size > 0, "FFT size must be positive");
+        let twiddles = (0..size).map(|k| {
+            Complex::polar(1.0, -2.0 * PI * k as f64 / size as f64)
+        }).collect();
+
+        FFT {
+            size: size,
+            twiddles: twiddles,
+        }
+    }
+
+    /// Get the size of this transform
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Compute the forward Fourier transform of `data` in place:
+    /// `X[k] = \sum_j x[j] exp(-2 i π j k / size)`.
+    pub fn forward(&self, data: &mut [Complex]) {
+        self.transform(data, false);
+    }
+
+    /// Compute the backward (non-normalized) Fourier transform of `data` in
+    /// place: `X[k] = \sum_j x[j] exp(2 i π j k / size)`.
+    pub fn backward(&self, data: &mut [Complex]) {
+        self.transform(data, true);
+    }
+
+    fn transform(&self, data: &mut [Complex], backward: bool) {
+        assert_eq!(data.len(), self.size, "wrong data size for this FFT");
+        let input = data.to_vec();
+        let mut scratch = vec![Complex::zero(); self.size];
+        self.recursive(&input, 0, 1, data, &mut scratch, backward);
+    }
+
+    /// Compute the transform of the `output.len()` values of `input` starting
+    /// at `offset` and separated by `stride`, and put the result in `output`.
+    fn recursive(&self, input: &[Complex], offset: usize, stride: usize, output: &mut [Complex], scratch: &mut [Complex], backward: bool) {
+        let n = output.len();
+        if n == 1 {
+            output[0] = input[offset];
+            return;
+        }
+
+        let p = smallest_factor(n);
+        let m = n / p;
+        // Transforms of size m for the p interleaved sub-sequences
+        for r in 0..p {
+            self.recursive(
+                input, offset + r * stride, stride * p,
+                &mut output[r * m..(r + 1) * m], &mut scratch[r * m..(r + 1) * m],
+                backward
+            );
+        }
+
+        // Combine the sub-transforms: X[k] = \sum_r w^(r k) Y_r[k mod m]
+        let step = self.size / n;
+        for k in 0..n {
+            let mut sum = output[k % m];
+            for r in 1..p {
+                let twiddle = self.twiddles[((r * k) % n) * step];
+                let twiddle = if backward {twiddle.conj()} else {twiddle};
+                sum = sum + twiddle * output[r * m + k % m];
+            }
+            scratch[k] = sum;
+        }
+        output.copy_from_slice(&scratch[..n]);
+    }
+}
+
+/// Get the smallest prime factor of `n`
+fn smallest_factor(n: usize) -> usize {
+    let mut factor = 2;
+    while factor * factor <= n {
+        if n % factor == 0 {
+            return factor;
+        }
+        factor += 1;
+    }
+    return n;
+}
+
+/// Compute the three dimensional Fourier transform of `data` in place, using
+/// the `ffts` transforms along each dimension. If `backward` is `true`, the
+/// backward transform is computed instead of the forward transform.
+pub fn fft3d(data: &mut Array3<Complex>, ffts: &[FFT], backward: bool) {
+    let (n0, n1, n2) = data.dim();
+    assert_eq!((n0, n1, n2), (ffts[0].size(), ffts[1].size(), ffts[2].size()));
+    let transform = |fft: &FFT, buffer: &mut [Complex]| {
+        if backward {
+            fft.backward(buffer);
+        } else {
+            fft.forward(buffer);
+        }
+    };
+
+    let mut buffer = vec![Complex::zero(); n0];
+    for j in 0..n1 {
+        for k in 0..n2 {
+            for i in 0..n0 {
+                buffer[i] = data[(i, j, k)];
+            }
+            transform(&ffts[0], &mut buffer);
+            for i in 0..n0 {
+                data[(i, j, k)] = buffer[i];
+            }
+        }
+    }
+
+    let mut buffer = vec![Complex::zero(); n1];
+    for i in 0..n0 {
+        for k in 0..n2 {
+            for j in 0..n1 {
+                buffer[j] = data[(i, j, k)];
+            }
+            transform(&ffts[1], &mut buffer);
+            for j in 0..n1 {
+                data[(i, j, k)] = buffer[j];
+            }
+        }
+    }
+
+    let mut buffer = vec![Complex::zero(); n2];
+    for i in 0..n0 {
+        for j in 0..n1 {
+            for k in 0..n2 {
+                buffer[k] = data[(i, j, k)];
+            }
+            transform(&ffts[2], &mut buffer);
+            for k in 0..n2 {
+                data[(i, j, k)] = buffer[k];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use types::{Complex, Zero};
+
+    fn naive_dft(data: &[Complex], sign: f64) -> Vec<Complex> {
+        let n = data.len();
+        (0..n).map(|k| {
+            let mut sum = Complex::zero();
+            for j in 0..n {
+                let phase = sign * 2.0 * PI * (j * k) as f64 / n as f64;
+                sum = sum + Complex::polar(1.0, phase) * data[j];
+            }
+            sum
+        }).collect()
+    }
+
+    #[test]
+    fn factors() {
+        assert_eq!(smallest_factor(2), 2);
+        assert_eq!(smallest_factor(9), 3);
+        assert_eq!(smallest_factor(35), 5);
+        assert_eq!(smallest_factor(13), 13);
+    }
+
+    #[test]
+    fn transforms() {
+        for &size in &[1, 2, 7, 8, 12, 30, 49] {
+            let data = (0..size).map(|i| {
+                Complex::cartesian(f64::sin(i as f64), 0.3 * i as f64)
+            }).collect::<Vec<_>>();
+            let fft = FFT::new(size);
+
+            let mut forward = data.clone();
+            fft.forward(&mut forward);
+            for (value, expected) in forward.iter().zip(&naive_dft(&data, -1.0)) {
+                assert_relative_eq!(value.real(), expected.real(), epsilon=1e-10);
+                assert_relative_eq!(value.imag(), expected.imag(), epsilon=1e-10);
+            }
+
+            let mut backward = data.clone();
+            fft.backward(&mut backward);
+            for (value, expected) in backward.iter().zip(&naive_dft(&data, 1.0)) {
+                assert_relative_eq!(value.real(), expected.real(), epsilon=1e-10);
+                assert_relative_eq!(value.imag(), expected.imag(), epsilon=1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_3d() {
+        let mut data = Array3::zeros((4, 5, 6));
+        for i in 0..4 {
+            for j in 0..5 {
+                for k in 0..6 {
+                    data[(i, j, k)] = Complex::cartesian((i + 2 * j) as f64, k as f64 - 1.0);
+                }
+            }
+        }
+        let initial = data.clone();
+
+        let ffts = [FFT::new(4), FFT::new(5), FFT::new(6)];
+        fft3d(&mut data, &ffts, false);
+        fft3d(&mut data, &ffts, true);
+        for i in 0..4 {
+            for j in 0..5 {
+                for k in 0..6 {
+                    let value = data[(i, j, k)] / 120.0;
+                    assert_relative_eq!(value.real(), initial[(i, j, k)].real(), epsilon=1e-10);
+                    assert_relative_eq!(value.imag(), initial[(i, j, k)].imag(), epsilon=1e-10);
+                }
+            }
+        }
+    }
+}
@@ -87,6 +87,11 @@ use energy::PairRestriction;
 /// assert_eq!(system.virial(), Matrix3::zero());
 /// ```
 pub trait GlobalPotential: GlobalCache + BoxCloneGlobal + Send + Sync {
+    /// Get a short name for this potential, without whitespace, used to label
+    /// its energy in outputs. The default name is `"Global"`.
+    fn name(&self) -> &str {
+        "Global"
+    }
     /// Return the cut off radius.
     fn cutoff(&self) -> Option<f64>;
     /// Compute the energetic contribution of this potential
@@ -190,6 +195,40 @@ pub trait CoulombicPotential : GlobalPotential + BoxCloneCoulombic {
     /// future call to `GlobalPotential::energy`, `GlobalPotential::force` or
     /// `GlobalPotential::virial` should use this restriction.
     fn set_restriction(&mut self, restriction: PairRestriction);
+
+    /// Compute the electrostatic interaction energy between the particles in
+    /// the `first` and `second` groups of the `configuration`. The groups are
+    /// given as lists of particles indexes, and each pair of different
+    /// particles is counted only once, even if the groups overlap.
+    ///
+    /// The default implementation computes this energy from the energy of
+    /// copies of the configuration where only some of the particles keep
+    /// their charge. Implementations should override it with a more
+    /// efficient computation when possible.
+    fn group_energy(&self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        // Energy of the configuration with only the particles selected by
+        // `charged` keeping their charge
+        let energy_with = |charged: &Fn(usize) -> bool| {
+            let mut configuration = configuration.clone();
+            for (i, particle) in configuration.particles_mut().enumerate() {
+                if !charged(i) {
+                    particle.charge = 0.0;
+                }
+            }
+            self.energy(&configuration)
+        };
+
+        let in_first = |i| first.contains(&i);
+        let in_second = |i| second.contains(&i);
+        let mut energy = energy_with(&|i| in_first(i) || in_second(i));
+        energy -= energy_with(&|i| in_first(i) && !in_second(i));
+        energy -= energy_with(&|i| in_second(i) && !in_first(i));
+        // Remove the self energy of the particles in both groups
+        for i in (0..configuration.size()).filter(|&i| in_first(i) && in_second(i)) {
+            energy -= energy_with(&|j: usize| j == i);
+        }
+        return energy;
+    }
 }
 
 impl_box_clone!(CoulombicPotential, BoxCloneCoulombic, box_clone_coulombic);
@@ -197,5 +236,97 @@ impl_box_clone!(CoulombicPotential, BoxCloneCoulombic, box_clone_coulombic);
 mod wolf;
 pub use self::wolf::Wolf;
 
+mod reaction_field;
+pub use self::reaction_field::ReactionField;
+
+mod dsf;
+pub use self::dsf::DampedShiftedForce;
+
 mod ewald;
 pub use self::ewald::{Ewald, SharedEwald};
+
+mod fft;
+mod spme;
+pub use self::spme::{SPME, SharedSPME};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::system_from_xyz;
+
+    /// Wolf summation using the default implementation of `group_energy`
+    #[derive(Clone)]
+    struct DefaultGroupEnergy(Wolf);
+
+    impl GlobalCache for DefaultGroupEnergy {
+        fn move_particles_cost(&self, _: &Configuration, _: &[usize], _: &[Vector3D]) -> f64 {
+            unimplemented!()
+        }
+
+        fn update(&self) {
+            unimplemented!()
+        }
+    }
+
+    impl GlobalPotential for DefaultGroupEnergy {
+        fn cutoff(&self) -> Option<f64> {
+            self.0.cutoff()
+        }
+
+        fn energy(&self, configuration: &Configuration) -> f64 {
+            self.0.energy(configuration)
+        }
+
+        fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+            self.0.forces(configuration, forces)
+        }
+
+        fn virial(&self, configuration: &Configuration) -> Matrix3 {
+            self.0.virial(configuration)
+        }
+    }
+
+    impl CoulombicPotential for DefaultGroupEnergy {
+        fn set_restriction(&mut self, restriction: PairRestriction) {
+            self.0.set_restriction(restriction)
+        }
+    }
+
+    #[test]
+    fn default_group_energy() {
+        let mut system = system_from_xyz("6
+        bonds cell: 20.0
+        O  0.0  0.0  0.0
+        H -0.7 -0.7  0.3
+        H  0.3 -0.3 -0.8
+        O  2.0  2.0  0.0
+        H  1.3  1.3  0.3
+        H  2.3  1.7 -0.8
+        ");
+        for particle in system.particles_mut() {
+            if particle.name() == "O" {
+                particle.charge = -0.8476;
+            } else if particle.name() == "H" {
+                particle.charge = 0.4238;
+            }
+        }
+
+        let mut wolf = Wolf::new(8.0);
+        wolf.set_restriction(PairRestriction::InterMolecular);
+        let default = DefaultGroupEnergy(wolf.clone());
+
+        let groups: &[(&[usize], &[usize])] = &[
+            (&[0, 1, 2], &[3, 4, 5]),
+            (&[0], &[1, 3, 4]),
+            // Overlapping groups
+            (&[0, 1, 3], &[1, 3, 4]),
+        ];
+        for &(first, second) in groups {
+            assert_relative_eq!(
+                default.group_energy(&system, first, second),
+                wolf.group_energy(&system, first, second),
+                epsilon = 1e-12
+            );
+        }
+    }
+}
@@ -0,0 +1,415 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use sys::{Configuration, NeighborList, GroupMasks};
+use types::{Matrix3, Vector3D, Zero};
+use consts::ELCC;
+use energy::{PairRestriction, RestrictionInfo};
+
+use super::{GlobalPotential, CoulombicPotential, GlobalCache};
+
+/// Reaction field treatment of coulombic interactions.
+///
+/// In the reaction field method [Tironi1995], all the charges further than
+/// the cutoff distance are replaced by a continuum with a given dielectric
+/// constant `ε_rf`. The resulting pair potential is
+///
+/// ```text
+/// V(r) = qi qj (1 / r + k_rf r^2 - c_rf) / (4 π ε_0)
+/// ```
+///
+/// with `k_rf = (ε_rf - 1) / ((2 ε_rf + 1) rc^3)` and `c_rf = 1 / rc + k_rf
+/// rc^2`, so that the potential goes to zero at the cutoff. For conducting
+/// boundary conditions (infinite dielectric constant), `k_rf = 1 / (2 rc^3)`.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::ReactionField;
+///
+/// // Water-like dielectric continuum after 12 A
+/// let reaction_field = ReactionField::new(12.0, 78.5);
+///
+/// use lumol::sys::System;
+/// use lumol::sys::Particle;
+/// use lumol::sys::UnitCell;
+/// use lumol::types::Vector3D;
+///
+/// // Setup a system containing a NaCl pair
+/// let mut system = System::with_cell(UnitCell::cubic(30.0));
+///
+/// let mut na = Particle::new("Na");
+/// na.charge = 1.0;
+/// na.position = Vector3D::new(0.0, 0.0, 0.0);
+///
+/// let mut cl = Particle::new("Cl");
+/// cl.charge = -1.0;
+/// cl.position = Vector3D::new(2.0, 0.0, 0.0);
+///
+/// system.add_particle(na);
+/// system.add_particle(cl);
+///
+/// // Use the reaction field for electrostatic interactions
+/// system.set_coulomb_potential(Box::new(reaction_field));
+///
+/// assert_eq!(system.potential_energy(), -0.05236844268598852);
+/// ```
+///
+/// [Tironi1995]: Tironi, I. G. et al. J. Chem. Phys. 102, 5451 (1995).
+#[derive(Clone, Debug)]
+pub struct ReactionField {
+    /// Cutoff radius
+    cutoff: f64,
+    /// Dielectric constant of the continuum
+    dielectric: f64,
+    /// Reaction field constant `k_rf`
+    k_rf: f64,
+    /// Energy shift constant `c_rf`
+    c_rf: f64,
+    /// Restriction scheme
+    restriction: PairRestriction,
+    /// Neighbor list for the pairs computations
+    neighbors: NeighborList,
+}
+
+impl ReactionField {
+    /// Create a new reaction field, using a cutoff of `cutoff` and a continuum
+    /// with the given `dielectric` constant after the cutoff. The dielectric
+    /// constant can be `f64::INFINITY`, to use conducting boundary conditions.
+    pub fn new(cutoff: f64, dielectric: f64) -> ReactionField {
+        assert!(cutoff > 0.0, "Got a negative cutoff in reaction field");
+        assert!(dielectric >= 1.0, "The dielectric constant of reaction field must be bigger than 1");
+
+        let k_rf = if dielectric.is_infinite() {
+            1.0 / (2.0 * cutoff * cutoff * cutoff)
+        } else {
+            (dielectric - 1.0) / ((2.0 * dielectric + 1.0) * cutoff * cutoff * cutoff)
+        };
+        let c_rf = 1.0 / cutoff + k_rf * cutoff * cutoff;
+
+        ReactionField {
+            cutoff: cutoff,
+            dielectric: dielectric,
+            k_rf: k_rf,
+            c_rf: c_rf,
+            restriction: PairRestriction::None,
+            neighbors: NeighborList::new(),
+        }
+    }
+
+    /// Get the dielectric constant of the continuum
+    pub fn dielectric(&self) -> f64 {
+        self.dielectric
+    }
+
+    /// Compute the energy for the pair of particles with charge `qi` and `qj`,
+    /// at the distance of `r`, with restriction information in `info`.
+    #[inline]
+    fn energy_pair(&self, info: RestrictionInfo, qi: f64, qj: f64, r: f64) -> f64 {
+        if r > self.cutoff || info.excluded {
+            return 0.0;
+        }
+        info.scaling * qi * qj * (1.0 / r + self.k_rf * r * r - self.c_rf) / ELCC
+    }
+
+    /// Compute the force for the pair of particles with charge `qi` and `qj`,
+    /// at the distance of `rij`, with restriction information in `info`.
+    #[inline]
+    fn force_pair(&self, info: RestrictionInfo, qi: f64, qj: f64, rij: &Vector3D) -> Vector3D {
+        let r = rij.norm();
+        if r > self.cutoff || info.excluded {
+            return Vector3D::zero();
+        }
+        let factor = 1.0 / (r * r * r) - 2.0 * self.k_rf;
+        return info.scaling * qi * qj * factor * rij / ELCC;
+    }
+}
+
+impl GlobalCache for ReactionField {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut e_old = 0.0;
+        let mut e_new = 0.0;
+
+        // Iterate over all interactions between a moved particle and a
+        // particle not moved
+        for (idx, &i) in idxes.iter().enumerate() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+
+            let old_position = configuration.particle(i).position;
+            for j in neighbors.around(&old_position) {
+                if idxes.contains(&j) {continue;}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r_old = configuration.distance(i, j);
+                e_old += self.energy_pair(info, qi, qj, r_old);
+            }
+
+            for j in neighbors.around(&newpos[idx]) {
+                if idxes.contains(&j) {continue;}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r_new = configuration.cell.distance(&newpos[idx], &configuration.particle(j).position);
+                e_new += self.energy_pair(info, qi, qj, r_new);
+            }
+        }
+
+        // Iterate over all interactions between two moved particles
+        for (idx, &i) in idxes.iter().enumerate() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for (jdx, &j) in idxes.iter().enumerate().skip(idx + 1) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let r_old = configuration.distance(i, j);
+                let r_new = configuration.cell.distance(&newpos[idx], &newpos[jdx]);
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                e_old += self.energy_pair(info, qi, qj, r_old);
+                e_new += self.energy_pair(info, qi, qj, r_new);
+            }
+        }
+
+        return e_new - e_old;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+impl GlobalPotential for ReactionField {
+    fn name(&self) -> &str {
+        "ReactionField"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.cutoff)
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut energy = 0.0;
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let r = configuration.distance(i, j);
+                energy += self.energy_pair(info, qi, qj, r);
+            }
+        }
+        return energy;
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let rij = configuration.nearest_image(i, j);
+                let force = self.force_pair(info, qi, qj, &rij);
+                forces[i] += force;
+                forces[j] -= force;
+            }
+        }
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut virial = Matrix3::zero();
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue;}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue;}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let rij = configuration.nearest_image(i, j);
+                let force = self.force_pair(info, qi, qj, &rij);
+                virial += force.tensorial(&rij);
+            }
+        }
+        return virial;
+    }
+}
+
+impl CoulombicPotential for ReactionField {
+    fn set_restriction(&mut self, restriction: PairRestriction) {
+        self.restriction = restriction;
+    }
+
+    fn group_energy(&self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        let masks = GroupMasks::new(configuration.size(), first, second);
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut energy = 0.0;
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 || !masks.in_any(i) {continue}
+            for &j in neighbors.of(i) {
+                if !masks.contains(i, j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                energy += self.energy_pair(info, qi, qj, configuration.distance(i, j));
+            }
+        }
+        return energy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    pub use super::*;
+    use sys::System;
+    use energy::GlobalPotential;
+    use utils::system_from_xyz;
+
+    pub fn testing_system() -> System {
+        let mut system = system_from_xyz("2
+        cell: 20.0
+        Cl 0.0 0.0 0.0
+        Na 1.5 0.0 0.0
+        ");
+        system.particle_mut(0).charge = -1.0;
+        system.particle_mut(1).charge = 1.0;
+        return system;
+    }
+
+    #[test]
+    #[should_panic]
+    fn small_dielectric() {
+        let _ = ReactionField::new(8.0, 0.5);
+    }
+
+    #[test]
+    fn energy() {
+        let system = testing_system();
+
+        // With a dielectric constant of 1, this is a shifted coulomb potential
+        let reaction_field = ReactionField::new(8.0, 1.0);
+        let expected = -(1.0 / 1.5 - 1.0 / 8.0) / ELCC;
+        assert_ulps_eq!(reaction_field.energy(&system), expected);
+
+        let reaction_field = ReactionField::new(8.0, ::std::f64::INFINITY);
+        let k_rf = 1.0 / (2.0 * 8.0 * 8.0 * 8.0);
+        let expected = -(1.0 / 1.5 + k_rf * 1.5 * 1.5 - 1.5 / 8.0) / ELCC;
+        assert_ulps_eq!(reaction_field.energy(&system), expected);
+
+        // Zero energy at the cutoff
+        let mut system = testing_system();
+        system.particle_mut(1).position[0] = 8.0;
+        let reaction_field = ReactionField::new(8.0, 78.5);
+        assert_ulps_eq!(reaction_field.energy(&system), 0.0);
+    }
+
+    #[test]
+    fn forces() {
+        let mut system = testing_system();
+        let reaction_field = ReactionField::new(8.0, 78.5);
+
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        reaction_field.forces(&system, &mut forces);
+        let norm = (forces[0] + forces[1]).norm();
+        // Total force should be null
+        assert_ulps_eq!(norm, 0.0);
+
+        // Finite difference computation of the force
+        let e = reaction_field.energy(&system);
+        let eps = 1e-9;
+        system.particle_mut(0).position[0] += eps;
+
+        let e1 = reaction_field.energy(&system);
+        assert_relative_eq!((e - e1) / eps, forces[0][0], epsilon=1e-6);
+    }
+
+    #[test]
+    fn virial() {
+        let system = testing_system();
+        let reaction_field = ReactionField::new(8.0, 78.5);
+
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        reaction_field.forces(&system, &mut forces);
+        let virial = reaction_field.virial(&system);
+        let expected = forces[0].tensorial(&Vector3D::new(-1.5, 0.0, 0.0));
+        assert_ulps_eq!(virial, expected);
+    }
+
+    mod cache {
+        use super::*;
+        use sys::System;
+        use types::Vector3D;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential, GlobalCache};
+
+        pub fn testing_system() -> System {
+            use utils::system_from_xyz;
+            let mut system = system_from_xyz("6
+            bonds cell: 20.0
+            O  0.0  0.0  0.0
+            H -0.7 -0.7  0.3
+            H  0.3 -0.3 -0.8
+            O  2.0  2.0  0.0
+            H  1.3  1.3  0.3
+            H  2.3  1.7 -0.8
+            ");
+            assert!(system.molecules().len() == 2);
+
+            for particle in system.particles_mut() {
+                if particle.name() == "O" {
+                    particle.charge = -0.8476;
+                } else if particle.name() == "H" {
+                    particle.charge = 0.4238;
+                }
+            }
+            return system;
+        }
+
+        #[test]
+        fn move_atoms() {
+            let mut system = testing_system();
+            let mut reaction_field = ReactionField::new(8.0, 78.5);
+            reaction_field.set_restriction(PairRestriction::InterMolecular);
+
+            let check = reaction_field.clone();
+
+            let old_e = check.energy(&system);
+            let idxes = &[0, 1];
+            let newpos = &[Vector3D::new(0.0, 0.0, 0.5), Vector3D::new(-0.7, 0.2, 1.5)];
+
+            let cost = reaction_field.move_particles_cost(&system, idxes, newpos);
+
+            system.particle_mut(0).position = newpos[0];
+            system.particle_mut(1).position = newpos[1];
+            let new_e = check.energy(&system);
+            assert_ulps_eq!(cost, new_e - old_e);
+        }
+    }
+}
@@ -0,0 +1,1376 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use special::Error;
+
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::f64::consts::{PI, FRAC_2_SQRT_PI};
+use std::f64;
+
+use sys::{Configuration, UnitCell, CellShape, NeighborList, GroupMasks};
+use types::{Matrix3, Vector3D, Array3, Complex, Zero, One};
+use consts::ELCC;
+use energy::{PairRestriction, RestrictionInfo};
+
+use super::{GlobalPotential, CoulombicPotential, GlobalCache};
+use super::fft::{FFT, fft3d};
+
+/// Smooth Particle Mesh Ewald summation for coulombic interactions.
+///
+/// As in the [Ewald][Ewald] summation, the coulombic potential is separated
+/// in a short-range part computed in real space and a long-range part computed
+/// in k-space. In SPME [Essmann1995], the charges are spread on a regular grid
+/// using cardinal B-splines, and the k-space sum is computed with fast Fourier
+/// transforms. This scales as `O(N log(N))` with the number of charges, instead
+/// of the `O(N kmax^3)` scaling of the Ewald summation.
+///
+/// The accuracy of the k-space part is controlled by the number of grid points
+/// along each unit cell vector, and by the order of the B-spline
+/// interpolation.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{SPME, SharedSPME};
+///
+/// let spme = SharedSPME::new(
+///     SPME::new(/* cutoff */ 4.5, /* grid */ [16, 16, 16], /* order */ 6)
+/// );
+///
+/// use lumol::sys::System;
+/// use lumol::sys::Particle;
+/// use lumol::sys::UnitCell;
+/// use lumol::types::Vector3D;
+///
+/// // Setup a system containing a NaCl pair
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+///
+/// let mut na = Particle::new("Na");
+/// na.charge = 1.0;
+/// na.position = Vector3D::new(0.0, 0.0, 0.0);
+///
+/// let mut cl = Particle::new("Cl");
+/// cl.charge = -1.0;
+/// cl.position = Vector3D::new(2.0, 0.0, 0.0);
+///
+/// system.add_particle(na);
+/// system.add_particle(cl);
+///
+/// // Use SPME summation for electrostatic interactions
+/// system.set_coulomb_potential(Box::new(spme));
+///
+/// let energy = system.potential_energy();
+/// assert!(f64::abs(energy - -0.07070136) < 1e-6);
+/// ```
+///
+/// [Ewald]: struct.Ewald.html
+/// [Essmann1995] Essmann, U. et al. J. Chem. Phys. 103, 8577 (1995).
+#[derive(Clone, Debug)]
+pub struct SPME {
+    /// Splitting parameter between k-space and real space
+    alpha: f64,
+    /// Cutoff radius in real space
+    rc: f64,
+    /// Number of grid points along each unit cell vector
+    grid: [usize; 3],
+    /// Order of the B-spline interpolation
+    order: usize,
+    /// Restriction scheme
+    restriction: PairRestriction,
+    /// Fourier transforms along each unit cell vector
+    ffts: Vec<FFT>,
+    /// Squared modulus of the B-spline Euler exponential factors `|b(m)|^2`
+    /// along each unit cell vector
+    moduli: Vec<Vec<f64>>,
+    /// Caching influence function `B(m) exp(-k^2 / (4 alpha^2)) / k^2`
+    influence: Array3<f64>,
+    /// Charges on the grid, and their Fourier transform. Cached allocation
+    charges: Array3<Complex>,
+    /// Neighbor list for the real space part
+    neighbors: NeighborList,
+    /// Guard for cache invalidation of `influence`
+    previous_cell: Option<UnitCell>,
+    /// k-space energy of the current configuration, used as the old energy
+    /// in Monte Carlo moves
+    kspace_current: Option<KSpaceEnergy>,
+    /// k-space energy of the configuration proposed in the last Monte Carlo
+    /// move, becoming the current one if the move is accepted
+    kspace_trial: Option<KSpaceEnergy>,
+}
+
+/// Cached k-space energy, together with the data used to compute it
+#[derive(Clone, Debug)]
+struct KSpaceEnergy {
+    /// Unit cell used for this energy
+    cell: UnitCell,
+    /// Charges of the particles
+    charges: Vec<f64>,
+    /// Positions of the particles
+    positions: Vec<Vector3D>,
+    /// The k-space energy
+    energy: f64,
+}
+
+impl KSpaceEnergy {
+    /// Check if this cached energy was computed for the given `cell`,
+    /// `charges` and `positions`.
+    fn is_valid(&self, cell: &UnitCell, charges: &[f64], positions: &[Vector3D]) -> bool {
+        self.cell == *cell && self.charges[..] == *charges && self.positions[..] == *positions
+    }
+}
+
+/// B-spline interpolation weights of a particle along one unit cell vector
+struct Splines {
+    /// Index of the first grid point. The weight `values[j]` is for the grid
+    /// point at `first - j` (modulo the grid size).
+    first: usize,
+    /// Weights for the grid points
+    values: Vec<f64>,
+    /// Derivatives of the weights with respect to the scaled fractional
+    /// coordinate
+    derivatives: Vec<f64>,
+}
+
+impl SPME {
+    /// Create a SPME summation using the given `cutoff` radius in real space,
+    /// `grid` points along each unit cell vector in k-space, and an
+    /// interpolation of the given `order` (4 gives a cubic interpolation).
+    pub fn new(cutoff: f64, grid: [usize; 3], order: usize) -> SPME {
+        assert!(cutoff > 0.0, "SPME cutoff must be positive");
+        assert!(order >= 3, "SPME interpolation order must be at least 3");
+        for &size in &grid {
+            assert!(size >= order, "SPME grid must contain at least as many points as the interpolation order");
+        }
+
+        let ffts = grid.iter().map(|&size| FFT::new(size)).collect();
+        let moduli = grid.iter().map(|&size| bspline_moduli(size, order)).collect();
+        let shape = (grid[0], grid[1], grid[2]);
+        SPME {
+            alpha: 3.0 * PI / (cutoff * 4.0),
+            rc: cutoff,
+            grid: grid,
+            order: order,
+            restriction: PairRestriction::None,
+            ffts: ffts,
+            moduli: moduli,
+            influence: Array3::zeros(shape),
+            charges: Array3::zeros(shape),
+            neighbors: NeighborList::new(),
+            previous_cell: None,
+            kspace_current: None,
+            kspace_trial: None,
+        }
+    }
+
+    /// Set the value of the alpha parameter for SPME computation. The default
+    /// is to use `alpha = 3 * π / (4 * rc)`.
+    pub fn set_alpha(&mut self, alpha: f64) {
+        assert!(alpha > 0.0, "SPME parameter alpha must be positive");
+        self.alpha = alpha;
+        // The influence function and the k-space energy depend on alpha
+        self.previous_cell = None;
+        self.kspace_current = None;
+        self.kspace_trial = None;
+    }
+
+    fn precompute(&mut self, cell: &UnitCell) {
+        if let Some(ref prev_cell) = self.previous_cell {
+            if cell == prev_cell {
+                // Do not recompute
+                return;
+            }
+        }
+        if cell.shape() == CellShape::Infinite {
+            fatal_error!("Can not use SPME with Infinite cell.");
+        }
+        self.previous_cell = Some(*cell);
+
+        let lenghts = cell.lengths();
+        let min_lenght = f64::min(f64::min(lenghts[0], lenghts[1]), lenghts[2]);
+        if self.rc > min_lenght / 2.0 {
+            warn!("The SPME cutoff is too high for this unit cell, energy might be wrong.");
+        }
+
+        // Precompute the B(m) exp(-k^2 / (4 a^2)) / k^2 terms, using signed
+        // indexes for the k vectors.
+        let (rec_va, rec_vb, rec_vc) = cell.reciprocal_vectors();
+        for ia in 0..self.grid[0] {
+            let ka = signed_index(ia, self.grid[0]) * rec_va;
+            for ib in 0..self.grid[1] {
+                let kb = ka + signed_index(ib, self.grid[1]) * rec_vb;
+                for ic in 0..self.grid[2] {
+                    let k = kb + signed_index(ic, self.grid[2]) * rec_vc;
+                    let k2 = k.norm2();
+                    let moduli = self.moduli[0][ia] * self.moduli[1][ib] * self.moduli[2][ic];
+                    self.influence[(ia, ib, ic)] = moduli * f64::exp(-k2 / (4.0 * self.alpha * self.alpha)) / k2;
+                }
+            }
+        }
+        self.influence[(0, 0, 0)] = 0.0;
+    }
+
+    /// Get the B-spline interpolation weights for a particle at the given
+    /// `fractional` coordinate along the unit cell vector `dim`.
+    fn splines(&self, fractional: f64, dim: usize) -> Splines {
+        let size = self.grid[dim];
+        let u = (fractional - f64::floor(fractional)) * size as f64;
+        let first = f64::floor(u);
+        let w = u - first;
+
+        // values[j] contains M_k(w + j) for the current order k, starting with
+        // M_1 and going up to M_order.
+        let mut values = vec![0.0; self.order];
+        values[0] = 1.0;
+        for k in 2..self.order {
+            bspline_step(w, k, &mut values);
+        }
+
+        // dM_n(x)/dx = M_{n-1}(x) - M_{n-1}(x - 1)
+        let mut derivatives = vec![0.0; self.order];
+        derivatives[0] = values[0];
+        for j in 1..self.order {
+            derivatives[j] = values[j] - values[j - 1];
+        }
+
+        bspline_step(w, self.order, &mut values);
+
+        Splines {
+            // `first` can be equal to `size` because of rounding errors
+            first: (first as usize) % size,
+            values: values,
+            derivatives: derivatives,
+        }
+    }
+}
+
+/// Update the B-spline `values` from `M_{k-1}(w + j)` to `M_k(w + j)`, using
+/// the recursion relation `M_k(x) = (x M_{k-1}(x) + (k - x) M_{k-1}(x - 1)) /
+/// (k - 1)`.
+fn bspline_step(w: f64, k: usize, values: &mut [f64]) {
+    let k = k as f64;
+    for j in (1..values.len()).rev() {
+        let x = w + j as f64;
+        values[j] = (x * values[j] + (k - x) * values[j - 1]) / (k - 1.0);
+    }
+    values[0] = w * values[0] / (k - 1.0);
+}
+
+/// Get the squared modulus of the B-spline Euler exponential factors
+/// `|b(m)|^2 = 1 / |\sum_k M_n(k + 1) exp(2 i π m k / size)|^2` for a grid
+/// with `size` points and interpolation of the given `order`.
+fn bspline_moduli(size: usize, order: usize) -> Vec<f64> {
+    // Values of M_n at integers
+    let mut values = vec![0.0; order];
+    values[0] = 1.0;
+    for k in 2..(order + 1) {
+        bspline_step(0.0, k, &mut values);
+    }
+
+    let mut denominators = (0..size).map(|m| {
+        let mut sum = Complex::zero();
+        for k in 0..(order - 1) {
+            let phase = 2.0 * PI * (m * k) as f64 / size as f64;
+            sum = sum + values[k + 1] * Complex::polar(1.0, phase);
+        }
+        sum.norm2()
+    }).collect::<Vec<_>>();
+
+    // For odd interpolation orders, the denominator is zero at m = size / 2.
+    // Use the average of the neighbors values instead.
+    for m in 0..size {
+        if denominators[m] < 1e-7 {
+            let previous = denominators[(m + size - 1) % size];
+            let next = denominators[(m + 1) % size];
+            denominators[m] = 0.5 * (previous + next);
+        }
+    }
+
+    return denominators.iter().map(|denominator| 1.0 / denominator).collect();
+}
+
+/// Get the signed index corresponding to the position `i` in a Fourier
+/// transform of the given `size`
+#[inline]
+fn signed_index(i: usize, size: usize) -> f64 {
+    if 2 * i <= size {
+        i as f64
+    } else {
+        i as f64 - size as f64
+    }
+}
+
+/// Real space part of the summation
+impl SPME {
+    /// Get the real-space energy for one pair at distance `r` with charges `qi`
+    /// and `qj` ; and with restriction information for this pair in `info`.
+    #[inline]
+    fn real_space_energy_pair(&self, info: RestrictionInfo, qi: f64, qj: f64, r: f64) -> f64 {
+        if r > self.rc || info.excluded {
+            return 0.0
+        }
+        return info.scaling * qi * qj * f64::erfc(self.alpha * r) / r / ELCC;
+    }
+
+    /// Get the real-space force for one pair at distance `rij` with charges
+    /// `qi` and `qj` ; and with restriction information for this pair in
+    /// `info`.
+    #[inline]
+    fn real_space_force_pair(&self, info: RestrictionInfo, qi: f64, qj: f64, rij: &Vector3D) -> Vector3D {
+        let r = rij.norm();
+        if r > self.rc || info.excluded {
+            return Vector3D::zero()
+        }
+        let mut factor = f64::erfc(self.alpha * r) / r;
+        factor += self.alpha * FRAC_2_SQRT_PI * f64::exp(-self.alpha * self.alpha * r * r);
+        factor *= info.scaling * qi * qj / (r * r) / ELCC;
+        return factor * rij;
+    }
+
+    /// Real space contribution to the energy
+    fn real_space_energy(&self, configuration: &Configuration) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.rc));
+        let mut energy = 0.0;
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let r = configuration.distance(i, j);
+                energy += self.real_space_energy_pair(info, qi, qj, r);
+            }
+        }
+        return energy;
+    }
+
+    /// Real space contribution to the forces
+    fn real_space_forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.rc));
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let rij = configuration.nearest_image(i, j);
+                let force = self.real_space_force_pair(info, qi, qj, &rij);
+                forces[i] += force;
+                forces[j] -= force;
+            }
+        }
+    }
+
+    /// Real space contribution to the virial
+    fn real_space_virial(&self, configuration: &Configuration) -> Matrix3 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.rc));
+        let mut virial = Matrix3::zero();
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue}
+            for &j in neighbors.of(i) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                let rij = configuration.nearest_image(i, j);
+                let force = self.real_space_force_pair(info, qi, qj, &rij);
+                virial += force.tensorial(&rij);
+            }
+        }
+        return virial;
+    }
+
+    fn real_space_move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.rc));
+        let mut e_old = 0.0;
+        let mut e_new = 0.0;
+
+        // Iterate over all interactions between a moved particle and a
+        // particle not moved
+        for (idx, &i) in idxes.iter().enumerate() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue}
+
+            let old_position = configuration.particle(i).position;
+            for j in neighbors.around(&old_position) {
+                if idxes.contains(&j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r_old = configuration.distance(i, j);
+                e_old += self.real_space_energy_pair(info, qi, qj, r_old);
+            }
+
+            for j in neighbors.around(&newpos[idx]) {
+                if idxes.contains(&j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r_new = configuration.cell.distance(&newpos[idx], &configuration.particle(j).position);
+                e_new += self.real_space_energy_pair(info, qi, qj, r_new);
+            }
+        }
+
+        // Iterate over all interactions between two moved particles
+        for (idx, &i) in idxes.iter().enumerate() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 {continue}
+            for (jdx, &j) in idxes.iter().enumerate().skip(idx + 1) {
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let r_old = configuration.distance(i, j);
+                let r_new = configuration.cell.distance(&newpos[idx], &newpos[jdx]);
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+
+                e_old += self.real_space_energy_pair(info, qi, qj, r_old);
+                e_new += self.real_space_energy_pair(info, qi, qj, r_new);
+            }
+        }
+
+        return e_new - e_old;
+    }
+}
+
+/// Self-interaction correction
+impl SPME {
+    /// Self-interaction contribution to the energy
+    fn self_energy(&self, configuration: &Configuration) -> f64 {
+        let mut q2 = 0.0;
+        for i in 0..configuration.size() {
+            q2 += configuration.particle(i).charge * configuration.particle(i).charge;
+        }
+        return -self.alpha / f64::sqrt(PI) * q2 / ELCC;
+    }
+}
+
+/// k-space part of the summation
+impl SPME {
+    /// Spread the charges of the particles at `positions` on the grid, and
+    /// compute the Fourier transform of the resulting grid.
+    fn charges_fft(&mut self, cell: &UnitCell, charges: &[f64], positions: &[Vector3D]) {
+        self.charges.fill(Complex::zero());
+        for (&charge, position) in charges.iter().zip(positions) {
+            if charge == 0.0 {continue}
+            let fractional = cell.fractional(position);
+            let splines = [
+                self.splines(fractional[0], 0),
+                self.splines(fractional[1], 1),
+                self.splines(fractional[2], 2),
+            ];
+
+            for a in 0..self.order {
+                let ia = (splines[0].first + self.grid[0] - a) % self.grid[0];
+                let qa = charge * splines[0].values[a];
+                for b in 0..self.order {
+                    let ib = (splines[1].first + self.grid[1] - b) % self.grid[1];
+                    let qab = qa * splines[1].values[b];
+                    for c in 0..self.order {
+                        let ic = (splines[2].first + self.grid[2] - c) % self.grid[2];
+                        let q = qab * splines[2].values[c];
+                        self.charges[(ia, ib, ic)] = self.charges[(ia, ib, ic)] + Complex::cartesian(q, 0.0);
+                    }
+                }
+            }
+        }
+        fft3d(&mut self.charges, &self.ffts, false);
+    }
+
+    /// Compute the k-space energy of the particles at `positions` with the
+    /// given `charges`.
+    fn kspace_energy_at(&mut self, cell: &UnitCell, charges: &[f64], positions: &[Vector3D]) -> f64 {
+        self.charges_fft(cell, charges, positions);
+        let mut energy = 0.0;
+        for (influence, charge) in self.influence.iter().zip(self.charges.iter()) {
+            energy += influence * charge.norm2();
+        }
+        return 2.0 * PI * energy / (cell.volume() * ELCC);
+    }
+
+    /// k-space contribution to the energy
+    fn kspace_energy(&mut self, configuration: &Configuration) -> f64 {
+        let charges = configuration.particles().map(|p| p.charge).collect::<Vec<_>>();
+        let positions = configuration.particles().map(|p| p.position).collect::<Vec<_>>();
+        return self.kspace_energy_at(&configuration.cell, &charges, &positions);
+    }
+
+    /// k-space contribution to the forces
+    fn kspace_forces(&mut self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let charges = configuration.particles().map(|p| p.charge).collect::<Vec<_>>();
+        let positions = configuration.particles().map(|p| p.position).collect::<Vec<_>>();
+        let cell = configuration.cell;
+
+        // Convolution of the charges with the influence function, giving the
+        // electrostatic potential on the grid.
+        self.charges_fft(&cell, &charges, &positions);
+        for (charge, influence) in self.charges.iter_mut().zip(self.influence.iter()) {
+            *charge = *charge * *influence;
+        }
+        fft3d(&mut self.charges, &self.ffts, true);
+
+        // Derivatives of the scaled fractional coordinates with respect to
+        // the cartesian coordinates
+        let (rec_va, rec_vb, rec_vc) = cell.reciprocal_vectors();
+        let scaled = [
+            self.grid[0] as f64 / (2.0 * PI) * rec_va,
+            self.grid[1] as f64 / (2.0 * PI) * rec_vb,
+            self.grid[2] as f64 / (2.0 * PI) * rec_vc,
+        ];
+
+        let factor = 4.0 * PI / (cell.volume() * ELCC);
+        for (i, position) in positions.iter().enumerate() {
+            let qi = charges[i];
+            if qi == 0.0 {continue}
+            let fractional = cell.fractional(position);
+            let splines = [
+                self.splines(fractional[0], 0),
+                self.splines(fractional[1], 1),
+                self.splines(fractional[2], 2),
+            ];
+
+            let mut gradient = Vector3D::zero();
+            for a in 0..self.order {
+                let ia = (splines[0].first + self.grid[0] - a) % self.grid[0];
+                for b in 0..self.order {
+                    let ib = (splines[1].first + self.grid[1] - b) % self.grid[1];
+                    for c in 0..self.order {
+                        let ic = (splines[2].first + self.grid[2] - c) % self.grid[2];
+                        let potential = self.charges[(ia, ib, ic)].real();
+                        gradient[0] += potential * splines[0].derivatives[a] * splines[1].values[b] * splines[2].values[c];
+                        gradient[1] += potential * splines[0].values[a] * splines[1].derivatives[b] * splines[2].values[c];
+                        gradient[2] += potential * splines[0].values[a] * splines[1].values[b] * splines[2].derivatives[c];
+                    }
+                }
+            }
+
+            let gradient = gradient[0] * scaled[0] + gradient[1] * scaled[1] + gradient[2] * scaled[2];
+            forces[i] -= factor * qi * gradient;
+        }
+    }
+
+    /// k-space contribution to the virial
+    fn kspace_virial(&mut self, configuration: &Configuration) -> Matrix3 {
+        let charges = configuration.particles().map(|p| p.charge).collect::<Vec<_>>();
+        let positions = configuration.particles().map(|p| p.position).collect::<Vec<_>>();
+        let cell = configuration.cell;
+        self.charges_fft(&cell, &charges, &positions);
+
+        let factor = 2.0 * PI / (cell.volume() * ELCC);
+        let (rec_va, rec_vb, rec_vc) = cell.reciprocal_vectors();
+        let mut virial = Matrix3::zero();
+        for ia in 0..self.grid[0] {
+            let ka = signed_index(ia, self.grid[0]) * rec_va;
+            for ib in 0..self.grid[1] {
+                let kb = ka + signed_index(ib, self.grid[1]) * rec_vb;
+                for ic in 0..self.grid[2] {
+                    // The k = 0 case is already handled in `influence`
+                    let influence = self.influence[(ia, ib, ic)];
+                    if influence == 0.0 {continue}
+
+                    let k = kb + signed_index(ic, self.grid[2]) * rec_vc;
+                    let k2 = k.norm2();
+                    let energy = factor * influence * self.charges[(ia, ib, ic)].norm2();
+                    let coefficient = 2.0 * (1.0 / k2 + 1.0 / (4.0 * self.alpha * self.alpha));
+                    virial += energy * (Matrix3::one() - coefficient * k.tensorial(&k));
+                }
+            }
+        }
+        return virial;
+    }
+
+    fn kspace_move_particles_cost(&mut self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let charges = configuration.particles().map(|p| p.charge).collect::<Vec<_>>();
+        let mut positions = configuration.particles().map(|p| p.position).collect::<Vec<_>>();
+        let cell = configuration.cell;
+
+        // The energy of the current configuration is cached from the previous
+        // accepted move, and only recomputed if the configuration changed
+        // since then.
+        let cached = match self.kspace_current {
+            Some(ref current) if current.is_valid(&cell, &charges, &positions) => Some(current.energy),
+            _ => None,
+        };
+        let e_old = match cached {
+            Some(energy) => energy,
+            None => {
+                let energy = self.kspace_energy_at(&cell, &charges, &positions);
+                self.kspace_current = Some(KSpaceEnergy {
+                    cell: cell,
+                    charges: charges.clone(),
+                    positions: positions.clone(),
+                    energy: energy,
+                });
+                energy
+            }
+        };
+
+        for (&i, position) in idxes.iter().zip(newpos) {
+            positions[i] = *position;
+        }
+        let e_new = self.kspace_energy_at(&cell, &charges, &positions);
+        self.kspace_trial = Some(KSpaceEnergy {
+            cell: cell,
+            charges: charges,
+            positions: positions,
+            energy: e_new,
+        });
+
+        return e_new - e_old;
+    }
+}
+
+/// Molecular correction for SPME summation
+impl SPME {
+    /// Get the part of the interaction between the particles at bond distance
+    /// `distance` that should be removed from the k-space sum. This is 1 for
+    /// excluded pairs, and `1 - scaling` for scaled pairs.
+    #[inline]
+    fn correction_coupling(&self, distance: i8) -> f64 {
+        let info = self.restriction.information(distance);
+        if info.excluded {
+            return 1.0;
+        } else {
+            return 1.0 - info.scaling;
+        }
+    }
+
+    /// Get the molecular correction energy for the pair with charges `qi` and
+    /// `qj`, at distance `r` and with the given `coupling`.
+    #[inline]
+    fn molcorrect_energy_pair(&self, coupling: f64, qi: f64, qj: f64, r: f64) -> f64 {
+        return - coupling * qi * qj / ELCC * f64::erf(self.alpha * r) / r;
+    }
+
+    /// Get the molecular correction force for the pair with charges `qi` and
+    /// `qj`, at distance `rij` and with the given `coupling`.
+    #[inline]
+    fn molcorrect_force_pair(&self, coupling: f64, qi: f64, qj: f64, rij: &Vector3D) -> Vector3D {
+        let r = rij.norm();
+        let qiqj = coupling * qi * qj / (ELCC * r * r);
+        let factor = qiqj * (self.alpha * FRAC_2_SQRT_PI * f64::exp(-self.alpha * self.alpha * r * r) - f64::erf(self.alpha * r) / r);
+        return factor * rij;
+    }
+
+    /// Get all the pairs `(i, j, coupling)` with a non-zero molecular
+    /// correction in the `configuration`.
+    fn molcorrect_pairs(&self, configuration: &Configuration) -> Vec<(usize, usize, f64)> {
+        let mut pairs = Vec::new();
+        match self.restriction {
+            PairRestriction::None => {},
+            PairRestriction::IntraMolecular => {
+                // All the inter-molecular pairs are excluded
+                for i in 0..configuration.size() {
+                    for j in (i + 1)..configuration.size() {
+                        let coupling = self.correction_coupling(configuration.bond_distance(i, j));
+                        if coupling != 0.0 {
+                            pairs.push((i, j, coupling));
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Only pairs in the same molecule can be excluded or scaled
+                for molecule in configuration.molecules() {
+                    for i in molecule.iter() {
+                        for j in (i + 1)..molecule.end() {
+                            let coupling = self.correction_coupling(configuration.bond_distance(i, j));
+                            if coupling != 0.0 {
+                                pairs.push((i, j, coupling));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        return pairs;
+    }
+
+    /// Molecular correction contribution to the energy
+    fn molcorrect_energy(&self, configuration: &Configuration) -> f64 {
+        let mut energy = 0.0;
+        for (i, j, coupling) in self.molcorrect_pairs(configuration) {
+            let qi = configuration.particle(i).charge;
+            let qj = configuration.particle(j).charge;
+            if qi == 0.0 || qj == 0.0 {continue}
+
+            let r = configuration.distance(i, j);
+            energy += self.molcorrect_energy_pair(coupling, qi, qj, r);
+        }
+        return energy;
+    }
+
+    /// Molecular correction contribution to the forces
+    fn molcorrect_forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        for (i, j, coupling) in self.molcorrect_pairs(configuration) {
+            let qi = configuration.particle(i).charge;
+            let qj = configuration.particle(j).charge;
+            if qi == 0.0 || qj == 0.0 {continue}
+
+            let rij = configuration.nearest_image(i, j);
+            let force = self.molcorrect_force_pair(coupling, qi, qj, &rij);
+            forces[i] += force;
+            forces[j] -= force;
+        }
+    }
+
+    /// Molecular correction contribution to the virial
+    fn molcorrect_virial(&self, configuration: &Configuration) -> Matrix3 {
+        let mut virial = Matrix3::zero();
+        for (i, j, coupling) in self.molcorrect_pairs(configuration) {
+            let qi = configuration.particle(i).charge;
+            let qj = configuration.particle(j).charge;
+            if qi == 0.0 || qj == 0.0 {continue}
+
+            let rij = configuration.nearest_image(i, j);
+            let force = self.molcorrect_force_pair(coupling, qi, qj, &rij);
+            virial += force.tensorial(&rij);
+        }
+        return virial;
+    }
+
+    fn molcorrect_move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let mut e_old = 0.0;
+        let mut e_new = 0.0;
+
+        let new_position = |i: usize| {
+            match idxes.iter().position(|&moved| moved == i) {
+                Some(idx) => newpos[idx],
+                None => configuration.particle(i).position,
+            }
+        };
+
+        for (i, j, coupling) in self.molcorrect_pairs(configuration) {
+            if !idxes.contains(&i) && !idxes.contains(&j) {continue}
+            let qi = configuration.particle(i).charge;
+            let qj = configuration.particle(j).charge;
+            if qi == 0.0 || qj == 0.0 {continue}
+
+            let r_old = configuration.distance(i, j);
+            let r_new = configuration.cell.distance(&new_position(i), &new_position(j));
+
+            e_old += self.molcorrect_energy_pair(coupling, qi, qj, r_old);
+            e_new += self.molcorrect_energy_pair(coupling, qi, qj, r_new);
+        }
+
+        return e_new - e_old;
+    }
+}
+
+/// Interaction energy between groups of particles
+impl SPME {
+    /// Get the interaction energy between the particles in the `first` and
+    /// `second` groups.
+    ///
+    /// The real space and molecular correction parts are pairwise
+    /// decomposable, and are directly computed for each pair. The k-space part
+    /// is recovered from k-space energies computed with only the charges of
+    /// some groups. When the groups overlap, the self-interaction of the
+    /// particles in both groups is approximated by the self-interaction of a
+    /// charge at the origin of the grid, which is exact only for an infinitely
+    /// fine grid.
+    fn group_energy(&mut self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        self.precompute(&configuration.cell);
+
+        let masks = GroupMasks::new(configuration.size(), first, second);
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.rc));
+        let mut energy = 0.0;
+        for i in 0..configuration.size() {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 || !masks.in_any(i) {continue}
+            for &j in neighbors.of(i) {
+                if !masks.contains(i, j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                let r = configuration.distance(i, j);
+                energy += self.real_space_energy_pair(info, qi, qj, r);
+            }
+        }
+
+        for (i, j, coupling) in self.molcorrect_pairs(configuration) {
+            if !masks.contains(i, j) {continue}
+            let qi = configuration.particle(i).charge;
+            let qj = configuration.particle(j).charge;
+            if qi == 0.0 || qj == 0.0 {continue}
+
+            let r = configuration.distance(i, j);
+            energy += self.molcorrect_energy_pair(coupling, qi, qj, r);
+        }
+
+        // The k-space energy of a set of charges contains all the pair
+        // interactions in this set, and the self-interaction of all the
+        // charges. We remove the pairs inside only one of the groups, and the
+        // self-interactions of the particles in both groups. All these
+        // energies use the same scratch buffer for the charges.
+        let cell = configuration.cell;
+        let positions = configuration.particles().map(|p| p.position).collect::<Vec<_>>();
+        let mut charges = vec![0.0; configuration.size()];
+        let mask_charges = |charges: &mut [f64], charged: &Fn(usize) -> bool| {
+            for (i, charge) in charges.iter_mut().enumerate() {
+                *charge = if charged(i) {configuration.particle(i).charge} else {0.0};
+            }
+        };
+
+        mask_charges(&mut charges, &|i| masks.in_any(i));
+        energy += self.kspace_energy_at(&cell, &charges, &positions);
+        mask_charges(&mut charges, &|i| masks.in_first(i) && !masks.in_second(i));
+        energy -= self.kspace_energy_at(&cell, &charges, &positions);
+        mask_charges(&mut charges, &|i| masks.in_second(i) && !masks.in_first(i));
+        energy -= self.kspace_energy_at(&cell, &charges, &positions);
+
+        let mut q2 = 0.0;
+        for i in 0..configuration.size() {
+            if masks.in_first(i) && masks.in_second(i) {
+                q2 += configuration.particle(i).charge * configuration.particle(i).charge;
+            }
+        }
+        if q2 != 0.0 {
+            let self_factor = self.kspace_energy_at(&cell, &[1.0], &[Vector3D::zero()]);
+            energy -= self_factor * q2;
+        }
+
+        return energy;
+    }
+}
+
+/// Thread-sade wrapper around SPME implementing `CoulombicPotential`.
+///
+/// This wrapper allow to share a SPME solver between threads (make it `Send
+/// + Sync`) while still using caching of the k-space data (with interior
+/// mutability).
+pub struct SharedSPME(RwLock<SPME>);
+
+impl SharedSPME {
+    /// Wrap `spme` in a thread-safe structure.
+    ///
+    /// # Example
+    /// ```
+    /// # use lumol::energy::{SPME, SharedSPME, CoulombicPotential};
+    /// let spme = SharedSPME::new(SPME::new(12.5, [32, 32, 32], 4));
+    /// let boxed: Box<CoulombicPotential> = Box::new(spme);
+    /// ```
+    pub fn new(spme: SPME) -> SharedSPME {
+        SharedSPME(RwLock::new(spme))
+    }
+
+    /// Get read access to the underlying SPME solver
+    fn read(&self) -> RwLockReadGuard<SPME> {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.0.read().expect("SPME lock is poisonned")
+    }
+
+    /// Get write access to the underlying SPME solver
+    fn write(&self) -> RwLockWriteGuard<SPME> {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.0.write().expect("SPME lock is poisonned")
+    }
+}
+
+impl Clone for SharedSPME {
+    fn clone(&self) -> SharedSPME {
+        SharedSPME::new(self.read().clone())
+    }
+}
+
+impl GlobalPotential for SharedSPME {
+    fn name(&self) -> &str {
+        "SPME"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.read().rc)
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let mut spme = self.write();
+        spme.precompute(&configuration.cell);
+        let real = spme.real_space_energy(configuration);
+        let self_e = spme.self_energy(configuration);
+        let kspace = spme.kspace_energy(configuration);
+        let molecular = spme.molcorrect_energy(configuration);
+        return real + self_e + kspace + molecular;
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let mut spme = self.write();
+        spme.precompute(&configuration.cell);
+
+        spme.real_space_forces(configuration, forces);
+        /* No self force */
+        spme.kspace_forces(configuration, forces);
+        spme.molcorrect_forces(configuration, forces);
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let mut spme = self.write();
+        spme.precompute(&configuration.cell);
+        let real = spme.real_space_virial(configuration);
+        /* No self virial */
+        let kspace = spme.kspace_virial(configuration);
+        let molecular = spme.molcorrect_virial(configuration);
+        return real + kspace + molecular;
+    }
+}
+
+impl CoulombicPotential for SharedSPME {
+    fn set_restriction(&mut self, restriction: PairRestriction) {
+        self.write().restriction = restriction;
+    }
+
+    fn group_energy(&self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        return self.write().group_energy(configuration, first, second);
+    }
+}
+
+impl GlobalCache for SharedSPME {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let mut spme = self.write();
+        spme.precompute(&configuration.cell);
+        let real = spme.real_space_move_particles_cost(configuration, idxes, newpos);
+        /* No self cost */
+        let kspace = spme.kspace_move_particles_cost(configuration, idxes, newpos);
+        let molecular = spme.molcorrect_move_particles_cost(configuration, idxes, newpos);
+        return real + kspace + molecular;
+    }
+
+    fn update(&self) {
+        let mut spme = self.write();
+        if let Some(trial) = spme.kspace_trial.take() {
+            spme.kspace_current = Some(trial);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    pub use super::*;
+    use sys::System;
+    use utils::system_from_xyz;
+
+    pub fn nacl_pair() -> System {
+        let mut system = system_from_xyz("2
+        cell: 20.0
+        Cl 0.0 0.0 0.0
+        Na 1.5 0.0 0.0
+        ");
+        system.particle_mut(0).charge = -1.0;
+        system.particle_mut(1).charge = 1.0;
+        return system;
+    }
+
+    pub fn water() -> System {
+        let mut system = system_from_xyz("6
+        bonds cell: 20.0
+        O  0.0  0.0  0.0
+        H -0.7 -0.7  0.3
+        H  0.3 -0.3 -0.8
+        O  2.0  2.0  0.0
+        H  1.3  1.3  0.3
+        H  2.3  1.7 -0.8
+        ");
+        assert!(system.molecules().len() == 2);
+
+        for particle in system.particles_mut() {
+            if particle.name() == "O" {
+                particle.charge = -0.8476;
+            } else if particle.name() == "H" {
+                particle.charge = 0.4238;
+            }
+        }
+        return system;
+    }
+
+    pub fn spme() -> SharedSPME {
+        SharedSPME::new(SPME::new(8.0, [32, 32, 32], 6))
+    }
+
+    mod errors {
+        use super::*;
+        use energy::GlobalPotential;
+        use sys::UnitCell;
+
+        #[test]
+        #[should_panic]
+        fn infinite_cell() {
+            let mut system = nacl_pair();
+            system.cell = UnitCell::new();
+            let _ = spme().energy(&system);
+        }
+
+        #[test]
+        #[should_panic]
+        fn negative_alpha() {
+            let mut spme = SPME::new(8.0, [32, 32, 32], 4);
+            spme.set_alpha(-45.2);
+        }
+
+        #[test]
+        #[should_panic]
+        fn small_grid() {
+            let _ = SPME::new(8.0, [32, 3, 32], 4);
+        }
+    }
+
+    mod splines {
+        use super::*;
+
+        #[test]
+        fn partition_of_unity() {
+            let spme = SPME::new(8.0, [10, 10, 10], 5);
+            for &fractional in &[0.0, 0.123, 0.5, 0.999, -0.3] {
+                let splines = spme.splines(fractional, 0);
+                let sum = splines.values.iter().sum::<f64>();
+                assert_ulps_eq!(sum, 1.0);
+                let sum = splines.derivatives.iter().sum::<f64>();
+                assert_ulps_eq!(sum, 0.0);
+            }
+        }
+
+        #[test]
+        fn cubic() {
+            let spme = SPME::new(8.0, [10, 10, 10], 4);
+            let splines = spme.splines(0.025, 0);
+            assert_eq!(splines.first, 0);
+            // M_4(0.25), M_4(1.25), M_4(2.25) and M_4(3.25)
+            assert_ulps_eq!(splines.values[0], 0.25 * 0.25 * 0.25 / 6.0);
+            assert_ulps_eq!(splines.values[1], 0.3151041666666667);
+            assert_ulps_eq!(splines.values[2], 0.6119791666666666);
+            assert_ulps_eq!(splines.values[3], 0.75 * 0.75 * 0.75 / 6.0);
+        }
+    }
+
+    mod pairs {
+        use super::*;
+        use energy::GlobalPotential;
+        use sys::UnitCell;
+
+        #[test]
+        fn energy() {
+            let system = nacl_pair();
+            let energy = spme().energy(&system);
+            // This was computed by hand
+            let energy_brute_force = -0.09262397663346732;
+            assert_relative_eq!(energy, energy_brute_force, epsilon=1e-4);
+        }
+
+        #[test]
+        fn alpha() {
+            let mut system = water();
+            system.cell = UnitCell::triclinic(14.0, 15.0, 16.0, 80.0, 95.0, 110.0);
+
+            // The energy should not depend on the splitting between real
+            // space and k-space
+            let mut spme = SPME::new(6.0, [48, 48, 48], 6);
+            spme.set_alpha(0.5);
+            let energy = SharedSPME::new(spme).energy(&system);
+
+            let mut spme = SPME::new(6.0, [48, 48, 48], 6);
+            spme.set_alpha(0.65);
+            let other = SharedSPME::new(spme).energy(&system);
+
+            assert_relative_eq!(energy, other, epsilon=1e-6);
+        }
+
+        #[test]
+        fn forces() {
+            let mut system = nacl_pair();
+            let spme = spme();
+
+            let mut forces = vec![Vector3D::zero(); 2];
+            spme.forces(&system, &mut forces);
+            let norm = (forces[0] + forces[1]).norm();
+            // Total force should be null
+            assert!(norm < 1e-6);
+
+            // Force is attractive
+            assert!(forces[0][0] > 0.0);
+            assert!(forces[1][0] < 0.0);
+
+            // Finite difference computation of the force
+            let e = spme.energy(&system);
+            let eps = 1e-9;
+            system.particle_mut(0).position[0] += eps;
+
+            let e1 = spme.energy(&system);
+            assert_relative_eq!((e - e1) / eps, forces[0][0], epsilon=1e-6);
+        }
+
+        #[test]
+        fn triclinic_forces() {
+            let mut system = water();
+            system.cell = UnitCell::triclinic(14.0, 15.0, 16.0, 80.0, 95.0, 110.0);
+            let spme = SharedSPME::new(SPME::new(6.0, [24, 24, 24], 5));
+
+            let mut forces = vec![Vector3D::zero(); 6];
+            spme.forces(&system, &mut forces);
+
+            let e = spme.energy(&system);
+            let eps = 1e-7;
+            for i in 0..3 {
+                system.particle_mut(3).position[i] += eps;
+                let e1 = spme.energy(&system);
+                system.particle_mut(3).position[i] -= eps;
+                assert_relative_eq!((e - e1) / eps, forces[3][i], epsilon=1e-5);
+            }
+        }
+    }
+
+    mod molecules {
+        use super::*;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential};
+
+        #[test]
+        fn energy() {
+            let system = water();
+            let mut spme = SPME::new(8.0, [32, 32, 32], 6);
+            spme.set_alpha(0.4);
+            let mut spme = SharedSPME::new(spme);
+            spme.set_restriction(PairRestriction::InterMolecular);
+            let energy = spme.energy(&system);
+
+            let mut other = SPME::new(8.0, [32, 32, 32], 6);
+            other.set_alpha(0.5);
+            let mut other = SharedSPME::new(other);
+            other.set_restriction(PairRestriction::InterMolecular);
+            assert_relative_eq!(energy, other.energy(&system), epsilon=1e-6);
+
+            // Only the inter-molecular interactions are included
+            let mut direct = 0.0;
+            for i in 0..3 {
+                for j in 3..6 {
+                    let qi = system.particle(i).charge;
+                    let qj = system.particle(j).charge;
+                    direct += qi * qj / system.distance(i, j) / ELCC;
+                }
+            }
+            // The periodic images contributes a bit to the energy
+            assert_relative_eq!(energy, direct, epsilon=1e-4);
+        }
+
+        #[test]
+        fn forces() {
+            let mut system = water();
+            let mut spme = spme();
+            spme.set_restriction(PairRestriction::Scale14(0.5));
+
+            let mut forces = vec![Vector3D::zero(); 6];
+            spme.forces(&system, &mut forces);
+
+            let e = spme.energy(&system);
+            let eps = 1e-7;
+            for i in 0..3 {
+                system.particle_mut(1).position[i] += eps;
+                let e1 = spme.energy(&system);
+                system.particle_mut(1).position[i] -= eps;
+                assert_relative_eq!((e - e1) / eps, forces[1][i], epsilon=1e-5);
+            }
+        }
+    }
+
+    mod virial {
+        use super::*;
+        use sys::UnitCell;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential};
+
+        /// Check the virial against finite differences of the energy under
+        /// an homogeneous deformation of the system.
+        fn check_virial(spme: &SharedSPME, system: &System) {
+            let virial = spme.virial(system);
+
+            let deformed_energy = |a: usize, b: usize, eps: f64| {
+                let mut deformation = Matrix3::one();
+                deformation[a][b] += eps;
+
+                let mut deformed = system.clone();
+                deformed.cell = system.cell.scale(deformation);
+                for particle in deformed.particles_mut() {
+                    particle.position = deformation * particle.position;
+                }
+                spme.energy(&deformed)
+            };
+
+            let eps = 1e-6;
+            for a in 0..3 {
+                for b in 0..3 {
+                    let delta = (deformed_energy(a, b, eps) - deformed_energy(a, b, -eps)) / (2.0 * eps);
+                    assert_relative_eq!(virial[a][b], -delta, epsilon=1e-7);
+                }
+            }
+        }
+
+        #[test]
+        fn pair() {
+            let system = nacl_pair();
+            check_virial(&spme(), &system);
+        }
+
+        #[test]
+        fn molecules() {
+            let mut system = water();
+            system.cell = UnitCell::triclinic(14.0, 15.0, 16.0, 80.0, 95.0, 110.0);
+            let mut spme = SharedSPME::new(SPME::new(6.0, [24, 24, 24], 5));
+            spme.set_restriction(PairRestriction::InterMolecular);
+            check_virial(&spme, &system);
+        }
+    }
+
+    mod group {
+        use super::*;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential};
+
+        /// Energy of the particles in `particles` alone in the configuration
+        fn partial_energy(spme: &SharedSPME, system: &System, particles: &[usize]) -> f64 {
+            let mut system = system.clone();
+            for (i, particle) in system.particles_mut().enumerate() {
+                if !particles.contains(&i) {
+                    particle.charge = 0.0;
+                }
+            }
+            spme.energy(&system)
+        }
+
+        #[test]
+        fn molecules() {
+            let system = water();
+            let mut spme = spme();
+            spme.set_restriction(PairRestriction::InterMolecular);
+
+            let first = &[0, 1, 2];
+            let second = &[3, 4, 5];
+            let total = spme.energy(&system);
+            let group = spme.group_energy(&system, first, second);
+            let partial = partial_energy(&spme, &system, first) + partial_energy(&spme, &system, second);
+            assert_relative_eq!(total, group + partial, epsilon = 1e-12);
+            assert_relative_eq!(group, spme.group_energy(&system, second, first), epsilon = 1e-12);
+        }
+
+        #[test]
+        fn overlap() {
+            let system = water();
+            let spme = spme();
+
+            let overlap = spme.group_energy(&system, &[0, 1, 3], &[1, 3, 4]);
+            let disjoint = spme.group_energy(&system, &[0], &[1, 3, 4])
+                         + spme.group_energy(&system, &[1, 3], &[4])
+                         + spme.group_energy(&system, &[1], &[3]);
+            // The self-interaction is only approximated for overlapping groups
+            assert_relative_eq!(overlap, disjoint, epsilon = 1e-5);
+        }
+    }
+
+    mod cache {
+        use super::*;
+        use types::Vector3D;
+        use energy::{GlobalPotential, PairRestriction, CoulombicPotential, GlobalCache};
+
+        #[test]
+        fn move_atoms() {
+            let mut system = water();
+            let mut spme = spme();
+            spme.set_restriction(PairRestriction::InterMolecular);
+
+            let spme_check = spme.clone();
+
+            let old_e = spme_check.energy(&system);
+            let idxes = &[0, 1];
+            let newpos = &[Vector3D::new(0.0, 0.0, 0.5), Vector3D::new(-0.7, 0.2, 1.5)];
+
+            let cost = spme.move_particles_cost(&system, idxes, newpos);
+
+            system.particle_mut(0).position = newpos[0];
+            system.particle_mut(1).position = newpos[1];
+            let new_e = spme_check.energy(&system);
+            assert_relative_eq!(cost, new_e - old_e, epsilon=1e-12);
+        }
+
+        #[test]
+        fn move_atoms_scaled() {
+            let mut system = water();
+            let mut spme = spme();
+            spme.set_restriction(PairRestriction::Scale14(0.5));
+
+            let spme_check = spme.clone();
+
+            let old_e = spme_check.energy(&system);
+            let idxes = &[1, 3, 5];
+            let newpos = &[
+                Vector3D::new(-0.7, 0.2, 1.5),
+                Vector3D::new(2.2, 1.8, 0.3),
+                Vector3D::new(2.4, 1.7, -0.6)
+            ];
+
+            let cost = spme.move_particles_cost(&system, idxes, newpos);
+
+            system.particle_mut(1).position = newpos[0];
+            system.particle_mut(3).position = newpos[1];
+            system.particle_mut(5).position = newpos[2];
+            let new_e = spme_check.energy(&system);
+            assert_relative_eq!(cost, new_e - old_e, epsilon=1e-12);
+        }
+
+        #[test]
+        fn successive_moves() {
+            let mut system = water();
+            let spme = spme();
+            let spme_check = spme.clone();
+
+            // Rejected move, the cached energy stays the one of the current
+            // configuration
+            let rejected = &[Vector3D::new(0.3, 0.0, 0.1)];
+            let cost = spme.move_particles_cost(&system, &[2], rejected);
+            let mut moved = system.clone();
+            moved.particle_mut(2).position = rejected[0];
+            assert_relative_eq!(cost, spme_check.energy(&moved) - spme_check.energy(&system), epsilon=1e-12);
+
+            // Accepted move, the cache is updated to the new configuration
+            let old_e = spme_check.energy(&system);
+            let accepted = &[Vector3D::new(0.0, 0.0, 0.5)];
+            let cost = spme.move_particles_cost(&system, &[0], accepted);
+            spme.update();
+            system.particle_mut(0).position = accepted[0];
+            let new_e = spme_check.energy(&system);
+            assert_relative_eq!(cost, new_e - old_e, epsilon=1e-12);
+
+            let old_e = new_e;
+            let newpos = &[Vector3D::new(-0.7, 0.2, 1.5)];
+            let cost = spme.move_particles_cost(&system, &[1], newpos);
+            system.particle_mut(1).position = newpos[0];
+            let new_e = spme_check.energy(&system);
+            assert_relative_eq!(cost, new_e - old_e, epsilon=1e-12);
+
+            // Changing the configuration outside of Monte Carlo moves
+            // invalidates the cache
+            system.particle_mut(3).position = Vector3D::new(2.0, 2.0, 0.0);
+            let old_e = spme_check.energy(&system);
+            let newpos = &[Vector3D::new(0.1, -0.2, 0.3)];
+            let cost = spme.move_particles_cost(&system, &[0], newpos);
+            system.particle_mut(0).position = newpos[0];
+            let new_e = spme_check.energy(&system);
+            assert_relative_eq!(cost, new_e - old_e, epsilon=1e-12);
+        }
+    }
+}
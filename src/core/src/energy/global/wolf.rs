@@ -4,7 +4,7 @@
 use special::Error;
 use std::f64::consts::PI;
 
-use sys::Configuration;
+use sys::{Configuration, GroupMasks};
 use types::{Matrix3, Vector3D, Zero};
 use consts::ELCC;
 use energy::{PairRestriction, RestrictionInfo};
@@ -169,6 +169,10 @@ impl GlobalCache for Wolf {
 }
 
 impl GlobalPotential for Wolf {
+    fn name(&self) -> &str {
+        "Wolf"
+    }
+
     fn cutoff(&self) -> Option<f64> {
         Some(self.cutoff)
     }
@@ -261,6 +265,26 @@ impl CoulombicPotential for Wolf {
     fn set_restriction(&mut self, restriction: PairRestriction) {
         self.restriction = restriction;
     }
+
+    fn group_energy(&self, configuration: &Configuration, first: &[usize], second: &[usize]) -> f64 {
+        let masks = GroupMasks::new(configuration.size(), first, second);
+        let natoms = configuration.size();
+        let mut energy = 0.0;
+        for i in 0..natoms {
+            let qi = configuration.particle(i).charge;
+            if qi == 0.0 || !masks.in_any(i) {continue}
+            for j in i+1..natoms {
+                if !masks.contains(i, j) {continue}
+                let qj = configuration.particle(j).charge;
+                if qj == 0.0 {continue}
+
+                let distance = configuration.bond_distance(i, j);
+                let info = self.restriction.information(distance);
+                energy += self.energy_pair(info, qi, qj, configuration.distance(i, j));
+            }
+        }
+        return energy;
+    }
 }
 
 #[cfg(test)]
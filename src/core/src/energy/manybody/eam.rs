@@ -0,0 +1,624 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use std::collections::BTreeSet;
+
+use sys::{Configuration, NeighborList};
+use types::{Matrix3, Vector3D, Zero};
+
+use energy::{GlobalPotential, GlobalCache};
+
+/// A function tabulated on a regular grid starting at 0, as used in the EAM
+/// potential files.
+///
+/// The function is interpolated between the grid points with cubic Hermite
+/// splines, using finite differences to estimate the derivatives at the grid
+/// points. After the last point, the function is linearly extrapolated.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::EAMFunction;
+///
+/// // f(x) = x^2 on the [0, 2] segment
+/// let function = EAMFunction::new(0.5, vec![0.0, 0.25, 1.0, 2.25, 4.0]);
+///
+/// assert_eq!(function.value(1.0), 1.0);
+/// assert!(f64::abs(function.value(1.2) - 1.44) < 1e-12);
+/// assert!(f64::abs(function.derivative(1.2) - 2.4) < 1e-12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct EAMFunction {
+    /// Distance between two grid points
+    step: f64,
+    /// Values of the function on the grid
+    values: Vec<f64>,
+    /// Derivatives of the function on the grid
+    derivatives: Vec<f64>,
+}
+
+impl EAMFunction {
+    /// Create a new `EAMFunction` with the given `values` at the points `0,
+    /// step, 2 step, ...`.
+    ///
+    /// # Panics
+    ///
+    /// If `step` is not strictly positive, or if there are less than three
+    /// values.
+    pub fn new(step: f64, values: Vec<f64>) -> EAMFunction {
+        assert!(step > 0.0, "step must be positive in EAMFunction");
+        let n = values.len();
+        assert!(n >= 3, "EAMFunction needs at least three values");
+
+        let mut derivatives = vec![0.0; n];
+        // Second order finite differences
+        derivatives[0] = (-3.0 * values[0] + 4.0 * values[1] - values[2]) / (2.0 * step);
+        for k in 1..(n - 1) {
+            derivatives[k] = (values[k + 1] - values[k - 1]) / (2.0 * step);
+        }
+        derivatives[n - 1] = (3.0 * values[n - 1] - 4.0 * values[n - 2] + values[n - 3]) / (2.0 * step);
+
+        EAMFunction {
+            step: step,
+            values: values,
+            derivatives: derivatives,
+        }
+    }
+
+    /// Get the value of the function at `x`.
+    pub fn value(&self, x: f64) -> f64 {
+        self.eval(x).0
+    }
+
+    /// Get the derivative of the function at `x`.
+    pub fn derivative(&self, x: f64) -> f64 {
+        self.eval(x).1
+    }
+
+    /// Get the value and the derivative of the function at `x`.
+    fn eval(&self, x: f64) -> (f64, f64) {
+        debug_assert!(x >= 0.0);
+        let last = self.values.len() - 1;
+        let k = (x / self.step).floor() as usize;
+        if k >= last {
+            let dx = x - last as f64 * self.step;
+            let value = self.values[last] + self.derivatives[last] * dx;
+            return (value, self.derivatives[last]);
+        }
+
+        let h = self.step;
+        let t = x / h - k as f64;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        let (y0, y1) = (self.values[k], self.values[k + 1]);
+        let (d0, d1) = (h * self.derivatives[k], h * self.derivatives[k + 1]);
+
+        let value = (2.0 * t3 - 3.0 * t2 + 1.0) * y0 + (t3 - 2.0 * t2 + t) * d0
+                  + (-2.0 * t3 + 3.0 * t2) * y1 + (t3 - t2) * d1;
+        let derivative = (6.0 * t2 - 6.0 * t) * y0 + (3.0 * t2 - 4.0 * t + 1.0) * d0
+                       + (-6.0 * t2 + 6.0 * t) * y1 + (3.0 * t2 - 2.0 * t) * d1;
+        return (value, derivative / h);
+    }
+}
+
+/// Embedded Atom Method potential for metals.
+///
+/// In the Embedded Atom Method (EAM) [Daw1984], the energy of an atom `i`
+/// contains an embedding term depending on the electronic density `rho_i`
+/// created by its neighbors at the atom position, and a pair term:
+///
+/// `E = sum_i F_a(rho_i) + sum_{i < j} phi_ab(r_ij)` with `rho_i = sum_{j !=
+/// i} rho_ba(r_ij)`,
+///
+/// where `a` is the element of the atom `i` and `b` is the element of the atom
+/// `j`. `rho_ba(r)` is the electronic density created by an atom of the
+/// element `b` at an atom of the element `a`. In the usual EAM, this density
+/// only depends on the element `b`; while in the Finnis-Sinclair [Finnis1984]
+/// variant of the method, it depends on both elements.
+///
+/// All the functions are tabulated on regular grids, using the same
+/// conventions as the `setfl` files: the pair functions are given as `r
+/// phi(r)`. The particles with a name not corresponding to any element of the
+/// potential do not interact with this potential.
+///
+/// [Daw1984]: https://doi.org/10.1103/PhysRevB.29.6443
+/// [Finnis1984]: https://doi.org/10.1080/01418618408244210
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{EAM, EAMFunction};
+/// use lumol::sys::{System, Particle, UnitCell};
+/// use lumol::types::Vector3D;
+///
+/// // Tabulate a simple potential with F(rho) = -sqrt(rho), rho(r) = exp(-r)
+/// // and phi(r) = 2 exp(-2 r), with a cutoff of 5 A.
+/// let embedding = (0..1000).map(|i| -f64::sqrt(i as f64 * 0.001)).collect();
+/// let step = 0.01;
+/// let density = (0..501).map(|i| f64::exp(-(i as f64 * step))).collect();
+/// let pair = (0..501).map(|i| {
+///     let r = i as f64 * step;
+///     2.0 * r * f64::exp(-2.0 * r)
+/// }).collect();
+///
+/// let eam = EAM::new(
+///     vec![String::from("Cu")],
+///     vec![EAMFunction::new(0.001, embedding)],
+///     vec![vec![EAMFunction::new(step, density)]],
+///     vec![vec![EAMFunction::new(step, pair)]],
+///     5.0,
+/// );
+///
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// system.add_particle(Particle::with_position("Cu", Vector3D::new(0.0, 0.0, 0.0)));
+/// system.add_particle(Particle::with_position("Cu", Vector3D::new(2.5, 0.0, 0.0)));
+/// system.add_global_potential(Box::new(eam));
+///
+/// let expected = -2.0 * f64::sqrt(f64::exp(-2.5)) + 2.0 * f64::exp(-5.0);
+/// assert!(f64::abs(system.potential_energy() - expected) < 1e-6);
+/// ```
+#[derive(Clone)]
+pub struct EAM {
+    /// Names of the elements in this potential
+    names: Vec<String>,
+    /// Embedding functions `F_a(rho)` for all elements
+    embedding: Vec<EAMFunction>,
+    /// Density functions, `density[b][a]` is the density created by an atom
+    /// of element `b` at an atom of element `a`
+    density: Vec<Vec<EAMFunction>>,
+    /// Pair functions `r phi_ab(r)`
+    pair: Vec<Vec<EAMFunction>>,
+    /// Cutoff distance of the potential
+    cutoff: f64,
+    /// Neighbor list for the computations
+    neighbors: NeighborList,
+}
+
+/// A pair of interacting particles `i` and `j`, with the vector `r_i - r_j`
+type Pair = (usize, usize, Vector3D);
+
+impl EAM {
+    /// Create a new EAM potential for the elements with the given `names`,
+    /// using the `embedding` functions `F_a(rho)`, the `density` functions
+    /// and the `pair` functions `r phi_ab(r)`. `density[b][a]` is the density
+    /// created by an atom of the element `b` at an atom of the element `a`.
+    /// `pair[a][b]` and `pair[b][a]` should be the same function. The
+    /// potential is zero after the `cutoff` distance.
+    ///
+    /// # Panics
+    ///
+    /// If the sizes of `embedding`, `density` or `pair` do not match the
+    /// number of elements, or if the cutoff is not positive.
+    pub fn new(
+        names: Vec<String>,
+        embedding: Vec<EAMFunction>,
+        density: Vec<Vec<EAMFunction>>,
+        pair: Vec<Vec<EAMFunction>>,
+        cutoff: f64
+    ) -> EAM {
+        let n = names.len();
+        assert!(cutoff > 0.0, "the cutoff must be positive in EAM potential");
+        assert_eq!(embedding.len(), n, "wrong number of embedding functions in EAM potential");
+        assert_eq!(density.len(), n, "wrong number of density functions in EAM potential");
+        assert!(density.iter().all(|d| d.len() == n), "wrong number of density functions in EAM potential");
+        assert_eq!(pair.len(), n, "wrong number of pair functions in EAM potential");
+        assert!(pair.iter().all(|p| p.len() == n), "wrong number of pair functions in EAM potential");
+
+        EAM {
+            names: names,
+            embedding: embedding,
+            density: density,
+            pair: pair,
+            cutoff: cutoff,
+            neighbors: NeighborList::new(),
+        }
+    }
+
+    /// Get the names of the elements in this potential
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Get the element index of all the particles in the `configuration`,
+    /// or `None` for particles not interacting with this potential.
+    fn elements(&self, configuration: &Configuration) -> Vec<Option<usize>> {
+        configuration.particles().map(|particle| {
+            self.names.iter().position(|name| name == particle.name())
+        }).collect()
+    }
+
+    /// Get all the pairs of interacting particles closer than the cutoff
+    fn pairs(&self, configuration: &Configuration, elements: &[Option<usize>]) -> Vec<Pair> {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+        let mut pairs = Vec::new();
+        for i in 0..configuration.size() {
+            if elements[i].is_none() {
+                continue;
+            }
+            for &j in neighbors.of(i) {
+                if elements[j].is_none() {
+                    continue;
+                }
+                let rij = configuration.nearest_image(i, j);
+                if rij.norm2() < self.cutoff * self.cutoff {
+                    pairs.push((i, j, rij));
+                }
+            }
+        }
+        return pairs;
+    }
+
+    /// Get the electronic densities at all the particles
+    fn densities(&self, elements: &[Option<usize>], pairs: &[Pair]) -> Vec<f64> {
+        let mut densities = vec![0.0; elements.len()];
+        for &(i, j, rij) in pairs {
+            let a = elements[i].expect("missing element in EAM potential");
+            let b = elements[j].expect("missing element in EAM potential");
+            let r = rij.norm();
+            densities[i] += self.density[b][a].value(r);
+            densities[j] += self.density[a][b].value(r);
+        }
+        return densities;
+    }
+
+    /// Get the pair energy `phi_ab(r)` and its derivative
+    fn pair_energy(&self, a: usize, b: usize, r: f64) -> (f64, f64) {
+        let (rphi, rphi_derivative) = self.pair[a][b].eval(r);
+        let phi = rphi / r;
+        return (phi, (rphi_derivative - phi) / r);
+    }
+
+    /// Get the derivative of the energy with respect to the distance for all
+    /// the `pairs`.
+    fn pair_derivatives(&self, configuration: &Configuration, pairs: &[Pair]) -> Vec<f64> {
+        let elements = self.elements(configuration);
+        let densities = self.densities(&elements, pairs);
+        let embedding_derivatives = elements.iter().zip(&densities).map(|(element, &rho)| {
+            match *element {
+                Some(a) => self.embedding[a].derivative(rho),
+                None => 0.0,
+            }
+        }).collect::<Vec<_>>();
+
+        pairs.iter().map(|&(i, j, rij)| {
+            let a = elements[i].expect("missing element in EAM potential");
+            let b = elements[j].expect("missing element in EAM potential");
+            let r = rij.norm();
+            let (_, dphi) = self.pair_energy(a, b, r);
+            embedding_derivatives[i] * self.density[b][a].derivative(r) +
+            embedding_derivatives[j] * self.density[a][b].derivative(r) + dphi
+        }).collect()
+    }
+
+    /// Get the density at the particle `i`, using the `position` function to
+    /// get the positions of the particles. The `candidates` contains all
+    /// particles that can be neighbors of `i`.
+    fn density_at<F>(&self, configuration: &Configuration, elements: &[Option<usize>], i: usize, candidates: &BTreeSet<usize>, position: F) -> f64
+        where F: Fn(usize) -> Vector3D {
+        let a = elements[i].expect("missing element in EAM potential");
+        let center = position(i);
+        let mut density = 0.0;
+        for &j in candidates {
+            if j == i {
+                continue;
+            }
+            if let Some(b) = elements[j] {
+                let mut rij = position(j) - center;
+                configuration.cell.vector_image(&mut rij);
+                let r = rij.norm();
+                if r < self.cutoff {
+                    density += self.density[b][a].value(r);
+                }
+            }
+        }
+        return density;
+    }
+}
+
+impl GlobalPotential for EAM {
+    fn name(&self) -> &str {
+        "EAM"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.cutoff)
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let elements = self.elements(configuration);
+        let pairs = self.pairs(configuration, &elements);
+        let densities = self.densities(&elements, &pairs);
+
+        let mut energy = 0.0;
+        for (element, &rho) in elements.iter().zip(&densities) {
+            if let Some(a) = *element {
+                energy += self.embedding[a].value(rho);
+            }
+        }
+
+        for &(i, j, rij) in &pairs {
+            let a = elements[i].expect("missing element in EAM potential");
+            let b = elements[j].expect("missing element in EAM potential");
+            energy += self.pair_energy(a, b, rij.norm()).0;
+        }
+        return energy;
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let elements = self.elements(configuration);
+        let pairs = self.pairs(configuration, &elements);
+        let derivatives = self.pair_derivatives(configuration, &pairs);
+        for (&(i, j, rij), derivative) in pairs.iter().zip(derivatives) {
+            let force = -derivative * rij.normalized();
+            forces[i] += force;
+            forces[j] -= force;
+        }
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let elements = self.elements(configuration);
+        let pairs = self.pairs(configuration, &elements);
+        let derivatives = self.pair_derivatives(configuration, &pairs);
+        let mut virial = Matrix3::zero();
+        for (&(_, _, rij), derivative) in pairs.iter().zip(derivatives) {
+            let force = -derivative * rij.normalized();
+            virial += force.tensorial(&rij);
+        }
+        return virial;
+    }
+}
+
+impl GlobalCache for EAM {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let elements = self.elements(configuration);
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.cutoff));
+
+        // Moving a particle changes the density at the particle and at all
+        // its neighbors, before and after the move.
+        let mut affected = BTreeSet::new();
+        for (idx, &i) in idxes.iter().enumerate() {
+            if elements[i].is_none() {
+                continue;
+            }
+            let _ = affected.insert(i);
+            let old_position = configuration.particle(i).position;
+            affected.extend(neighbors.around(&old_position));
+            affected.extend(neighbors.around(&newpos[idx]));
+        }
+
+        let old_position = |i: usize| configuration.particle(i).position;
+        let new_position = |i: usize| {
+            match idxes.iter().position(|&moved| moved == i) {
+                Some(idx) => newpos[idx],
+                None => configuration.particle(i).position,
+            }
+        };
+
+        let candidates = |position: &Vector3D| {
+            let mut candidates = neighbors.around(position).into_iter().collect::<BTreeSet<_>>();
+            candidates.extend(idxes.iter().cloned());
+            candidates
+        };
+
+        let mut cost = 0.0;
+        for &i in &affected {
+            if let Some(a) = elements[i] {
+                let old_rho = self.density_at(configuration, &elements, i, &candidates(&old_position(i)), &old_position);
+                let new_rho = self.density_at(configuration, &elements, i, &candidates(&new_position(i)), &new_position);
+                cost += self.embedding[a].value(new_rho) - self.embedding[a].value(old_rho);
+            }
+        }
+
+        // Pair contributions from the moved particles
+        let pair_energy = |i: usize, candidates: &BTreeSet<usize>, position: &Fn(usize) -> Vector3D| {
+            let a = elements[i].expect("missing element in EAM potential");
+            let mut energy = 0.0;
+            for &j in candidates {
+                // Pairs of moved particles are only counted once
+                if j == i || (j < i && idxes.contains(&j)) {
+                    continue;
+                }
+                if let Some(b) = elements[j] {
+                    let mut rij = position(j) - position(i);
+                    configuration.cell.vector_image(&mut rij);
+                    let r = rij.norm();
+                    if r < self.cutoff {
+                        energy += self.pair_energy(a, b, r).0;
+                    }
+                }
+            }
+            energy
+        };
+
+        for &i in idxes {
+            if elements[i].is_none() {
+                continue;
+            }
+            cost -= pair_energy(i, &candidates(&old_position(i)), &old_position);
+            cost += pair_energy(i, &candidates(&new_position(i)), &new_position);
+        }
+
+        return cost;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::{System, Particle, UnitCell};
+    use energy::GlobalPotential;
+
+    const STEP: f64 = 0.002;
+    const CUTOFF: f64 = 4.5;
+
+    fn tabulate<F: Fn(f64) -> f64>(n: usize, function: F) -> EAMFunction {
+        EAMFunction::new(STEP, (0..n).map(|i| function(i as f64 * STEP)).collect())
+    }
+
+    /// Smooth cutoff for the radial functions
+    fn smooth(r: f64) -> f64 {
+        if r < CUTOFF {
+            let x = (CUTOFF - r) / CUTOFF;
+            x * x * x * x
+        } else {
+            0.0
+        }
+    }
+
+    /// Get a two elements EAM potential, using a Finnis-Sinclair like
+    /// density.
+    fn eam() -> EAM {
+        let n = (CUTOFF / STEP) as usize + 1;
+        let embedding = vec![
+            tabulate(10000, |rho| -f64::sqrt(rho)),
+            tabulate(10000, |rho| -1.3 * f64::sqrt(rho) + 0.1 * rho),
+        ];
+        let density = vec![
+            vec![tabulate(n, |r| 0.2 * smooth(r)), tabulate(n, |r| 0.25 * smooth(r))],
+            vec![tabulate(n, |r| 0.3 * smooth(r)), tabulate(n, |r| 0.15 * smooth(r))],
+        ];
+        let pair = vec![
+            vec![tabulate(n, |r| 10.0 * r * f64::exp(-r) * smooth(r)), tabulate(n, |r| 8.0 * r * f64::exp(-r) * smooth(r))],
+            vec![tabulate(n, |r| 8.0 * r * f64::exp(-r) * smooth(r)), tabulate(n, |r| 12.0 * r * f64::exp(-r) * smooth(r))],
+        ];
+        EAM::new(vec![String::from("A"), String::from("B")], embedding, density, pair, CUTOFF)
+    }
+
+    /// Get a perturbed fcc crystal of A and B particles
+    fn system() -> System {
+        const BASIS: [[f64; 3]; 4] = [[0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+        let a = 3.6;
+        let mut system = System::with_cell(UnitCell::cubic(3.0 * a));
+        let mut n = 0;
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    for basis in &BASIS {
+                        let name = if n % 3 == 0 {"B"} else {"A"};
+                        let mut particle = Particle::new(name);
+                        particle.position = a * Vector3D::new(
+                            i as f64 + basis[0], j as f64 + basis[1], k as f64 + basis[2]
+                        );
+                        // Deterministic perturbation
+                        let x = n as f64;
+                        particle.position += 0.1 * Vector3D::new(f64::sin(x), f64::cos(2.0 * x), f64::sin(3.0 * x));
+                        system.add_particle(particle);
+                        n += 1;
+                    }
+                }
+            }
+        }
+        return system;
+    }
+
+    #[test]
+    fn function() {
+        let function = EAMFunction::new(0.1, (0..30).map(|i| f64::sin(i as f64 * 0.1)).collect());
+        for &x in &[0.0, 0.05, 0.77, 1.234, 2.85] {
+            assert_relative_eq!(function.value(x), f64::sin(x), epsilon=1e-4);
+            assert_relative_eq!(function.derivative(x), f64::cos(x), epsilon=5e-3);
+        }
+
+        // Continuity at the grid points
+        let eps = 1e-10;
+        assert_relative_eq!(function.value(1.0 - eps), function.value(1.0 + eps), epsilon=1e-8);
+        assert_relative_eq!(function.derivative(1.0 - eps), function.derivative(1.0 + eps), epsilon=1e-8);
+
+        // Linear extrapolation
+        let last = 2.9;
+        let slope = function.derivative(last);
+        assert_relative_eq!(function.value(3.5), function.value(last) + 0.6 * slope, epsilon=1e-12);
+    }
+
+    #[test]
+    fn dimer() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_particle(Particle::with_position("A", Vector3D::new(0.0, 0.0, 0.0)));
+        system.add_particle(Particle::with_position("B", Vector3D::new(0.0, 2.5, 0.0)));
+        system.add_particle(Particle::with_position("C", Vector3D::new(0.0, 0.0, 2.5)));
+
+        let r: f64 = 2.5;
+        let rho_a = 0.3 * smooth(r);
+        let rho_b = 0.25 * smooth(r);
+        let expected = -f64::sqrt(rho_a) - 1.3 * f64::sqrt(rho_b) + 0.1 * rho_b
+                     + 8.0 * f64::exp(-r) * smooth(r);
+        assert_relative_eq!(eam().energy(&system), expected, epsilon=1e-5);
+
+        let mut forces = vec![Vector3D::zero(); 3];
+        eam().forces(&system, &mut forces);
+        assert_eq!(forces[2], Vector3D::zero());
+        assert_relative_eq!(forces[0], -forces[1], epsilon=1e-12);
+    }
+
+    #[test]
+    fn forces() {
+        let mut system = system();
+        let eam = eam();
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        eam.forces(&system, &mut forces);
+
+        let total = forces.iter().fold(Vector3D::zero(), |sum, force| sum + force);
+        assert!(total.norm() < 1e-10);
+
+        let eps = 1e-6;
+        for &i in &[0, 5, 17, 42, 100] {
+            for axis in 0..3 {
+                system.particle_mut(i).position[axis] += eps;
+                let e_plus = eam.energy(&system);
+                system.particle_mut(i).position[axis] -= 2.0 * eps;
+                let e_minus = eam.energy(&system);
+                system.particle_mut(i).position[axis] += eps;
+
+                let expected = -(e_plus - e_minus) / (2.0 * eps);
+                assert_relative_eq!(forces[i][axis], expected, epsilon=1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn virial() {
+        let system = system();
+        let eam = eam();
+        let virial = eam.virial(&system);
+
+        let eps = 1e-6;
+        let mut energies = Vec::new();
+        for &scale in &[1.0 + eps, 1.0 - eps] {
+            let mut scaled = system.clone();
+            scaled.cell = UnitCell::cubic(3.0 * 3.6 * scale);
+            for particle in scaled.particles_mut() {
+                particle.position *= scale;
+            }
+            energies.push(eam.energy(&scaled));
+        }
+        let expected = -(energies[0] - energies[1]) / (2.0 * eps);
+        assert_relative_eq!(virial.trace(), expected, epsilon=1e-5);
+        assert_relative_eq!(virial, virial.transposed(), epsilon=1e-10);
+    }
+
+    #[test]
+    fn move_particles_cost() {
+        let system = system();
+        let eam = eam();
+        let idxes = [3, 4, 60];
+        let newpos = [
+            system.particle(3).position + Vector3D::new(0.2, 0.1, -0.3),
+            system.particle(4).position + Vector3D::new(-0.1, 0.0, 0.15),
+            system.particle(60).position + Vector3D::new(0.05, -0.2, 0.1),
+        ];
+
+        let cost = eam.move_particles_cost(&system, &idxes, &newpos);
+
+        let mut moved = system.clone();
+        for (&i, position) in idxes.iter().zip(&newpos) {
+            moved.particle_mut(i).position = *position;
+        }
+        let expected = eam.energy(&moved) - eam.energy(&system);
+        assert_relative_eq!(cost, expected, epsilon=1e-10);
+    }
+}
@@ -0,0 +1,464 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Many-body potentials are potentials where the energy of a particle depends
+//! on all its neighbors at once, and not only on pairs of particles.
+//!
+//! They are evaluated over the neighbors of each particle inside a cutoff
+//! distance, and not from the molecular topology like angles and dihedral
+//! angles.
+use std::collections::BTreeSet;
+
+use sys::{Configuration, NeighborList};
+use types::{Matrix3, Vector3D, Zero};
+
+use energy::{GlobalPotential, GlobalCache};
+
+/// A many-body potential, where the energy of a particle is a function of the
+/// positions of all its neighbors inside a cutoff distance.
+///
+/// The total energy is the sum of the site energies of all the particles. The
+/// site energy of a particle is computed from the vectors going from this
+/// particle to each of its neighbors.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::ManyBodyPotential;
+/// use lumol::types::Vector3D;
+///
+/// /// Energy proportional to the coordination number of a particle.
+/// #[derive(Clone)]
+/// struct Coordination {
+///     cutoff: f64,
+/// }
+///
+/// impl ManyBodyPotential for Coordination {
+///     fn cutoff(&self) -> f64 {
+///         self.cutoff
+///     }
+///
+///     fn energy(&self, neighbors: &[Vector3D]) -> f64 {
+///         -(neighbors.len() as f64)
+///     }
+///
+///     fn gradient(&self, _: &[Vector3D], gradient: &mut [Vector3D]) {
+///         for g in gradient {
+///             *g = Vector3D::new(0.0, 0.0, 0.0);
+///         }
+///     }
+/// }
+///
+/// let potential = Coordination{cutoff: 3.0};
+/// let neighbors = [Vector3D::new(1.0, 0.0, 0.0), Vector3D::new(0.0, 1.0, 0.0)];
+/// assert_eq!(potential.energy(&neighbors), -2.0);
+/// ```
+pub trait ManyBodyPotential: BoxCloneManyBody + Send + Sync {
+    /// Get the cutoff distance of this potential.
+    fn cutoff(&self) -> f64;
+
+    /// Get the site energy of a particle with the given `neighbors`. Each
+    /// neighbor is given by the vector going from the particle to the
+    /// neighbor, and all neighbors are closer than the cutoff.
+    fn energy(&self, neighbors: &[Vector3D]) -> f64;
+
+    /// Compute the gradient of the site energy of a particle with respect to
+    /// the position of each of its `neighbors`, and store it in `gradient`.
+    /// The `neighbors` and `gradient` slices have the same size.
+    fn gradient(&self, neighbors: &[Vector3D], gradient: &mut [Vector3D]);
+}
+
+impl_box_clone!(ManyBodyPotential, BoxCloneManyBody, box_clone_manybody);
+
+/// Neighbors of a particle: index of the neighbor, and vector from the
+/// particle to the neighbor
+type Neighborhood = Vec<(usize, Vector3D)>;
+
+/// Many-body interaction acting on all the particles with a given name.
+///
+/// This interaction uses a [`ManyBodyPotential`][ManyBodyPotential] to compute
+/// the energy, forces and virial of the particles with a given name, and can
+/// be added to a system as a [`GlobalPotential`][GlobalPotential].
+///
+/// [ManyBodyPotential]: trait.ManyBodyPotential.html
+/// [GlobalPotential]: trait.GlobalPotential.html
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{ManyBodyInteraction, StillingerWeber};
+/// use lumol::sys::{System, Particle, UnitCell};
+/// use lumol::types::Vector3D;
+/// use lumol::units;
+///
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// let positions = [[0.0, 0.0, 0.0], [2.35, 0.0, 0.0], [0.0, 2.35, 0.0]];
+/// for position in &positions {
+///     let mut particle = Particle::new("Si");
+///     particle.position = Vector3D::new(position[0], position[1], position[2]);
+///     system.add_particle(particle);
+/// }
+///
+/// let silicon = StillingerWeber::silicon();
+/// system.add_global_potential(Box::new(ManyBodyInteraction::new(Box::new(silicon), "Si")));
+///
+/// let energy = units::to(system.potential_energy(), "eV").unwrap();
+/// assert!(f64::abs(energy - -4.2567373619) < 1e-6);
+/// ```
+#[derive(Clone)]
+pub struct ManyBodyInteraction {
+    /// The many-body potential
+    potential: Box<ManyBodyPotential>,
+    /// Name of the particles interacting with this potential
+    name: String,
+    /// Neighbor list for the computations
+    neighbors: NeighborList,
+}
+
+impl ManyBodyInteraction {
+    /// Create a new `ManyBodyInteraction` using the given `potential` for all
+    /// the particles named `name`.
+    pub fn new<S: Into<String>>(potential: Box<ManyBodyPotential>, name: S) -> ManyBodyInteraction {
+        ManyBodyInteraction {
+            potential: potential,
+            name: name.into(),
+            neighbors: NeighborList::new(),
+        }
+    }
+
+    /// Get the name of the particles interacting with this potential
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check if the particle at index `i` in the `configuration` interacts
+    /// with this potential.
+    #[inline]
+    fn is_active(&self, configuration: &Configuration, i: usize) -> bool {
+        configuration.particle(i).name() == self.name
+    }
+
+    /// Get the neighbors closer than the cutoff of all the particles in the
+    /// `configuration`. The neighbors of particles not interacting with this
+    /// potential are empty.
+    fn neighborhoods(&self, configuration: &Configuration) -> Vec<Neighborhood> {
+        let cutoff = self.potential.cutoff();
+        let neighbors = self.neighbors.neighbors(configuration, Some(cutoff));
+        let mut neighborhoods = vec![Vec::new(); configuration.size()];
+        for i in 0..configuration.size() {
+            if !self.is_active(configuration, i) {
+                continue;
+            }
+
+            for &j in neighbors.of(i) {
+                if !self.is_active(configuration, j) {
+                    continue;
+                }
+
+                // nearest_image gives r_i - r_j
+                let rij = configuration.nearest_image(i, j);
+                if rij.norm2() < cutoff * cutoff {
+                    neighborhoods[i].push((j, -rij));
+                    neighborhoods[j].push((i, rij));
+                }
+            }
+        }
+        return neighborhoods;
+    }
+
+    /// Get the site energy of the particle `i` in the `configuration`, using
+    /// the `position` function to get the positions of the particles. The
+    /// `candidates` contains all particles that can be neighbors of `i`.
+    fn site_energy<F>(&self, configuration: &Configuration, i: usize, candidates: &BTreeSet<usize>, position: F) -> f64
+        where F: Fn(usize) -> Vector3D {
+        let cutoff = self.potential.cutoff();
+        let center = position(i);
+        let mut vectors = Vec::new();
+        for &j in candidates {
+            if j == i || !self.is_active(configuration, j) {
+                continue;
+            }
+            let mut rij = position(j) - center;
+            configuration.cell.vector_image(&mut rij);
+            if rij.norm2() < cutoff * cutoff {
+                vectors.push(rij);
+            }
+        }
+        return self.potential.energy(&vectors);
+    }
+}
+
+impl GlobalPotential for ManyBodyInteraction {
+    fn name(&self) -> &str {
+        "ManyBody"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.potential.cutoff())
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let mut energy = 0.0;
+        for neighborhood in self.neighborhoods(configuration) {
+            if neighborhood.is_empty() {
+                continue;
+            }
+            let vectors = neighborhood.iter().map(|&(_, r)| r).collect::<Vec<_>>();
+            energy += self.potential.energy(&vectors);
+        }
+        return energy;
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let mut gradient = Vec::new();
+        for (i, neighborhood) in self.neighborhoods(configuration).iter().enumerate() {
+            if neighborhood.is_empty() {
+                continue;
+            }
+            let vectors = neighborhood.iter().map(|&(_, r)| r).collect::<Vec<_>>();
+            gradient.clear();
+            gradient.resize(vectors.len(), Vector3D::zero());
+            self.potential.gradient(&vectors, &mut gradient);
+
+            for (&(j, _), g) in neighborhood.iter().zip(&gradient) {
+                forces[j] -= g;
+                forces[i] += g;
+            }
+        }
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let mut virial = Matrix3::zero();
+        let mut gradient = Vec::new();
+        for neighborhood in self.neighborhoods(configuration) {
+            if neighborhood.is_empty() {
+                continue;
+            }
+            let vectors = neighborhood.iter().map(|&(_, r)| r).collect::<Vec<_>>();
+            gradient.clear();
+            gradient.resize(vectors.len(), Vector3D::zero());
+            self.potential.gradient(&vectors, &mut gradient);
+
+            for (r, g) in vectors.iter().zip(&gradient) {
+                // The force acting on the neighbor is -g
+                virial -= g.tensorial(r);
+            }
+        }
+        return virial;
+    }
+}
+
+impl GlobalCache for ManyBodyInteraction {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let neighbors = self.neighbors.neighbors(configuration, Some(self.potential.cutoff()));
+
+        // Moving a particle changes the site energy of the particle and of
+        // all its neighbors, before and after the move.
+        let mut affected = BTreeSet::new();
+        for (idx, &i) in idxes.iter().enumerate() {
+            if !self.is_active(configuration, i) {
+                continue;
+            }
+            let _ = affected.insert(i);
+            let old_position = configuration.particle(i).position;
+            affected.extend(neighbors.around(&old_position));
+            affected.extend(neighbors.around(&newpos[idx]));
+        }
+
+        let old_position = |i: usize| configuration.particle(i).position;
+        let new_position = |i: usize| {
+            match idxes.iter().position(|&moved| moved == i) {
+                Some(idx) => newpos[idx],
+                None => configuration.particle(i).position,
+            }
+        };
+
+        let mut e_old = 0.0;
+        let mut e_new = 0.0;
+        for &i in &affected {
+            if !self.is_active(configuration, i) {
+                continue;
+            }
+
+            let mut candidates = neighbors.around(&old_position(i)).into_iter().collect::<BTreeSet<_>>();
+            candidates.extend(idxes.iter().cloned());
+            e_old += self.site_energy(configuration, i, &candidates, &old_position);
+
+            let mut candidates = neighbors.around(&new_position(i)).into_iter().collect::<BTreeSet<_>>();
+            candidates.extend(idxes.iter().cloned());
+            e_new += self.site_energy(configuration, i, &candidates, &new_position);
+        }
+
+        return e_new - e_old;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+mod stillinger_weber;
+pub use self::stillinger_weber::StillingerWeber;
+
+mod tersoff;
+pub use self::tersoff::Tersoff;
+
+mod eam;
+pub use self::eam::{EAM, EAMFunction};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::{System, Particle, UnitCell};
+    use energy::GlobalPotential;
+    use units;
+
+    /// Get a 2x2x2 supercell of diamond silicon with the lattice constant `a`,
+    /// slightly perturbed from the perfect crystal.
+    fn silicon(a: f64) -> System {
+        const BASIS: [[f64; 3]; 8] = [
+            [0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0],
+            [0.25, 0.25, 0.25], [0.25, 0.75, 0.75], [0.75, 0.25, 0.75], [0.75, 0.75, 0.25],
+        ];
+        let mut system = System::with_cell(UnitCell::cubic(2.0 * a));
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    for basis in &BASIS {
+                        let mut particle = Particle::new("Si");
+                        particle.position = a * Vector3D::new(
+                            i as f64 + basis[0], j as f64 + basis[1], k as f64 + basis[2]
+                        );
+                        system.add_particle(particle);
+                    }
+                }
+            }
+        }
+        return system;
+    }
+
+    fn perturb(system: &mut System) {
+        system.particle_mut(3).position += Vector3D::new(0.1, -0.05, 0.07);
+        system.particle_mut(10).position += Vector3D::new(-0.08, 0.02, 0.1);
+        system.particle_mut(42).position += Vector3D::new(0.03, 0.12, -0.04);
+    }
+
+    fn potentials() -> Vec<ManyBodyInteraction> {
+        vec![
+            ManyBodyInteraction::new(Box::new(StillingerWeber::silicon()), "Si"),
+            ManyBodyInteraction::new(Box::new(Tersoff::silicon()), "Si"),
+        ]
+    }
+
+    #[test]
+    fn cohesive_energy() {
+        let system = silicon(5.431);
+        let expected = [-4.3366, -4.6304];
+        for (potential, &expected) in potentials().iter().zip(&expected) {
+            let energy = units::to(potential.energy(&system), "eV").unwrap() / 64.0;
+            assert_relative_eq!(energy, expected, epsilon=1e-4);
+
+            // No forces in the perfect crystal
+            let mut forces = vec![Vector3D::zero(); system.size()];
+            potential.forces(&system, &mut forces);
+            for force in forces {
+                assert!(force.norm() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn other_particles() {
+        let mut system = silicon(5.431);
+        system.particle_mut(5).set_name("C");
+        for potential in potentials() {
+            let mut forces = vec![Vector3D::zero(); system.size()];
+            potential.forces(&system, &mut forces);
+            assert_eq!(forces[5], Vector3D::zero());
+
+            let energy = potential.energy(&system);
+            system.particle_mut(5).position += Vector3D::new(0.3, 0.2, 0.1);
+            assert_eq!(potential.energy(&system), energy);
+            system.particle_mut(5).position -= Vector3D::new(0.3, 0.2, 0.1);
+        }
+    }
+
+    #[test]
+    fn forces() {
+        let mut system = silicon(5.431);
+        perturb(&mut system);
+        for potential in potentials() {
+            let mut forces = vec![Vector3D::zero(); system.size()];
+            potential.forces(&system, &mut forces);
+
+            let total = forces.iter().fold(Vector3D::zero(), |sum, force| sum + force);
+            assert!(total.norm() < 1e-12);
+
+            let eps = 1e-6;
+            for &i in &[0, 3, 4, 10, 42] {
+                for axis in 0..3 {
+                    system.particle_mut(i).position[axis] += eps;
+                    let e_plus = potential.energy(&system);
+                    system.particle_mut(i).position[axis] -= 2.0 * eps;
+                    let e_minus = potential.energy(&system);
+                    system.particle_mut(i).position[axis] += eps;
+
+                    let expected = -(e_plus - e_minus) / (2.0 * eps);
+                    assert_relative_eq!(forces[i][axis], expected, epsilon=1e-8);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn virial() {
+        let mut system = silicon(5.431);
+        perturb(&mut system);
+        for potential in potentials() {
+            let virial = potential.virial(&system);
+
+            // The trace of the virial is minus the derivative of the energy
+            // with respect to an isotropic scaling of the system.
+            let eps = 1e-6;
+            let mut energies = Vec::new();
+            for &scale in &[1.0 + eps, 1.0 - eps] {
+                let mut scaled = system.clone();
+                scaled.cell = UnitCell::cubic(2.0 * 5.431 * scale);
+                for particle in scaled.particles_mut() {
+                    particle.position *= scale;
+                }
+                energies.push(potential.energy(&scaled));
+            }
+            let expected = -(energies[0] - energies[1]) / (2.0 * eps);
+            assert_relative_eq!(virial.trace(), expected, epsilon=1e-6);
+
+            // The virial is symmetric
+            assert_relative_eq!(virial, virial.transposed(), epsilon=1e-10);
+        }
+    }
+
+    #[test]
+    fn move_particles_cost() {
+        let mut system = silicon(5.431);
+        perturb(&mut system);
+        for potential in potentials() {
+            let idxes = [4, 10, 11];
+            let newpos = [
+                system.particle(4).position + Vector3D::new(0.2, 0.1, -0.3),
+                system.particle(10).position + Vector3D::new(-0.1, 0.0, 0.15),
+                system.particle(11).position + Vector3D::new(0.05, -0.2, 0.1),
+            ];
+
+            let old_energy = potential.energy(&system);
+            let cost = potential.move_particles_cost(&system, &idxes, &newpos);
+
+            let mut moved = system.clone();
+            for (&i, position) in idxes.iter().zip(&newpos) {
+                moved.particle_mut(i).position = *position;
+            }
+            let new_energy = potential.energy(&moved);
+            assert_relative_eq!(cost, new_energy - old_energy, epsilon=1e-10);
+        }
+    }
+}
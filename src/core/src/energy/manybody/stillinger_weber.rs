@@ -0,0 +1,189 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use types::Vector3D;
+use units;
+
+use super::ManyBodyPotential;
+
+/// Stillinger-Weber potential.
+///
+/// The Stillinger-Weber potential [Stillinger1985] contains a two-body and a
+/// three-body term, favoring a given angle between the neighbors of a particle.
+/// The energy is `E = sum_{i < j} phi_2(r_ij) + sum_i sum_{j < k} phi_3(r_ij,
+/// r_ik, theta_jik)` with
+///
+/// - `phi_2(r) = A epsilon (B (sigma / r)^p - (sigma / r)^q) exp(sigma / (r -
+///   a sigma))`;
+/// - `phi_3(r_ij, r_ik, theta) = lambda epsilon (cos(theta) - cos(theta_0))^2
+///   exp(gamma sigma / (r_ij - a sigma)) exp(gamma sigma / (r_ik - a sigma))`.
+///
+/// The cutoff distance is `a sigma`. This potential is used for silicon, and
+/// for the mW coarse-grained model of water [Molinero2009].
+///
+/// [Stillinger1985]: https://doi.org/10.1103/PhysRevB.31.5262
+/// [Molinero2009]: https://doi.org/10.1021/jp805227c
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{ManyBodyPotential, StillingerWeber};
+/// use lumol::types::Vector3D;
+/// use lumol::units;
+///
+/// let silicon = StillingerWeber::silicon();
+/// assert_eq!(silicon.cutoff(), 3.77118);
+///
+/// let neighbors = [Vector3D::new(2.35, 0.0, 0.0)];
+/// let energy = units::to(silicon.energy(&neighbors), "eV").unwrap();
+/// assert!(f64::abs(energy - -1.0841427970) < 1e-6);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct StillingerWeber {
+    /// Energy constant `epsilon`
+    pub epsilon: f64,
+    /// Distance constant `sigma`
+    pub sigma: f64,
+    /// Reduced cutoff distance `a`
+    pub a: f64,
+    /// Strength of the three-body term `lambda`
+    pub lambda: f64,
+    /// Width of the three-body term `gamma`
+    pub gamma: f64,
+    /// Cosine of the preferred angle `cos(theta_0)`
+    pub cos_theta0: f64,
+    /// Strength of the two-body term `A`
+    pub pair_a: f64,
+    /// Repulsive two-body term constant `B`
+    pub pair_b: f64,
+    /// Exponent of the repulsive two-body term `p`
+    pub p: f64,
+    /// Exponent of the attractive two-body term `q`
+    pub q: f64,
+}
+
+impl StillingerWeber {
+    /// Get the original parameters for silicon from [Stillinger1985].
+    ///
+    /// [Stillinger1985]: https://doi.org/10.1103/PhysRevB.31.5262
+    pub fn silicon() -> StillingerWeber {
+        StillingerWeber {
+            epsilon: units::from(2.1683, "eV").expect("bad unit"),
+            sigma: 2.0951,
+            a: 1.8,
+            lambda: 21.0,
+            gamma: 1.2,
+            cos_theta0: -1.0 / 3.0,
+            pair_a: 7.049556277,
+            pair_b: 0.6022245584,
+            p: 4.0,
+            q: 0.0,
+        }
+    }
+
+    /// Get the parameters for the mW model of water from [Molinero2009].
+    ///
+    /// [Molinero2009]: https://doi.org/10.1021/jp805227c
+    pub fn mw_water() -> StillingerWeber {
+        StillingerWeber {
+            epsilon: units::from(6.189, "kcal/mol").expect("bad unit"),
+            sigma: 2.3925,
+            a: 1.8,
+            lambda: 23.15,
+            gamma: 1.2,
+            cos_theta0: -1.0 / 3.0,
+            pair_a: 7.049556277,
+            pair_b: 0.6022245584,
+            p: 4.0,
+            q: 0.0,
+        }
+    }
+
+    /// Get the two-body energy `phi_2(r)` and its derivative with respect to
+    /// `r`.
+    fn pair(&self, r: f64) -> (f64, f64) {
+        let rc = self.a * self.sigma;
+        if r >= rc {
+            return (0.0, 0.0);
+        }
+        let sr = self.sigma / r;
+        let polynomial = self.pair_b * f64::powf(sr, self.p) - f64::powf(sr, self.q);
+        let polynomial_derivative = (
+            -self.p * self.pair_b * f64::powf(sr, self.p) + self.q * f64::powf(sr, self.q)
+        ) / r;
+        let exp = f64::exp(self.sigma / (r - rc));
+        let exp_derivative = -self.sigma / ((r - rc) * (r - rc)) * exp;
+
+        let prefactor = self.pair_a * self.epsilon;
+        let energy = prefactor * polynomial * exp;
+        let derivative = prefactor * (polynomial_derivative * exp + polynomial * exp_derivative);
+        return (energy, derivative);
+    }
+
+    /// Get the radial part of the three-body term `exp(gamma sigma / (r - a
+    /// sigma))` and its derivative with respect to `r`.
+    fn radial(&self, r: f64) -> (f64, f64) {
+        let rc = self.a * self.sigma;
+        if r >= rc {
+            return (0.0, 0.0);
+        }
+        let value = f64::exp(self.gamma * self.sigma / (r - rc));
+        let derivative = -self.gamma * self.sigma / ((r - rc) * (r - rc)) * value;
+        return (value, derivative);
+    }
+}
+
+impl ManyBodyPotential for StillingerWeber {
+    fn cutoff(&self) -> f64 {
+        self.a * self.sigma
+    }
+
+    fn energy(&self, neighbors: &[Vector3D]) -> f64 {
+        let mut energy = 0.0;
+        for (j, rij) in neighbors.iter().enumerate() {
+            let r_ij = rij.norm();
+            // The two-body energy is shared between the two particles
+            energy += 0.5 * self.pair(r_ij).0;
+
+            let (radial_j, _) = self.radial(r_ij);
+            for rik in &neighbors[(j + 1)..] {
+                let r_ik = rik.norm();
+                let (radial_k, _) = self.radial(r_ik);
+                let cos = (rij * rik) / (r_ij * r_ik);
+                let delta = cos - self.cos_theta0;
+                energy += self.lambda * self.epsilon * delta * delta * radial_j * radial_k;
+            }
+        }
+        return energy;
+    }
+
+    fn gradient(&self, neighbors: &[Vector3D], gradient: &mut [Vector3D]) {
+        debug_assert_eq!(neighbors.len(), gradient.len());
+        for g in gradient.iter_mut() {
+            *g = Vector3D::new(0.0, 0.0, 0.0);
+        }
+
+        let prefactor = self.lambda * self.epsilon;
+        for (j, rij) in neighbors.iter().enumerate() {
+            let r_ij = rij.norm();
+            let (_, pair_derivative) = self.pair(r_ij);
+            gradient[j] += 0.5 * pair_derivative / r_ij * rij;
+
+            let (radial_j, radial_derivative_j) = self.radial(r_ij);
+            for (k, rik) in neighbors.iter().enumerate().skip(j + 1) {
+                let r_ik = rik.norm();
+                let (radial_k, radial_derivative_k) = self.radial(r_ik);
+                let cos = (rij * rik) / (r_ij * r_ik);
+                let delta = cos - self.cos_theta0;
+
+                // Derivatives of cos(theta) with respect to rij and rik
+                let dcos_drij = rik / (r_ij * r_ik) - cos / (r_ij * r_ij) * rij;
+                let dcos_drik = rij / (r_ij * r_ik) - cos / (r_ik * r_ik) * rik;
+
+                let angular = 2.0 * prefactor * delta * radial_j * radial_k;
+                let radial = prefactor * delta * delta;
+                gradient[j] += angular * dcos_drij + radial * radial_derivative_j * radial_k / r_ij * rij;
+                gradient[k] += angular * dcos_drik + radial * radial_j * radial_derivative_k / r_ik * rik;
+            }
+        }
+    }
+}
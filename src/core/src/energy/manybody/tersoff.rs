@@ -0,0 +1,276 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use std::f64::consts::PI;
+
+use types::Vector3D;
+use units;
+
+use super::ManyBodyPotential;
+
+/// Tersoff potential.
+///
+/// The Tersoff potential [Tersoff1988] is a bond-order potential, where the
+/// strength of the bond between two particles depends on the environment of
+/// the bond. The energy is `E = 1/2 sum_i sum_{j != i} f_C(r_ij) (f_R(r_ij) +
+/// b_ij f_A(r_ij))` with
+///
+/// - `f_R(r) = A exp(-lambda_1 r)` the repulsive term;
+/// - `f_A(r) = -B exp(-lambda_2 r)` the attractive term;
+/// - `f_C(r)` a smooth cutoff function going from 1 at `R - D` to 0 at `R +
+///   D`;
+/// - `b_ij = (1 + beta^n zeta_ij^n)^(-1 / 2n)` the bond order, with `zeta_ij =
+///   sum_{k != i, j} f_C(r_ik) g(theta_ijk) exp(lambda_3^m (r_ij - r_ik)^m)`
+///   and `g(theta) = gamma (1 + c^2 / d^2 - c^2 / (d^2 + (h - cos(theta))^2))`.
+///
+/// This potential is used for silicon, carbon and germanium.
+///
+/// [Tersoff1988]: https://doi.org/10.1103/PhysRevB.37.6991
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{ManyBodyPotential, Tersoff};
+/// use lumol::types::Vector3D;
+/// use lumol::units;
+///
+/// let silicon = Tersoff::silicon();
+/// assert_eq!(silicon.cutoff(), 3.2);
+///
+/// let neighbors = [Vector3D::new(2.35, 0.0, 0.0)];
+/// let energy = units::to(silicon.energy(&neighbors), "eV").unwrap();
+/// assert!(f64::abs(energy - -1.3082314106) < 1e-6);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Tersoff {
+    /// Repulsive energy constant `A`
+    pub a: f64,
+    /// Attractive energy constant `B`
+    pub b: f64,
+    /// Repulsive decay constant `lambda_1`
+    pub lambda1: f64,
+    /// Attractive decay constant `lambda_2`
+    pub lambda2: f64,
+    /// Decay constant of the three-body term `lambda_3`
+    pub lambda3: f64,
+    /// Exponent of the three-body term `m`, usually 1 or 3
+    pub m: i32,
+    /// Bond order constant `beta`
+    pub beta: f64,
+    /// Bond order exponent `n`
+    pub n: f64,
+    /// Angular term strength `gamma`
+    pub gamma: f64,
+    /// Angular term constant `c`
+    pub c: f64,
+    /// Angular term constant `d`
+    pub d: f64,
+    /// Angular term constant `h`, cosine of the preferred angle
+    pub h: f64,
+    /// Center of the cutoff function `R`
+    pub r: f64,
+    /// Half width of the cutoff function `D`
+    pub cutoff_width: f64,
+}
+
+impl Tersoff {
+    /// Get the parameters for silicon from [Tersoff1988].
+    ///
+    /// [Tersoff1988]: https://doi.org/10.1103/PhysRevB.38.9902
+    pub fn silicon() -> Tersoff {
+        let ev = units::from(1.0, "eV").expect("bad unit");
+        Tersoff {
+            a: 3264.7 * ev,
+            b: 95.373 * ev,
+            lambda1: 3.2394,
+            lambda2: 1.3258,
+            lambda3: 1.3258,
+            m: 3,
+            beta: 0.33675,
+            n: 22.956,
+            gamma: 1.0,
+            c: 4.8381,
+            d: 2.0417,
+            h: 0.0,
+            r: 3.0,
+            cutoff_width: 0.2,
+        }
+    }
+
+    /// Get the parameters for carbon from [Tersoff1988].
+    ///
+    /// [Tersoff1988]: https://doi.org/10.1103/PhysRevLett.61.2879
+    pub fn carbon() -> Tersoff {
+        let ev = units::from(1.0, "eV").expect("bad unit");
+        Tersoff {
+            a: 1393.6 * ev,
+            b: 346.74 * ev,
+            lambda1: 3.4879,
+            lambda2: 2.2119,
+            lambda3: 0.0,
+            m: 3,
+            beta: 1.5724e-7,
+            n: 0.72751,
+            gamma: 1.0,
+            c: 38049.0,
+            d: 4.3484,
+            h: -0.57058,
+            r: 1.95,
+            cutoff_width: 0.15,
+        }
+    }
+
+    /// Get the cutoff function `f_C(r)` and its derivative
+    fn cutoff_function(&self, r: f64) -> (f64, f64) {
+        if r < self.r - self.cutoff_width {
+            return (1.0, 0.0);
+        } else if r > self.r + self.cutoff_width {
+            return (0.0, 0.0);
+        }
+        let x = 0.5 * PI * (r - self.r) / self.cutoff_width;
+        let value = 0.5 - 0.5 * f64::sin(x);
+        let derivative = -0.25 * PI / self.cutoff_width * f64::cos(x);
+        return (value, derivative);
+    }
+
+    /// Get the angular term `g(cos(theta))` and its derivative with respect
+    /// to `cos(theta)`
+    fn angular(&self, cos: f64) -> (f64, f64) {
+        let c2 = self.c * self.c;
+        let d2 = self.d * self.d;
+        let delta = self.h - cos;
+        let denominator = d2 + delta * delta;
+        let value = self.gamma * (1.0 + c2 / d2 - c2 / denominator);
+        let derivative = -2.0 * self.gamma * c2 * delta / (denominator * denominator);
+        return (value, derivative);
+    }
+
+    /// Get the exponential term `exp(lambda_3^m (r_ij - r_ik)^m)` and its
+    /// derivative with respect to `r_ij - r_ik`
+    fn exponential(&self, delta: f64) -> (f64, f64) {
+        let lambda = f64::powi(self.lambda3, self.m);
+        let value = f64::exp(lambda * f64::powi(delta, self.m));
+        let derivative = lambda * self.m as f64 * f64::powi(delta, self.m - 1) * value;
+        return (value, derivative);
+    }
+
+    /// Get the bond order `b_ij` and its derivative with respect to `zeta_ij`
+    fn bond_order(&self, zeta: f64) -> (f64, f64) {
+        if zeta <= 0.0 {
+            return (1.0, 0.0);
+        }
+        let bz = f64::powf(self.beta * zeta, self.n);
+        let value = f64::powf(1.0 + bz, -0.5 / self.n);
+        let derivative = -0.5 * bz / zeta * f64::powf(1.0 + bz, -0.5 / self.n - 1.0);
+        return (value, derivative);
+    }
+
+    /// Get the repulsive and attractive terms `f_R(r)` and `f_A(r)`, and their
+    /// derivatives.
+    fn radial(&self, r: f64) -> ((f64, f64), (f64, f64)) {
+        let repulsive = self.a * f64::exp(-self.lambda1 * r);
+        let attractive = -self.b * f64::exp(-self.lambda2 * r);
+        return (
+            (repulsive, -self.lambda1 * repulsive),
+            (attractive, -self.lambda2 * attractive),
+        );
+    }
+
+    /// Compute `zeta_ij` for the neighbor `j`
+    fn zeta(&self, neighbors: &[Vector3D], j: usize) -> f64 {
+        let rij = &neighbors[j];
+        let r_ij = rij.norm();
+        let mut zeta = 0.0;
+        for (k, rik) in neighbors.iter().enumerate() {
+            if k == j {
+                continue;
+            }
+            let r_ik = rik.norm();
+            let (cutoff, _) = self.cutoff_function(r_ik);
+            if cutoff == 0.0 {
+                continue;
+            }
+            let cos = (rij * rik) / (r_ij * r_ik);
+            zeta += cutoff * self.angular(cos).0 * self.exponential(r_ij - r_ik).0;
+        }
+        return zeta;
+    }
+}
+
+impl ManyBodyPotential for Tersoff {
+    fn cutoff(&self) -> f64 {
+        self.r + self.cutoff_width
+    }
+
+    fn energy(&self, neighbors: &[Vector3D]) -> f64 {
+        let mut energy = 0.0;
+        for (j, rij) in neighbors.iter().enumerate() {
+            let r_ij = rij.norm();
+            let (cutoff, _) = self.cutoff_function(r_ij);
+            if cutoff == 0.0 {
+                continue;
+            }
+            let ((repulsive, _), (attractive, _)) = self.radial(r_ij);
+            let (bond_order, _) = self.bond_order(self.zeta(neighbors, j));
+            energy += 0.5 * cutoff * (repulsive + bond_order * attractive);
+        }
+        return energy;
+    }
+
+    fn gradient(&self, neighbors: &[Vector3D], gradient: &mut [Vector3D]) {
+        debug_assert_eq!(neighbors.len(), gradient.len());
+        for g in gradient.iter_mut() {
+            *g = Vector3D::new(0.0, 0.0, 0.0);
+        }
+
+        for (j, rij) in neighbors.iter().enumerate() {
+            let r_ij = rij.norm();
+            let (cutoff_j, cutoff_derivative_j) = self.cutoff_function(r_ij);
+            if cutoff_j == 0.0 {
+                continue;
+            }
+            let unit_j = rij / r_ij;
+
+            let ((repulsive, repulsive_derivative), (attractive, attractive_derivative)) = self.radial(r_ij);
+            let (bond_order, bond_order_derivative) = self.bond_order(self.zeta(neighbors, j));
+
+            // Derivative of the pair term at fixed bond order
+            let pair = cutoff_derivative_j * (repulsive + bond_order * attractive)
+                     + cutoff_j * (repulsive_derivative + bond_order * attractive_derivative);
+            gradient[j] += 0.5 * pair * unit_j;
+
+            // Derivative of the bond order with respect to the positions of
+            // all the neighbors
+            let prefactor = 0.5 * cutoff_j * attractive * bond_order_derivative;
+            if prefactor == 0.0 {
+                continue;
+            }
+            for (k, rik) in neighbors.iter().enumerate() {
+                if k == j {
+                    continue;
+                }
+                let r_ik = rik.norm();
+                let (cutoff_k, cutoff_derivative_k) = self.cutoff_function(r_ik);
+                if cutoff_k == 0.0 {
+                    continue;
+                }
+                let unit_k = rik / r_ik;
+
+                let cos = (rij * rik) / (r_ij * r_ik);
+                let (angular, angular_derivative) = self.angular(cos);
+                let (exponential, exponential_derivative) = self.exponential(r_ij - r_ik);
+
+                let dcos_drij = rik / (r_ij * r_ik) - cos / r_ij * unit_j;
+                let dcos_drik = rij / (r_ij * r_ik) - cos / r_ik * unit_k;
+
+                let dzeta_drij = cutoff_k * angular_derivative * exponential * dcos_drij
+                               + cutoff_k * angular * exponential_derivative * unit_j;
+                let dzeta_drik = cutoff_derivative_k * angular * exponential * unit_k
+                               + cutoff_k * angular_derivative * exponential * dcos_drik
+                               - cutoff_k * angular * exponential_derivative * unit_k;
+
+                gradient[j] += prefactor * dzeta_drij;
+                gradient[k] += prefactor * dzeta_drik;
+            }
+        }
+    }
+}
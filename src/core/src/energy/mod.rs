@@ -22,6 +22,10 @@
 //! - [`DihedralPotential`][DihedralPotential] for covalent dihedral angles
 //!   interactions.
 //!
+//! Bonded terms needing the full geometry of an angle, like Urey-Bradley or
+//! class-II cross terms, implement the [`AngleGeometryPotential`]
+//! [AngleGeometryPotential] trait instead.
+//!
 //! ```
 //! use lumol::energy::{Potential, PairPotential, DihedralPotential};
 //!
@@ -52,11 +56,20 @@
 //! specific version of global potentials used to compute electrostatic
 //! interactions.
 //!
+//! Restraints on the positions of particles, or on the distances, angles and
+//! dihedral angles between arbitrary particles are also implemented as global
+//! potentials, as well as external fields acting on the particles: uniform
+//! electric field and constant forces. Walls on the faces of the unit cell
+//! use a [`WallPotential`][WallPotential] for the interaction between the
+//! particles and the wall.
+//!
 //! [Potential]: trait.Potential.html
 //! [PairPotential]: trait.PairPotential.html
 //! [BondPotential]: trait.BondPotential.html
 //! [AnglePotential]: trait.AnglePotential.html
 //! [DihedralPotential]: trait.DihedralPotential.html
+//! [AngleGeometryPotential]: trait.AngleGeometryPotential.html
+//! [WallPotential]: trait.WallPotential.html
 //! [GlobalPotential]: trait.GlobalPotential.html
 //! [CoulombicPotential]: trait.CoulombicPotential.html
 use types::{Matrix3, Vector3D};
@@ -192,6 +205,14 @@ pub trait BondPotential : Potential + BoxCloneBond {
         let force = fact * rn;
         force.tensorial(r)
     }
+
+    /// Get the maximal length of a bond using this potential, if any. The
+    /// energy of the bond is infinite after this length, and molecular
+    /// dynamics simulations will stop with an error if a bond is stretched
+    /// past it. The default implementation returns `None`.
+    fn max_length(&self) -> Option<f64> {
+        None
+    }
 }
 impl_box_clone!(BondPotential, BoxCloneBond, box_clone_bond);
 
@@ -242,6 +263,13 @@ impl_box_clone!(DihedralPotential, BoxCloneDihedral, box_clone_dihedral);
 mod functions;
 pub use self::functions::{NullPotential, LennardJones, Harmonic, CosineHarmonic};
 pub use self::functions::{Torsion, Buckingham, BornMayerHuggins, MorsePotential};
+pub use self::functions::{Mie, SoftCoreLennardJones, TablePotential};
+pub use self::functions::{RyckaertBellemans, Fourier};
+pub use self::functions::{Fene, WCA, Quartic};
+
+mod angles;
+pub use self::angles::AngleGeometryPotential;
+pub use self::angles::{UreyBradley, BondBond, BondAngle};
 
 mod computations;
 pub use self::computations::{Computation, TableComputation};
@@ -251,7 +279,24 @@ pub use self::restrictions::{PairRestriction, RestrictionInfo};
 
 mod global;
 pub use self::global::{GlobalPotential, GlobalCache, CoulombicPotential};
-pub use self::global::{Wolf, Ewald, SharedEwald};
+pub use self::global::{Wolf, ReactionField, DampedShiftedForce};
+pub use self::global::{Ewald, SharedEwald, SPME, SharedSPME};
 
 mod pairs;
-pub use self::pairs::PairInteraction;
+pub use self::pairs::{PairInteraction, Switching};
+
+mod manybody;
+pub use self::manybody::{ManyBodyPotential, ManyBodyInteraction};
+pub use self::manybody::{StillingerWeber, Tersoff};
+pub use self::manybody::{EAM, EAMFunction};
+
+mod restraints;
+pub use self::restraints::{PositionRestraint, DistanceRestraint};
+pub use self::restraints::{AngleRestraint, DihedralRestraint};
+
+mod external;
+pub use self::external::{ElectricField, ConstantForce};
+
+mod walls;
+pub use self::walls::{WallPotential, HardWall, LennardJones93, LennardJones1043};
+pub use self::walls::{Wall, CellFace};
@@ -4,13 +4,150 @@
 use energy::{PairPotential, PairRestriction};
 use types::{Vector3D, Matrix3, One, Zero};
 
+/// Switching functions bringing smoothly pair interactions to zero between
+/// a switching distance `r_on` and the cutoff distance `r_off`.
+///
+/// In the following, `S(r)` is the switching function, `V(r)` the energy and
+/// `f(r)` the force of the underlying potential, and `x = (r - r_on) / (r_off
+/// - r_on)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Switching {
+    /// CHARMM energy switch: the energy is `V(r) S(r)` with `S(r) = (r_off^2 -
+    /// r^2)^2 (r_off^2 + 2 r^2 - 3 r_on^2) / (r_off^2 - r_on^2)^3`.
+    Energy,
+    /// Force switch: the force is `f(r) S(r)` with the same `S(r)` as the
+    /// CHARMM energy switch. The energy is the integral of this force,
+    /// tabulated once when creating the interaction, and is shifted by a
+    /// constant before `r_on`.
+    Force,
+    /// Cubic polynomial energy switch: the energy is `V(r) S(r)` with `S(x) =
+    /// 1 - 3 x^2 + 2 x^3`.
+    Cubic,
+    /// Quintic polynomial energy switch: the energy is `V(r) S(r)` with `S(x)
+    /// = 1 - 10 x^3 + 15 x^4 - 6 x^5`. The force is continuous and derivable
+    /// at `r_on` and `r_off`.
+    Quintic,
+}
+
+impl Switching {
+    /// Get the value of the switching function and of its derivative at the
+    /// distance `r`, for a switch between `on` and `off`.
+    fn switch(&self, on: f64, off: f64, r: f64) -> (f64, f64) {
+        if r <= on {
+            return (1.0, 0.0);
+        } else if r >= off {
+            return (0.0, 0.0);
+        }
+
+        match *self {
+            Switching::Energy | Switching::Force => {
+                let (on2, off2, r2) = (on * on, off * off, r * r);
+                let denominator = (off2 - on2) * (off2 - on2) * (off2 - on2);
+                let value = (off2 - r2) * (off2 - r2) * (off2 + 2.0 * r2 - 3.0 * on2) / denominator;
+                let derivative = 12.0 * r * (off2 - r2) * (on2 - r2) / denominator;
+                (value, derivative)
+            }
+            Switching::Cubic => {
+                let x = (r - on) / (off - on);
+                let value = 1.0 - 3.0 * x * x + 2.0 * x * x * x;
+                let derivative = (-6.0 * x + 6.0 * x * x) / (off - on);
+                (value, derivative)
+            }
+            Switching::Quintic => {
+                let x = (r - on) / (off - on);
+                let x2 = x * x;
+                let value = 1.0 - 10.0 * x2 * x + 15.0 * x2 * x2 - 6.0 * x2 * x2 * x;
+                let derivative = (-30.0 * x2 + 60.0 * x2 * x - 30.0 * x2 * x2) / (off - on);
+                (value, derivative)
+            }
+        }
+    }
+}
+
 /// The different way to compute non-bonded pair interactions
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 enum PairComputation {
     /// Using only a cutoff distance
     Cutoff,
     /// Using a cutoff distance and a shift
     Shifted(f64),
+    /// Using a switching function between `on` and the cutoff distance. The
+    /// `shift` and `table` are only used for force switching.
+    Switched {
+        /// The switching function
+        switching: Switching,
+        /// The switching distance
+        on: f64,
+        /// Energy shift before the switching distance
+        shift: f64,
+        /// Values of the energy and its derivative on a regular grid between
+        /// `on` and the cutoff distance.
+        table: Vec<(f64, f64)>,
+    },
+}
+
+/// Number of intervals in the tabulated energy for force switching
+const FORCE_SWITCH_INTERVALS: usize = 512;
+
+/// Abscissas of the 8-points Gauss-Legendre quadrature on [-1, 1]
+const GAUSS_POINTS: [f64; 8] = [
+    -0.9602898564975363, -0.7966664774136267, -0.5255324099163290, -0.1834346424956498,
+    0.1834346424956498, 0.5255324099163290, 0.7966664774136267, 0.9602898564975363
+];
+
+/// Weights of the 8-points Gauss-Legendre quadrature on [-1, 1]
+const GAUSS_WEIGHTS: [f64; 8] = [
+    0.1012285362903763, 0.2223810344533745, 0.3137066458778873, 0.3626837833783620,
+    0.3626837833783620, 0.3137066458778873, 0.2223810344533745, 0.1012285362903763
+];
+
+/// Integrate the `function` between `a` and `b`, using a composite
+/// Gauss-Legendre quadrature.
+fn integrate<F: Fn(f64) -> f64>(function: F, a: f64, b: f64) -> f64 {
+    const INTERVALS: usize = 8;
+    let width = (b - a) / INTERVALS as f64;
+    let mut integral = 0.0;
+    for interval in 0..INTERVALS {
+        let center = a + (interval as f64 + 0.5) * width;
+        for (point, weight) in GAUSS_POINTS.iter().zip(GAUSS_WEIGHTS.iter()) {
+            integral += weight * function(center + 0.5 * width * point);
+        }
+    }
+    return 0.5 * width * integral;
+}
+
+/// Tabulate the energy corresponding to the force `f(r) S(r)` between `on`
+/// and `off`, using `FORCE_SWITCH_INTERVALS` intervals. Each entry contains
+/// the energy and its derivative, the energy being zero at `off`.
+fn force_switch_table(potential: &PairPotential, switching: Switching, on: f64, off: f64) -> Vec<(f64, f64)> {
+    let delta = (off - on) / FORCE_SWITCH_INTERVALS as f64;
+    let derivative = |r: f64| -potential.force(r) * switching.switch(on, off, r).0;
+
+    let mut table = vec![(0.0, 0.0); FORCE_SWITCH_INTERVALS + 1];
+    for i in (0..FORCE_SWITCH_INTERVALS).rev() {
+        let r = on + i as f64 * delta;
+        let energy = table[i + 1].0 - integrate(&derivative, r, r + delta);
+        table[i] = (energy, derivative(r));
+    }
+    return table;
+}
+
+/// Interpolate the energy at `r` from a `table` created by
+/// `force_switch_table` between `on` and `off`, using cubic Hermite
+/// polynomials. The derivative of the interpolated energy is continuous, and
+/// matches the switched force on the grid points.
+fn interpolate_table(table: &[(f64, f64)], on: f64, off: f64, r: f64) -> f64 {
+    let delta = (off - on) / (table.len() - 1) as f64;
+    let position = (r - on) / delta;
+    let i = ::std::cmp::min(position.floor() as usize, table.len() - 2);
+    let t = position - i as f64;
+
+    let (e0, d0) = table[i];
+    let (e1, d1) = table[i + 1];
+    let t2 = t * t;
+    let t3 = t2 * t;
+    return (2.0 * t3 - 3.0 * t2 + 1.0) * e0 + (t3 - 2.0 * t2 + t) * delta * d0
+         + (-2.0 * t3 + 3.0 * t2) * e1 + (t3 - t2) * delta * d1;
 }
 
 /// A non-bonded interaction between two particle.
@@ -94,6 +231,53 @@ impl PairInteraction {
         }
     }
 
+    /// Create a new `PairInteraction` with the given `cutoff`, using the
+    /// `switching` function to bring the interaction smoothly to zero
+    /// between the `on` distance and the `cutoff`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol::energy::{PairInteraction, Switching};
+    /// use lumol::energy::LennardJones;
+    ///
+    /// let potential = Box::new(LennardJones{sigma: 1.0, epsilon: 2.0});
+    /// let interaction = PairInteraction::switched(potential, Switching::Energy, 2.5, 3.0);
+    ///
+    /// // energy is not modified before the switching distance
+    /// assert_eq!(interaction.energy(2.0), -0.123046875);
+    ///
+    /// // energy and force goes smoothly to zero at the cutoff
+    /// assert!(interaction.energy(2.999).abs() < 1e-6);
+    /// assert!(interaction.force(2.999).abs() < 1e-3);
+    /// assert_eq!(interaction.energy(3.0), 0.0);
+    /// ```
+    pub fn switched(potential: Box<PairPotential>, switching: Switching, on: f64, cutoff: f64) -> PairInteraction {
+        assert!(0.0 < on && on < cutoff, "the switching distance must be between 0 and the cutoff");
+        let (shift, table) = if switching == Switching::Force {
+            // Energy is the integral of the switched force, which is computed
+            // once and tabulated. The shift ensures that the energy is
+            // continuous at `on`.
+            let table = force_switch_table(&*potential, switching, on, cutoff);
+            (potential.energy(on) - table[0].0, table)
+        } else {
+            (0.0, Vec::new())
+        };
+
+        PairInteraction {
+            potential: potential,
+            cutoff: cutoff,
+            restriction: PairRestriction::None,
+            computation: PairComputation::Switched {
+                switching: switching,
+                on: on,
+                shift: shift,
+                table: table,
+            },
+            tail: false,
+        }
+    }
+
     /// Enable the use of tail corrections for energy and virial contribution
     /// of this pair interaction.
     ///
@@ -190,6 +374,17 @@ impl PairInteraction {
             match self.computation {
                 PairComputation::Cutoff => energy,
                 PairComputation::Shifted(shift) => energy - shift,
+                PairComputation::Switched{switching, on, shift, ref table} => {
+                    if switching == Switching::Force {
+                        if r <= on {
+                            energy - shift
+                        } else {
+                            interpolate_table(table, on, self.cutoff, r)
+                        }
+                    } else {
+                        energy * switching.switch(on, self.cutoff, r).0
+                    }
+                }
             }
         }
     }
@@ -213,7 +408,18 @@ impl PairInteraction {
         if r >= self.cutoff {
             0.0
         } else {
-            self.potential.force(r)
+            let force = self.potential.force(r);
+            match self.computation {
+                PairComputation::Cutoff | PairComputation::Shifted(..) => force,
+                PairComputation::Switched{switching, on, ..} => {
+                    let (switch, derivative) = switching.switch(on, self.cutoff, r);
+                    if switching == Switching::Force {
+                        force * switch
+                    } else {
+                        force * switch - self.potential.energy(r) * derivative
+                    }
+                }
+            }
         }
     }
 
@@ -238,7 +444,15 @@ impl PairInteraction {
         if r.norm() >= self.cutoff {
             Matrix3::zero()
         } else {
-            self.potential.virial(r)
+            match self.computation {
+                PairComputation::Cutoff | PairComputation::Shifted(..) => {
+                    self.potential.virial(r)
+                }
+                PairComputation::Switched{..} => {
+                    let force = self.force(r.norm()) * r.normalized();
+                    force.tensorial(r)
+                }
+            }
         }
     }
 
@@ -258,9 +472,16 @@ impl PairInteraction {
     /// ```
     pub fn tail_energy(&self) -> f64 {
         if self.tail {
-            self.potential.tail_energy(self.cutoff)
+            let mut tail = self.potential.tail_energy(self.cutoff);
+            if let PairComputation::Switched{on, ..} = self.computation {
+                // Add the part of the energy removed by the switch
+                tail += integrate(|r| {
+                    r * r * (self.potential.energy(r) - self.energy(r))
+                }, on, self.cutoff);
+            }
+            return tail;
         } else {
-            0.0
+            return 0.0;
         }
     }
 
@@ -289,7 +510,14 @@ impl PairInteraction {
     pub fn tail_virial(&self) -> Matrix3 {
         if self.tail {
             let tensor = Matrix3::one() / 3.0;
-            return self.potential.tail_virial(self.cutoff) * tensor;
+            let mut tail = self.potential.tail_virial(self.cutoff);
+            if let PairComputation::Switched{on, ..} = self.computation {
+                // Add the part of the virial removed by the switch
+                tail += integrate(|r| {
+                    r * r * r * (self.potential.force(r) - self.force(r))
+                }, on, self.cutoff);
+            }
+            return tail * tensor;
         } else {
             return Matrix3::zero();
         }
@@ -301,6 +529,7 @@ mod tests {
     use super::*;
     use energy::{NullPotential, LennardJones, PairRestriction};
     use energy::Potential;
+    use types::Vector3D;
 
     #[test]
     fn restriction() {
@@ -337,6 +566,75 @@ mod tests {
         assert_eq!(pairs.energy(4.1), 0.0);
     }
 
+    #[test]
+    fn switched() {
+        let lj = LennardJones{sigma: 1.0, epsilon: 2.0};
+        for &switching in &[Switching::Energy, Switching::Force, Switching::Cubic, Switching::Quintic] {
+            let pairs = PairInteraction::switched(Box::new(lj), switching, 3.0, 4.0);
+
+            assert_eq!(pairs.force(2.5), lj.force(2.5));
+            if switching != Switching::Force {
+                assert_eq!(pairs.energy(2.5), lj.energy(2.5));
+            }
+
+            // Continuity at the switching distance and at the cutoff
+            let eps = 1e-9;
+            assert_relative_eq!(pairs.energy(3.0 - eps), pairs.energy(3.0 + eps), epsilon=1e-8);
+            assert_relative_eq!(pairs.force(3.0 - eps), pairs.force(3.0 + eps), epsilon=1e-8);
+            assert_relative_eq!(pairs.energy(4.0 - eps), 0.0, epsilon=1e-8);
+            assert_relative_eq!(pairs.force(4.0 - eps), 0.0, epsilon=1e-8);
+
+            assert_eq!(pairs.force(4.1), 0.0);
+            assert_eq!(pairs.energy(4.1), 0.0);
+
+            // The force is the derivative of the energy
+            for &r in &[2.5, 3.2, 3.5, 3.9] {
+                let eps = 1e-6;
+                let finite_difference = -(pairs.energy(r + eps) - pairs.energy(r - eps)) / (2.0 * eps);
+                assert_relative_eq!(pairs.force(r), finite_difference, epsilon=1e-8);
+
+                let r = Vector3D::new(r, 0.0, 0.0);
+                assert_eq!(pairs.virial(&r)[0][0], pairs.force(r[0]) * r[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn force_switch_energy() {
+        let lj = LennardJones{sigma: 1.0, epsilon: 2.0};
+        let pairs = PairInteraction::switched(Box::new(lj), Switching::Force, 3.0, 4.0);
+
+        for &r in &[3.0, 3.0001, 3.2, 3.51, 3.77, 3.9999] {
+            let expected = integrate(|x| {
+                lj.force(x) * Switching::Force.switch(3.0, 4.0, x).0
+            }, r, 4.0);
+            assert_relative_eq!(pairs.energy(r), expected, epsilon=1e-12);
+        }
+
+        // The energy before the switching distance is shifted
+        let shift = lj.energy(3.0) - pairs.energy(3.0);
+        assert_relative_eq!(pairs.energy(2.5), lj.energy(2.5) - shift, epsilon=1e-12);
+    }
+
+    #[test]
+    fn switched_tail_corrections() {
+        let lj = LennardJones{sigma: 1.0, epsilon: 2.0};
+        let mut pairs = PairInteraction::switched(Box::new(lj), Switching::Quintic, 3.0, 4.0);
+        pairs.enable_tail_corrections();
+
+        // With tail corrections, the switched potential is equivalent to the
+        // potential with a cutoff at the switching distance.
+        let mut reference = PairInteraction::new(Box::new(lj), 3.0);
+        reference.enable_tail_corrections();
+
+        let switched = integrate(|r| r * r * pairs.energy(r), 3.0, 4.0);
+        assert_relative_eq!(pairs.tail_energy() + switched, reference.tail_energy(), epsilon=1e-12);
+
+        let switched = integrate(|r| r * r * r * pairs.force(r), 3.0, 4.0);
+        let expected = reference.tail_virial().trace();
+        assert_relative_eq!(pairs.tail_virial().trace() + switched, expected, epsilon=1e-12);
+    }
+
     #[test]
     fn tail_corrections() {
         let lj = LennardJones{sigma: 1.0, epsilon: 2.0};
@@ -0,0 +1,566 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Harmonic restraints on the positions of particles, or on the distances,
+//! angles and dihedral angles between arbitrary particles.
+//!
+//! All the restraints use a flat-bottomed harmonic function of the deviation
+//! `delta` of the restrained coordinate from its reference value: `V = 1/2 k
+//! (|delta| - width)^2` if `|delta| > width`, and `V = 0` otherwise. Setting
+//! `width` to zero gives a purely harmonic restraint.
+use std::f64::consts::PI;
+
+use sys::{Configuration, UnitCell};
+use types::{Matrix3, Vector3D, Zero};
+
+use energy::{GlobalPotential, GlobalCache};
+
+/// Get the energy and the derivative of the energy of a flat-bottomed
+/// harmonic restraint, for a deviation `delta` from the reference value.
+fn flat_bottom(k: f64, width: f64, delta: f64) -> (f64, f64) {
+    let excess = f64::abs(delta) - width;
+    if excess <= 0.0 {
+        (0.0, 0.0)
+    } else {
+        (0.5 * k * excess * excess, k * excess * f64::signum(delta))
+    }
+}
+
+/// Compute the cost of moving the particles at `idxes` to `newpos`, for a
+/// restraint acting on `particles`, and with the `energy` function taking the
+/// positions of the particles as a function.
+fn move_particles_cost<F>(
+    configuration: &Configuration,
+    particles: &[usize],
+    idxes: &[usize],
+    newpos: &[Vector3D],
+    energy: F
+) -> f64 where F: Fn(&Fn(usize) -> Vector3D) -> f64 {
+    if !idxes.iter().any(|i| particles.contains(i)) {
+        return 0.0;
+    }
+
+    let old_position = |i: usize| configuration.particle(i).position;
+    let new_position = |i: usize| {
+        match idxes.iter().position(|&moved| moved == i) {
+            Some(idx) => newpos[idx],
+            None => configuration.particle(i).position,
+        }
+    };
+    return energy(&new_position) - energy(&old_position);
+}
+
+/// Harmonic restraint of the positions of some particles to reference
+/// positions.
+///
+/// The restrained coordinate is the distance between the particle and its
+/// reference position, using periodic boundary conditions. The virial
+/// contribution is computed as if the reference positions were fixed
+/// particles bonded to the restrained particles.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, PositionRestraint};
+/// use lumol::sys::{System, Particle};
+/// use lumol::types::Vector3D;
+///
+/// let mut system = System::new();
+/// system.add_particle(Particle::new("Ar"));
+/// system.particle_mut(0).position = Vector3D::new(1.0, 0.0, 0.0);
+///
+/// let restraint = PositionRestraint {
+///     references: vec![(0, Vector3D::new(0.0, 0.0, 0.0))],
+///     k: 10.0,
+///     width: 0.0,
+/// };
+/// assert_eq!(restraint.energy(&system), 5.0);
+///
+/// // With a flat bottom, the restraint only acts outside of a sphere
+/// let restraint = PositionRestraint {
+///     references: vec![(0, Vector3D::new(0.0, 0.0, 0.0))],
+///     k: 10.0,
+///     width: 2.0,
+/// };
+/// assert_eq!(restraint.energy(&system), 0.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PositionRestraint {
+    /// Indexes of the restrained particles, and the associated reference
+    /// positions
+    pub references: Vec<(usize, Vector3D)>,
+    /// Force constant of the restraint
+    pub k: f64,
+    /// Radius of the flat bottom region around the reference positions
+    pub width: f64,
+}
+
+impl PositionRestraint {
+    /// Get the displacement of a particle at `position` from its `reference`
+    fn displacement(cell: &UnitCell, position: Vector3D, reference: &Vector3D) -> Vector3D {
+        let mut delta = position - reference;
+        cell.vector_image(&mut delta);
+        return delta;
+    }
+
+    /// Get the force acting on a particle with the displacement `delta`
+    fn force(&self, delta: &Vector3D) -> Vector3D {
+        let r = delta.norm();
+        let (_, derivative) = flat_bottom(self.k, self.width, r);
+        if derivative == 0.0 {
+            return Vector3D::zero();
+        }
+        return - derivative / r * delta;
+    }
+
+    fn energy_with(&self, cell: &UnitCell, position: &Fn(usize) -> Vector3D) -> f64 {
+        let mut energy = 0.0;
+        for &(i, ref reference) in &self.references {
+            let delta = PositionRestraint::displacement(cell, position(i), reference);
+            energy += flat_bottom(self.k, self.width, delta.norm()).0;
+        }
+        return energy;
+    }
+}
+
+impl GlobalPotential for PositionRestraint {
+    fn name(&self) -> &str {
+        "PositionRestraint"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        self.energy_with(&configuration.cell, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        for &(i, ref reference) in &self.references {
+            let position = configuration.particle(i).position;
+            let delta = PositionRestraint::displacement(&configuration.cell, position, reference);
+            forces[i] += self.force(&delta);
+        }
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let mut virial = Matrix3::zero();
+        for &(i, ref reference) in &self.references {
+            let position = configuration.particle(i).position;
+            let delta = PositionRestraint::displacement(&configuration.cell, position, reference);
+            virial += self.force(&delta).tensorial(&delta);
+        }
+        return virial;
+    }
+}
+
+impl GlobalCache for PositionRestraint {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let particles = self.references.iter().map(|&(i, _)| i).collect::<Vec<_>>();
+        move_particles_cost(configuration, &particles, idxes, newpos, |position| {
+            self.energy_with(&configuration.cell, position)
+        })
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+/// Harmonic restraint of the distance between two particles.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, DistanceRestraint};
+/// use lumol::sys::{System, Particle};
+/// use lumol::types::Vector3D;
+///
+/// let mut system = System::new();
+/// system.add_particle(Particle::new("Ar"));
+/// system.add_particle(Particle::new("Ar"));
+/// system.particle_mut(1).position = Vector3D::new(4.0, 0.0, 0.0);
+///
+/// let restraint = DistanceRestraint{particles: [0, 1], k: 2.0, x0: 3.0, width: 0.0};
+/// assert_eq!(restraint.energy(&system), 1.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DistanceRestraint {
+    /// Indexes of the restrained particles
+    pub particles: [usize; 2],
+    /// Force constant of the restraint
+    pub k: f64,
+    /// Reference distance
+    pub x0: f64,
+    /// Half width of the flat bottom region around the reference distance
+    pub width: f64,
+}
+
+impl DistanceRestraint {
+    fn energy_with(&self, cell: &UnitCell, position: &Fn(usize) -> Vector3D) -> f64 {
+        let (i, j) = (self.particles[0], self.particles[1]);
+        let r = cell.distance(&position(i), &position(j));
+        return flat_bottom(self.k, self.width, r - self.x0).0;
+    }
+
+    /// Get the vector between the particles, and the force acting on the
+    /// first particle.
+    fn force(&self, configuration: &Configuration) -> (Vector3D, Vector3D) {
+        let (i, j) = (self.particles[0], self.particles[1]);
+        let rij = configuration.nearest_image(i, j);
+        let r = rij.norm();
+        let (_, derivative) = flat_bottom(self.k, self.width, r - self.x0);
+        if derivative == 0.0 {
+            return (rij, Vector3D::zero());
+        }
+        return (rij, - derivative / r * rij);
+    }
+}
+
+impl GlobalPotential for DistanceRestraint {
+    fn name(&self) -> &str {
+        "DistanceRestraint"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        self.energy_with(&configuration.cell, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let (i, j) = (self.particles[0], self.particles[1]);
+        let (_, force) = self.force(configuration);
+        forces[i] += force;
+        forces[j] -= force;
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let (rij, force) = self.force(configuration);
+        return force.tensorial(&rij);
+    }
+}
+
+impl GlobalCache for DistanceRestraint {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        move_particles_cost(configuration, &self.particles, idxes, newpos, |position| {
+            self.energy_with(&configuration.cell, position)
+        })
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+/// Harmonic restraint of the angle formed by three particles.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, AngleRestraint};
+/// use lumol::sys::{System, Particle};
+/// use lumol::types::Vector3D;
+/// use std::f64::consts::PI;
+///
+/// let mut system = System::new();
+/// system.add_particle(Particle::new("Ar"));
+/// system.add_particle(Particle::new("Ar"));
+/// system.add_particle(Particle::new("Ar"));
+/// system.particle_mut(0).position = Vector3D::new(1.0, 0.0, 0.0);
+/// system.particle_mut(2).position = Vector3D::new(0.0, 1.0, 0.0);
+///
+/// let restraint = AngleRestraint{particles: [0, 1, 2], k: 2.0, x0: PI / 2.0, width: 0.0};
+/// assert!(restraint.energy(&system).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AngleRestraint {
+    /// Indexes of the restrained particles, the second one being at the
+    /// center of the angle
+    pub particles: [usize; 3],
+    /// Force constant of the restraint
+    pub k: f64,
+    /// Reference angle
+    pub x0: f64,
+    /// Half width of the flat bottom region around the reference angle
+    pub width: f64,
+}
+
+impl AngleRestraint {
+    fn energy_with(&self, cell: &UnitCell, position: &Fn(usize) -> Vector3D) -> f64 {
+        let (i, j, k) = (self.particles[0], self.particles[1], self.particles[2]);
+        let theta = cell.angle(&position(i), &position(j), &position(k));
+        return flat_bottom(self.k, self.width, theta - self.x0).0;
+    }
+}
+
+impl GlobalPotential for AngleRestraint {
+    fn name(&self) -> &str {
+        "AngleRestraint"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        self.energy_with(&configuration.cell, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let (i, j, k) = (self.particles[0], self.particles[1], self.particles[2]);
+        let (theta, d1, d2, d3) = configuration.angle_and_derivatives(i, j, k);
+        let (_, derivative) = flat_bottom(self.k, self.width, theta - self.x0);
+        forces[i] -= derivative * d1;
+        forces[j] -= derivative * d2;
+        forces[k] -= derivative * d3;
+    }
+
+    fn virial(&self, _: &Configuration) -> Matrix3 {
+        // Angular terms do not contribute to the virial, see the comment in
+        // the Virial compute.
+        Matrix3::zero()
+    }
+}
+
+impl GlobalCache for AngleRestraint {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        move_particles_cost(configuration, &self.particles, idxes, newpos, |position| {
+            self.energy_with(&configuration.cell, position)
+        })
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+/// Harmonic restraint of the dihedral angle formed by four particles.
+///
+/// The deviation from the reference angle is taken in the `[-pi, pi]` range,
+/// so that restraints close to the `-pi/pi` boundary behave as expected.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, DihedralRestraint};
+/// use lumol::sys::{System, Particle};
+/// use lumol::types::Vector3D;
+/// use std::f64::consts::PI;
+///
+/// let mut system = System::new();
+/// for _ in 0..4 {
+///     system.add_particle(Particle::new("Ar"));
+/// }
+/// system.particle_mut(0).position = Vector3D::new(0.0, 1.0, 0.0);
+/// system.particle_mut(2).position = Vector3D::new(1.0, 0.0, 0.0);
+/// system.particle_mut(3).position = Vector3D::new(1.0, -1.0, 0.0);
+///
+/// // This is a trans dihedral angle
+/// let restraint = DihedralRestraint{particles: [0, 1, 2, 3], k: 2.0, x0: PI, width: 0.0};
+/// assert!(restraint.energy(&system).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DihedralRestraint {
+    /// Indexes of the restrained particles
+    pub particles: [usize; 4],
+    /// Force constant of the restraint
+    pub k: f64,
+    /// Reference dihedral angle
+    pub x0: f64,
+    /// Half width of the flat bottom region around the reference angle
+    pub width: f64,
+}
+
+impl DihedralRestraint {
+    /// Get the deviation of `phi` from the reference angle, in the `[-pi, pi]`
+    /// range.
+    fn delta(&self, phi: f64) -> f64 {
+        let mut delta = (phi - self.x0) % (2.0 * PI);
+        if delta > PI {
+            delta -= 2.0 * PI;
+        } else if delta < -PI {
+            delta += 2.0 * PI;
+        }
+        return delta;
+    }
+
+    fn energy_with(&self, cell: &UnitCell, position: &Fn(usize) -> Vector3D) -> f64 {
+        let (i, j, k, m) = (self.particles[0], self.particles[1], self.particles[2], self.particles[3]);
+        let phi = cell.dihedral(&position(i), &position(j), &position(k), &position(m));
+        return flat_bottom(self.k, self.width, self.delta(phi)).0;
+    }
+}
+
+impl GlobalPotential for DihedralRestraint {
+    fn name(&self) -> &str {
+        "DihedralRestraint"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        self.energy_with(&configuration.cell, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        let (i, j, k, m) = (self.particles[0], self.particles[1], self.particles[2], self.particles[3]);
+        let (phi, d1, d2, d3, d4) = configuration.dihedral_and_derivatives(i, j, k, m);
+        let (_, derivative) = flat_bottom(self.k, self.width, self.delta(phi));
+        forces[i] -= derivative * d1;
+        forces[j] -= derivative * d2;
+        forces[k] -= derivative * d3;
+        forces[m] -= derivative * d4;
+    }
+
+    fn virial(&self, _: &Configuration) -> Matrix3 {
+        // Angular terms do not contribute to the virial, see the comment in
+        // the Virial compute.
+        Matrix3::zero()
+    }
+}
+
+impl GlobalCache for DihedralRestraint {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        move_particles_cost(configuration, &self.particles, idxes, newpos, |position| {
+            self.energy_with(&configuration.cell, position)
+        })
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::{System, Particle, UnitCell};
+    use energy::GlobalPotential;
+
+    fn testing_system() -> System {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        let positions = [
+            Vector3D::new(0.1, 1.2, 0.3),
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(1.3, -0.2, 0.1),
+            Vector3D::new(1.7, -1.1, 0.9),
+        ];
+        for position in &positions {
+            let mut particle = Particle::new("Ar");
+            particle.position = *position;
+            system.add_particle(particle);
+        }
+        return system;
+    }
+
+    fn check_forces(system: &System, potential: &GlobalPotential) {
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        potential.forces(system, &mut forces);
+
+        let eps = 1e-6;
+        for i in 0..system.size() {
+            for axis in 0..3 {
+                let mut moved = system.clone();
+                moved.particle_mut(i).position[axis] += eps;
+                let finite_diff = -(potential.energy(&moved) - potential.energy(system)) / eps;
+                assert_ulps_eq!(forces[i][axis], finite_diff, epsilon = 1e-5);
+            }
+        }
+    }
+
+    fn check_move_cost(system: &System, potential: &GlobalPotential) {
+        let idxes = [0, 2];
+        let newpos = [Vector3D::new(0.3, 1.0, -0.2), Vector3D::new(1.0, 0.3, 0.4)];
+        let cost = potential.move_particles_cost(system, &idxes, &newpos);
+
+        let mut moved = system.clone();
+        moved.particle_mut(0).position = newpos[0];
+        moved.particle_mut(2).position = newpos[1];
+        assert_ulps_eq!(cost, potential.energy(&moved) - potential.energy(system));
+    }
+
+    #[test]
+    fn flat_bottom_function() {
+        assert_eq!(flat_bottom(2.0, 0.0, 1.5), (2.25, 3.0));
+        assert_eq!(flat_bottom(2.0, 0.5, -1.5), (1.0, -2.0));
+        assert_eq!(flat_bottom(2.0, 0.5, 0.3), (0.0, 0.0));
+    }
+
+    #[test]
+    fn position() {
+        let system = testing_system();
+        let restraint = PositionRestraint {
+            references: vec![(0, Vector3D::new(0.0, 1.0, 0.0)), (2, Vector3D::new(9.5, 0.0, 0.0))],
+            k: 12.0,
+            width: 0.1,
+        };
+
+        // Using periodic boundary conditions for the reference positions
+        let d0 = f64::sqrt(0.1 * 0.1 + 0.2 * 0.2 + 0.3 * 0.3) - 0.1;
+        let d2 = f64::sqrt(1.8 * 1.8 + 0.2 * 0.2 + 0.1 * 0.1) - 0.1;
+        assert_ulps_eq!(restraint.energy(&system), 6.0 * (d0 * d0 + d2 * d2));
+
+        check_forces(&system, &restraint);
+        check_move_cost(&system, &restraint);
+        assert_eq!(restraint.move_particles_cost(&system, &[1, 3], &[Vector3D::zero(), Vector3D::zero()]), 0.0);
+
+        let mut forces = vec![Vector3D::zero(); 4];
+        restraint.forces(&system, &mut forces);
+        let mut virial = Matrix3::zero();
+        virial += forces[0].tensorial(&Vector3D::new(0.1, 0.2, 0.3));
+        virial += forces[2].tensorial(&Vector3D::new(1.8, -0.2, 0.1));
+        assert_ulps_eq!(restraint.virial(&system), virial, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn distance() {
+        let system = testing_system();
+        let restraint = DistanceRestraint{particles: [0, 3], k: 8.0, x0: 2.0, width: 0.2};
+
+        let r = system.distance(0, 3);
+        assert_ulps_eq!(restraint.energy(&system), 4.0 * (r - 2.2) * (r - 2.2), epsilon = 1e-12);
+
+        check_forces(&system, &restraint);
+        check_move_cost(&system, &restraint);
+
+        let mut forces = vec![Vector3D::zero(); 4];
+        restraint.forces(&system, &mut forces);
+        let virial = forces[0].tensorial(&system.nearest_image(0, 3));
+        assert_ulps_eq!(restraint.virial(&system), virial, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn angle() {
+        let system = testing_system();
+        let restraint = AngleRestraint{particles: [0, 1, 2], k: 8.0, x0: 1.5, width: 0.0};
+
+        let delta = system.angle(0, 1, 2) - 1.5;
+        assert_ulps_eq!(restraint.energy(&system), 4.0 * delta * delta);
+
+        check_forces(&system, &restraint);
+        check_move_cost(&system, &restraint);
+    }
+
+    #[test]
+    fn dihedral() {
+        let system = testing_system();
+        let phi = system.dihedral(0, 1, 2, 3);
+        // Use a reference on the other side of the -pi/pi boundary
+        let x0 = phi - 0.3 + 2.0 * PI;
+        let restraint = DihedralRestraint{particles: [0, 1, 2, 3], k: 8.0, x0: x0, width: 0.1};
+        assert_ulps_eq!(restraint.energy(&system), 4.0 * 0.2 * 0.2, epsilon = 1e-12);
+
+        check_forces(&system, &restraint);
+        check_move_cost(&system, &restraint);
+    }
+}
@@ -0,0 +1,477 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Walls acting on the particles close to one of the faces of the unit cell.
+//!
+//! Walls are used to confine a system in one or more directions, typically
+//! together with a slab unit cell which is not periodic along the z axis. The
+//! interaction between a wall and a particle only depends on the distance `d`
+//! between the particle and the wall plane, and is given by a
+//! [`WallPotential`](trait.WallPotential.html).
+use std::f64;
+use std::f64::consts::{PI, SQRT_2};
+
+use sys::{Configuration, CellShape};
+use types::{Matrix3, Vector3D, Zero};
+
+use energy::{Potential, GlobalPotential, GlobalCache};
+
+/// Marker trait for potentials that can be used for walls. The variable of
+/// these potentials is the distance between a particle and the wall plane.
+///
+/// # Example
+///
+/// ```
+/// use lumol::energy::{Potential, WallPotential};
+///
+/// // A no-op potential
+/// #[derive(Clone)]
+/// struct Null;
+///
+/// impl Potential for Null {
+///     fn energy(&self, x: f64) -> f64 {0.0}
+///     fn force(&self, x: f64) -> f64 {0.0}
+/// }
+///
+/// // Now we can use the Null potential for walls
+/// impl WallPotential for Null {}
+/// ```
+pub trait WallPotential : Potential + BoxCloneWall {}
+impl_box_clone!(WallPotential, BoxCloneWall, box_clone_wall);
+
+/// Hard wall potential.
+///
+/// The energy is infinite when the distance to the wall is smaller than
+/// `sigma`, and zero otherwise. This potential does not produce any force, and
+/// can only be used in Monte Carlo simulations.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{Potential, HardWall};
+///
+/// let potential = HardWall{sigma: 1.5};
+/// assert_eq!(potential.energy(1.0), std::f64::INFINITY);
+/// assert_eq!(potential.energy(2.0), 0.0);
+/// assert_eq!(potential.force(1.0), 0.0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct HardWall {
+    /// Minimal distance between the particles and the wall
+    pub sigma: f64,
+}
+
+impl Potential for HardWall {
+    fn energy(&self, d: f64) -> f64 {
+        if d < self.sigma {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+
+    fn force(&self, _: f64) -> f64 {
+        0.0
+    }
+}
+
+impl WallPotential for HardWall {}
+
+/// Lennard-Jones 9-3 wall potential.
+///
+/// This potential comes from the integration of a Lennard-Jones potential over
+/// a semi-infinite volume, and is defined as
+///
+/// ```text
+/// V(d) = epsilon * [2/15 (sigma/d)^9 - (sigma/d)^3]
+/// ```
+///
+/// The energy is infinite for particles on or behind the wall (`d <= 0`).
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{Potential, LennardJones93};
+///
+/// let potential = LennardJones93{sigma: 2.0, epsilon: 0.5};
+/// assert_eq!(potential.energy(2.0), 0.5 * (2.0 / 15.0 - 1.0));
+/// assert_eq!(potential.force(2.0), 0.5 * (6.0 / 5.0 - 3.0) / 2.0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LennardJones93 {
+    /// Distance constant of the potential
+    pub sigma: f64,
+    /// Energy constant of the potential
+    pub epsilon: f64,
+}
+
+impl Potential for LennardJones93 {
+    fn energy(&self, d: f64) -> f64 {
+        if d <= 0.0 {
+            return f64::INFINITY;
+        }
+        let s3 = (self.sigma / d).powi(3);
+        return self.epsilon * (2.0 / 15.0 * s3 * s3 * s3 - s3);
+    }
+
+    fn force(&self, d: f64) -> f64 {
+        if d <= 0.0 {
+            return 0.0;
+        }
+        let s3 = (self.sigma / d).powi(3);
+        return self.epsilon * (6.0 / 5.0 * s3 * s3 * s3 - 3.0 * s3) / d;
+    }
+}
+
+impl WallPotential for LennardJones93 {}
+
+/// Lennard-Jones 10-4-3 wall potential.
+///
+/// This potential comes from the integration of a Lennard-Jones potential over
+/// a stack of atomic layers, and is defined as
+///
+/// ```text
+/// V(d) = 2 pi epsilon * [2/5 (sigma/d)^10 - (sigma/d)^4 - sqrt(2) sigma^3 / (3 (d + 0.61 sigma / sqrt(2))^3)]
+/// ```
+///
+/// The energy is infinite for particles on or behind the wall (`d <= 0`).
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{Potential, LennardJones1043};
+///
+/// let potential = LennardJones1043{sigma: 2.0, epsilon: 0.5};
+/// assert!(potential.energy(2.0) < 0.0);
+/// assert!(potential.force(1.0) > 0.0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LennardJones1043 {
+    /// Distance constant of the potential
+    pub sigma: f64,
+    /// Energy constant of the potential
+    pub epsilon: f64,
+}
+
+impl LennardJones1043 {
+    /// Get the offset of the `d^-3` term in the potential
+    fn delta(&self) -> f64 {
+        0.61 * self.sigma / SQRT_2
+    }
+}
+
+impl Potential for LennardJones1043 {
+    fn energy(&self, d: f64) -> f64 {
+        if d <= 0.0 {
+            return f64::INFINITY;
+        }
+        let s2 = (self.sigma / d).powi(2);
+        let s4 = s2 * s2;
+        let s3 = self.sigma.powi(3);
+        let last = SQRT_2 * s3 / (3.0 * (d + self.delta()).powi(3));
+        return 2.0 * PI * self.epsilon * (2.0 / 5.0 * s4 * s4 * s2 - s4 - last);
+    }
+
+    fn force(&self, d: f64) -> f64 {
+        if d <= 0.0 {
+            return 0.0;
+        }
+        let s2 = (self.sigma / d).powi(2);
+        let s4 = s2 * s2;
+        let s3 = self.sigma.powi(3);
+        let last = SQRT_2 * s3 / (d + self.delta()).powi(4);
+        return 2.0 * PI * self.epsilon * ((4.0 * s4 * s4 * s2 - 4.0 * s4) / d - last);
+    }
+}
+
+impl WallPotential for LennardJones1043 {}
+
+/// The faces of an orthorhombic unit cell, where walls can be placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellFace {
+    /// The face at `x = 0`
+    XMin,
+    /// The face at `x = a`
+    XMax,
+    /// The face at `y = 0`
+    YMin,
+    /// The face at `y = b`
+    YMax,
+    /// The face at `z = 0`
+    ZMin,
+    /// The face at `z = c`
+    ZMax,
+}
+
+impl CellFace {
+    /// Get the axis normal to this face
+    fn axis(&self) -> usize {
+        match *self {
+            CellFace::XMin | CellFace::XMax => 0,
+            CellFace::YMin | CellFace::YMax => 1,
+            CellFace::ZMin | CellFace::ZMax => 2,
+        }
+    }
+
+    /// Is this face at the origin of the cell?
+    fn is_min(&self) -> bool {
+        match *self {
+            CellFace::XMin | CellFace::YMin | CellFace::ZMin => true,
+            CellFace::XMax | CellFace::YMax | CellFace::ZMax => false,
+        }
+    }
+}
+
+/// A wall on one of the faces of an orthorhombic unit cell, interacting with
+/// all the particles with a given name.
+///
+/// The distance between a particle and the wall is computed from the
+/// coordinates of the particle without applying periodic boundary conditions.
+/// Walls should be used along non-periodic directions, or along directions
+/// where particles can not cross the cell boundaries.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::energy::{GlobalPotential, Wall, CellFace, LennardJones93};
+/// use lumol::sys::{System, Particle, UnitCell};
+/// use lumol::types::{Vector3D, Zero};
+///
+/// let mut system = System::with_cell(UnitCell::slab(10.0, 10.0, 20.0));
+/// system.add_particle(Particle::new("Ar"));
+/// system.particle_mut(0).position = Vector3D::new(5.0, 5.0, 18.0);
+///
+/// let wall = Wall::new(CellFace::ZMax, "Ar", Box::new(LennardJones93{sigma: 2.0, epsilon: 0.5}));
+/// assert_eq!(wall.energy(&system), 0.5 * (2.0 / 15.0 - 1.0));
+///
+/// // The wall pushes the particle away
+/// let mut forces = vec![Vector3D::zero()];
+/// wall.forces(&system, &mut forces);
+/// assert!(forces[0][2] > 0.0);
+/// ```
+#[derive(Clone)]
+pub struct Wall {
+    /// The face of the cell where the wall is
+    face: CellFace,
+    /// Name of the particles interacting with the wall
+    atoms: String,
+    /// Potential used for the interactions
+    potential: Box<WallPotential>,
+}
+
+impl Wall {
+    /// Create a new wall on the `face` of the unit cell, interacting with the
+    /// particles named `atoms` through the `potential`.
+    pub fn new<S: Into<String>>(face: CellFace, atoms: S, potential: Box<WallPotential>) -> Wall {
+        Wall {
+            face: face,
+            atoms: atoms.into(),
+            potential: potential,
+        }
+    }
+
+    /// Get the face of the cell where this wall is
+    pub fn face(&self) -> CellFace {
+        self.face
+    }
+
+    /// Get the name of the particles interacting with this wall
+    pub fn atoms(&self) -> &str {
+        &self.atoms
+    }
+
+    fn is_affected(&self, configuration: &Configuration, i: usize) -> bool {
+        configuration.particle(i).name() == self.atoms
+    }
+
+    /// Get the distance between the `position` and the wall, and the unit
+    /// vector normal to the wall, pointing toward the inside of the cell.
+    fn distance(&self, configuration: &Configuration, position: &Vector3D) -> (f64, Vector3D) {
+        if configuration.cell.shape() != CellShape::Orthorhombic {
+            panic!("Walls can only be used with orthorhombic unit cells");
+        }
+        let axis = self.face.axis();
+        let mut normal = Vector3D::zero();
+        if self.face.is_min() {
+            normal[axis] = 1.0;
+            return (position[axis], normal);
+        } else {
+            normal[axis] = -1.0;
+            let length = configuration.cell.lengths()[axis];
+            return (length - position[axis], normal);
+        }
+    }
+
+    fn energy_with(&self, configuration: &Configuration, particles: &[usize], position: &Fn(usize) -> Vector3D) -> f64 {
+        let mut energy = 0.0;
+        for &i in particles {
+            if self.is_affected(configuration, i) {
+                let (distance, _) = self.distance(configuration, &position(i));
+                energy += self.potential.energy(distance);
+            }
+        }
+        return energy;
+    }
+}
+
+impl GlobalPotential for Wall {
+    fn name(&self) -> &str {
+        "Wall"
+    }
+
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let particles = (0..configuration.size()).collect::<Vec<_>>();
+        self.energy_with(configuration, &particles, &|i| configuration.particle(i).position)
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+        for (i, particle) in configuration.particles().enumerate() {
+            if self.is_affected(configuration, i) {
+                let (distance, normal) = self.distance(configuration, &particle.position);
+                forces[i] += self.potential.force(distance) * normal;
+            }
+        }
+    }
+
+    fn virial(&self, configuration: &Configuration) -> Matrix3 {
+        let mut virial = Matrix3::zero();
+        for (i, particle) in configuration.particles().enumerate() {
+            if self.is_affected(configuration, i) {
+                let (distance, normal) = self.distance(configuration, &particle.position);
+                let force = self.potential.force(distance) * normal;
+                virial += force.tensorial(&(distance * normal));
+            }
+        }
+        return virial;
+    }
+}
+
+impl GlobalCache for Wall {
+    fn move_particles_cost(&self, configuration: &Configuration, idxes: &[usize], newpos: &[Vector3D]) -> f64 {
+        let old = self.energy_with(configuration, idxes, &|i| configuration.particle(i).position);
+        let new = self.energy_with(configuration, idxes, &|i| {
+            let idx = idxes.iter().position(|&moved| moved == i).expect("missing moved particle");
+            newpos[idx]
+        });
+        return new - old;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::{System, Particle, UnitCell};
+    use energy::GlobalPotential;
+    use types::One;
+
+    const EPS: f64 = 1e-6;
+
+    #[test]
+    fn potentials_forces() {
+        let potentials: Vec<Box<WallPotential>> = vec![
+            Box::new(LennardJones93{sigma: 2.0, epsilon: 0.5}),
+            Box::new(LennardJones1043{sigma: 2.0, epsilon: 0.5}),
+        ];
+        for potential in potentials {
+            for &d in &[1.2, 2.0, 3.5] {
+                let finite = -(potential.energy(d + EPS) - potential.energy(d - EPS)) / (2.0 * EPS);
+                assert_relative_eq!(potential.force(d), finite, epsilon = 1e-6);
+            }
+            assert_eq!(potential.energy(0.0), f64::INFINITY);
+            assert_eq!(potential.energy(-1.0), f64::INFINITY);
+        }
+    }
+
+    fn testing_system() -> System {
+        let mut system = System::with_cell(UnitCell::slab(10.0, 12.0, 20.0));
+        system.add_particle(Particle::new("Ar"));
+        system.particle_mut(0).position = Vector3D::new(1.0, 2.0, 2.5);
+        system.add_particle(Particle::new("Ar"));
+        system.particle_mut(1).position = Vector3D::new(3.0, 9.0, 17.0);
+        system.add_particle(Particle::new("He"));
+        system.particle_mut(2).position = Vector3D::new(5.0, 5.0, 1.0);
+        return system;
+    }
+
+    #[test]
+    fn wall() {
+        let system = testing_system();
+        let potential = LennardJones93{sigma: 2.0, epsilon: 0.5};
+
+        let wall = Wall::new(CellFace::ZMin, "Ar", Box::new(potential));
+        assert_eq!(wall.energy(&system), potential.energy(2.5) + potential.energy(17.0));
+
+        let mut forces = vec![Vector3D::zero(); 3];
+        wall.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::new(0.0, 0.0, potential.force(2.5)));
+        assert_eq!(forces[1], Vector3D::new(0.0, 0.0, potential.force(17.0)));
+        assert_eq!(forces[2], Vector3D::zero());
+
+        let virial = wall.virial(&system);
+        let expected = 2.5 * potential.force(2.5) + 17.0 * potential.force(17.0);
+        assert_ulps_eq!(virial[2][2], expected);
+        assert_eq!(virial[0][0], 0.0);
+        assert_eq!(virial[1][1], 0.0);
+
+        let wall = Wall::new(CellFace::YMax, "Ar", Box::new(potential));
+        assert_eq!(wall.energy(&system), potential.energy(10.0) + potential.energy(3.0));
+
+        let mut forces = vec![Vector3D::zero(); 3];
+        wall.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::new(0.0, -potential.force(10.0), 0.0));
+        assert_eq!(forces[1], Vector3D::new(0.0, -potential.force(3.0), 0.0));
+    }
+
+    #[test]
+    fn virial() {
+        // The virial is the derivative of the energy with respect to a
+        // scaling of the positions and of the cell
+        let system = testing_system();
+        let wall = Wall::new(CellFace::ZMax, "Ar", Box::new(LennardJones1043{sigma: 2.0, epsilon: 0.5}));
+
+        let scaled = |factor: f64| {
+            let mut scaling = Matrix3::one();
+            scaling[2][2] = factor;
+            let mut system = system.clone();
+            system.cell = system.cell.scale(scaling);
+            for particle in system.particles_mut() {
+                particle.position[2] *= factor;
+            }
+            return system;
+        };
+
+        let finite = -(wall.energy(&scaled(1.0 + EPS)) - wall.energy(&scaled(1.0 - EPS))) / (2.0 * EPS);
+        assert_relative_eq!(wall.virial(&system)[2][2], finite, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn move_particles_cost() {
+        let system = testing_system();
+        let wall = Wall::new(CellFace::ZMin, "Ar", Box::new(HardWall{sigma: 2.0}));
+        assert_eq!(wall.energy(&system), 0.0);
+
+        let newpos = [Vector3D::new(1.0, 2.0, 1.5), Vector3D::new(5.0, 5.0, 0.5)];
+        assert_eq!(wall.move_particles_cost(&system, &[0, 2], &newpos), f64::INFINITY);
+
+        let newpos = [Vector3D::new(1.0, 2.0, 3.5), Vector3D::new(5.0, 5.0, 0.5)];
+        assert_eq!(wall.move_particles_cost(&system, &[0, 2], &newpos), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Walls can only be used with orthorhombic unit cells")]
+    fn triclinic() {
+        let mut system = testing_system();
+        system.cell = UnitCell::triclinic(10.0, 10.0, 10.0, 90.0, 80.0, 90.0);
+        let wall = Wall::new(CellFace::ZMin, "Ar", Box::new(HardWall{sigma: 2.0}));
+        let _ = wall.energy(&system);
+    }
+}
@@ -8,7 +8,8 @@ use std::fs::File;
 use std::path::{Path, PathBuf};
 
 use utils;
-use sys::System;
+use sys::{System, Selection};
+use sys::compute::{Compute, PotentialEnergyComponents, GroupEnergy};
 use sys::{TrajectoryBuilder, TrajectoryError, Trajectory, OpenMode};
 
 /// The `Output` trait define the interface for all the quantities outputted by
@@ -139,6 +140,174 @@ impl Output for EnergyOutput {
     }
 }
 
+/******************************************************************************/
+/// The `ConservedEnergyOutput` write the conserved energy of the system to a
+/// text file, organized as: `TotalEnergy     ExtendedEnergy
+/// ConservedEnergy`. The extended energy is the energy of the thermostats and
+/// barostats degrees of freedom, and this output can be used to monitor the
+/// energy drift of a simulation.
+pub struct ConservedEnergyOutput {
+    file: File,
+    path: PathBuf
+}
+
+impl ConservedEnergyOutput {
+    /// Create a new `ConservedEnergyOutput` writing to `filename`. The file is
+    /// replaced if it already exists.
+    pub fn new<P: AsRef<Path>>(filename: P) -> Result<ConservedEnergyOutput, io::Error> {
+        Ok(ConservedEnergyOutput{
+            file: try!(File::create(filename.as_ref())),
+            path: filename.as_ref().to_owned(),
+        })
+    }
+}
+
+impl Output for ConservedEnergyOutput {
+    fn setup(&mut self, _: &System) {
+        if let Err(err) = writeln!(&mut self.file, "# Conserved energy of the simulation (kJ/mol)") {
+            fatal_error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+        if let Err(err) = writeln!(&mut self.file, "# Step Total Extended Conserved") {
+            fatal_error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+
+    fn write(&mut self, system: &System) {
+        let total = utils::unit_to(system.total_energy(), "kJ/mol");
+        let extended = utils::unit_to(system.extended_energy(), "kJ/mol");
+        let conserved = utils::unit_to(system.conserved_energy(), "kJ/mol");
+        if let Err(err) = writeln!(&mut self.file, "{} {} {} {}", system.step(), total, extended, conserved) {
+            error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+}
+
+/******************************************************************************/
+/// The `DetailedEnergyOutput` write all the components of the energy of the
+/// system to a text file, organized as: `Pairs PairsTail Bonds Angles
+/// Dihedrals Impropers Coulomb <globals> Potential Kinetic Total`.
+///
+/// There is one column for each global potential, in the order they were
+/// added to the system, labelled with the potential
+/// [name](../energy/trait.GlobalPotential.html#method.name). When multiple
+/// global potentials share the same name, they are numbered in order:
+/// `DistanceRestraint1 DistanceRestraint2`.
+pub struct DetailedEnergyOutput {
+    file: File,
+    path: PathBuf
+}
+
+impl DetailedEnergyOutput {
+    /// Create a new `DetailedEnergyOutput` writing to `filename`. The file is
+    /// replaced if it already exists.
+    pub fn new<P: AsRef<Path>>(filename: P) -> Result<DetailedEnergyOutput, io::Error> {
+        Ok(DetailedEnergyOutput{
+            file: try!(File::create(filename.as_ref())),
+            path: filename.as_ref().to_owned(),
+        })
+    }
+}
+
+impl Output for DetailedEnergyOutput {
+    fn setup(&mut self, system: &System) {
+        if let Err(err) = writeln!(&mut self.file, "# Energy components of the simulation (kJ/mol)") {
+            fatal_error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+        let mut header = String::from("# Step Pairs PairsTail Bonds Angles Dihedrals Impropers Coulomb");
+        let names = system.global_potentials().iter().map(|global| global.name()).collect::<Vec<_>>();
+        for (i, name) in names.iter().enumerate() {
+            let count = names.iter().filter(|&other| other == name).count();
+            if count == 1 {
+                header.push_str(&format!(" {}", name));
+            } else {
+                let index = names[..i].iter().filter(|&other| other == name).count() + 1;
+                header.push_str(&format!(" {}{}", name, index));
+            }
+        }
+        header.push_str(" Potential Kinetic Total");
+        if let Err(err) = writeln!(&mut self.file, "{}", header) {
+            fatal_error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+
+    fn write(&mut self, system: &System) {
+        let components = PotentialEnergyComponents.compute(system);
+        let potential = components.total();
+        let kinetic = system.kinetic_energy();
+
+        let mut values = vec![
+            components.pairs,
+            components.pairs_tail,
+            components.bonds,
+            components.angles,
+            components.dihedrals,
+            components.impropers,
+            components.coulomb,
+        ];
+        values.extend_from_slice(&components.globals);
+        values.push(potential);
+        values.push(kinetic);
+        values.push(potential + kinetic);
+
+        let mut line = format!("{}", system.step());
+        for value in values {
+            line.push_str(&format!(" {}", utils::unit_to(value, "kJ/mol")));
+        }
+        if let Err(err) = writeln!(&mut self.file, "{}", line) {
+            error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+}
+
+/******************************************************************************/
+/// The `GroupEnergyOutput` write the interaction energy between two groups of
+/// particles to a text file, organized as: `Pairs Coulomb Total`. See the
+/// [`GroupEnergy`](../sys/compute/struct.GroupEnergy.html) compute for more
+/// information.
+pub struct GroupEnergyOutput {
+    file: File,
+    path: PathBuf,
+    compute: GroupEnergy,
+}
+
+impl GroupEnergyOutput {
+    /// Create a new `GroupEnergyOutput` writing to `filename` the interaction
+    /// energy between the `first` and `second` selections. The file is
+    /// replaced if it already exists.
+    pub fn new<P: AsRef<Path>>(filename: P, first: Selection, second: Selection) -> Result<GroupEnergyOutput, io::Error> {
+        Ok(GroupEnergyOutput{
+            file: try!(File::create(filename.as_ref())),
+            path: filename.as_ref().to_owned(),
+            compute: GroupEnergy {
+                first: first,
+                second: second,
+            },
+        })
+    }
+}
+
+impl Output for GroupEnergyOutput {
+    fn setup(&mut self, _: &System) {
+        if let Err(err) = writeln!(&mut self.file, "# Interaction energy between groups (kJ/mol)") {
+            fatal_error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+        if let Err(err) = writeln!(&mut self.file, "# Step Pairs Coulomb Total") {
+            fatal_error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+
+    fn write(&mut self, system: &System) {
+        let energies = self.compute.compute(system);
+        let pairs = utils::unit_to(energies.pairs, "kJ/mol");
+        let coulomb = utils::unit_to(energies.coulomb, "kJ/mol");
+        let total = utils::unit_to(energies.total(), "kJ/mol");
+
+        if let Err(err) = writeln!(&mut self.file, "{} {} {} {}", system.step(), pairs, coulomb, total) {
+            error!("Could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+}
+
 /******************************************************************************/
 /// The `PropertiesOutput` write various physical properties of the system to
 /// a file. These properties are:
@@ -192,7 +361,8 @@ mod tests {
 
     use super::*;
     use sys::System;
-    use energy::{PairInteraction, Harmonic};
+    use energy::{PairInteraction, Harmonic, DistanceRestraint, ElectricField};
+    use types::Vector3D;
     use utils::{unit_from, system_from_xyz};
 
     fn testing_system() -> System {
@@ -260,6 +430,106 @@ F 1.3 0 0
         check_file_content(file, content);
     }
 
+    #[test]
+    fn conserved_energy() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut system = testing_system();
+        system.add_extended_energy(unit_from(2.5, "kJ/mol"));
+        {
+            let mut out = ConservedEnergyOutput::new(tempfile.path()).unwrap();
+            out.setup(&system);
+            out.write(&system);
+            out.finish(&system);
+        }
+
+        let content = "\
+# Conserved energy of the simulation (kJ/mol)
+# Step Total Extended Conserved
+0 1.5000000000000027 2.5 4.000000000000002
+";
+
+        let file = tempfile.reopen().unwrap();
+        check_file_content(file, content);
+    }
+
+    #[test]
+    fn detailed_energy() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut system = testing_system();
+        system.add_global_potential(Box::new(DistanceRestraint{
+            particles: [0, 1],
+            k: unit_from(100.0, "kJ/mol/A^2"),
+            x0: unit_from(1.0, "A"),
+            width: 0.0,
+        }));
+        {
+            let mut out = DetailedEnergyOutput::new(tempfile.path()).unwrap();
+            out.setup(&system);
+            out.write(&system);
+            out.finish(&system);
+        }
+
+        let content = "\
+# Energy components of the simulation (kJ/mol)
+# Step Pairs PairsTail Bonds Angles Dihedrals Impropers Coulomb DistanceRestraint Potential Kinetic Total
+0 1.5000000000000027 0 0 0 0 0 0 4.500000000000001 6.0000000000000036 0 6.0000000000000036
+";
+
+        let file = tempfile.reopen().unwrap();
+        check_file_content(file, content);
+    }
+
+    #[test]
+    fn detailed_energy_labels() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let mut system = testing_system();
+        for &x0 in &[1.0, 2.0] {
+            system.add_global_potential(Box::new(DistanceRestraint{
+                particles: [0, 1],
+                k: unit_from(100.0, "kJ/mol/A^2"),
+                x0: unit_from(x0, "A"),
+                width: 0.0,
+            }));
+        }
+        system.add_global_potential(Box::new(ElectricField{field: Vector3D::new(0.0, 0.0, 1.0)}));
+        {
+            let mut out = DetailedEnergyOutput::new(tempfile.path()).unwrap();
+            out.setup(&system);
+            out.finish(&system);
+        }
+
+        let content = "\
+# Energy components of the simulation (kJ/mol)
+# Step Pairs PairsTail Bonds Angles Dihedrals Impropers Coulomb DistanceRestraint1 DistanceRestraint2 ElectricField Potential Kinetic Total
+";
+
+        let file = tempfile.reopen().unwrap();
+        check_file_content(file, content);
+    }
+
+    #[test]
+    fn group_energy() {
+        let tempfile = NamedTempFile::new().unwrap();
+        let system = testing_system();
+        {
+            let first = Selection::Range(0..1);
+            let second = Selection::Range(1..2);
+            let mut out = GroupEnergyOutput::new(tempfile.path(), first, second).unwrap();
+            out.setup(&system);
+            out.write(&system);
+            out.finish(&system);
+        }
+
+        let content = "\
+# Interaction energy between groups (kJ/mol)
+# Step Pairs Coulomb Total
+0 1.5000000000000027 0 1.5000000000000027
+";
+
+        let file = tempfile.reopen().unwrap();
+        check_file_content(file, content);
+    }
+
     #[test]
     fn cell() {
         let tempfile = NamedTempFile::new().unwrap();
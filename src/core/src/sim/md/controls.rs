@@ -4,10 +4,17 @@
 //! While running a simulation, we often want to have control over some
 //! simulation parameters: the temperature, the pressure, etc. This is the goal
 //! of the control algorithms, all implementing of the `Control` trait.
-use types::{Matrix3, Vector3D, Zero};
-use sys::System;
+use rand::distributions::{ChiSquared, Normal, Range, Sample};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+use std::mem;
+
+use types::{Matrix3, Vector3D, One, Zero};
+use consts::K_BOLTZMANN;
+use sys::{System, Configuration, EnergyCache};
 use sys::veloc;
 use sim::Alternator;
+use sim::mc::MoveCounter;
 
 /// Trait for controlling some parameters in a system during a simulation.
 pub trait Control {
@@ -18,6 +25,13 @@ pub trait Control {
     /// Do your job, control algorithm!
     fn control(&mut self, system: &mut System);
 
+    /// Check if the last call to `control` changed the positions of the
+    /// particles or the unit cell of the system. The integrator is then
+    /// notified, to compute the forces acting on the particles again.
+    fn moved_particles(&self) -> bool {
+        false
+    }
+
     /// Function called once at the end of the simulation.
     fn finish(&mut self, _: &System) {}
 }
@@ -104,15 +118,568 @@ impl Control for BerendsenThermostat {
 }
 impl Thermostat for BerendsenThermostat {}
 
+/******************************************************************************/
+/// Chain of Nosé-Hoover thermostats, coupled to some degrees of freedom of a
+/// system. This is used by the `NoseHooverThermostat` to thermalize the
+/// particles, and by the extended system barostats to thermalize both the
+/// particles and the barostat.
+///
+/// The time constant and the times given to the functions of this struct must
+/// be expressed in the same units.
+pub(crate) struct NoseHooverChain {
+    /// Target temperature
+    temperature: f64,
+    /// Time constant of the chain
+    tau: f64,
+    /// Positions of the thermostats in the chain
+    positions: Vec<f64>,
+    /// Velocities of the thermostats in the chain, in units of the inverse of
+    /// the time unit used for `tau`
+    velocities: Vec<f64>,
+}
+
+impl NoseHooverChain {
+    /// Create a new chain of `length` thermostats acting at temperature
+    /// `temperature`, with a time constant of `tau`.
+    pub fn new(temperature: f64, tau: f64, length: usize) -> NoseHooverChain {
+        NoseHooverChain {
+            temperature: temperature,
+            tau: tau,
+            positions: vec![0.0; length],
+            velocities: vec![0.0; length],
+        }
+    }
+
+    /// Get the masses of the thermostats in the chain, for a system with
+    /// `dof` degrees of freedom.
+    fn masses(&self, dof: f64) -> Vec<f64> {
+        let kt = K_BOLTZMANN * self.temperature;
+        let mut masses = vec![kt * self.tau * self.tau; self.velocities.len()];
+        masses[0] *= dof;
+        return masses;
+    }
+
+    /// Get the energy of the chain, for a system with `dof` degrees of
+    /// freedom.
+    pub fn energy(&self, dof: f64) -> f64 {
+        let kt = K_BOLTZMANN * self.temperature;
+        let masses = self.masses(dof);
+        let mut energy = 0.0;
+        for (j, (&mass, &velocity)) in masses.iter().zip(&self.velocities).enumerate() {
+            energy += 0.5 * mass * velocity * velocity;
+            if j == 0 {
+                energy += dof * kt * self.positions[j];
+            } else {
+                energy += kt * self.positions[j];
+            }
+        }
+        return energy;
+    }
+
+    /// Get the force acting on the thermostat `j` in the chain, given the
+    /// kinetic energy of the system and the masses of the thermostats.
+    fn force(&self, j: usize, kinetic: f64, masses: &[f64], dof: f64) -> f64 {
+        let kt = K_BOLTZMANN * self.temperature;
+        if j == 0 {
+            (2.0 * kinetic - dof * kt) / masses[0]
+        } else {
+            let previous = self.velocities[j - 1];
+            (masses[j - 1] * previous * previous - kt) / masses[j]
+        }
+    }
+
+    /// Update the velocity of the thermostat `j` in the chain for a time `h`,
+    /// taking into account the friction from the next thermostat.
+    fn update_velocity(&mut self, j: usize, h: f64, kinetic: f64, masses: &[f64], dof: f64) {
+        let last = self.velocities.len() - 1;
+        let friction = if j == last {1.0} else {f64::exp(-self.velocities[j + 1] * h / 4.0)};
+        self.velocities[j] *= friction;
+        self.velocities[j] += self.force(j, kinetic, masses, dof) * h / 2.0;
+        self.velocities[j] *= friction;
+    }
+
+    /// Propagate the chain for a time `h`, when coupled to `dof` degrees of
+    /// freedom with a `kinetic` energy. This returns the factor by which the
+    /// velocities of the coupled degrees of freedom must be scaled.
+    pub fn propagate(&mut self, kinetic: f64, h: f64, dof: f64) -> f64 {
+        let masses = self.masses(dof);
+        let length = self.velocities.len();
+
+        for j in (0..length).rev() {
+            self.update_velocity(j, h, kinetic, &masses, dof);
+        }
+
+        let factor = f64::exp(-self.velocities[0] * h);
+        let kinetic = kinetic * factor * factor;
+
+        for j in 0..length {
+            self.positions[j] += self.velocities[j] * h;
+        }
+
+        for j in 0..length {
+            self.update_velocity(j, h, kinetic, &masses, dof);
+        }
+
+        return factor;
+    }
+}
+
+/******************************************************************************/
+/// Nosé-Hoover chain thermostat.
+///
+/// This thermostat couples the system to a chain of additional degrees of
+/// freedom [MKT1992], and samples the canonical ensemble. The equations of
+/// motion of the chain are integrated using the Trotter decomposition of
+/// [MTTK1996]. The thermostat is applied after each step of the integrator,
+/// which gives a symmetric splitting of the equations of motion when used
+/// with the velocity-Verlet integrator.
+///
+/// The energy of the chain is added to the system
+/// [extended energy](../../sys/struct.System.html#method.extended_energy), so
+/// that the conserved quantity of the simulation can be monitored.
+///
+/// [MKT1992] G. J. Martyna, M. L. Klein & M. Tuckerman, J. Chem. Phys. 97,
+/// 2635 (1992); doi: 10.1063/1.463940
+///
+/// [MTTK1996] G. J. Martyna, M. E. Tuckerman, D. J. Tobias & M. L. Klein,
+/// Mol. Phys. 87, 1117 (1996); doi: 10.1080/00268979600100761
+pub struct NoseHooverThermostat {
+    /// Chain of thermostats, with a time constant expressed as a
+    /// multiplicative factor of the integrator timestep.
+    chain: NoseHooverChain,
+    /// Energy of the chain at the last call to `control`
+    energy: f64,
+    /// Number of degrees of freedom removed from the system by other
+    /// controls or constraints
+    removed_dof: usize,
+}
+
+impl NoseHooverThermostat {
+    /// Create a new `NoseHooverThermostat` acting at temperature
+    /// `temperature`, with a time constant of `tau` times the integrator
+    /// timestep, and a chain of three thermostats.
+    pub fn new(temperature: f64, tau: f64) -> NoseHooverThermostat {
+        NoseHooverThermostat::with_chain(temperature, tau, 3)
+    }
+
+    /// Create a new `NoseHooverThermostat` acting at temperature
+    /// `temperature`, with a time constant of `tau` times the integrator
+    /// timestep, and a chain of `length` thermostats.
+    pub fn with_chain(temperature: f64, tau: f64, length: usize) -> NoseHooverThermostat {
+        assert!(temperature > 0.0, "The temperature must be positive in Nose-Hoover thermostat.");
+        assert!(tau > 0.0, "The time constant 'tau' must be positive in Nose-Hoover thermostat.");
+        assert!(length > 0, "The chain length must be at least 1 in Nose-Hoover thermostat.");
+        NoseHooverThermostat {
+            chain: NoseHooverChain::new(temperature, tau, length),
+            energy: 0.0,
+            removed_dof: 0,
+        }
+    }
+
+    /// Set the number of degrees of freedom removed from the system by other
+    /// algorithms. This should be 3 when the global translation is removed
+    /// with the `RemoveTranslation` control.
+    pub fn set_removed_dof(&mut self, removed: usize) {
+        self.removed_dof = removed;
+    }
+
+    /// Propagate the chain and the velocities of the particles in the
+    /// `system` for a time `h`, expressed in integrator timesteps.
+    fn propagate_chain(&mut self, system: &mut System, h: f64, dof: f64) {
+        let factor = self.chain.propagate(system.kinetic_energy(), h, dof);
+        for particle in system.particles_mut() {
+            particle.velocity *= factor;
+        }
+    }
+}
+
+impl Control for NoseHooverThermostat {
+    fn control(&mut self, system: &mut System) {
+        let dof = (3 * system.size() - self.removed_dof) as f64;
+        // The thermostat is applied between two integrator steps, so we
+        // propagate the chain by half a timestep for the end of the previous
+        // step, and half a timestep for the start of the next one.
+        self.propagate_chain(system, 0.5, dof);
+        self.propagate_chain(system, 0.5, dof);
+
+        let energy = self.chain.energy(dof);
+        system.add_extended_energy(energy - self.energy);
+        self.energy = energy;
+    }
+}
+
+impl Thermostat for NoseHooverThermostat {}
+
+/******************************************************************************/
+/// Stochastic velocity rescaling thermostat.
+///
+/// This thermostat [BDP2007] rescales the velocities of all the particles by
+/// a random factor, chosen such that the kinetic energy follows the
+/// canonical distribution. The relaxation of the kinetic energy is similar to
+/// the one in the Berendsen thermostat, but this algorithm samples the
+/// canonical ensemble.
+///
+/// The energy exchanged with the heat bath is added to the system
+/// [extended energy](../../sys/struct.System.html#method.extended_energy), so
+/// that the conserved quantity of the simulation can be monitored.
+///
+/// [BDP2007] G. Bussi, D. Donadio & M. Parrinello, J. Chem. Phys. 126, 014101
+/// (2007); doi: 10.1063/1.2408420
+pub struct BussiThermostat {
+    /// Target temperature
+    temperature: f64,
+    /// Time constant of the thermostat, expressed as a multiplicative factor
+    /// of the integrator timestep.
+    tau: f64,
+    /// Standard normal distribution
+    normal: Normal,
+    /// Random number generator
+    rng: Isaac64Rng,
+    /// Number of degrees of freedom removed from the system by other
+    /// controls or constraints
+    removed_dof: usize,
+}
+
+impl BussiThermostat {
+    /// Create a new `BussiThermostat` acting at temperature `temperature`,
+    /// with a time constant of `tau` times the integrator timestep.
+    pub fn new(temperature: f64, tau: f64) -> BussiThermostat {
+        assert!(temperature >= 0.0, "The temperature must be positive in thermostats.");
+        assert!(tau >= 0.0, "The time constant 'tau' must be positive in Bussi thermostat.");
+        BussiThermostat {
+            temperature: temperature,
+            tau: tau,
+            normal: Normal::new(0.0, 1.0),
+            rng: Isaac64Rng::from_seed(&[42]),
+            removed_dof: 0,
+        }
+    }
+
+    /// Set the number of degrees of freedom removed from the system by other
+    /// algorithms. This should be 3 when the global translation is removed
+    /// with the `RemoveTranslation` control.
+    pub fn set_removed_dof(&mut self, removed: usize) {
+        self.removed_dof = removed;
+    }
+
+    /// Set the seed of the random number generator. The default seed is 42.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.reseed(&[seed]);
+    }
+
+    /// Get the sum of `n` squared independent gaussian random numbers
+    fn sum_squared_gaussians(&mut self, n: f64) -> f64 {
+        if n < 1.0 {
+            0.0
+        } else {
+            ChiSquared::new(n).sample(&mut self.rng)
+        }
+    }
+}
+
+impl Control for BussiThermostat {
+    fn control(&mut self, system: &mut System) {
+        let kinetic = system.kinetic_energy();
+        if kinetic == 0.0 {
+            return;
+        }
+
+        let dof = (3 * system.size() - self.removed_dof) as f64;
+        let target = 0.5 * dof * K_BOLTZMANN * self.temperature;
+        let c = f64::exp(-1.0 / self.tau);
+        let ratio = target / (dof * kinetic);
+
+        let r1 = self.normal.sample(&mut self.rng);
+        let others = self.sum_squared_gaussians(dof - 1.0);
+        let alpha2 = c + (1.0 - c) * ratio * (r1 * r1 + others)
+                   + 2.0 * r1 * f64::sqrt(c * (1.0 - c) * ratio);
+
+        let mut factor = f64::sqrt(alpha2);
+        // The random kick can be strong enough to reverse the direction of
+        // the velocities
+        if r1 + f64::sqrt(c / ((1.0 - c) * ratio)) < 0.0 {
+            factor = -factor;
+        }
+
+        for particle in system.particles_mut() {
+            particle.velocity *= factor;
+        }
+        system.add_extended_energy((1.0 - alpha2) * kinetic);
+    }
+}
+
+impl Thermostat for BussiThermostat {}
+
+/******************************************************************************/
+/// Andersen thermostat.
+///
+/// This thermostat [A1980] simulates collisions of the particles with a heat
+/// bath, by drawing new velocities from the Maxwell-Boltzmann distribution at
+/// the target temperature. The collisions can either happen for each particle
+/// independently, or for all the particles at once (massive collisions).
+///
+/// This thermostat samples the canonical ensemble, but the random collisions
+/// destroy the dynamical correlations of the system.
+///
+/// [A1980] H. C. Andersen, J. Chem. Phys. 72, 2384 (1980); doi: 10.1063/1.439486
+pub struct AndersenThermostat {
+    /// Target temperature
+    temperature: f64,
+    /// Mean time between two collisions, expressed as a multiplicative factor
+    /// of the integrator timestep.
+    tau: f64,
+    /// Are the collisions happening for all the particles at once?
+    massive: bool,
+    /// Standard normal distribution
+    normal: Normal,
+    /// Random number generator
+    rng: Isaac64Rng,
+}
+
+impl AndersenThermostat {
+    /// Create a new `AndersenThermostat` acting at temperature `temperature`,
+    /// where each particle collides with the heat bath every `tau` integrator
+    /// timesteps on average.
+    pub fn new(temperature: f64, tau: f64) -> AndersenThermostat {
+        assert!(temperature >= 0.0, "The temperature must be positive in thermostats.");
+        assert!(tau >= 1.0, "The time constant 'tau' must be larger than 1 in Andersen thermostat.");
+        AndersenThermostat {
+            temperature: temperature,
+            tau: tau,
+            massive: false,
+            normal: Normal::new(0.0, 1.0),
+            rng: Isaac64Rng::from_seed(&[42]),
+        }
+    }
+
+    /// Create a new massive `AndersenThermostat` acting at temperature
+    /// `temperature`, where all the particles collide with the heat bath at
+    /// once, every `tau` integrator timesteps on average.
+    pub fn massive(temperature: f64, tau: f64) -> AndersenThermostat {
+        let mut thermostat = AndersenThermostat::new(temperature, tau);
+        thermostat.massive = true;
+        return thermostat;
+    }
+
+    /// Set the seed of the random number generator. The default seed is 42.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.reseed(&[seed]);
+    }
+
+    /// Get a new velocity for a particle with the given `mass`, from the
+    /// Maxwell-Boltzmann distribution.
+    fn new_velocity(&mut self, mass: f64) -> Vector3D {
+        let factor = f64::sqrt(K_BOLTZMANN * self.temperature / mass);
+        Vector3D::new(
+            factor * self.normal.sample(&mut self.rng),
+            factor * self.normal.sample(&mut self.rng),
+            factor * self.normal.sample(&mut self.rng),
+        )
+    }
+}
+
+impl Control for AndersenThermostat {
+    fn control(&mut self, system: &mut System) {
+        let probability = 1.0 / self.tau;
+        if self.massive {
+            if self.rng.next_f64() < probability {
+                for particle in system.particles_mut() {
+                    particle.velocity = self.new_velocity(particle.mass);
+                }
+            }
+        } else {
+            for particle in system.particles_mut() {
+                if self.rng.next_f64() < probability {
+                    particle.velocity = self.new_velocity(particle.mass);
+                }
+            }
+        }
+    }
+}
+
+impl Thermostat for AndersenThermostat {}
+
+/******************************************************************************/
+/// Monte Carlo barostat.
+///
+/// This control performs Metropolis Monte Carlo volume moves during a
+/// molecular dynamics simulation, to sample the isothermal-isobaric ensemble
+/// without a deterministic barostat [CKAL2004]. The cell is scaled
+/// isotropically, and the centers of mass of the molecules are scaled with it
+/// while the intramolecular configuration is kept fixed. The velocities are
+/// not modified, and a thermostat must be used to set the temperature of the
+/// simulation.
+///
+/// This control should usually be wrapped in an `Alternator` to only try a
+/// volume move every few steps. The amplitude of the volume moves can be
+/// adjusted during the simulation to reach a target acceptance ratio.
+///
+/// [CKAL2004] J. Åqvist, P. Wennerström, M. Nervall, S. Bjelic & B. O.
+/// Brandsdal, Chem. Phys. Lett. 384, 288 (2004); doi: 10.1016/j.cplett.2003.12.039
+pub struct MonteCarloBarostat {
+    /// Target pressure
+    pressure: f64,
+    /// Temperature used in the Metropolis criterion
+    temperature: f64,
+    /// Maximal volume change
+    delta: f64,
+    /// Sampling range for the volume change
+    range: Range<f64>,
+    /// Counter for the attempted and accepted volume moves
+    counter: MoveCounter,
+    /// Number of attempted moves between two updates of the amplitude
+    update_frequency: u64,
+    /// Energy cache used to compute the energy change of the moves
+    cache: EnergyCache,
+    /// Configuration before the volume move
+    previous: Configuration,
+    /// Largest cutoff of the potentials in the system
+    maximum_cutoff: Option<f64>,
+    /// Was the last volume move accepted?
+    accepted: bool,
+    /// Random number generator
+    rng: Isaac64Rng,
+}
+
+impl MonteCarloBarostat {
+    /// Create a new `MonteCarloBarostat` with target pressure `pressure`,
+    /// using a temperature of `temperature` in the Metropolis criterion, and
+    /// a maximal volume change of `delta`.
+    pub fn new(pressure: f64, temperature: f64, delta: f64) -> MonteCarloBarostat {
+        assert!(temperature > 0.0, "The temperature must be positive in Monte Carlo barostat.");
+        assert!(delta > 0.0, "delta must be positive in Monte Carlo barostat.");
+        MonteCarloBarostat {
+            pressure: pressure,
+            temperature: temperature,
+            delta: delta,
+            range: Range::new(-delta, delta),
+            counter: MoveCounter::new(None),
+            update_frequency: 0,
+            cache: EnergyCache::new(),
+            previous: Configuration::new(),
+            maximum_cutoff: None,
+            accepted: false,
+            rng: Isaac64Rng::from_seed(&[42]),
+        }
+    }
+
+    /// Set the target acceptance ratio of the volume moves. The amplitude of
+    /// the moves is updated every `update_frequency` attempted moves to reach
+    /// this acceptance ratio.
+    pub fn set_target_acceptance(&mut self, acceptance: f64, update_frequency: u64) {
+        assert!(update_frequency > 0, "The update frequency must be positive in Monte Carlo barostat.");
+        self.counter.set_acceptance(Some(acceptance));
+        self.update_frequency = update_frequency;
+    }
+
+    /// Set the seed of the random number generator. The default seed is 42.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.reseed(&[seed]);
+    }
+
+    /// Scale the cell of the `system` to the `volume`, and move the centers
+    /// of mass of the molecules accordingly. Returns `false` if the new cell
+    /// is too small for the cutoff of the potentials.
+    fn scale_system(&mut self, system: &mut System, volume: f64) -> bool {
+        let factor = f64::cbrt(volume / system.volume());
+        system.cell.scale_mut(factor * Matrix3::one());
+        if let Some(maximum_cutoff) = self.maximum_cutoff {
+            if system.cell.lengths().iter().any(|&d| 0.5 * d <= maximum_cutoff) {
+                return false;
+            }
+        }
+
+        for (mi, molecule) in self.previous.molecules().iter().enumerate() {
+            let old_com = system.molecule_com(mi);
+            let fractional = self.previous.cell.fractional(&old_com);
+            let delta = system.cell.cartesian(&fractional) - old_com;
+            for pi in molecule.iter() {
+                system.particle_mut(pi).position += delta;
+            }
+        }
+        return true;
+    }
+}
+
+impl Control for MonteCarloBarostat {
+    fn setup(&mut self, system: &System) {
+        if system.cell.is_infinite() {
+            fatal_error!("Cannot use Monte Carlo barostat with infinite simulation cell.")
+        }
+        self.maximum_cutoff = system.maximum_cutoff();
+    }
+
+    fn control(&mut self, system: &mut System) {
+        // The molecular dynamics moved the particles since the last call, so
+        // the cache must be computed again.
+        self.cache.init(system);
+        self.previous = (**system).clone();
+
+        let old_volume = system.volume();
+        let new_volume = old_volume + self.range.sample(&mut self.rng);
+        self.counter.ncalled += 1;
+        self.counter.nattempted += 1;
+
+        let accepted = if new_volume > 0.0 && self.scale_system(system, new_volume) {
+            let beta = 1.0 / (K_BOLTZMANN * self.temperature);
+            let delta_energy = self.cache.move_all_rigid_molecules_cost(system);
+            let nmolecules = system.molecules().len() as f64;
+            let cost = beta * (delta_energy + self.pressure * (new_volume - old_volume))
+                     - nmolecules * f64::ln(new_volume / old_volume);
+            cost <= 0.0 || self.rng.next_f64() < f64::exp(-cost)
+        } else {
+            false
+        };
+
+        if accepted {
+            self.cache.update(system);
+            self.counter.naccepted += 1;
+        } else {
+            mem::swap(&mut **system, &mut self.previous);
+        }
+        self.accepted = accepted;
+
+        if self.counter.nattempted == self.update_frequency {
+            if let Some(scaling) = self.counter.compute_scaling_factor() {
+                self.delta *= scaling;
+                self.range = Range::new(-self.delta, self.delta);
+            }
+            self.counter.naccepted = 0;
+            self.counter.nattempted = 0;
+        }
+    }
+
+    fn moved_particles(&self) -> bool {
+        self.accepted
+    }
+
+    fn finish(&mut self, _: &System) {
+        info!("Statistics for Monte Carlo barostat");
+        info!("  Calls     : {}", self.counter.ncalled);
+        info!("  Amplitude : {} A^3", self.delta);
+    }
+}
+
 /******************************************************************************/
 
 impl<T> Control for Alternator<T> where T: Control {
+    fn setup(&mut self, system: &System) {
+        self.as_mut().setup(system)
+    }
 
     fn control(&mut self, system: &mut System) {
         if self.can_run() {
             self.as_mut().control(system)
         }
     }
+
+    fn moved_particles(&self) -> bool {
+        self.has_run() && self.as_ref().moved_particles()
+    }
+
+    fn finish(&mut self, system: &System) {
+        self.as_mut().finish(system)
+    }
 }
 
 /// Remove global translation from the system
@@ -248,6 +815,224 @@ mod tests {
         assert_ulps_eq!(temperature, 250.0, epsilon=1e-9);
     }
 
+    #[test]
+    fn nose_hoover_thermostat() {
+        let mut system = testing_system();
+        let conserved = system.total_energy() + system.extended_energy();
+
+        let mut thermostat = NoseHooverThermostat::new(250.0, 10.0);
+        let mut temperatures = Vec::new();
+        for step in 0..5000 {
+            thermostat.control(&mut system);
+            if step >= 1000 {
+                temperatures.push(system.temperature());
+            }
+        }
+
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 250.0, max_relative = 1e-2);
+
+        let energy = system.total_energy() + system.extended_energy();
+        assert_relative_eq!(energy, conserved, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn nose_hoover_removed_translation() {
+        // Use a small system, where the 3 degrees of freedom of the center of
+        // mass make a visible difference in the temperature.
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for i in 0..4 {
+            let mut particle = Particle::new("Cl");
+            particle.position = Vector3D::new(i as f64 * 2.0, 0.0, 0.0);
+            system.add_particle(particle);
+        }
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.init(&mut system);
+        RemoveTranslation::new().control(&mut system);
+
+        let mut thermostat = NoseHooverThermostat::new(250.0, 10.0);
+        thermostat.set_removed_dof(3);
+        let dof = (3 * system.size() - 3) as f64;
+        let mut temperatures = Vec::new();
+        for step in 0..5000 {
+            thermostat.control(&mut system);
+            if step >= 1000 {
+                temperatures.push(2.0 * system.kinetic_energy() / (dof * K_BOLTZMANN));
+            }
+        }
+
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 250.0, max_relative = 1e-2);
+    }
+
+    #[test]
+    fn bussi_thermostat() {
+        let mut system = testing_system();
+        let conserved = system.total_energy() + system.extended_energy();
+
+        let mut thermostat = BussiThermostat::new(250.0, 10.0);
+        let mut temperatures = Vec::new();
+        for step in 0..5000 {
+            thermostat.control(&mut system);
+            if step >= 1000 {
+                temperatures.push(system.temperature());
+            }
+        }
+
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 250.0, max_relative = 1e-2);
+
+        let energy = system.total_energy() + system.extended_energy();
+        assert_relative_eq!(energy, conserved, max_relative = 1e-9);
+    }
+
+    #[test]
+    fn bussi_removed_translation() {
+        // Use a small system, where the 3 degrees of freedom of the center of
+        // mass make a visible difference in the temperature.
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for i in 0..8 {
+            let mut particle = Particle::new("Cl");
+            particle.position = Vector3D::new(i as f64 * 2.0, 0.0, 0.0);
+            system.add_particle(particle);
+        }
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.init(&mut system);
+        RemoveTranslation::new().control(&mut system);
+
+        let mut thermostat = BussiThermostat::new(250.0, 10.0);
+        thermostat.set_removed_dof(3);
+        let dof = (3 * system.size() - 3) as f64;
+        let mut temperatures = Vec::new();
+        for step in 0..50000 {
+            thermostat.control(&mut system);
+            if step >= 1000 {
+                temperatures.push(2.0 * system.kinetic_energy() / (dof * K_BOLTZMANN));
+            }
+        }
+
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 250.0, max_relative = 3e-2);
+    }
+
+    #[test]
+    fn bussi_seed() {
+        let run = |seed| {
+            let mut system = testing_system();
+            let mut thermostat = BussiThermostat::new(250.0, 10.0);
+            thermostat.seed(seed);
+            for _ in 0..10 {
+                thermostat.control(&mut system);
+            }
+            system.temperature()
+        };
+
+        assert_eq!(run(12), run(12));
+        assert!(run(12) != run(13));
+    }
+
+    #[test]
+    fn andersen_thermostat() {
+        let mut system = testing_system();
+        let mut thermostat = AndersenThermostat::new(250.0, 10.0);
+        let mut temperatures = Vec::new();
+        for step in 0..500 {
+            thermostat.control(&mut system);
+            if step >= 100 {
+                temperatures.push(system.temperature());
+            }
+        }
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 250.0, max_relative = 1e-2);
+
+        // Only some of the particles should collide at each step
+        let velocities = system.particles().map(|p| p.velocity).collect::<Vec<_>>();
+        thermostat.control(&mut system);
+        let changed = system.particles().zip(&velocities).filter(|&(p, v)| p.velocity != *v).count();
+        assert!(changed > 0 && changed < system.size());
+    }
+
+    #[test]
+    fn andersen_massive_thermostat() {
+        let mut system = testing_system();
+        let mut thermostat = AndersenThermostat::massive(250.0, 2.0);
+        thermostat.seed(7);
+
+        let mut temperatures = Vec::new();
+        for _ in 0..500 {
+            let velocities = system.particles().map(|p| p.velocity).collect::<Vec<_>>();
+            thermostat.control(&mut system);
+            let changed = system.particles().zip(&velocities).filter(|&(p, v)| p.velocity != *v).count();
+            // Either all or none of the particles collide
+            assert!(changed == 0 || changed == system.size());
+            temperatures.push(system.temperature());
+        }
+        let temperature = temperatures[100..].iter().sum::<f64>() / 400.0;
+        assert_relative_eq!(temperature, 250.0, max_relative = 1e-2);
+    }
+
+    fn ideal_gas(volume: f64) -> System {
+        let mut system = System::with_cell(UnitCell::cubic(f64::cbrt(volume)));
+        for i in 0..50 {
+            let mut first = Particle::new("He");
+            first.position = Vector3D::new(0.3 * i as f64, 0.0, 0.0);
+            let mut second = Particle::new("He");
+            second.position = Vector3D::new(0.3 * i as f64, 1.0, 0.5);
+            system.add_particle(first);
+            system.add_particle(second);
+            let _ = system.add_bond(2 * i, 2 * i + 1);
+        }
+        return system;
+    }
+
+    #[test]
+    fn monte_carlo_barostat() {
+        let mut system = ideal_gas(10000.0);
+        // The average volume of a perfect gas of N molecules is (N + 1) kT / P
+        let pressure = 51.0 * K_BOLTZMANN * 300.0 / 10000.0;
+        let mut barostat = MonteCarloBarostat::new(pressure, 300.0, 1000.0);
+        barostat.setup(&system);
+
+        let mut volumes = Vec::new();
+        for step in 0..20000 {
+            barostat.control(&mut system);
+            if step >= 2000 {
+                volumes.push(system.volume());
+            }
+        }
+        let volume = volumes.iter().sum::<f64>() / volumes.len() as f64;
+        assert_relative_eq!(volume, 10000.0, max_relative = 5e-2);
+
+        // Molecules are moved rigidly
+        for i in 0..50 {
+            let distance = system.distance(2 * i, 2 * i + 1);
+            assert_ulps_eq!(distance, f64::sqrt(1.25), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn monte_carlo_barostat_acceptance() {
+        let mut system = ideal_gas(10000.0);
+        let pressure = 51.0 * K_BOLTZMANN * 300.0 / 10000.0;
+        let mut barostat = MonteCarloBarostat::new(pressure, 300.0, 10.0);
+        barostat.set_target_acceptance(0.5, 100);
+        barostat.setup(&system);
+
+        for _ in 0..2000 {
+            barostat.control(&mut system);
+        }
+        // The initial amplitude is too small, and the moves are almost always
+        // accepted
+        assert!(barostat.delta > 10.0);
+        assert_eq!(barostat.counter.ncalled, 2000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_delta_monte_carlo_barostat() {
+        let _ = MonteCarloBarostat::new(1.0, 300.0, -10.0);
+    }
+
     #[test]
     #[should_panic]
     fn negative_temperature_rescale() {
@@ -260,6 +1045,36 @@ mod tests {
         let _ = BerendsenThermostat::new(-56.0, 1000.0);
     }
 
+    #[test]
+    #[should_panic]
+    fn negative_temperature_nose_hoover() {
+        let _ = NoseHooverThermostat::new(-56.0, 100.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_temperature_bussi() {
+        let _ = BussiThermostat::new(-56.0, 100.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_temperature_andersen() {
+        let _ = AndersenThermostat::new(-56.0, 100.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn small_timestep_andersen() {
+        let _ = AndersenThermostat::massive(300.0, 0.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_chain_nose_hoover() {
+        let _ = NoseHooverThermostat::with_chain(300.0, 100.0, 0);
+    }
+
     #[test]
     fn remove_translation() {
         let mut system = system_from_xyz("2
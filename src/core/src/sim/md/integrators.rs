@@ -1,7 +1,14 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
+use std::collections::BTreeMap;
+
+use rand::distributions::{Normal, Sample};
+use rand::{Isaac64Rng, SeedableRng};
+
+use consts::K_BOLTZMANN;
 use types::{Vector3D, Matrix3, One, Zero};
-use sys::System;
+use sys::{System, CellShape};
+use sim::md::NoseHooverChain;
 
 /// The `Integrator` trait define integrator interface for molecular dynamics.
 /// An integrator is an algorithm responsible for propagating the equations of
@@ -13,6 +20,19 @@ pub trait Integrator {
     /// Integrate the equations of motion. This is called at every step of the
     /// simulation.
     fn integrate(&mut self, system: &mut System);
+    /// Function called when the positions of the particles or the unit cell
+    /// were modified outside of the integrator, for example by an accepted
+    /// move of a Monte Carlo barostat. Integrators storing the forces acting
+    /// on the particles must compute them again here.
+    fn positions_changed(&mut self, _: &System) {}
+}
+
+/// Compute the accelerations of all the particles in the `system`
+fn accelerations(system: &System) -> Vec<Vector3D> {
+    let forces = system.forces();
+    system.particles().zip(forces).map(|(particle, force)| {
+        force / particle.mass
+    }).collect()
 }
 
 /// Velocity-Verlet integrator. This one is reversible and symplectic.
@@ -38,6 +58,10 @@ impl Integrator for VelocityVerlet {
         self.accelerations = vec![Vector3D::zero(); system.size()];
     }
 
+    fn positions_changed(&mut self, system: &System) {
+        self.accelerations = accelerations(system);
+    }
+
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
 
@@ -86,6 +110,11 @@ impl Integrator for Verlet {
         }
     }
 
+    fn positions_changed(&mut self, system: &System) {
+        // The positions at t - ∆t are approximated again from the velocities
+        self.setup(system);
+    }
+
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
         let dt2 = self.timestep * self.timestep;
@@ -131,6 +160,10 @@ impl Integrator for LeapFrog {
         self.accelerations = vec![Vector3D::zero(); system.size()];
     }
 
+    fn positions_changed(&mut self, system: &System) {
+        self.accelerations = accelerations(system);
+    }
+
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
         let dt2 = self.timestep * self.timestep;
@@ -149,6 +182,115 @@ impl Integrator for LeapFrog {
     }
 }
 
+/******************************************************************************/
+/// Langevin dynamics integrator, using the BAOAB splitting of the equations of
+/// motion [LM2013]. This integrator samples the canonical ensemble at the
+/// given temperature, the coupling to the heat bath being controlled by the
+/// friction coefficient of the particles.
+///
+/// Random forces do not conserve the total momentum of the system, which will
+/// slowly drift unless the `RemoveTranslation` control is used. This control
+/// is applied after the integrator step, and then only removes the random
+/// momentum added to the center of mass.
+///
+/// [LM2013] B. Leimkuhler & C. Matthews, Appl. Math. Res. eXpress 2013, 34
+/// (2013); doi: 10.1093/amrx/abs010
+pub struct Langevin {
+    /// Timestep for the integrator
+    timestep: f64,
+    /// Temperature of the heat bath
+    temperature: f64,
+    /// Default friction coefficient
+    friction: f64,
+    /// Friction coefficients for specific particles names
+    frictions: BTreeMap<String, f64>,
+    /// Storing the accelerations
+    accelerations: Vec<Vector3D>,
+    /// Velocities scaling factor `exp(-friction * timestep)` for each particle
+    scaling: Vec<f64>,
+    /// Standard normal distribution for the random forces
+    normal: Normal,
+    /// Random number generator for the random forces
+    rng: Isaac64Rng,
+}
+
+impl Langevin {
+    /// Create a new Langevin integrator with a timestep of `timestep`, at the
+    /// given `temperature`, and using a friction coefficient of `friction` for
+    /// all the particles.
+    pub fn new(timestep: f64, temperature: f64, friction: f64) -> Langevin {
+        assert!(temperature >= 0.0, "The temperature must be positive in Langevin integrator.");
+        assert!(friction >= 0.0, "The friction must be positive in Langevin integrator.");
+        Langevin {
+            timestep: timestep,
+            temperature: temperature,
+            friction: friction,
+            frictions: BTreeMap::new(),
+            accelerations: Vec::new(),
+            scaling: Vec::new(),
+            normal: Normal::new(0.0, 1.0),
+            rng: Isaac64Rng::from_seed(&[42]),
+        }
+    }
+
+    /// Use a friction coefficient of `friction` for all the particles with
+    /// the given `name`, instead of the default friction coefficient.
+    pub fn set_friction<S: Into<String>>(&mut self, name: S, friction: f64) {
+        assert!(friction >= 0.0, "The friction must be positive in Langevin integrator.");
+        let _ = self.frictions.insert(name.into(), friction);
+    }
+
+    /// Set the seed of the random number generator. The default seed is 42.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.reseed(&[seed]);
+    }
+}
+
+impl Integrator for Langevin {
+    fn setup(&mut self, system: &System) {
+        self.scaling = system.particles().map(|particle| {
+            let friction = self.frictions.get(particle.name()).cloned().unwrap_or(self.friction);
+            f64::exp(-friction * self.timestep)
+        }).collect();
+
+        self.accelerations = accelerations(system);
+    }
+
+    fn positions_changed(&mut self, system: &System) {
+        self.accelerations = accelerations(system);
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        let dt = self.timestep;
+        let kt = K_BOLTZMANN * self.temperature;
+
+        for (i, part) in system.particles_mut().enumerate() {
+            // B: update velocities at t + ∆t/2
+            part.velocity += 0.5 * dt * self.accelerations[i];
+            // A: update positions at t + ∆t/2
+            part.position += 0.5 * dt * part.velocity;
+            // O: exact solution of the Ornstein-Uhlenbeck process
+            let c1 = self.scaling[i];
+            let c2 = f64::sqrt((1.0 - c1 * c1) * kt / part.mass);
+            let noise = Vector3D::new(
+                self.normal.sample(&mut self.rng),
+                self.normal.sample(&mut self.rng),
+                self.normal.sample(&mut self.rng),
+            );
+            part.velocity = c1 * part.velocity + c2 * noise;
+            // A: update positions at t + ∆t
+            part.position += 0.5 * dt * part.velocity;
+        }
+
+        let forces = system.forces();
+        // B: update accelerations and velocities at t + ∆t
+        for (i, part) in system.particles_mut().enumerate() {
+            self.accelerations[i] = forces[i] / part.mass;
+            part.velocity += 0.5 * dt * self.accelerations[i];
+        }
+    }
+}
+
 /******************************************************************************/
 /// This is needed for the `BerendsenBarostat` implementation. The value comes
 /// from the DL_POLY source code.
@@ -189,6 +331,10 @@ impl Integrator for BerendsenBarostat {
         self.accelerations = vec![Vector3D::zero(); system.size()];
     }
 
+    fn positions_changed(&mut self, system: &System) {
+        self.accelerations = accelerations(system);
+    }
+
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
 
@@ -255,6 +401,10 @@ impl Integrator for AnisoBerendsenBarostat {
         self.accelerations = vec![Vector3D::zero(); system.size()];
     }
 
+    fn positions_changed(&mut self, system: &System) {
+        self.accelerations = accelerations(system);
+    }
+
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
 
@@ -287,3 +437,631 @@ impl Integrator for AnisoBerendsenBarostat {
         }
     }
 }
+
+/******************************************************************************/
+/// Compute the exponential of a `matrix` using its Taylor series. This is
+/// only accurate for matrices with a small norm, such as the barostat
+/// velocity multiplied by the timestep.
+fn exp_matrix(matrix: Matrix3) -> Matrix3 {
+    let mut result = Matrix3::one();
+    let mut term = Matrix3::one();
+    for n in 1..8 {
+        term = term * matrix / n as f64;
+        result += term;
+    }
+    return result;
+}
+
+/// Degrees of freedom of the cell used by an extended system barostat
+#[derive(Clone, Copy)]
+enum CellDegrees {
+    /// Isotropic scaling of the cell
+    Isotropic,
+    /// Independent scaling of the cell lengths
+    Diagonal,
+    /// Fully flexible cell, without rotations
+    Flexible,
+}
+
+impl CellDegrees {
+    /// Get the number of degrees of freedom of the barostat
+    fn count(&self) -> f64 {
+        match *self {
+            CellDegrees::Isotropic => 1.0,
+            CellDegrees::Diagonal => 3.0,
+            CellDegrees::Flexible => 6.0,
+        }
+    }
+
+    /// Project the `matrix` onto the allowed degrees of freedom
+    fn project(&self, matrix: Matrix3) -> Matrix3 {
+        match *self {
+            CellDegrees::Isotropic => matrix.trace() / 3.0 * Matrix3::one(),
+            CellDegrees::Diagonal => {
+                let mut diagonal = Matrix3::zero();
+                for i in 0..3 {
+                    diagonal[(i, i)] = matrix[(i, i)];
+                }
+                diagonal
+            }
+            CellDegrees::Flexible => 0.5 * (matrix + matrix.transposed()),
+        }
+    }
+}
+
+/// Extended system barostat, integrating the equations of motion of [MTTK1996]
+/// with the velocity-Verlet algorithm and the Trotter decomposition of
+/// [TLSMM2006]. Both the particles and the barostat are coupled to a chain of
+/// Nosé-Hoover thermostats. This is the implementation shared by
+/// `MTKBarostat` and `ParrinelloRahmanBarostat`.
+///
+/// [MTTK1996] G. J. Martyna, M. E. Tuckerman, D. J. Tobias & M. L. Klein,
+/// Mol. Phys. 87, 1117 (1996); doi: 10.1080/00268979600100761
+///
+/// [TLSMM2006] M. E. Tuckerman, J. Alejandre, R. López-Rendón, A. L. Jochim &
+/// G. J. Martyna, J. Phys. A 39, 5629 (2006); doi: 10.1088/0305-4470/39/19/S18
+struct ExtendedBarostat {
+    /// Timestep for the integrator
+    timestep: f64,
+    /// Target pressure
+    pressure: f64,
+    /// Target temperature
+    temperature: f64,
+    /// Barostat time constant, expressed in units of the timestep
+    tau: f64,
+    /// Degrees of freedom of the cell
+    degrees: CellDegrees,
+    /// Velocity of the barostat, in units of inverse time
+    velocity: Matrix3,
+    /// Nosé-Hoover chain coupled to the particles
+    particles_chain: NoseHooverChain,
+    /// Nosé-Hoover chain coupled to the barostat
+    barostat_chain: NoseHooverChain,
+    /// Storing the accelerations
+    accelerations: Vec<Vector3D>,
+    /// Storing the virial at the current positions
+    virial: Matrix3,
+    /// Number of degrees of freedom of the particles
+    dof: f64,
+    /// Extended energy at the end of the last step, including the `P V` term
+    energy: f64,
+}
+
+impl ExtendedBarostat {
+    /// Create a new extended system barostat with isotropic cell fluctuations
+    fn new(timestep: f64, pressure: f64, temperature: f64, tau: f64, thermostat_tau: f64) -> ExtendedBarostat {
+        assert!(temperature > 0.0, "The temperature must be positive in barostats.");
+        assert!(tau > 0.0, "The barostat timestep must be positive.");
+        assert!(thermostat_tau > 0.0, "The thermostat timestep must be positive in barostats.");
+        let thermostat_tau = thermostat_tau * timestep;
+        ExtendedBarostat {
+            timestep: timestep,
+            pressure: pressure,
+            temperature: temperature,
+            tau: tau,
+            degrees: CellDegrees::Isotropic,
+            velocity: Matrix3::zero(),
+            particles_chain: NoseHooverChain::new(temperature, thermostat_tau, 3),
+            barostat_chain: NoseHooverChain::new(temperature, thermostat_tau, 3),
+            accelerations: Vec::new(),
+            virial: Matrix3::zero(),
+            dof: 0.0,
+            energy: 0.0,
+        }
+    }
+
+    /// Get the mass of each component of the barostat velocity
+    fn mass(&self) -> f64 {
+        let tau = self.tau * self.timestep;
+        (self.dof + 3.0) * K_BOLTZMANN * self.temperature * tau * tau / 3.0
+    }
+
+    /// Get the kinetic energy of the barostat
+    fn kinetic_energy(&self) -> f64 {
+        let mut sum = 0.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                sum += self.velocity[(i, j)] * self.velocity[(i, j)];
+            }
+        }
+        return 0.5 * self.mass() * sum;
+    }
+
+    /// Get the energy of the extended degrees of freedom
+    fn extended_energy(&self, system: &System) -> f64 {
+        self.kinetic_energy() + self.pressure * system.volume()
+        + self.particles_chain.energy(self.dof)
+        + self.barostat_chain.energy(self.degrees.count())
+    }
+
+    /// Propagate both thermostat chains for half a timestep
+    fn propagate_chains(&mut self, system: &mut System) {
+        let h = 0.5 * self.timestep;
+
+        let kinetic = self.kinetic_energy();
+        let count = self.degrees.count();
+        let factor = self.barostat_chain.propagate(kinetic, h, count);
+        self.velocity *= factor;
+
+        let kinetic = system.kinetic_energy();
+        let factor = self.particles_chain.propagate(kinetic, h, self.dof);
+        for particle in system.particles_mut() {
+            particle.velocity *= factor;
+        }
+    }
+
+    /// Update the barostat velocity for half a timestep
+    fn update_barostat(&mut self, system: &System) {
+        let mut kinetic = Matrix3::zero();
+        for particle in system.particles() {
+            kinetic += particle.mass * particle.velocity.tensorial(&particle.velocity);
+        }
+        let volume = system.volume();
+
+        let mut force = kinetic + self.virial - volume * self.pressure * Matrix3::one();
+        force += (kinetic.trace() / self.dof) * Matrix3::one();
+        let force = self.degrees.project(force);
+
+        self.velocity += 0.5 * self.timestep / self.mass() * force;
+    }
+
+    /// Update the particles velocities for half a timestep
+    fn update_velocities(&self, system: &mut System) {
+        let dt = self.timestep;
+        let friction = self.velocity + self.velocity.trace() / self.dof * Matrix3::one();
+        let scaling = exp_matrix(-0.25 * dt * friction);
+        for (i, part) in system.particles_mut().enumerate() {
+            part.velocity = scaling * part.velocity;
+            part.velocity += 0.5 * dt * self.accelerations[i];
+            part.velocity = scaling * part.velocity;
+        }
+    }
+
+    fn setup(&mut self, system: &System) {
+        assert!(!system.cell.is_infinite(), "Can not use barostats with infinite cell");
+        self.degrees = match self.degrees {
+            CellDegrees::Isotropic => CellDegrees::Isotropic,
+            _ => match system.cell.shape() {
+                CellShape::Triclinic => CellDegrees::Flexible,
+                _ => CellDegrees::Diagonal,
+            }
+        };
+
+        self.dof = 3.0 * system.size() as f64;
+        self.virial = system.virial();
+        self.accelerations = accelerations(system);
+        self.energy = self.extended_energy(system);
+    }
+
+    /// Compute the virial and the accelerations again after the positions or
+    /// the cell were modified outside of the integrator
+    fn positions_changed(&mut self, system: &System) {
+        self.virial = system.virial();
+        self.accelerations = accelerations(system);
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        let dt = self.timestep;
+
+        self.propagate_chains(system);
+        self.update_barostat(system);
+        self.update_velocities(system);
+
+        // Update the positions and the cell at t + ∆t
+        let scaling = exp_matrix(0.5 * dt * self.velocity);
+        for part in system.particles_mut() {
+            part.position = scaling * part.position;
+            part.position += dt * part.velocity;
+            part.position = scaling * part.position;
+        }
+        system.cell = system.cell.scale(scaling * scaling);
+
+        self.virial = system.virial();
+        let forces = system.forces();
+        for (i, part) in system.particles().enumerate() {
+            self.accelerations[i] = forces[i] / part.mass;
+        }
+
+        self.update_velocities(system);
+        self.update_barostat(system);
+        self.propagate_chains(system);
+
+        // Only the change in the extended energy is added to the system, so
+        // the extended energy of the system does not include the energy at
+        // setup, and in particular the initial `P V` term.
+        let energy = self.extended_energy(system);
+        system.add_extended_energy(energy - self.energy);
+        self.energy = energy;
+    }
+}
+
+/// Martyna-Tobias-Klein barostat integrator, with isotropic cell
+/// fluctuations. This integrator samples the isothermal-isobaric ensemble,
+/// the particles and the barostat being both coupled to a Nosé-Hoover chain
+/// at the target temperature [MTTK1996]. It should not be used together with
+/// an additional thermostat.
+///
+/// The energy of the barostat and the thermostats is added to the system
+/// [extended energy](../../sys/struct.System.html#method.extended_energy), so
+/// that the conserved quantity of the simulation can be monitored. This energy
+/// is relative to the state at `setup`: the initial `P V` term is not included.
+///
+/// [MTTK1996] G. J. Martyna, M. E. Tuckerman, D. J. Tobias & M. L. Klein,
+/// Mol. Phys. 87, 1117 (1996); doi: 10.1080/00268979600100761
+pub struct MTKBarostat {
+    barostat: ExtendedBarostat,
+}
+
+impl MTKBarostat {
+    /// Create a new MTK barostat with an integration timestep of `timestep`,
+    /// a target pressure of `pressure` and a target temperature of
+    /// `temperature`. The time constant of the barostat is `tau` and the time
+    /// constant of the thermostats is `thermostat_tau`, both expressed in
+    /// units of the integration timestep.
+    pub fn new(timestep: f64, pressure: f64, temperature: f64, tau: f64, thermostat_tau: f64) -> MTKBarostat {
+        MTKBarostat {
+            barostat: ExtendedBarostat::new(timestep, pressure, temperature, tau, thermostat_tau)
+        }
+    }
+}
+
+impl Integrator for MTKBarostat {
+    fn setup(&mut self, system: &System) {
+        self.barostat.setup(system);
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        self.barostat.integrate(system);
+    }
+
+    fn positions_changed(&mut self, system: &System) {
+        self.barostat.positions_changed(system);
+    }
+}
+
+/// Parrinello-Rahman barostat integrator, with fully flexible cell
+/// fluctuations. This integrator samples the isothermal-isobaric ensemble
+/// using the equations of motion of [MTTK1996] for flexible cells, the
+/// particles and the barostat being both coupled to a Nosé-Hoover chain at the
+/// target temperature. It should not be used together with an additional
+/// thermostat.
+///
+/// All the cell parameters fluctuate for triclinic cells, while only the cell
+/// lengths fluctuate for orthorhombic cells. The cell velocity is kept
+/// symmetric to prevent rotations of the cell.
+///
+/// The energy of the barostat and the thermostats is added to the system
+/// [extended energy](../../sys/struct.System.html#method.extended_energy), so
+/// that the conserved quantity of the simulation can be monitored. This energy
+/// is relative to the state at `setup`: the initial `P V` term is not included.
+///
+/// [MTTK1996] G. J. Martyna, M. E. Tuckerman, D. J. Tobias & M. L. Klein,
+/// Mol. Phys. 87, 1117 (1996); doi: 10.1080/00268979600100761
+pub struct ParrinelloRahmanBarostat {
+    barostat: ExtendedBarostat,
+}
+
+impl ParrinelloRahmanBarostat {
+    /// Create a new Parrinello-Rahman barostat with an integration timestep of
+    /// `timestep`, a target hydrostatic pressure of `pressure` and a target
+    /// temperature of `temperature`. The time constant of the barostat is
+    /// `tau` and the time constant of the thermostats is `thermostat_tau`,
+    /// both expressed in units of the integration timestep.
+    pub fn new(timestep: f64, pressure: f64, temperature: f64, tau: f64, thermostat_tau: f64) -> ParrinelloRahmanBarostat {
+        let mut barostat = ExtendedBarostat::new(timestep, pressure, temperature, tau, thermostat_tau);
+        barostat.degrees = CellDegrees::Flexible;
+        ParrinelloRahmanBarostat {
+            barostat: barostat
+        }
+    }
+}
+
+impl Integrator for ParrinelloRahmanBarostat {
+    fn setup(&mut self, system: &System) {
+        self.barostat.setup(system);
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        self.barostat.integrate(system);
+    }
+
+    fn positions_changed(&mut self, system: &System) {
+        self.barostat.positions_changed(system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts::K_BOLTZMANN;
+    use sys::{System, UnitCell, Particle};
+    use sys::veloc::{BoltzmannVelocities, InitVelocities};
+    use sim::md::{Control, RemoveTranslation};
+
+    fn testing_system() -> System {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for i in 0..10 {
+            for j in 0..10 {
+                for k in 0..10 {
+                    let name = if (i + j + k) % 2 == 0 {"Ar"} else {"He"};
+                    let mut particle = Particle::new(name);
+                    particle.position = Vector3D::new(
+                        i as f64 * 2.0, j as f64 * 2.0, k as f64 * 2.0
+                    );
+                    system.add_particle(particle);
+                }
+            }
+        }
+        return system;
+    }
+
+    #[test]
+    fn langevin_temperature() {
+        let mut system = testing_system();
+        let mut langevin = Langevin::new(1.0, 300.0, 0.01);
+        langevin.setup(&system);
+
+        // Velocities should follow a Maxwell-Boltzmann distribution, so we
+        // accumulate the moments of the reduced velocities over the run.
+        let mut temperatures = Vec::new();
+        let (mut n, mut mean, mut variance, mut kurtosis) = (0.0, 0.0, 0.0, 0.0);
+        for step in 0..3000 {
+            langevin.integrate(&mut system);
+            if step >= 1000 {
+                temperatures.push(system.temperature());
+                for particle in system.particles() {
+                    let factor = f64::sqrt(particle.mass / (K_BOLTZMANN * 300.0));
+                    for k in 0..3 {
+                        let v = factor * particle.velocity[k];
+                        n += 1.0;
+                        mean += v;
+                        variance += v * v;
+                        kurtosis += v * v * v * v;
+                    }
+                }
+            }
+        }
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 300.0, max_relative = 1e-2);
+
+        let mean = mean / n;
+        let variance = variance / n;
+        let kurtosis = kurtosis / n;
+        assert!(f64::abs(mean) < 0.02);
+        assert!(f64::abs(variance - 1.0) < 0.03);
+        assert!(f64::abs(kurtosis - 3.0) < 0.15);
+    }
+
+    #[test]
+    fn langevin_friction() {
+        let mut system = testing_system();
+        let mut langevin = Langevin::new(1.0, 300.0, 0.01);
+        langevin.set_friction("He", 0.0);
+        langevin.setup(&system);
+
+        for _ in 0..100 {
+            langevin.integrate(&mut system);
+        }
+
+        for particle in system.particles() {
+            if particle.name() == "He" {
+                assert_eq!(particle.velocity, Vector3D::zero());
+            } else {
+                assert!(particle.velocity.norm() > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn langevin_seed() {
+        let run = |seed| {
+            let mut system = testing_system();
+            let mut langevin = Langevin::new(1.0, 300.0, 0.01);
+            langevin.seed(seed);
+            langevin.setup(&system);
+            for _ in 0..10 {
+                langevin.integrate(&mut system);
+            }
+            system.particle(0).velocity
+        };
+
+        assert_eq!(run(12), run(12));
+        assert!(run(12) != run(13));
+    }
+
+    #[test]
+    fn langevin_remove_translation() {
+        let mut system = testing_system();
+        let mut langevin = Langevin::new(1.0, 300.0, 0.01);
+        let mut control = RemoveTranslation::new();
+        langevin.setup(&system);
+
+        for _ in 0..100 {
+            langevin.integrate(&mut system);
+            control.control(&mut system);
+
+            let momentum = system.particles().fold(Vector3D::zero(), |momentum, particle| {
+                momentum + particle.mass * particle.velocity
+            });
+            assert!(momentum.norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn langevin_remove_translation_temperature() {
+        // Use a small system, where the 3 degrees of freedom of the center of
+        // mass make a visible difference in the temperature.
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for i in 0..8 {
+            let name = if i % 2 == 0 {"Ar"} else {"He"};
+            let mut particle = Particle::new(name);
+            particle.position = Vector3D::new(i as f64 * 2.0, 0.0, 0.0);
+            system.add_particle(particle);
+        }
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.init(&mut system);
+
+        let mut langevin = Langevin::new(1.0, 300.0, 0.1);
+        let mut control = RemoveTranslation::new();
+        langevin.setup(&system);
+
+        let dof = (3 * system.size() - 3) as f64;
+        let mut temperatures = Vec::new();
+        for step in 0..21000 {
+            langevin.integrate(&mut system);
+            control.control(&mut system);
+            if step >= 1000 {
+                temperatures.push(2.0 * system.kinetic_energy() / (dof * K_BOLTZMANN));
+            }
+        }
+        let temperature = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+        assert_relative_eq!(temperature, 300.0, max_relative = 2e-2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn langevin_negative_friction() {
+        let mut langevin = Langevin::new(1.0, 300.0, 0.01);
+        langevin.set_friction("He", -1.0);
+    }
+
+    fn barostat_system(cell: UnitCell) -> System {
+        let mut system = testing_system();
+        system.cell = cell;
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.init(&mut system);
+        return system;
+    }
+
+    fn ideal_gas_pressure(system: &System) -> f64 {
+        system.size() as f64 * K_BOLTZMANN * 300.0 / system.volume()
+    }
+
+    #[test]
+    fn mtk_barostat() {
+        let mut system = barostat_system(UnitCell::cubic(20.0));
+        // Half the pressure of the initial configuration
+        let pressure = 0.5 * ideal_gas_pressure(&system);
+        let initial_volume = system.volume();
+
+        let mut barostat = MTKBarostat::new(1.0, pressure, 300.0, 100.0, 50.0);
+        barostat.setup(&system);
+        let conserved = system.conserved_energy();
+
+        for _ in 0..2000 {
+            barostat.integrate(&mut system);
+        }
+
+        let energy = system.conserved_energy();
+        assert!(f64::abs((energy - conserved) / conserved) < 1e-3);
+        assert!(system.volume() > initial_volume);
+        // The cell stays cubic
+        assert_ulps_eq!(system.cell.a(), system.cell.b());
+        assert_ulps_eq!(system.cell.a(), system.cell.c());
+    }
+
+    #[test]
+    fn parrinello_rahman_barostat_orthorhombic() {
+        let mut system = barostat_system(UnitCell::ortho(20.0, 21.0, 22.0));
+        let pressure = 2.0 * ideal_gas_pressure(&system);
+        let initial_volume = system.volume();
+
+        let mut barostat = ParrinelloRahmanBarostat::new(1.0, pressure, 300.0, 100.0, 50.0);
+        barostat.setup(&system);
+        let conserved = system.conserved_energy();
+
+        for _ in 0..2000 {
+            barostat.integrate(&mut system);
+        }
+
+        let energy = system.conserved_energy();
+        assert!(f64::abs((energy - conserved) / conserved) < 1e-3);
+        assert!(system.volume() < initial_volume);
+        // The cell stays orthorhombic
+        let (a, b, c) = (system.cell.vect_a(), system.cell.vect_b(), system.cell.vect_c());
+        assert_eq!(a[1], 0.0);
+        assert_eq!(a[2], 0.0);
+        assert_eq!(b[0], 0.0);
+        assert_eq!(b[2], 0.0);
+        assert_eq!(c[0], 0.0);
+        assert_eq!(c[1], 0.0);
+    }
+
+    #[test]
+    fn parrinello_rahman_barostat_triclinic() {
+        let mut system = barostat_system(UnitCell::triclinic(20.0, 20.0, 20.0, 90.0, 80.0, 100.0));
+        let pressure = ideal_gas_pressure(&system);
+
+        let mut barostat = ParrinelloRahmanBarostat::new(1.0, pressure, 300.0, 100.0, 50.0);
+        barostat.setup(&system);
+        let conserved = system.conserved_energy();
+
+        let mut off_diagonal = Vec::new();
+        for _ in 0..2000 {
+            barostat.integrate(&mut system);
+            let energy = system.conserved_energy();
+            assert!(f64::abs((energy - conserved) / conserved) < 1e-4);
+            off_diagonal.push(barostat.barostat.velocity[(0, 1)]);
+        }
+
+        // The off-diagonal cell velocity is sampled: it takes both signs, with
+        // a magnitude comparable to equipartition, where the symmetric
+        // off-diagonal component has a mass of 2 W.
+        let sign_changes = off_diagonal.windows(2).filter(|v| v[0] * v[1] < 0.0).count();
+        assert!(sign_changes >= 4);
+
+        let mean_square = off_diagonal.iter().map(|v| v * v).sum::<f64>() / off_diagonal.len() as f64;
+        let expected = K_BOLTZMANN * 300.0 / (2.0 * barostat.barostat.mass());
+        assert!(mean_square.sqrt() > 0.05 * expected.sqrt());
+        assert!(mean_square.sqrt() < 5.0 * expected.sqrt());
+    }
+
+    #[test]
+    fn mtk_barostat_ideal_gas() {
+        // Ideal gas in the isothermal-isobaric ensemble: the volume follows a
+        // gamma distribution, with <V> = (N + 1) kT / P and Var(V) = (N + 1)
+        // (kT / P)^2.
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for i in 0..50 {
+            let mut particle = Particle::new("He");
+            particle.position = Vector3D::new(
+                (i % 5) as f64 * 4.0, ((i / 5) % 5) as f64 * 4.0, (i / 25) as f64 * 4.0
+            );
+            system.add_particle(particle);
+        }
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.init(&mut system);
+
+        // Start away from the average volume, as the deterministic dynamics
+        // of an ideal gas does not move when started at equilibrium.
+        let n = system.size() as f64;
+        let kt = K_BOLTZMANN * 300.0;
+        let pressure = 0.75 * (n + 1.0) * kt / system.volume();
+
+        let mut barostat = MTKBarostat::new(1.0, pressure, 300.0, 20.0, 10.0);
+        barostat.setup(&system);
+
+        let mut volumes = Vec::new();
+        for step in 0..50_000 {
+            barostat.integrate(&mut system);
+            if step >= 5_000 {
+                volumes.push(system.volume());
+            }
+        }
+
+        let count = volumes.len() as f64;
+        let mean = volumes.iter().sum::<f64>() / count;
+        let variance = volumes.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / count;
+
+        assert_relative_eq!(mean, (n + 1.0) * kt / pressure, max_relative = 2e-2);
+        assert_relative_eq!(variance, (n + 1.0) * (kt / pressure) * (kt / pressure), max_relative = 0.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn barostat_infinite_cell() {
+        let system = barostat_system(UnitCell::new());
+        let mut barostat = MTKBarostat::new(1.0, 1.0, 300.0, 100.0, 50.0);
+        barostat.setup(&system);
+    }
+}
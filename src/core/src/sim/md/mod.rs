@@ -7,12 +7,17 @@ pub use self::integrators::Integrator;
 pub use self::integrators::VelocityVerlet;
 pub use self::integrators::Verlet;
 pub use self::integrators::LeapFrog;
+pub use self::integrators::Langevin;
 pub use self::integrators::BerendsenBarostat;
 pub use self::integrators::AnisoBerendsenBarostat;
+pub use self::integrators::{MTKBarostat, ParrinelloRahmanBarostat};
 
 mod controls;
+pub(crate) use self::controls::NoseHooverChain;
 pub use self::controls::{Control, Thermostat};
-pub use self::controls::{RescaleThermostat, BerendsenThermostat};
+pub use self::controls::{RescaleThermostat, BerendsenThermostat, NoseHooverThermostat};
+pub use self::controls::{BussiThermostat, AndersenThermostat};
+pub use self::controls::MonteCarloBarostat;
 pub use self::controls::{RemoveTranslation, RemoveRotation};
 
 mod molecular_dynamics;
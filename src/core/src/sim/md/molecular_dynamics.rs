@@ -15,6 +15,9 @@ pub struct MolecularDynamics {
     thermostat: Option<Box<Thermostat>>,
     /// Control algorithms in the simulation.
     controls: Vec<Box<Control>>,
+    /// Should we check the bonds length at each step? This is only needed if
+    /// some bond potentials have a maximal length.
+    check_bonds: bool,
 }
 
 impl MolecularDynamics {
@@ -31,6 +34,7 @@ impl MolecularDynamics {
             integrator: integrator,
             thermostat: None,
             controls: Vec::new(),
+            check_bonds: false,
         }
     }
 
@@ -51,6 +55,17 @@ impl Propagator for MolecularDynamics {
     }
 
     fn setup(&mut self, system: &System) {
+        self.check_bonds = system.molecules().iter().any(|molecule| {
+            molecule.bonds().iter().any(|bond| {
+                system.bond_potentials(bond.i(), bond.j()).iter().any(|potential| {
+                    potential.max_length().is_some()
+                })
+            })
+        });
+        if self.check_bonds {
+            check_bonds_length(system);
+        }
+
         self.integrator.setup(system);
         for control in &mut self.controls {
             control.setup(system);
@@ -59,13 +74,24 @@ impl Propagator for MolecularDynamics {
 
     fn propagate(&mut self, system: &mut System) {
         self.integrator.integrate(system);
+        if self.check_bonds {
+            check_bonds_length(system);
+        }
 
         if let Some(ref mut thermostat) = self.thermostat {
             thermostat.control(system);
         }
 
+        let mut moved = false;
         for control in &mut self.controls {
             control.control(system);
+            moved |= control.moved_particles();
+        }
+
+        // Controls changing the positions invalidate the forces stored in
+        // the integrator.
+        if moved {
+            self.integrator.positions_changed(system);
         }
     }
 
@@ -75,3 +101,135 @@ impl Propagator for MolecularDynamics {
         }
     }
 }
+
+/// Check that no bond in the `system` is stretched past the maximal length of
+/// its potentials, and panic with an explicit message if this is the case.
+/// This prevents the simulation from continuing with infinite energies.
+fn check_bonds_length(system: &System) {
+    for molecule in system.molecules() {
+        for bond in molecule.bonds() {
+            let (i, j) = (bond.i(), bond.j());
+            for potential in system.bond_potentials(i, j) {
+                if let Some(max_length) = potential.max_length() {
+                    let r = system.nearest_image(i, j).norm();
+                    if r >= max_length {
+                        panic!(
+                            "The bond between particles {} ({}) and {} ({}) is stretched to {} A, \
+                            past the maximal length of {} A of its potential. The timestep might be too large.",
+                            i, system.particle(i).name(), j, system.particle(j).name(), r, max_length
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sim::{Propagator, Alternator};
+    use sim::md::MonteCarloBarostat;
+    use sys::{Particle, UnitCell};
+    use sys::veloc::{BoltzmannVelocities, InitVelocities};
+    use energy::{Fene, LennardJones, NullPotential, PairInteraction};
+    use types::Vector3D;
+    use utils::{system_from_xyz, unit_from};
+
+    fn testing_system(distance: f64) -> System {
+        let mut system = system_from_xyz(&format!("2
+        cell: 20.0
+        Ar 0.0 0.0 0.0
+        Ar {} 0.0 0.0
+        ", distance));
+        assert!(system.add_bond(0, 1).is_empty());
+        system.add_pair_potential("Ar", "Ar", PairInteraction::new(Box::new(NullPotential), 3.0));
+        system.add_bond_potential("Ar", "Ar", Box::new(Fene{k: 30.0, r_max: 1.5}));
+        return system;
+    }
+
+    #[test]
+    fn fene_bond() {
+        let mut system = testing_system(1.0);
+        let mut md = MolecularDynamics::new(1e-3);
+        md.setup(&system);
+        assert!(md.check_bonds);
+        for _ in 0..10 {
+            md.propagate(&mut system);
+        }
+        assert!(system.potential_energy().is_finite());
+    }
+
+    #[test]
+    #[should_panic(expected = "is stretched to 2 A, past the maximal length of 1.5 A")]
+    fn stretched_fene_bond() {
+        let system = testing_system(2.0);
+        let mut md = MolecularDynamics::new(1e-3);
+        md.setup(&system);
+    }
+
+    /// Integrator checking that the forces it stored at the end of the
+    /// previous step are still the forces acting on the particles.
+    struct CheckForces {
+        forces: Vec<Vector3D>,
+    }
+
+    impl Integrator for CheckForces {
+        fn setup(&mut self, system: &System) {
+            self.forces = system.forces();
+        }
+
+        fn integrate(&mut self, system: &mut System) {
+            for (force, expected) in system.forces().iter().zip(&self.forces) {
+                assert_ulps_eq!(force, expected, epsilon = 1e-12);
+            }
+
+            for particle in system.particles_mut() {
+                particle.position += 1.0 * particle.velocity;
+            }
+            self.forces = system.forces();
+        }
+
+        fn positions_changed(&mut self, system: &System) {
+            self.forces = system.forces();
+        }
+    }
+
+    #[test]
+    fn monte_carlo_barostat() {
+        let mut system = System::with_cell(UnitCell::cubic(12.0));
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    let mut particle = Particle::new("Ar");
+                    particle.position = Vector3D::new(
+                        i as f64 * 4.0 + 0.1 * j as f64, j as f64 * 4.0, k as f64 * 4.0 + 0.2 * i as f64
+                    );
+                    system.add_particle(particle);
+                }
+            }
+        }
+        system.add_pair_potential("Ar", "Ar", PairInteraction::new(Box::new(LennardJones{
+            sigma: unit_from(3.4, "A"),
+            epsilon: unit_from(1.0, "kJ/mol"),
+        }), 5.0));
+        BoltzmannVelocities::new(300.0).init(&mut system);
+
+        let mut md = MolecularDynamics::from_integrator(Box::new(CheckForces{forces: Vec::new()}));
+        md.add_control(Box::new(Alternator::new(2, MonteCarloBarostat::new(
+            unit_from(100.0, "bar"), 300.0, 10.0
+        ))));
+        md.setup(&system);
+
+        let mut moves = 0;
+        for _ in 0..100 {
+            let volume = system.volume();
+            md.propagate(&mut system);
+            if system.volume() != volume {
+                moves += 1;
+            }
+        }
+        // Check that some volume moves were accepted
+        assert!(moves > 10);
+    }
+}
@@ -63,6 +63,11 @@ impl<T> Alternator<T> {
         self.count += 1;
         self.count % self.every == 0
     }
+
+    /// Check if the algorithm was run at the last call to `can_run`.
+    pub fn has_run(&self) -> bool {
+        self.count != 0 && self.count % self.every == 0
+    }
 }
 
 impl<T> AsRef<T> for Alternator<T> {
@@ -34,6 +34,8 @@ pub struct EnergyCache {
     angles: f64,
     /// Energy of all the dihedrals angles in the system
     dihedrals: f64,
+    /// Energy of all the improper dihedrals angles in the system
+    impropers: f64,
     /// Energy of coulombic interactions
     coulomb: f64,
     /// Energy of global interactions
@@ -52,6 +54,7 @@ impl EnergyCache {
             bonds: 0.0,
             angles: 0.0,
             dihedrals: 0.0,
+            impropers: 0.0,
             coulomb: 0.0,
             global: 0.0,
             updater: None,
@@ -66,6 +69,7 @@ impl EnergyCache {
         self.bonds = 0.0;
         self.angles = 0.0;
         self.dihedrals = 0.0;
+        self.impropers = 0.0;
         self.coulomb = 0.0;
         self.global = 0.0;
     }
@@ -78,9 +82,10 @@ impl EnergyCache {
         self.pairs_cache.resize_if_different((system.size(), system.size()));
 
         let evaluator = system.energy_evaluator();
+        let neighbors = system.neighbors();
 
         for i in 0..system.size() {
-            for j in (i + 1)..system.size() {
+            for &j in neighbors.of(i) {
                 let r = system.nearest_image(i, j).norm();
                 let energy = evaluator.pair(r, i, j);
                 self.pairs_cache[(i, j)] = energy;
@@ -93,6 +98,7 @@ impl EnergyCache {
         self.bonds = evaluator.bonds();
         self.angles = evaluator.angles();
         self.dihedrals = evaluator.dihedrals();
+        self.impropers = evaluator.impropers();
         self.coulomb = evaluator.coulomb();
         self.global = evaluator.global();
     }
@@ -106,6 +112,7 @@ impl EnergyCache {
         energy += self.bonds;
         energy += self.angles;
         energy += self.dihedrals;
+        energy += self.impropers;
 
         energy += self.coulomb;
         energy += self.global;
@@ -144,24 +151,33 @@ impl EnergyCache {
     /// effectively moved.
     pub fn move_particles_cost(&mut self, system: &System, idxes: Vec<usize>, newpos: &[Vector3D]) -> f64 {
         let evaluator = system.energy_evaluator();
+        let neighbors = system.neighbors();
 
-        // First, go for pair interactions
-        let mut new_pairs = Array2::<f64>::zeros((system.size(), system.size()));
+        // First, go for pair interactions. `new_pairs` contains the new values
+        // of the modified entries in the pairs cache, in the order in which
+        // they should be set.
+        let mut new_pairs = Vec::new();
         let mut pairs_delta = 0.0;
         // Interactions with the sub-system not being moved
         for (i, &part_i) in idxes.iter().enumerate() {
-            for part_j in 0..system.size() {
+            // Remove the interactions at the old position
+            for part_j in neighbors.around(&system.particle(part_i).position) {
                 // Exclude interactions inside the sub-system.
                 if idxes.contains(&part_j) {continue}
 
+                pairs_delta -= self.pairs_cache[(part_i, part_j)];
+                new_pairs.push((part_i, part_j, 0.0));
+            }
+
+            // Add the interactions at the new position
+            for part_j in neighbors.around(&newpos[i]) {
+                if idxes.contains(&part_j) {continue}
+
                 let r = system.cell.distance(&system.particle(part_j).position, &newpos[i]);
                 let energy = evaluator.pair(r, part_i, part_j);
 
                 pairs_delta += energy;
-                new_pairs[(part_i, part_j)] += energy;
-                new_pairs[(part_j, part_i)] += energy;
-
-                pairs_delta -= self.pairs_cache[(part_i, part_j)];
+                new_pairs.push((part_i, part_j, energy));
             }
         }
 
@@ -172,8 +188,7 @@ impl EnergyCache {
                 let energy = evaluator.pair(r, part_i, part_j);
 
                 pairs_delta += energy;
-                new_pairs[(part_i, part_j)] += energy;
-                new_pairs[(part_j, part_i)] += energy;
+                new_pairs.push((part_i, part_j, energy));
 
                 pairs_delta -= self.pairs_cache[(part_i, part_j)];
             }
@@ -184,6 +199,7 @@ impl EnergyCache {
         let mut bonds = 0.0;
         let mut angles = 0.0;
         let mut dihedrals = 0.0;
+        let mut impropers = 0.0;
         for molecule in system.molecules() {
             for bond in molecule.bonds() {
                 let (i, j) = (bond.i(), bond.j());
@@ -200,6 +216,12 @@ impl EnergyCache {
                 let rk = new_position(system, k, &idxes, newpos);
                 let theta = system.cell.angle(ri, rj, rk);
                 angles += evaluator.angle(theta, i, j, k);
+
+                let mut r1 = ri - rj;
+                system.cell.vector_image(&mut r1);
+                let mut r2 = rk - rj;
+                system.cell.vector_image(&mut r2);
+                angles += evaluator.angle_geometry(&r1, &r2, i, j, k);
             }
 
             for dihedral in molecule.dihedrals() {
@@ -211,6 +233,17 @@ impl EnergyCache {
                 let phi = system.cell.dihedral(ri, rj, rk, rm);
                 dihedrals += evaluator.dihedral(phi, i, j, k, m);
             }
+
+            for improper in molecule.impropers() {
+                let improper = system.improper_particles(improper);
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                let ri = new_position(system, i, &idxes, newpos);
+                let rj = new_position(system, j, &idxes, newpos);
+                let rk = new_position(system, k, &idxes, newpos);
+                let rm = new_position(system, m, &idxes, newpos);
+                let phi = system.cell.dihedral(ri, rj, rk, rm);
+                impropers += evaluator.improper(phi, i, j, k, m);
+            }
         }
 
         let coulomb_delta = if let Some(coulomb) = system.coulomb_potential() {
@@ -230,6 +263,7 @@ impl EnergyCache {
                                + (bonds - self.bonds)
                                + (angles - self.angles)
                                + (dihedrals - self.dihedrals)
+                               + (impropers - self.impropers)
                                + coulomb_delta + global_delta;
 
         self.updater = Some(Box::new(move |cache, system| {
@@ -237,25 +271,16 @@ impl EnergyCache {
             cache.bonds = bonds;
             cache.angles = angles;
             cache.dihedrals = dihedrals;
+            cache.impropers = impropers;
 
             cache.pairs += pairs_delta;
             cache.coulomb += coulomb_delta;
             cache.global += global_delta;
 
-            let (n, m) = new_pairs.dim();
-            debug_assert_eq!(n, m);
-            debug_assert_eq!((n, m), cache.pairs_cache.dim());
-            // only loop over the indices that actually changed
-            for &i in &idxes {
-                for j in 0..n {
-                    if idxes.contains(&j) {continue}
-                    cache.pairs_cache[(i, j)] = new_pairs[(i, j)];
-                    cache.pairs_cache[(j, i)] = new_pairs[(i, j)];
-                }
-                for &k in idxes.iter().skip(i + 1) {
-                    cache.pairs_cache[(i, k)] = new_pairs[(i, k)];
-                    cache.pairs_cache[(k, i)] = new_pairs[(i, k)];
-                }
+            // only loop over the pairs that actually changed
+            for &(i, j, energy) in &new_pairs {
+                cache.pairs_cache[(i, j)] = energy;
+                cache.pairs_cache[(j, i)] = energy;
             }
 
             // Update the cache for the global potentials
@@ -288,28 +313,24 @@ impl EnergyCache {
     /// MUST be called if the molecules are effectively moved.
     pub fn move_all_rigid_molecules_cost(&mut self, system: &System) -> f64 {
         let evaluator = system.energy_evaluator();
+        let neighbors = system.neighbors();
 
-        let mut new_pairs = Array2::<f64>::zeros((system.size(), system.size()));
-        let mut pairs_delta = 0.0;
-        // Loop over all molecule pairs
-        for (i, mi) in system.molecules().iter().enumerate() {
-            for mj in system.molecules().iter().skip(i + 1) {
-                // Loop over all particles in the molecules
-                for pi in mi.iter() {
-                    for pj in mj.iter() {
-                        let r = system.cell.distance(
-                            &system.particle(pi).position,
-                            &system.particle(pj).position
-                        );
-                        let energy = evaluator.pair(r, pi, pj);
-                        pairs_delta += energy;
-                        new_pairs[(pi, pj)] += energy;
-                        new_pairs[(pj, pi)] += energy;
-                        pairs_delta -= self.pairs_cache[(pi, pj)];
-                    }
+        // Recompute all the pairs, using the neighbor list. The intramolecular
+        // pairs do not change, but computing them allows to rebuild the whole
+        // pairs cache without looping over all the pairs in the system.
+        let mut new_pairs = Vec::new();
+        let mut pairs = 0.0;
+        for i in 0..system.size() {
+            for &j in neighbors.of(i) {
+                let r = system.nearest_image(i, j).norm();
+                let energy = evaluator.pair(r, i, j);
+                if energy != 0.0 {
+                    new_pairs.push((i, j, energy));
                 }
+                pairs += energy;
             }
         }
+        let pairs_delta = pairs - self.pairs;
 
         // temporarily, recompute all interactions
         let new_coulomb = evaluator.coulomb();
@@ -322,24 +343,16 @@ impl EnergyCache {
                                + (new_coulomb - self.coulomb)
                                + (new_global - self.global);
 
-        self.updater = Some(Box::new(move |cache, system| {
+        self.updater = Some(Box::new(move |cache, _| {
             cache.pairs += pairs_delta;
             cache.pairs_tail = pairs_tail;
             cache.coulomb = new_coulomb;
             cache.global = new_global;
 
-            let (n, m) = new_pairs.dim();
-            debug_assert_eq!(n, m);
-            debug_assert_eq!((n, m), cache.pairs_cache.dim());
-            for (i, mi) in system.molecules().iter().enumerate() {
-                for mj in system.molecules().iter().skip(i + 1) {
-                    for pi in mi.iter() {
-                        for pj in mj.iter() {
-                            cache.pairs_cache[(pi, pj)] = new_pairs[(pi, pj)];
-                            cache.pairs_cache[(pj, pi)] = new_pairs[(pi, pj)];
-                        }
-                    }
-                }
+            cache.pairs_cache.fill(0.0);
+            for &(i, j, energy) in &new_pairs {
+                cache.pairs_cache[(i, j)] = energy;
+                cache.pairs_cache[(j, i)] = energy;
             }
         }));
         cost
@@ -7,7 +7,7 @@ use std::f64::consts::PI;
 
 use consts::K_BOLTZMANN;
 use types::{Matrix3, Vector3D, Zero, One};
-use sys::System;
+use sys::{System, Selection, GroupMasks};
 use parallel::prelude::*;
 use parallel::ThreadLocalStore;
 
@@ -30,12 +30,13 @@ impl Compute for Forces {
     fn compute(&self, system: &System) -> Vec<Vector3D> {
         let natoms = system.size();
         let thread_forces_store = ThreadLocalStore::new(|| vec![Vector3D::zero(); natoms]);
+        let neighbors = system.neighbors();
 
         (0..natoms).into_par_iter().for_each(|i| {
 
             let mut thread_forces = thread_forces_store.borrow_mut();
 
-            for j in (i+1)..system.size() {
+            for &j in neighbors.of(i) {
                 let distance = system.bond_distance(i, j);
                 let d = system.nearest_image(i, j);
                 let dn = d.normalized();
@@ -79,6 +80,24 @@ impl Compute for Forces {
                     forces[j] += force * d2;
                     forces[k] += force * d3;
                 }
+
+                let (direct, reversed) = system.angle_geometry_potentials(i, j, k);
+                if !direct.is_empty() || !reversed.is_empty() {
+                    let r1 = system.nearest_image(i, j);
+                    let r2 = system.nearest_image(k, j);
+                    for potential in direct {
+                        let (force_i, force_j, force_k) = potential.forces(&r1, &r2);
+                        forces[i] += force_i;
+                        forces[j] += force_j;
+                        forces[k] += force_k;
+                    }
+                    for potential in reversed {
+                        let (force_k, force_j, force_i) = potential.forces(&r2, &r1);
+                        forces[i] += force_i;
+                        forces[j] += force_j;
+                        forces[k] += force_k;
+                    }
+                }
             }
 
             for dihedral in molecule.dihedrals() {
@@ -92,6 +111,19 @@ impl Compute for Forces {
                     forces[m] += force * d4;
                 }
             }
+
+            for improper in molecule.impropers() {
+                let improper = system.improper_particles(improper);
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                let (phi, d1, d2, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+                for potential in system.improper_potentials(i, j, k, m) {
+                    let force = potential.force(phi);
+                    forces[i] += force * d1;
+                    forces[j] += force * d2;
+                    forces[k] += force * d3;
+                    forces[m] += force * d4;
+                }
+            }
         }
 
         if let Some(coulomb) = system.coulomb_potential() {
@@ -118,6 +150,7 @@ impl Compute for PotentialEnergy {
         energy += evaluator.bonds();
         energy += evaluator.angles();
         energy += evaluator.dihedrals();
+        energy += evaluator.impropers();
         energy += evaluator.coulomb();
         energy += evaluator.global();
 
@@ -126,6 +159,126 @@ impl Compute for PotentialEnergy {
     }
 }
 
+/******************************************************************************/
+/// The separated components of the potential energy of a system
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnergyComponents {
+    /// Energy of the non-bonded pair interactions
+    pub pairs: f64,
+    /// Long range corrections to the energy of the pair interactions
+    pub pairs_tail: f64,
+    /// Energy of the covalent bonds
+    pub bonds: f64,
+    /// Energy of the covalent angles
+    pub angles: f64,
+    /// Energy of the covalent dihedral angles
+    pub dihedrals: f64,
+    /// Energy of the improper dihedral angles
+    pub impropers: f64,
+    /// Energy of the electrostatic interactions
+    pub coulomb: f64,
+    /// Energy of each of the global potentials, in the same order as
+    /// `System::global_potentials`
+    pub globals: Vec<f64>,
+}
+
+impl EnergyComponents {
+    /// Get the total potential energy, *i.e.* the sum of all the components
+    pub fn total(&self) -> f64 {
+        let mut energy = self.pairs + self.pairs_tail;
+        energy += self.bonds + self.angles + self.dihedrals + self.impropers;
+        energy += self.coulomb;
+        energy += self.globals.iter().sum::<f64>();
+        return energy;
+    }
+}
+
+/// Compute all the components of the potential energy of the system
+/// separately. Contrary to `PotentialEnergy`, this does not check that the
+/// energy is finite, to help locating the source of infinite energies.
+pub struct PotentialEnergyComponents;
+impl Compute for PotentialEnergyComponents {
+    type Output = EnergyComponents;
+    fn compute(&self, system: &System) -> EnergyComponents {
+        let evaluator = system.energy_evaluator();
+        EnergyComponents {
+            pairs: evaluator.pairs(),
+            pairs_tail: evaluator.pairs_tail(),
+            bonds: evaluator.bonds(),
+            angles: evaluator.angles(),
+            dihedrals: evaluator.dihedrals(),
+            impropers: evaluator.impropers(),
+            coulomb: evaluator.coulomb(),
+            globals: evaluator.globals(),
+        }
+    }
+}
+
+/******************************************************************************/
+/// The interaction energy between two groups of particles
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupEnergies {
+    /// Energy of the non-bonded pair interactions between the groups
+    pub pairs: f64,
+    /// Energy of the electrostatic interactions between the groups
+    pub coulomb: f64,
+}
+
+impl GroupEnergies {
+    /// Get the total interaction energy between the groups
+    pub fn total(&self) -> f64 {
+        self.pairs + self.coulomb
+    }
+}
+
+/// Compute the interaction energy between the particles in the `first` and
+/// `second` selections. This includes the pair interactions (without long
+/// range corrections) and the electrostatic interactions, and uses the same
+/// restrictions as the full energy. Each pair of different particles is
+/// counted only once, even if the selections overlap.
+///
+/// See [`CoulombicPotential::group_energy`][group_energy] for the details of
+/// the electrostatic interactions with Ewald-like solvers.
+///
+/// [group_energy]: ../../energy/trait.CoulombicPotential.html#method.group_energy
+pub struct GroupEnergy {
+    /// First group of particles
+    pub first: Selection,
+    /// Second group of particles
+    pub second: Selection,
+}
+
+impl Compute for GroupEnergy {
+    type Output = GroupEnergies;
+    fn compute(&self, system: &System) -> GroupEnergies {
+        let first = self.first.select(system);
+        let second = self.second.select(system);
+
+        let masks = GroupMasks::new(system.size(), &first, &second);
+        let evaluator = system.energy_evaluator();
+        let neighbors = system.neighbors();
+        let mut pairs = 0.0;
+        for i in 0..system.size() {
+            if !masks.in_any(i) {continue}
+            for &j in neighbors.of(i) {
+                if !masks.contains(i, j) {continue}
+                let r = system.nearest_image(i, j).norm();
+                pairs += evaluator.pair(r, i, j);
+            }
+        }
+
+        let coulomb = match system.coulomb_potential() {
+            Some(coulomb) => coulomb.group_energy(system, &first, &second),
+            None => 0.0,
+        };
+
+        GroupEnergies {
+            pairs: pairs,
+            coulomb: coulomb,
+        }
+    }
+}
+
 /******************************************************************************/
 /// Compute the kinetic energy of the system
 pub struct KineticEnergy;
@@ -153,6 +306,18 @@ impl Compute for TotalEnergy {
     }
 }
 
+/******************************************************************************/
+/// Compute the conserved energy of the system, *i.e.* the total energy plus
+/// the energy of the extended degrees of freedom used by thermostats and
+/// barostats.
+pub struct ConservedEnergy;
+impl Compute for ConservedEnergy {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        return TotalEnergy.compute(system) + system.extended_energy();
+    }
+}
+
 /******************************************************************************/
 /// Compute the instantaneous temperature of the system
 pub struct Temperature;
@@ -186,9 +351,10 @@ impl Compute for Virial {
         assert!(!system.cell.is_infinite(), "Can not compute virial for infinite cell");
 
         // Pair potentials contributions
+        let neighbors = system.neighbors();
         let mut virial = (0..system.size()).par_map(|i| {
             let mut local_virial = Matrix3::zero();
-            for j in (i+1)..system.size() {
+            for &j in neighbors.of(i) {
                 let distance = system.bond_distance(i, j);
                 for potential in system.pair_potentials(i, j) {
                     let info = potential.restriction().information(distance);
@@ -223,10 +389,29 @@ impl Compute for Virial {
                     virial += potential.virial(&r);
                 }
             }
+
+            // Angle geometry potentials (Urey-Bradley, bond-bond and
+            // bond-angle) also depend on the bond lengths, and contribute
+            // to the virial
+            for angle in molecule.angles() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                let (direct, reversed) = system.angle_geometry_potentials(i, j, k);
+                if direct.is_empty() && reversed.is_empty() {
+                    continue;
+                }
+                let r1 = system.nearest_image(i, j);
+                let r2 = system.nearest_image(k, j);
+                for potential in direct {
+                    virial += potential.virial(&r1, &r2);
+                }
+                for potential in reversed {
+                    virial += potential.virial(&r2, &r1);
+                }
+            }
         }
 
-        // Angles and dihedrals potentials do not contribute as they only have
-        // an angular part (see DL_POLY 4 manual page 18, or Smith, W., 1993,
+        // Angles, dihedrals and impropers potentials only depending on angles
+        // do not contribute (see DL_POLY 4 manual page 18, or Smith, W., 1993,
         // CCP5 Information Quarterly, 39, 14. 18, 21, 24).
 
         if let Some(coulomb) = system.coulomb_potential() {
@@ -322,9 +507,11 @@ impl Compute for Pressure {
 #[cfg(test)]
 mod test {
     use super::*;
-    use sys::System;
+    use sys::{System, Particle, UnitCell};
     use sys::veloc::{InitVelocities, BoltzmannVelocities};
     use energy::{Harmonic, NullPotential, PairInteraction};
+    use energy::{UreyBradley, BondBond, BondAngle, AngleGeometryPotential};
+    use energy::{DistanceRestraint, Wolf};
     use consts::K_BOLTZMANN;
     use utils::{unit_from, system_from_xyz};
 
@@ -396,6 +583,71 @@ mod test {
         return system;
     }
 
+    fn test_improper_system() -> System {
+        let mut system = system_from_xyz("4
+        cell: 10.0
+        C 0.0 0.0 0.3
+        F 1.0 0.0 0.0
+        F -0.5 0.8 0.1
+        F -0.4 -0.9 0.0
+        ");
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(0, 2).is_empty());
+        assert!(system.add_bond(0, 3).is_empty());
+        assert_eq!(system.molecule(0).impropers().len(), 1);
+
+        system.add_pair_potential("F", "F",
+            PairInteraction::new(Box::new(NullPotential), 0.0)
+        );
+        system.add_bond_potential("C", "F", Box::new(NullPotential));
+        system.add_angle_potential("F", "C", "F", Box::new(NullPotential));
+
+        system.add_improper_potential("C", "F", "F", "F",
+            Box::new(Harmonic{
+                k: unit_from(100.0, "kJ/mol/rad^2"),
+                x0: 0.0,
+        }));
+
+        return system;
+    }
+
+    fn test_angle_geometry_system() -> System {
+        let mut system = system_from_xyz("3
+        cell: 10.0
+        O 1.2 0.1 0.0
+        C 0.0 0.0 0.0
+        H -0.4 0.9 0.2
+        ");
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
+
+        system.add_pair_potential("O", "H",
+            PairInteraction::new(Box::new(NullPotential), 0.0)
+        );
+        system.add_bond_potential("O", "C", Box::new(NullPotential));
+        system.add_bond_potential("C", "H", Box::new(NullPotential));
+        system.add_angle_potential("O", "C", "H", Box::new(NullPotential));
+
+        system.add_angle_geometry_potential("O", "C", "H",
+            Box::new(UreyBradley{k: unit_from(30.0, "kJ/mol/A^2"), r0: 2.0})
+        );
+        system.add_angle_geometry_potential("O", "C", "H",
+            Box::new(BondBond{k: unit_from(20.0, "kJ/mol/A^2"), r1: 1.1, r2: 1.0})
+        );
+        // Defined in the reverse order, r1 is the C-H vector
+        system.add_angle_geometry_potential("H", "C", "O",
+            Box::new(BondAngle{
+                k1: unit_from(10.0, "kJ/mol/A"),
+                k2: unit_from(25.0, "kJ/mol/A"),
+                r1: 1.0,
+                r2: 1.1,
+                theta0: unit_from(110.0, "deg"),
+            })
+        );
+
+        return system;
+    }
+
     #[test]
     fn forces_pairs() {
         let system = &test_pairs_system();
@@ -422,6 +674,73 @@ mod test {
         assert_ulps_eq!(forces_tot.norm2(), 0.0);
     }
 
+    #[test]
+    fn forces_impropers() {
+        let mut system = test_improper_system();
+        let res = Forces.compute(&system);
+        let forces_tot = res[0] + res[1] + res[2] + res[3];
+        assert_ulps_eq!(forces_tot.norm2(), 0.0);
+
+        // Compare with finite differences of the energy
+        let eps = 1e-6;
+        for i in 0..4 {
+            for axis in 0..3 {
+                system.particle_mut(i).position[axis] += eps;
+                let energy_plus = PotentialEnergy.compute(&system);
+                system.particle_mut(i).position[axis] -= 2.0 * eps;
+                let energy_minus = PotentialEnergy.compute(&system);
+                system.particle_mut(i).position[axis] += eps;
+
+                let force = -(energy_plus - energy_minus) / (2.0 * eps);
+                assert_relative_eq!(res[i][axis], force, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn forces_angle_geometry() {
+        let mut system = test_angle_geometry_system();
+        let res = Forces.compute(&system);
+        let forces_tot = res[0] + res[1] + res[2];
+        assert_relative_eq!(forces_tot.norm(), 0.0, epsilon = 1e-12);
+
+        // Compare with finite differences of the energy
+        let eps = 1e-6;
+        for i in 0..3 {
+            for axis in 0..3 {
+                system.particle_mut(i).position[axis] += eps;
+                let energy_plus = PotentialEnergy.compute(&system);
+                system.particle_mut(i).position[axis] -= 2.0 * eps;
+                let energy_minus = PotentialEnergy.compute(&system);
+                system.particle_mut(i).position[axis] += eps;
+
+                let force = -(energy_plus - energy_minus) / (2.0 * eps);
+                assert_relative_eq!(res[i][axis], force, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn energy_angle_geometry() {
+        let system = test_angle_geometry_system();
+        let r_oc = system.nearest_image(0, 1);
+        let r_hc = system.nearest_image(2, 1);
+
+        let urey_bradley = UreyBradley{k: unit_from(30.0, "kJ/mol/A^2"), r0: 2.0};
+        let bond_bond = BondBond{k: unit_from(20.0, "kJ/mol/A^2"), r1: 1.1, r2: 1.0};
+        let bond_angle = BondAngle{
+            k1: unit_from(10.0, "kJ/mol/A"),
+            k2: unit_from(25.0, "kJ/mol/A"),
+            r1: 1.0,
+            r2: 1.1,
+            theta0: unit_from(110.0, "deg"),
+        };
+        let expected = urey_bradley.energy(&r_oc, &r_hc)
+                     + bond_bond.energy(&r_oc, &r_hc)
+                     + bond_angle.energy(&r_hc, &r_oc);
+        assert_relative_eq!(PotentialEnergy.compute(&system), expected, epsilon = 1e-12);
+    }
+
     #[test]
     fn energy_pairs() {
         let system = &test_pairs_system();
@@ -443,6 +762,114 @@ mod test {
         assert_ulps_eq!(PotentialEnergy.compute(&system), unit_from(1800.0, "kJ/mol"));
     }
 
+    #[test]
+    fn energy_components() {
+        let mut system = test_molecular_system();
+        system.add_global_potential(Box::new(DistanceRestraint{
+            particles: [0, 3],
+            k: unit_from(10.0, "kJ/mol/A^2"),
+            x0: unit_from(2.0, "A"),
+            width: 0.0,
+        }));
+
+        let components = PotentialEnergyComponents.compute(&system);
+        assert_eq!(components.pairs, 0.0);
+        assert_eq!(components.pairs_tail, 0.0);
+        assert_ulps_eq!(components.bonds, unit_from(150.0, "kJ/mol"));
+        assert_eq!(components.impropers, 0.0);
+        assert_eq!(components.coulomb, 0.0);
+        assert_eq!(components.globals.len(), 1);
+
+        let distance = f64::sqrt(5.0) - 2.0;
+        let expected = 0.5 * unit_from(10.0, "kJ/mol/A^2") * distance * distance;
+        assert_ulps_eq!(components.globals[0], expected);
+
+        assert_ulps_eq!(components.total(), PotentialEnergy.compute(&system));
+    }
+
+    #[test]
+    fn group_energy() {
+        let mut system = test_pairs_system();
+        system.particle_mut(0).charge = -1.0;
+        system.particle_mut(1).charge = 1.0;
+        system.set_coulomb_potential(Box::new(Wolf::new(4.0)));
+
+        let compute = GroupEnergy {
+            first: Selection::Range(0..1),
+            second: Selection::Range(1..2),
+        };
+        let energies = compute.compute(&system);
+        assert_ulps_eq!(energies.pairs, PotentialEnergyComponents.compute(&system).pairs);
+        assert!(energies.coulomb < 0.0);
+        assert_ulps_eq!(energies.total(), energies.pairs + energies.coulomb);
+
+        // Overlapping selections count the pair only once
+        let compute = GroupEnergy {
+            first: Selection::Name(String::from("F")),
+            second: Selection::Range(0..2),
+        };
+        assert_eq!(compute.compute(&system), energies);
+
+        // No interactions of a particle with itself
+        let compute = GroupEnergy {
+            first: Selection::Range(0..1),
+            second: Selection::Range(0..1),
+        };
+        let energies = compute.compute(&system);
+        assert_eq!(energies.pairs, 0.0);
+        assert_eq!(energies.coulomb, 0.0);
+    }
+
+    #[test]
+    fn energy_impropers() {
+        let system = test_improper_system();
+        let phi = system.dihedral(0, 1, 2, 3);
+        assert!(phi.abs() > 0.1);
+        let expected = 0.5 * unit_from(100.0, "kJ/mol/rad^2") * phi * phi;
+        assert_ulps_eq!(PotentialEnergy.compute(&system), expected);
+    }
+
+    #[test]
+    fn energy_impropers_order() {
+        // The energy of an improper must not depend on the order of the atoms
+        // in the configuration
+        let positions = [
+            ("C", Vector3D::new(0.0, 0.0, 0.3)),
+            ("O", Vector3D::new(1.0, 0.0, 0.0)),
+            ("N", Vector3D::new(-0.5, 0.8, 0.1)),
+            ("H", Vector3D::new(-0.4, -0.9, 0.0)),
+        ];
+
+        let mut energies = Vec::new();
+        for order in &[[0, 1, 2, 3], [0, 3, 2, 1], [0, 2, 3, 1]] {
+            let mut system = System::with_cell(UnitCell::cubic(10.0));
+            for &index in order {
+                let (name, position) = positions[index];
+                let mut particle = Particle::new(name);
+                particle.position = position;
+                system.add_particle(particle);
+            }
+            assert!(system.add_bond(0, 1).is_empty());
+            assert!(system.add_bond(0, 2).is_empty());
+            assert!(system.add_bond(0, 3).is_empty());
+
+            system.add_improper_potential("C", "O", "N", "H", Box::new(Harmonic{
+                k: unit_from(100.0, "kJ/mol/rad^2"),
+                x0: 0.2,
+            }));
+
+            let find = |name| (0..4).find(|&i| system.particle(i).name() == name).unwrap();
+            let phi = system.dihedral(0, find("O"), find("N"), find("H"));
+            let expected = 0.5 * unit_from(100.0, "kJ/mol/rad^2") * (phi - 0.2) * (phi - 0.2);
+            let energy = system.energy_evaluator().impropers();
+            assert_ulps_eq!(energy, expected);
+            energies.push(energy);
+        }
+
+        assert_ulps_eq!(energies[0], energies[1]);
+        assert_ulps_eq!(energies[0], energies[2]);
+    }
+
     #[test]
     fn temperature() {
         let system = &test_pairs_system();
@@ -492,6 +919,23 @@ mod test {
         assert_eq!(virial, system.virial());
     }
 
+    #[test]
+    fn virial_angle_geometry() {
+        let mut system = test_angle_geometry_system();
+        let virial = Virial.compute(&system);
+        assert_relative_eq!(virial, virial.transposed(), epsilon = 1e-12);
+
+        // The trace of the virial is the derivative of the energy with
+        // respect to an isotropic scaling of the positions
+        let eps = 1e-6;
+        let energy = PotentialEnergy.compute(&system);
+        for particle in system.particles_mut() {
+            particle.position *= 1.0 + eps;
+        }
+        let scaled = PotentialEnergy.compute(&system);
+        assert_relative_eq!(virial.trace(), -(scaled - energy) / eps, epsilon = 1e-5);
+    }
+
     #[test]
     #[should_panic]
     fn pressure_at_temperature_negative_temperature() {
@@ -23,7 +23,10 @@ pub enum CellShape {
 /// An UnitCell defines the system physical boundaries.
 ///
 /// The shape of the cell can be any of the [`CellShape`][CellShape], and will
-/// influence how periodic boundary conditions are applied.
+/// influence how periodic boundary conditions are applied. Orthorhombic cells
+/// can also be created without periodic boundary conditions along the z axis
+/// with [`UnitCell::slab`](#method.slab), to simulate systems with interfaces
+/// or confined between walls.
 ///
 /// [CellShape]: enum.CellShape.html
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -35,6 +38,8 @@ pub struct UnitCell {
     inv: Matrix3,
     /// Unit cell shape
     shape: CellShape,
+    /// Are periodic boundary conditions used along the z axis?
+    periodic_z: bool,
 }
 
 impl UnitCell {
@@ -43,7 +48,8 @@ impl UnitCell {
         UnitCell{
             cell: Matrix3::zero(),
             inv: Matrix3::zero(),
-            shape: CellShape::Infinite
+            shape: CellShape::Infinite,
+            periodic_z: true,
         }
     }
     /// Create an orthorhombic unit cell, with side lengths `a, b, c`.
@@ -55,7 +61,8 @@ impl UnitCell {
         UnitCell{
             cell: cell,
             inv: cell.inverse(),
-            shape: CellShape::Orthorhombic
+            shape: CellShape::Orthorhombic,
+            periodic_z: true,
         }
     }
     /// Create a cubic unit cell, with side lengths `length, length, length`.
@@ -67,9 +74,21 @@ impl UnitCell {
         UnitCell{
             cell: cell,
             inv: cell.inverse(),
-            shape: CellShape::Orthorhombic
+            shape: CellShape::Orthorhombic,
+            periodic_z: true,
         }
     }
+    /// Create an orthorhombic slab unit cell, with side lengths `a, b, c`.
+    ///
+    /// This cell is periodic along the x and y axes, but not along the z
+    /// axis. The `c` length is used for the volume of the cell and by the
+    /// electrostatic solvers, and should be larger than the thickness of the
+    /// system.
+    pub fn slab(a: f64, b: f64, c: f64) -> UnitCell {
+        let mut cell = UnitCell::ortho(a, b, c);
+        cell.periodic_z = false;
+        return cell;
+    }
     /// Create a triclinic unit cell, with side lengths `a, b, c` and angles
     /// `alpha, beta, gamma`.
     pub fn triclinic(a: f64, b: f64, c: f64, alpha: f64, beta: f64, gamma: f64) -> UnitCell {
@@ -92,7 +111,8 @@ impl UnitCell {
         UnitCell{
             cell: cell,
             inv: cell.inverse(),
-            shape: CellShape::Triclinic
+            shape: CellShape::Triclinic,
+            periodic_z: true,
         }
     }
 
@@ -107,6 +127,13 @@ impl UnitCell {
         self.shape() == CellShape::Infinite
     }
 
+    /// Check if this unit cell uses periodic boundary conditions along the z
+    /// axis. This is `false` for slab cells, and `true` for all the other
+    /// cells.
+    pub fn is_periodic_z(&self) -> bool {
+        self.periodic_z
+    }
+
     /// Get the first vector of the cell
     pub fn vect_a(&self) -> Vector3D {
         let x = self.cell[(0, 0)];
@@ -237,7 +264,7 @@ impl UnitCell {
     /// new scaled unit cell
     #[inline] pub fn scale(&self, s: Matrix3) -> UnitCell {
         let cell = s * self.cell;
-        UnitCell{cell: cell, inv: cell.inverse(), shape: self.shape}
+        UnitCell{cell: cell, inv: cell.inverse(), shape: self.shape, periodic_z: self.periodic_z}
     }
 
     /// Get the reciprocal vectors of this unit cell
@@ -260,14 +287,16 @@ impl UnitCell {
 impl UnitCell {
     /// Wrap a vector in the unit cell, obeying the periodic boundary conditions.
     /// For a cubic cell of side length `L`, this produce a vector with all
-    /// components in `[0, L)`.
+    /// components in `[0, L)`. The z component is not modified for slab cells.
     pub fn wrap_vector(&self, vect: &mut Vector3D) {
         match self.shape {
             CellShape::Infinite => (),
             CellShape::Orthorhombic => {
                 vect[0] -= f64::floor(vect[0] / self.a()) * self.a();
                 vect[1] -= f64::floor(vect[1] / self.b()) * self.b();
-                vect[2] -= f64::floor(vect[2] / self.c()) * self.c();
+                if self.periodic_z {
+                    vect[2] -= f64::floor(vect[2] / self.c()) * self.c();
+                }
             },
             CellShape::Triclinic => {
                 let mut fractional = self.fractional(vect);
@@ -281,14 +310,17 @@ impl UnitCell {
 
     /// Find the image of a vector in the unit cell, obeying the periodic
     /// boundary conditions. For a cubic cell of side length `L`, this produce a
-    /// vector with all components in `[-L/2, L/2)`.
+    /// vector with all components in `[-L/2, L/2)`. The z component is not
+    /// modified for slab cells.
     pub fn vector_image(&self, vect: &mut Vector3D) {
         match self.shape {
             CellShape::Infinite => (),
             CellShape::Orthorhombic => {
                 vect[0] -= f64::round(vect[0] / self.a()) * self.a();
                 vect[1] -= f64::round(vect[1] / self.b()) * self.b();
-                vect[2] -= f64::round(vect[2] / self.c()) * self.c();
+                if self.periodic_z {
+                    vect[2] -= f64::round(vect[2] / self.c()) * self.c();
+                }
             },
             CellShape::Triclinic => {
                 let mut fractional = self.fractional(vect);
@@ -497,6 +529,34 @@ mod tests {
         assert_eq!(cell.gamma(), 90.0);
 
         assert_eq!(cell.volume(), 3.0 * 4.0 * 5.0);
+        assert!(cell.is_periodic_z());
+    }
+
+    #[test]
+    fn slab() {
+        let cell = UnitCell::slab(3.0, 4.0, 5.0);
+        assert_eq!(cell.shape(), CellShape::Orthorhombic);
+        assert!(!cell.is_infinite());
+        assert!(!cell.is_periodic_z());
+
+        assert_eq!(cell.lengths(), [3.0, 4.0, 5.0]);
+        assert_eq!(cell.volume(), 3.0 * 4.0 * 5.0);
+
+        let cell = cell.scale(2.0 * Matrix3::one());
+        assert_eq!(cell.lengths(), [6.0, 8.0, 10.0]);
+        assert!(!cell.is_periodic_z());
+
+        let mut v = Vector3D::new(7.0, -1.0, 12.0);
+        cell.wrap_vector(&mut v);
+        assert_eq!(v, Vector3D::new(1.0, 7.0, 12.0));
+
+        let mut v = Vector3D::new(7.0, -1.0, 12.0);
+        cell.vector_image(&mut v);
+        assert_eq!(v, Vector3D::new(1.0, -1.0, 12.0));
+
+        let u = &Vector3D::zero();
+        let v = &Vector3D::new(1.0, 2.0, 9.0);
+        assert_eq!(cell.distance(u, v), f64::sqrt(86.0));
     }
 
     #[test]
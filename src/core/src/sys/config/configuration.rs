@@ -203,6 +203,23 @@ impl Configuration {
         return permutations;
     }
 
+    /// Add an improper dihedral angle around the particle at index `center`,
+    /// with the particles at indexes `i`, `j` and `k`, in this order. All
+    /// these particles should already be in the same molecule.
+    ///
+    /// Improper dihedral angles are automatically created around particles
+    /// with exactly three bonds, this function is only needed for additional
+    /// improper dihedral angles, or to fix the order of the particles in the
+    /// improper angle `center-i-j-k`.
+    pub fn add_improper(&mut self, center: usize, i: usize, j: usize, k: usize) {
+        let molid = self.molids[center];
+        assert!(
+            self.molids[i] == molid && self.molids[j] == molid && self.molids[k] == molid,
+            "all the particles in an improper dihedral angle must be in the same molecule"
+        );
+        self.molecules[molid].add_improper(center, i, j, k);
+    }
+
     /// Removes particle at index `i` and any associated bonds, angle or dihedral
     pub fn remove_particle(&mut self, i: usize) {
         let id = self.molids[i];
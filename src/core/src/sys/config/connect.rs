@@ -100,6 +100,48 @@ impl Dihedral {
     #[inline] pub fn m(&self) -> usize {self.m}
 }
 
+/// An `Improper` dihedral angle around the particle at index `i`, formed with
+/// the particles at indexes `j`, `k` and `m`.
+///
+/// The central particle `i` is usually bonded to the three other particles.
+/// The particles are stored in the order they were given, and the improper
+/// angle is the dihedral angle `i-j-k-m`, which is zero when the four
+/// particles are in the same plane. Changing the order of the outer particles
+/// can change the sign of the improper angle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Improper {
+    i: usize,
+    j: usize,
+    k: usize,
+    m: usize,
+}
+
+impl Improper {
+    /// Create a new Improper dihedral angle around the particle at index
+    /// `center`, with the particles at indexes `first`, `second` and `third`.
+    pub fn new(center: usize, first: usize, second: usize, third: usize) -> Improper {
+        assert_ne!(center, first);
+        assert_ne!(center, second);
+        assert_ne!(center, third);
+        assert_ne!(first, second);
+        assert_ne!(second, third);
+        assert_ne!(first, third);
+        Improper{i: center, j: first, k: second, m: third}
+    }
+
+    /// Get the central particle in the improper dihedral angle
+    #[inline] pub fn i(&self) -> usize {self.i}
+
+    /// Get the first outer particle in the improper dihedral angle
+    #[inline] pub fn j(&self) -> usize {self.j}
+
+    /// Get the second outer particle in the improper dihedral angle
+    #[inline] pub fn k(&self) -> usize {self.k}
+
+    /// Get the third outer particle in the improper dihedral angle
+    #[inline] pub fn m(&self) -> usize {self.m}
+}
+
 mod distance {
     bitflags! {
         /// The `BondDistance` bitflag encode the topological distance between
@@ -160,4 +202,16 @@ mod test {
         assert_eq!(dihedral.k, 6);
         assert_eq!(dihedral.m, 8);
     }
+
+    #[test]
+    fn improper() {
+        let improper = Improper::new(3, 8, 0, 6);
+        assert_eq!(improper.i, 3);
+        assert_eq!(improper.j, 8);
+        assert_eq!(improper.k, 0);
+        assert_eq!(improper.m, 6);
+
+        assert!(improper != Improper::new(3, 6, 8, 0));
+        assert!(improper != Improper::new(0, 3, 6, 8));
+    }
 }
@@ -16,7 +16,7 @@ mod cells;
 pub use self::cells::{UnitCell, CellShape};
 
 mod connect;
-pub use self::connect::{Bond, Angle, Dihedral};
+pub use self::connect::{Bond, Angle, Dihedral, Improper};
 pub use self::connect::BondDistance;
 pub use self::connect::{BONDED_12, BONDED_13, BONDED_14, BONDED_FAR};
 
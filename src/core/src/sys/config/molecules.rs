@@ -9,12 +9,12 @@ use std::hash::{Hash, Hasher};
 use std::collections::hash_map::DefaultHasher;
 
 use types::Array2;
-use sys::{Particle, Bond, Angle, Dihedral};
+use sys::{Particle, Bond, Angle, Dihedral, Improper};
 use sys::{BondDistance, BONDED_12, BONDED_13, BONDED_14};
 
 #[derive(Debug, Clone)]
 /// A molecule is the basic building block for a topology. It contains data
-/// about the connectivity (bonds, angles, dihedrals) in the system.
+/// about the connectivity (bonds, angles, dihedrals, impropers) in the system.
 pub struct Molecule {
     /// All the bonds in the molecule.
     bonds: HashSet<Bond>,
@@ -24,6 +24,11 @@ pub struct Molecule {
     /// All the dihedral angles in the molecule. This is rebuilt as needed from
     /// the bond list.
     dihedrals: HashSet<Dihedral>,
+    /// All the improper dihedral angles in the molecule. This is rebuilt as
+    /// needed from the bond list and the explicit impropers.
+    impropers: HashSet<Improper>,
+    /// Improper dihedral angles explicitly added to the molecule.
+    explicit_impropers: HashSet<Improper>,
     /// Matrix of bond distances in the molecule. The item at index `i, j`
     /// encode the bond distance between the particles `i + self.first` and
     /// `j + self.first`
@@ -47,6 +52,8 @@ impl Molecule {
             bonds: HashSet::new(),
             angles: HashSet::new(),
             dihedrals: HashSet::new(),
+            impropers: HashSet::new(),
+            explicit_impropers: HashSet::new(),
             distances: Array2::default((1, 1)),
             range: i..i+1,
             cached_hash: 0
@@ -86,6 +93,17 @@ impl Molecule {
             bond.i().hash(&mut hasher);
             bond.j().hash(&mut hasher);
         }
+
+        let mut impropers = self.explicit_impropers.iter()
+                                .map(|improper| Improper::new(
+                                    improper.i() - self.start(),
+                                    improper.j() - self.start(),
+                                    improper.k() - self.start(),
+                                    improper.m() - self.start(),
+                                ))
+                                .collect::<Vec<_>>();
+        impropers.sort();
+        impropers.hash(&mut hasher);
         self.cached_hash = hasher.finish();
     }
 
@@ -93,9 +111,11 @@ impl Molecule {
     fn cleanup(&mut self) {
         self.angles.clear();
         self.dihedrals.clear();
+        self.impropers.clear();
     }
 
-    /// Rebuild the full list of angles and dihedral angles from the list of bonds
+    /// Rebuild the full list of angles, dihedral angles and improper dihedral
+    /// angles from the list of bonds
     fn rebuild(&mut self) {
         self.cleanup();
         for bond1 in &self.bonds {
@@ -142,6 +162,38 @@ impl Molecule {
                 }
             }
         }
+
+        // Find improper dihedral angles around particles with three bonds.
+        // The outer particles are sorted by index, and the order used to
+        // compute the improper angle is selected from the potentials.
+        for center in self.iter() {
+            let mut neighbors = self.bonds.iter().filter_map(|bond| {
+                if bond.i() == center {
+                    Some(bond.j())
+                } else if bond.j() == center {
+                    Some(bond.i())
+                } else {
+                    None
+                }
+            }).collect::<Vec<_>>();
+
+            if neighbors.len() != 3 {
+                continue;
+            }
+            neighbors.sort();
+
+            // Explicit impropers with the same particles take precedence
+            let explicit = self.explicit_impropers.iter().any(|improper| {
+                let mut others = [improper.j(), improper.k(), improper.m()];
+                others.sort();
+                improper.i() == center && others == [neighbors[0], neighbors[1], neighbors[2]]
+            });
+            if !explicit {
+                self.impropers.insert(Improper::new(center, neighbors[0], neighbors[1], neighbors[2]));
+            }
+        }
+        self.impropers.extend(self.explicit_impropers.iter().cloned());
+
         self.rebuild_connections();
         self.rehash();
     }
@@ -194,6 +246,9 @@ impl Molecule {
             self.dihedrals.insert(*dihedral);
         }
 
+        self.impropers.extend(other.impropers);
+        self.explicit_impropers.extend(other.explicit_impropers);
+
         self.rebuild_connections();
         self.rehash();
     }
@@ -240,6 +295,17 @@ impl Molecule {
             ));
         }
         self.dihedrals = new_dihedrals;
+
+        let translate = |impropers: &HashSet<Improper>| {
+            impropers.iter().map(|improper| Improper::new(
+                improper.i().wrapping_add(delta),
+                improper.j().wrapping_add(delta),
+                improper.k().wrapping_add(delta),
+                improper.m().wrapping_add(delta)
+            )).collect()
+        };
+        self.impropers = translate(&self.impropers);
+        self.explicit_impropers = translate(&self.explicit_impropers);
     }
 
     /// Add a bond between the particles at indexes `i` and `j`. These particles
@@ -252,9 +318,24 @@ impl Molecule {
         self.rebuild();
     }
 
-    /// Removes particle at index `i` and any associated bonds, angle or
-    /// dihedral. This function also update the indexes for the
-    /// bonds/angles/dihedral by remove 1 to all the values `> i`
+    /// Add an improper dihedral angle around the particle at index `center`,
+    /// with the particles at indexes `i`, `j` and `k`, in this order. These
+    /// particles are assumed to be in the molecule. Improper dihedral angles
+    /// are already created automatically around particles with exactly three
+    /// bonds, this function should be used for additional improper dihedral
+    /// angles, or to fix the order of the particles in an improper angle.
+    pub fn add_improper(&mut self, center: usize, i: usize, j: usize, k: usize) {
+        assert!(self.contains(center));
+        assert!(self.contains(i));
+        assert!(self.contains(j));
+        assert!(self.contains(k));
+        self.explicit_impropers.insert(Improper::new(center, i, j, k));
+        self.rebuild();
+    }
+
+    /// Removes particle at index `i` and any associated bonds, angle,
+    /// dihedral or improper. This function also update the indexes for the
+    /// bonds/angles/dihedral/impropers by remove 1 to all the values `> i`
     pub fn remove_particle(&mut self, i: usize) {
         assert!(self.contains(i));
         // Remove bonds containing the particle `i`
@@ -276,6 +357,14 @@ impl Molecule {
         }
 
         self.bonds = new_bonds;
+
+        let shift = |index: usize| if index > i { index - 1 } else { index };
+        self.explicit_impropers = self.explicit_impropers.iter().filter(|improper| {
+            improper.i() != i && improper.j() != i && improper.k() != i && improper.m() != i
+        }).map(|improper| Improper::new(
+            shift(improper.i()), shift(improper.j()), shift(improper.k()), shift(improper.m())
+        )).collect();
+
         self.range.end -= 1;
         self.rebuild();
     }
@@ -295,6 +384,11 @@ impl Molecule {
         &self.dihedrals
     }
 
+    /// Get the internal list of improper dihedral angles
+    #[inline] pub fn impropers(&self) -> &HashSet<Improper> {
+        &self.impropers
+    }
+
     /// Get the bond distance between the particles `i` and `j`
     #[inline] pub fn bond_distance(&self, i: usize, j: usize) -> BondDistance {
         assert!(self.contains(i) && self.contains(j));
@@ -341,7 +435,7 @@ impl<'a> IntoIterator for &'a Molecule {
 #[cfg(test)]
 mod test {
     use super::*;
-    use sys::{Bond, Angle, Dihedral};
+    use sys::{Bond, Angle, Dihedral, Improper};
     use sys::{BONDED_12, BONDED_13, BONDED_14};
 
     #[test]
@@ -525,4 +619,54 @@ mod test {
         molecule.rehash();
         assert_eq!(molecule.cached_hash, hash);
     }
+
+    #[test]
+    fn impropers() {
+        // Create formaldehyde like this
+        //       H               2
+        //       |               |
+        //   O = C - H       1 = 0 - 3
+        let mut molecule = Molecule::new(0);
+        for i in 1..4 {
+            molecule.merge_with(Molecule::new(i));
+        }
+
+        molecule.add_bond(0, 1);
+        molecule.add_bond(0, 2);
+        assert_eq!(molecule.impropers().len(), 0);
+
+        molecule.add_bond(0, 3);
+        assert_eq!(molecule.impropers().len(), 1);
+        assert!(molecule.impropers().contains(&Improper::new(0, 1, 2, 3)));
+
+        // Explicit impropers are kept when rebuilding the topology
+        molecule.add_improper(1, 0, 2, 3);
+        assert_eq!(molecule.impropers().len(), 2);
+        assert!(molecule.impropers().contains(&Improper::new(1, 0, 2, 3)));
+
+        // Explicit impropers replace the generated ones with the same
+        // particles, and keep the order of the particles
+        molecule.add_improper(0, 3, 2, 1);
+        assert_eq!(molecule.impropers().len(), 2);
+        assert!(molecule.impropers().contains(&Improper::new(0, 3, 2, 1)));
+        assert!(!molecule.impropers().contains(&Improper::new(0, 1, 2, 3)));
+
+        let hash = molecule.cached_hash;
+        molecule.translate_by(5);
+        assert!(molecule.impropers().contains(&Improper::new(5, 8, 7, 6)));
+        assert!(molecule.impropers().contains(&Improper::new(6, 5, 7, 8)));
+        molecule.rehash();
+        assert_eq!(molecule.cached_hash, hash);
+
+        let mut other = Molecule::new(9);
+        other.merge_with(Molecule::new(10));
+        molecule.merge_with(other);
+        assert_eq!(molecule.impropers().len(), 2);
+        molecule.add_bond(9, 10);
+        assert_eq!(molecule.impropers().len(), 2);
+
+        molecule.remove_particle(8);
+        assert_eq!(molecule.impropers().len(), 0);
+        assert_eq!(molecule.bonds().len(), 3);
+    }
 }
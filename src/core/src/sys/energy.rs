@@ -9,6 +9,7 @@
 use std::f64::consts::PI;
 
 use sys::System;
+use types::Vector3D;
 use parallel::prelude::*;
 
 /// An helper struct to evaluate energy components of a system.
@@ -43,11 +44,11 @@ impl<'a> EnergyEvaluator<'a> {
 
     /// Compute the energy of all the pairs in the system
     pub fn pairs(&self) -> f64 {
-
+        let neighbors = self.system.neighbors();
         (0..self.system.size()).par_map(|i| {
             let mut local_energy = 0.0;
 
-            for j in (i+1)..self.system.size() {
+            for &j in neighbors.of(i) {
                 let r = self.system.nearest_image(i, j).norm();
                 local_energy += self.pair(r, i, j);
             }
@@ -110,7 +111,24 @@ impl<'a> EnergyEvaluator<'a> {
         return energy;
     }
 
-    /// Compute the energy of all the angles in the system
+    /// Compute the energy associated with the potentials using the full
+    /// geometry of the angle `i, j, k`, with `r1 = r_i - r_j` and `r2 = r_k -
+    /// r_j`
+    #[inline]
+    pub fn angle_geometry(&self, r1: &Vector3D, r2: &Vector3D, i: usize, j: usize, k: usize) -> f64 {
+        let mut energy = 0.0;
+        let (direct, reversed) = self.system.angle_geometry_potentials(i, j, k);
+        for potential in direct {
+            energy += potential.energy(r1, r2);
+        }
+        for potential in reversed {
+            energy += potential.energy(r2, r1);
+        }
+        return energy;
+    }
+
+    /// Compute the energy of all the angles in the system, including the
+    /// potentials using the full geometry of the angles
     pub fn angles(&self) -> f64 {
         let mut energy = 0.0;
         for molecule in self.system.molecules() {
@@ -118,6 +136,10 @@ impl<'a> EnergyEvaluator<'a> {
                 let (i, j, k) = (angle.i(), angle.j(), angle.k());
                 let theta = self.system.angle(i, j, k);
                 energy += self.angle(theta, i, j, k);
+
+                let r1 = self.system.nearest_image(i, j);
+                let r2 = self.system.nearest_image(k, j);
+                energy += self.angle_geometry(&r1, &r2, i, j, k);
             }
         }
         return energy;
@@ -147,6 +169,31 @@ impl<'a> EnergyEvaluator<'a> {
         return energy;
     }
 
+    /// Compute the energy associated with the improper dihedral angle around
+    /// `i`, with `j`, `k` and `m` at angle `phi`
+    #[inline]
+    pub fn improper(&self, phi: f64, i: usize, j: usize, k: usize, m: usize) -> f64 {
+        let mut energy = 0.0;
+        for potential in self.system.improper_potentials(i, j, k, m) {
+            energy += potential.energy(phi);
+        }
+        return energy;
+    }
+
+    /// Compute the energy of all the improper dihedral angles in the system
+    pub fn impropers(&self) -> f64 {
+        let mut energy = 0.0;
+        for molecule in self.system.molecules() {
+            for improper in molecule.impropers() {
+                let improper = self.system.improper_particles(improper);
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                let phi = self.system.dihedral(i, j, k, m);
+                energy += self.improper(phi, i, j, k, m);
+            }
+        }
+        return energy;
+    }
+
     /// Compute the energy of the electrostatic interactions
     #[inline]
     pub fn coulomb(&self) -> f64 {
@@ -166,6 +213,12 @@ impl<'a> EnergyEvaluator<'a> {
         }
         return energy;
     }
+
+    /// Compute the energy of each global potential separately, in the same
+    /// order as `System::global_potentials`
+    pub fn globals(&self) -> Vec<f64> {
+        self.system.global_potentials().iter().map(|global| global.energy(self.system)).collect()
+    }
 }
 
 #[cfg(test)]
@@ -9,6 +9,7 @@ use std::collections::BTreeMap;
 use std::cmp::{min, max};
 
 use energy::{PairInteraction, BondPotential, AnglePotential, DihedralPotential};
+use energy::AngleGeometryPotential;
 use energy::{GlobalPotential, CoulombicPotential};
 use sys::ParticleKind as Kind;
 
@@ -47,6 +48,7 @@ type PairKind = (Kind, Kind);
 type BondKind = (Kind, Kind);
 type AngleKind = (Kind, Kind, Kind);
 type DihedralKind = (Kind, Kind, Kind, Kind);
+type ImproperKind = (Kind, Kind, Kind, Kind);
 
 /// The `Interaction` type hold all data about the potentials in the system.
 ///
@@ -59,8 +61,13 @@ pub struct Interactions {
     bonds: BTreeMap<BondKind, Vec<Box<BondPotential>>>,
     /// Angle potentials
     angles: BTreeMap<AngleKind, Vec<Box<AnglePotential>>>,
+    /// Angle potentials using the full geometry of the angle. These are not
+    /// normalized, as the outer particles are not equivalent.
+    angle_geometries: BTreeMap<AngleKind, Vec<Box<AngleGeometryPotential>>>,
     /// Dihedral angles potentials
     dihedrals: BTreeMap<DihedralKind, Vec<Box<DihedralPotential>>>,
+    /// Improper dihedral angles potentials
+    impropers: BTreeMap<ImproperKind, Vec<Box<DihedralPotential>>>,
     /// Coulombic potential solver
     pub coulomb: Option<Box<CoulombicPotential>>,
     /// Global potentials
@@ -74,7 +81,9 @@ impl Interactions {
             pairs: BTreeMap::new(),
             bonds: BTreeMap::new(),
             angles: BTreeMap::new(),
+            angle_geometries: BTreeMap::new(),
             dihedrals: BTreeMap::new(),
+            impropers: BTreeMap::new(),
             coulomb: None,
             globals: Vec::new(),
         }
@@ -101,6 +110,13 @@ impl Interactions {
         angles.push(potential);
     }
 
+    /// Add the `potential` angle interaction using the full geometry of the
+    /// angle `(i, j, k)`. The order of the particles is kept as is.
+    pub fn add_angle_geometry(&mut self, i: Kind, j: Kind, k: Kind, potential: Box<AngleGeometryPotential>) {
+        let angles = self.angle_geometries.entry((i, j, k)).or_insert(Vec::new());
+        angles.push(potential);
+    }
+
     /// Add the `potential` dihedral interaction for the dihedral angle `(i, j,
     /// k, m)`
     pub fn add_dihedral(&mut self, i: Kind, j: Kind, k: Kind, m: Kind, potential: Box<DihedralPotential>) {
@@ -108,6 +124,13 @@ impl Interactions {
         let dihedrals = self.dihedrals.entry((i, j, k, m)).or_insert(Vec::new());
         dihedrals.push(potential);
     }
+
+    /// Add the `potential` improper dihedral interaction for the improper
+    /// dihedral angle around `i`, with `j`, `k` and `m` in this order
+    pub fn add_improper(&mut self, i: Kind, j: Kind, k: Kind, m: Kind, potential: Box<DihedralPotential>) {
+        let impropers = self.impropers.entry((i, j, k, m)).or_insert(Vec::new());
+        impropers.push(potential);
+    }
 }
 
 impl Interactions {
@@ -129,12 +152,38 @@ impl Interactions {
         self.angles.get(&(i, j, k)).map_or(&[], |angles| &**angles)
     }
 
+    /// Get all angle interactions using the full geometry of the angle
+    /// corresponding exactly to the angle `(i, j, k)`, *i.e.* without the
+    /// interactions defined for the angle `(k, j, i)`.
+    pub fn angle_geometries(&self, i: Kind, j: Kind, k: Kind) -> &[Box<AngleGeometryPotential>] {
+        self.angle_geometries.get(&(i, j, k)).map_or(&[], |angles| &**angles)
+    }
+
     /// Get all dihedral interactions corresponding to the dihedral `(i, j, k, m)`
     pub fn dihedrals(&self, i: Kind, j: Kind, k: Kind, m: Kind) -> &[Box<DihedralPotential>] {
         let (i, j, k, m) = normalize_dihedral(i, j, k, m);
         self.dihedrals.get(&(i, j, k, m)).map_or(&[], |dihedrals| &**dihedrals)
     }
 
+    /// Get all improper dihedral interactions corresponding to the improper
+    /// dihedral angle around `i`, with `j`, `k` and `m` in this order
+    pub fn impropers(&self, i: Kind, j: Kind, k: Kind, m: Kind) -> &[Box<DihedralPotential>] {
+        self.impropers.get(&(i, j, k, m)).map_or(&[], |impropers| &**impropers)
+    }
+
+    /// Get the maximal cutoff of all the pair interactions, or `None` if
+    /// there are no pair interactions.
+    pub fn maximum_pairs_cutoff(&self) -> Option<f64> {
+        let cutoff = self.pairs.values()
+                               .flat_map(|i| i.iter().map(|pair| pair.cutoff()))
+                               .fold(f64::NAN, f64::max);
+        if cutoff.is_nan() {
+            None
+        } else {
+            Some(cutoff)
+        }
+    }
+
     /// Get maximum cutoff from `coulomb`, `pairs` and `global` interactons.
     pub fn maximum_cutoff(&self) -> Option<f64> {
         // Coulomb potential, return cutoff
@@ -155,10 +204,7 @@ impl Interactions {
         let mut maximum_cutoff = f64::max(global_cutoff, coulomb_cutoff);
 
         // Pair interactions, return maximum cutoff
-        let pairs_cutoff = self.pairs
-                               .values()
-                               .flat_map(|i| i.iter().map(|pair| pair.cutoff()))
-                               .fold(f64::NAN, f64::max);
+        let pairs_cutoff = self.maximum_pairs_cutoff().unwrap_or(f64::NAN);
 
         maximum_cutoff = f64::max(maximum_cutoff, pairs_cutoff);
         if maximum_cutoff.is_nan() {
@@ -173,7 +219,7 @@ impl Interactions {
 mod test {
     use super::*;
 
-    use energy::{NullPotential, Wolf, PairInteraction};
+    use energy::{NullPotential, Wolf, PairInteraction, UreyBradley};
     use sys::ParticleKind as Kind;
 
     #[test]
@@ -310,6 +356,20 @@ mod test {
         assert_eq!(interactions.angles(Kind(55), Kind(55), Kind(55)).len(), 0);
     }
 
+    #[test]
+    fn angle_geometries() {
+        let mut interactions = Interactions::new();
+
+        let potential = UreyBradley{k: 1.0, r0: 1.0};
+        interactions.add_angle_geometry(Kind(0), Kind(1), Kind(2), Box::new(potential));
+        assert_eq!(interactions.angle_geometries(Kind(0), Kind(1), Kind(2)).len(), 1);
+        assert_eq!(interactions.angle_geometries(Kind(2), Kind(1), Kind(0)).len(), 0);
+        assert_eq!(interactions.angles(Kind(0), Kind(1), Kind(2)).len(), 0);
+
+        // 'out of bounds' kinds
+        assert_eq!(interactions.angle_geometries(Kind(55), Kind(55), Kind(55)).len(), 0);
+    }
+
     #[test]
     fn dihedrals() {
         let mut interactions = Interactions::new();
@@ -334,6 +394,23 @@ mod test {
         assert_eq!(interactions.dihedrals(Kind(55), Kind(55), Kind(55), Kind(55)).len(), 0);
     }
 
+    #[test]
+    fn impropers() {
+        let mut interactions = Interactions::new();
+
+        interactions.add_improper(Kind(0), Kind(1), Kind(2), Kind(3), Box::new(NullPotential));
+        assert_eq!(interactions.impropers(Kind(0), Kind(1), Kind(2), Kind(3)).len(), 1);
+        // The order of the atoms matters
+        assert_eq!(interactions.impropers(Kind(0), Kind(3), Kind(1), Kind(2)).len(), 0);
+        assert_eq!(interactions.impropers(Kind(3), Kind(2), Kind(1), Kind(0)).len(), 0);
+
+        // Improper and proper dihedral angles are stored separately
+        assert_eq!(interactions.dihedrals(Kind(0), Kind(1), Kind(2), Kind(3)).len(), 0);
+
+        // 'out of bounds' kinds
+        assert_eq!(interactions.impropers(Kind(55), Kind(55), Kind(55), Kind(55)).len(), 0);
+    }
+
     #[test]
     fn test_maximum_cutoff() {
         let mut interactions = Interactions::new();
@@ -18,6 +18,13 @@ pub use self::energy::EnergyEvaluator;
 mod cache;
 pub use self::cache::EnergyCache;
 
+mod neighbors;
+pub use self::neighbors::{NeighborList, Neighbors};
+
+mod selection;
+pub use self::selection::Selection;
+pub(crate) use self::selection::GroupMasks;
+
 mod chfl;
 pub use self::chfl::{Trajectory, TrajectoryError, TrajectoryBuilder, OpenMode};
 pub use self::chfl::read_molecule;
@@ -0,0 +1,522 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Neighbor lists for the computation of pair interactions.
+//!
+//! Looping over all the pairs of particles in a system scales as `O(N^2)`.
+//! Because pair interactions are truncated at a cutoff distance, only the
+//! pairs closer than this cutoff contribute to the energy, forces and virial.
+//! This module provides Verlet lists, containing for each particle all the
+//! particles closer than the cutoff plus a *skin* distance. These lists are
+//! built in `O(N)` using linked cells, and are only rebuilt when a particle
+//! moved by more than half of the skin since the last build.
+use std::cmp::{min, max};
+use std::sync::{RwLock, RwLockReadGuard};
+
+use types::Vector3D;
+use sys::{Configuration, UnitCell};
+
+/// Default value for the skin distance of neighbor lists, in Angstroms
+const DEFAULT_SKIN: f64 = 1.0;
+
+/// Linked cells, used to build the neighbor lists in `O(N)`.
+///
+/// The unit cell is divided in sub-cells along each of its vectors, with a
+/// distance between the faces of the sub-cells bigger than the cutoff. Any
+/// pair of particles closer than the cutoff are then in the same or in
+/// neighboring sub-cells. In triclinic cells, the distance between the faces
+/// is smaller than the length of the cell vectors, and is computed from the
+/// cell volume and the area of the faces.
+#[derive(Clone, Debug)]
+struct LinkedCells {
+    /// Number of sub-cells along each one of the unit cell vectors
+    ncells: [usize; 3],
+    /// List of particles in each sub-cell
+    cells: Vec<Vec<usize>>,
+}
+
+impl LinkedCells {
+    /// Create new linked cells in the unit `cell`, with sub-cells bigger than
+    /// `cutoff` and containing the particles at `positions`.
+    fn new(cell: &UnitCell, cutoff: f64, positions: &[Vector3D]) -> LinkedCells {
+        let mut ncells = [1, 1, 1];
+        if !cell.is_infinite() {
+            let widths = perpendicular_widths(cell);
+            for k in 0..3 {
+                ncells[k] = max(1, f64::floor(widths[k] / cutoff) as usize);
+            }
+            // Particles are not wrapped along the z axis in slab cells, so
+            // all of them must be in the same sub-cell along this direction.
+            if !cell.is_periodic_z() {
+                ncells[2] = 1;
+            }
+        }
+
+        let mut linked = LinkedCells {
+            ncells: ncells,
+            cells: vec![Vec::new(); ncells[0] * ncells[1] * ncells[2]],
+        };
+
+        for (i, position) in positions.iter().enumerate() {
+            let index = linked.index(linked.cell_of(cell, position));
+            linked.cells[index].push(i);
+        }
+
+        return linked;
+    }
+
+    /// Get the sub-cell containing the given `position`
+    fn cell_of(&self, cell: &UnitCell, position: &Vector3D) -> [usize; 3] {
+        if cell.is_infinite() {
+            return [0, 0, 0];
+        }
+
+        let fractional = cell.fractional(position);
+        let mut result = [0, 0, 0];
+        for k in 0..3 {
+            let n = self.ncells[k];
+            let wrapped = fractional[k] - f64::floor(fractional[k]);
+            result[k] = min((wrapped * n as f64) as usize, n - 1);
+        }
+        return result;
+    }
+
+    /// Get the linear index of the sub-cell `cell`
+    fn index(&self, cell: [usize; 3]) -> usize {
+        (cell[0] * self.ncells[1] + cell[1]) * self.ncells[2] + cell[2]
+    }
+
+    /// Get the sorted list of all the particles in the sub-cell `cell` and
+    /// in the sub-cells around it.
+    fn around(&self, cell: [usize; 3]) -> Vec<usize> {
+        let (na, nb, nc) = (self.ncells[0], self.ncells[1], self.ncells[2]);
+        let mut indexes = Vec::with_capacity(27);
+        // Using `n + delta - 1` with delta in [0, 3) instead of delta in
+        // [-1, 2) to stay with unsigned integers.
+        for da in 0..3 {
+            let a = (cell[0] + na + da - 1) % na;
+            for db in 0..3 {
+                let b = (cell[1] + nb + db - 1) % nb;
+                for dc in 0..3 {
+                    let c = (cell[2] + nc + dc - 1) % nc;
+                    indexes.push(self.index([a, b, c]));
+                }
+            }
+        }
+        // With less than three sub-cells in a direction, the same sub-cell
+        // can appear multiple times.
+        indexes.sort();
+        indexes.dedup();
+
+        let mut particles = Vec::new();
+        for index in indexes {
+            particles.extend_from_slice(&self.cells[index]);
+        }
+        particles.sort();
+        return particles;
+    }
+}
+
+/// Get the distances between the pairs of opposite faces of the unit `cell`,
+/// i.e. the widths of the cell perpendicular to the planes (b, c), (c, a) and
+/// (a, b).
+fn perpendicular_widths(cell: &UnitCell) -> [f64; 3] {
+    let (a, b, c) = (cell.vect_a(), cell.vect_b(), cell.vect_c());
+    let volume = cell.volume();
+    [
+        volume / (b ^ c).norm(),
+        volume / (c ^ a).norm(),
+        volume / (a ^ b).norm(),
+    ]
+}
+
+/// A Verlet list, containing for each particle the list of neighbors inside a
+/// given cutoff.
+#[derive(Clone, Debug)]
+struct VerletList {
+    /// Cutoff used to build the list, including the skin
+    cutoff: f64,
+    /// Unit cell used to build the list
+    cell: UnitCell,
+    /// Positions of the particles at the time the list was built
+    positions: Vec<Vector3D>,
+    /// Linked cells used to build the list
+    linked: LinkedCells,
+    /// `neighbors[i]` contains the indexes `j > i` of all the particles closer
+    /// than `cutoff` to the particle `i`, sorted in increasing order.
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl VerletList {
+    /// Build a new Verlet list for the `configuration`, using the given
+    /// `cutoff`.
+    fn new(configuration: &Configuration, cutoff: f64) -> VerletList {
+        let cell = configuration.cell;
+        let positions = configuration.particles().map(|p| p.position).collect::<Vec<_>>();
+        let linked = LinkedCells::new(&cell, cutoff, &positions);
+
+        let cutoff2 = cutoff * cutoff;
+        let mut neighbors = vec![Vec::new(); positions.len()];
+        for a in 0..linked.ncells[0] {
+            for b in 0..linked.ncells[1] {
+                for c in 0..linked.ncells[2] {
+                    let candidates = linked.around([a, b, c]);
+                    for &i in &linked.cells[linked.index([a, b, c])] {
+                        for &j in candidates.iter().filter(|&&j| j > i) {
+                            let mut d = positions[i] - positions[j];
+                            cell.vector_image(&mut d);
+                            if d.norm2() < cutoff2 {
+                                neighbors[i].push(j);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        VerletList {
+            cutoff: cutoff,
+            cell: cell,
+            positions: positions,
+            linked: linked,
+            neighbors: neighbors,
+        }
+    }
+
+    /// Check if this list is still valid for the `configuration`, with the
+    /// given `cutoff` and `skin`.
+    fn is_valid(&self, configuration: &Configuration, cutoff: f64, skin: f64) -> bool {
+        if self.cutoff != cutoff || self.cell != configuration.cell {
+            return false;
+        }
+
+        if self.positions.len() != configuration.size() {
+            return false;
+        }
+
+        let max_displacement2 = 0.25 * skin * skin;
+        for (particle, old) in configuration.particles().zip(&self.positions) {
+            let mut displacement = particle.position - *old;
+            self.cell.vector_image(&mut displacement);
+            if displacement.norm2() > max_displacement2 {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+/// Neighbor lists settings and storage.
+///
+/// The neighbor list is used by default for all the pair interactions
+/// computations. It is automatically rebuilt when needed: when the unit cell
+/// or the number of particles changes, or when any particle moved by more
+/// than half of the skin distance since the last build.
+///
+/// The neighbor list can be disabled, in which case all the pairs in the
+/// system are used. This gives the same results, but is slower for big
+/// systems.
+#[derive(Debug)]
+pub struct NeighborList {
+    /// Skin distance to add to the cutoff
+    skin: f64,
+    /// Is the neighbor list used?
+    enabled: bool,
+    /// Current Verlet list, rebuilt as needed
+    list: RwLock<Option<VerletList>>,
+}
+
+impl Clone for NeighborList {
+    fn clone(&self) -> NeighborList {
+        NeighborList {
+            skin: self.skin,
+            enabled: self.enabled,
+            list: RwLock::new(self.list.read().expect("poisoned lock in neighbor list").clone()),
+        }
+    }
+}
+
+impl NeighborList {
+    /// Create a new enabled `NeighborList`, with the default skin distance of
+    /// 1 A.
+    pub fn new() -> NeighborList {
+        NeighborList {
+            skin: DEFAULT_SKIN,
+            enabled: true,
+            list: RwLock::new(None),
+        }
+    }
+
+    /// Get the skin distance of this neighbor list
+    pub fn skin(&self) -> f64 {
+        self.skin
+    }
+
+    /// Set the skin distance of this neighbor list to `skin`. Bigger skin
+    /// distances mean less frequent rebuilds of the list, but more pairs in
+    /// the list.
+    pub fn set_skin(&mut self, skin: f64) {
+        assert!(skin >= 0.0, "the neighbor list skin must be positive");
+        self.skin = skin;
+        self.list = RwLock::new(None);
+    }
+
+    /// Is this neighbor list enabled?
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable the use of this neighbor list
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Disable the use of this neighbor list, and loop over all the pairs of
+    /// particles instead.
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.list = RwLock::new(None);
+    }
+
+    /// Get the neighbors of all the particles in the `configuration`, for
+    /// interactions with the given `cutoff`. The neighbor list is rebuilt
+    /// if needed. If `cutoff` is `None`, or if the neighbor list is disabled,
+    /// all the pairs in the configuration are returned.
+    pub fn neighbors(&self, configuration: &Configuration, cutoff: Option<f64>) -> Neighbors {
+        let cutoff = match cutoff {
+            Some(cutoff) if self.enabled => cutoff + self.skin,
+            _ => return Neighbors::all(configuration.size()),
+        };
+
+        loop {
+            // Only take the write lock when the list needs to be rebuilt, so
+            // that multiple `Neighbors` can be alive at the same time.
+            {
+                let list = self.list.read().expect("poisoned lock in neighbor list");
+                if self.is_valid(&list, configuration, cutoff) {
+                    return Neighbors {
+                        inner: NeighborsImpl::List(list)
+                    };
+                }
+            }
+
+            let mut list = self.list.write().expect("poisoned lock in neighbor list");
+            if !self.is_valid(&list, configuration, cutoff) {
+                *list = Some(VerletList::new(configuration, cutoff));
+            }
+        }
+    }
+
+    /// Check if the Verlet `list` is valid for the `configuration` and the
+    /// given `cutoff`.
+    fn is_valid(&self, list: &Option<VerletList>, configuration: &Configuration, cutoff: f64) -> bool {
+        match *list {
+            Some(ref list) => list.is_valid(configuration, cutoff, self.skin),
+            None => false,
+        }
+    }
+}
+
+enum NeighborsImpl<'a> {
+    /// All the particles are neighbors
+    All(Vec<usize>),
+    /// Use the neighbors from a Verlet list
+    List(RwLockReadGuard<'a, Option<VerletList>>),
+}
+
+/// Neighbors of the particles in a configuration, as returned by
+/// `NeighborList::neighbors` or `System::neighbors`.
+///
+/// The neighbors contain at least all the pairs closer than the cutoff, and
+/// are only valid as long as the configuration is not modified.
+pub struct Neighbors<'a> {
+    inner: NeighborsImpl<'a>,
+}
+
+impl<'a> Neighbors<'a> {
+    /// Create a `Neighbors` containing all the pairs of `size` particles
+    fn all(size: usize) -> Neighbors<'a> {
+        Neighbors {
+            inner: NeighborsImpl::All((0..size).collect())
+        }
+    }
+
+    /// Get the indexes `j > i` of the neighbors of the particle `i`, sorted
+    /// in increasing order.
+    pub fn of(&self, i: usize) -> &[usize] {
+        match self.inner {
+            NeighborsImpl::All(ref all) => &all[(i + 1)..],
+            NeighborsImpl::List(ref list) => {
+                let list = list.as_ref().expect("missing Verlet list in neighbors");
+                &list.neighbors[i]
+            }
+        }
+    }
+
+    /// Get the indexes of all the particles that can be closer than the
+    /// cutoff to the given `position`, sorted in increasing order. This is
+    /// useful to compute the interactions of a particle at a new position,
+    /// for example in Monte Carlo simulations.
+    pub fn around(&self, position: &Vector3D) -> Vec<usize> {
+        match self.inner {
+            NeighborsImpl::All(ref all) => all.clone(),
+            NeighborsImpl::List(ref list) => {
+                let list = list.as_ref().expect("missing Verlet list in neighbors");
+                let cell = list.linked.cell_of(&list.cell, position);
+                let cutoff2 = list.cutoff * list.cutoff;
+                list.linked.around(cell).into_iter().filter(|&j| {
+                    let mut d = list.positions[j] - *position;
+                    list.cell.vector_image(&mut d);
+                    d.norm2() < cutoff2
+                }).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::{System, Particle, UnitCell};
+    use energy::{PairInteraction, LennardJones};
+    use types::Vector3D;
+
+    use rand::distributions::{Range, IndependentSample};
+    use rand::{XorShiftRng, SeedableRng};
+
+    fn testing_system(cell: UnitCell) -> System {
+        let mut system = System::with_cell(cell);
+        let mut rng = XorShiftRng::from_seed([42, 2, 78, 12]);
+        let range = Range::new(0.0, 1.0);
+        for _ in 0..300 {
+            let fractional = Vector3D::new(
+                range.ind_sample(&mut rng),
+                range.ind_sample(&mut rng),
+                range.ind_sample(&mut rng)
+            );
+            let mut particle = Particle::new("Ar");
+            particle.position = system.cell.cartesian(&fractional);
+            system.add_particle(particle);
+        }
+
+        system.add_pair_potential("Ar", "Ar", PairInteraction::new(
+            Box::new(LennardJones{sigma: 2.0, epsilon: 1.5}), 5.0
+        ));
+        return system;
+    }
+
+    fn check_brute_force(system: &System) {
+        let mut brute_force = system.clone();
+        brute_force.neighbor_list_mut().disable();
+
+        assert_ulps_eq!(system.potential_energy(), brute_force.potential_energy());
+
+        let forces = system.forces();
+        let expected = brute_force.forces();
+        for (force, expected) in forces.iter().zip(&expected) {
+            assert_ulps_eq!(force, expected);
+        }
+
+        assert_ulps_eq!(system.virial(), brute_force.virial());
+    }
+
+    #[test]
+    fn neighbors() {
+        let system = testing_system(UnitCell::cubic(20.0));
+        let neighbors = system.neighbors();
+
+        let cutoff = 5.0 + system.neighbor_list().skin();
+        for i in 0..system.size() {
+            let list = neighbors.of(i);
+            for j in (i + 1)..system.size() {
+                let r = system.distance(i, j);
+                if r < cutoff {
+                    assert!(list.contains(&j));
+                } else {
+                    assert!(!list.contains(&j));
+                }
+            }
+
+            let around = neighbors.around(&system.particle(i).position);
+            for j in 0..system.size() {
+                if system.distance(i, j) < 5.0 {
+                    assert!(around.contains(&j));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn orthorhombic() {
+        let mut system = testing_system(UnitCell::ortho(18.0, 22.0, 25.0));
+        check_brute_force(&system);
+
+        // Small displacements do not rebuild the list, but are still correct
+        for particle in system.particles_mut() {
+            particle.position += Vector3D::new(0.2, -0.1, 0.3);
+        }
+        check_brute_force(&system);
+
+        // Big displacements rebuild the list
+        for particle in system.particles_mut() {
+            particle.position += Vector3D::new(-1.2, 0.7, 0.0);
+        }
+        check_brute_force(&system);
+    }
+
+    #[test]
+    fn triclinic() {
+        let mut system = testing_system(UnitCell::triclinic(20.0, 21.0, 25.0, 80.0, 95.0, 110.0));
+        check_brute_force(&system);
+
+        for particle in system.particles_mut() {
+            particle.position += Vector3D::new(-0.2, 0.1, 0.4);
+        }
+        check_brute_force(&system);
+
+        system.cell = UnitCell::triclinic(21.0, 21.0, 24.0, 75.0, 95.0, 110.0);
+        check_brute_force(&system);
+    }
+
+    #[test]
+    fn skewed_triclinic() {
+        // The distance between the faces of this cell along c is smaller than
+        // the cutoff plus skin when using 4 sub-cells along the length of c.
+        let cell = UnitCell::triclinic(21.0, 21.0, 24.0, 75.0, 95.0, 110.0);
+        let widths = perpendicular_widths(&cell);
+        assert!(widths[2] / 4.0 < 6.0);
+        let system = testing_system(cell);
+        check_brute_force(&system);
+
+        // Strongly skewed cell, with three sub-cells along each vector while
+        // the cell lengths would give eight sub-cells
+        let cell = UnitCell::triclinic(48.0, 48.0, 52.0, 60.0, 120.0, 65.0);
+        let mut system = testing_system(cell);
+        check_brute_force(&system);
+
+        for particle in system.particles_mut() {
+            particle.position += Vector3D::new(1.2, -0.7, 0.5);
+        }
+        check_brute_force(&system);
+    }
+
+    #[test]
+    fn slab() {
+        let mut system = testing_system(UnitCell::slab(20.0, 21.0, 25.0));
+        check_brute_force(&system);
+
+        // Particles outside of the cell along z do not interact with the
+        // periodic images
+        for particle in system.particles_mut() {
+            particle.position += Vector3D::new(0.0, 0.0, 4.0);
+        }
+        check_brute_force(&system);
+    }
+
+    #[test]
+    fn small_cell() {
+        // Less than three sub-cells in each direction
+        let system = testing_system(UnitCell::cubic(12.0));
+        check_brute_force(&system);
+    }
+}
@@ -0,0 +1,183 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Selection of groups of particles in a configuration
+use std::ops::Range;
+
+use sys::Configuration;
+
+/// A selection of particles in a configuration, used to define groups of
+/// particles.
+///
+/// # Examples
+///
+/// ```
+/// use lumol::sys::{System, Particle, Selection};
+///
+/// let mut system = System::new();
+/// system.add_particle(Particle::new("O"));
+/// system.add_particle(Particle::new("H"));
+/// system.add_particle(Particle::new("O"));
+///
+/// let selection = Selection::Name(String::from("O"));
+/// assert_eq!(selection.select(&system), vec![0, 2]);
+///
+/// let selection = Selection::Range(1..3);
+/// assert_eq!(selection.select(&system), vec![1, 2]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selection {
+    /// All the particles with the given name
+    Name(String),
+    /// All the particles in the molecules with the given molecule type. See
+    /// [`molecule_type`](fn.molecule_type.html) for more information.
+    MoleculeType(u64),
+    /// All the particles with an index in the given range. Indexes outside of
+    /// the configuration are ignored.
+    Range(Range<usize>),
+}
+
+impl Selection {
+    /// Get the sorted list of indexes of the particles selected in the
+    /// `configuration`.
+    pub fn select(&self, configuration: &Configuration) -> Vec<usize> {
+        match *self {
+            Selection::Name(ref name) => {
+                (0..configuration.size()).filter(|&i| configuration.particle(i).name() == name).collect()
+            }
+            Selection::MoleculeType(moltype) => {
+                let mut particles = Vec::new();
+                for molid in configuration.molecules_with_moltype(moltype) {
+                    particles.extend(configuration.molecule(molid).iter());
+                }
+                particles.sort();
+                particles
+            }
+            Selection::Range(ref range) => {
+                let end = ::std::cmp::min(range.end, configuration.size());
+                (range.start..end).collect()
+            }
+        }
+    }
+}
+
+/// Membership of the particles of a configuration in two groups, used to
+/// select the pairs between these groups in the usual pair loops.
+pub(crate) struct GroupMasks {
+    /// `in_first[i]` is `true` if the particle `i` is in the first group
+    in_first: Vec<bool>,
+    /// `in_second[i]` is `true` if the particle `i` is in the second group
+    in_second: Vec<bool>,
+}
+
+impl GroupMasks {
+    /// Create the masks for the `first` and `second` groups in a
+    /// configuration containing `size` particles.
+    pub fn new(size: usize, first: &[usize], second: &[usize]) -> GroupMasks {
+        let mut in_first = vec![false; size];
+        for &i in first {
+            in_first[i] = true;
+        }
+        let mut in_second = vec![false; size];
+        for &i in second {
+            in_second[i] = true;
+        }
+        GroupMasks {
+            in_first: in_first,
+            in_second: in_second,
+        }
+    }
+
+    /// Is the particle `i` in the first group?
+    #[inline]
+    pub fn in_first(&self, i: usize) -> bool {
+        self.in_first[i]
+    }
+
+    /// Is the particle `i` in the second group?
+    #[inline]
+    pub fn in_second(&self, i: usize) -> bool {
+        self.in_second[i]
+    }
+
+    /// Is the particle `i` in any of the groups?
+    #[inline]
+    pub fn in_any(&self, i: usize) -> bool {
+        self.in_first[i] || self.in_second[i]
+    }
+
+    /// Is the pair of different particles `(i, j)` between the two groups?
+    /// When the groups overlap, the pairs `(i, j)` and `(j, i)` are the same
+    /// and the pair loop must visit only one of them.
+    #[inline]
+    pub fn contains(&self, i: usize, j: usize) -> bool {
+        self.in_first[i] && self.in_second[j] || self.in_first[j] && self.in_second[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::System;
+    use utils::system_from_xyz;
+
+    fn testing_system() -> System {
+        system_from_xyz("7
+        bonds cell: 20.0
+        O  0.0  0.0  0.0
+        H -0.7 -0.7  0.3
+        H  0.3 -0.3 -0.8
+        O  4.0  4.0  0.0
+        H  3.3  3.3  0.3
+        H  4.3  3.7 -0.8
+        Na 8.0  8.0  8.0
+        ")
+    }
+
+    #[test]
+    fn select() {
+        let system = testing_system();
+        assert_eq!(system.molecules().len(), 3);
+
+        assert_eq!(Selection::Name(String::from("H")).select(&system), vec![1, 2, 4, 5]);
+        assert_eq!(Selection::Name(String::from("Cl")).select(&system), Vec::<usize>::new());
+
+        let water = system.molecule_type(0);
+        assert_eq!(Selection::MoleculeType(water).select(&system), vec![0, 1, 2, 3, 4, 5]);
+        let sodium = system.molecule_type(2);
+        assert_eq!(Selection::MoleculeType(sodium).select(&system), vec![6]);
+
+        assert_eq!(Selection::Range(2..4).select(&system), vec![2, 3]);
+        assert_eq!(Selection::Range(5..12).select(&system), vec![5, 6]);
+    }
+
+    fn pairs(masks: &GroupMasks, size: usize) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..size {
+            for j in (i + 1)..size {
+                if masks.contains(i, j) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        return pairs;
+    }
+
+    #[test]
+    fn masks() {
+        let masks = GroupMasks::new(5, &[0, 1], &[2, 3]);
+        assert!(masks.in_first(0) && !masks.in_second(0));
+        assert!(masks.in_second(3) && !masks.in_first(3));
+        assert!(!masks.in_any(4));
+        assert_eq!(pairs(&masks, 5), vec![(0, 2), (0, 3), (1, 2), (1, 3)]);
+
+        let masks = GroupMasks::new(3, &[0, 1, 2], &[0, 1, 2]);
+        assert_eq!(pairs(&masks, 3), vec![(0, 1), (0, 2), (1, 2)]);
+
+        let masks = GroupMasks::new(3, &[0, 1], &[1, 2]);
+        assert_eq!(pairs(&masks, 3), vec![(0, 1), (0, 2), (1, 2)]);
+
+        let masks = GroupMasks::new(3, &[2, 1], &[1, 0]);
+        assert_eq!(pairs(&masks, 3), vec![(0, 1), (0, 2), (1, 2)]);
+    }
+}
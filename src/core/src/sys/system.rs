@@ -7,10 +7,11 @@ use std::collections::BTreeMap;
 use types::{Vector3D, Matrix3};
 
 use energy::{PairInteraction, BondPotential, AnglePotential, DihedralPotential};
-use energy::{GlobalPotential, CoulombicPotential};
+use energy::{GlobalPotential, CoulombicPotential, AngleGeometryPotential};
 
-use sys::{Configuration, Particle, ParticleKind, UnitCell};
+use sys::{Configuration, Particle, ParticleKind, UnitCell, Improper};
 use sys::{Composition, Interactions, EnergyEvaluator};
+use sys::{NeighborList, Neighbors};
 
 /// The `System` type hold all the data about a simulated system.
 ///
@@ -36,6 +37,10 @@ pub struct System {
     step: u64,
     /// Externally managed temperature for the system
     external_temperature: Option<f64>,
+    /// Energy of the extended degrees of freedom used by the simulation
+    extended_energy: f64,
+    /// Neighbor list for the pair interactions
+    neighbor_list: NeighborList,
 }
 
 impl System {
@@ -59,6 +64,8 @@ impl System {
             interactions: Interactions::new(),
             step: 0,
             external_temperature: None,
+            extended_energy: 0.0,
+            neighbor_list: NeighborList::new(),
         }
     }
 
@@ -118,6 +125,22 @@ impl System {
         self.external_temperature = temperature;
     }
 
+    /// Get the energy of the extended degrees of freedom of the simulation
+    /// (thermostats, barostats, ...), including the energy they exchanged
+    /// with the system. The sum of this energy and the total energy of the
+    /// system is conserved when using such algorithms.
+    pub fn extended_energy(&self) -> f64 {
+        self.extended_energy
+    }
+
+    /// Add `energy` to the energy of the extended degrees of freedom of the
+    /// simulation. This should be called by the algorithms exchanging energy
+    /// with the system, each time they change the energy of their extended
+    /// degrees of freedom.
+    pub fn add_extended_energy(&mut self, energy: f64) {
+        self.extended_energy += energy;
+    }
+
 
 
     /// Guess the bonds in the configuration using the chemfiles algorithm.
@@ -168,6 +191,17 @@ impl System {
         self.interactions.add_angle(kind_i, kind_j, kind_k, potential)
     }
 
+    /// Add the `potential` angle interaction using the full geometry of the
+    /// angle `(i, j, k)`. Contrary to `add_angle_potential`, the order of the
+    /// particles matters: the potential will be called with `r1` pointing to
+    /// the `i` particle and `r2` pointing to the `k` particle.
+    pub fn add_angle_geometry_potential(&mut self, i: &str, j: &str, k: &str, potential: Box<AngleGeometryPotential>) {
+        let kind_i = self.get_kind(i);
+        let kind_j = self.get_kind(j);
+        let kind_k = self.get_kind(k);
+        self.interactions.add_angle_geometry(kind_i, kind_j, kind_k, potential)
+    }
+
     /// Add the `potential` dihedral interaction for the dihedral angle `(i, j,
     /// k, m)`
     pub fn add_dihedral_potential(&mut self, i: &str, j: &str, k: &str, m: &str, potential: Box<DihedralPotential>) {
@@ -178,6 +212,17 @@ impl System {
         self.interactions.add_dihedral(kind_i, kind_j, kind_k, kind_m, potential)
     }
 
+    /// Add the `potential` improper dihedral interaction for the improper
+    /// dihedral angle around `i`, with `j`, `k` and `m`. The order of `j`, `k`
+    /// and `m` defines the improper angle `i-j-k-m` used with this potential.
+    pub fn add_improper_potential(&mut self, i: &str, j: &str, k: &str, m: &str, potential: Box<DihedralPotential>) {
+        let kind_i = self.get_kind(i);
+        let kind_j = self.get_kind(j);
+        let kind_k = self.get_kind(k);
+        let kind_m = self.get_kind(m);
+        self.interactions.add_improper(kind_i, kind_j, kind_k, kind_m, potential)
+    }
+
     /// Set the coulombic interaction for all pairs to `potential`
     pub fn set_coulomb_potential(&mut self, potential: Box<CoulombicPotential>) {
         self.interactions.coulomb = Some(potential);
@@ -240,6 +285,27 @@ impl System {
         return angles;
     }
 
+    /// Get the lists of angle interactions using the full geometry of the
+    /// angle, acting between the particles at indexes `i`, `j` and `k`.
+    ///
+    /// The first list contains the potentials defined for the `i-j-k` angle,
+    /// and the second list the potentials defined for the `k-j-i` angle. The
+    /// potentials in the second list must be called with the `r1` and `r2`
+    /// vectors swapped. No warning is emitted if there are no potentials, as
+    /// these interactions are optional.
+    pub fn angle_geometry_potentials(&self, i: usize, j: usize, k: usize) -> (&[Box<AngleGeometryPotential>], &[Box<AngleGeometryPotential>]) {
+        let kind_i = self.particle(i).kind;
+        let kind_j = self.particle(j).kind;
+        let kind_k = self.particle(k).kind;
+        let direct = self.interactions.angle_geometries(kind_i, kind_j, kind_k);
+        let reversed = if kind_i == kind_k {
+            &[]
+        } else {
+            self.interactions.angle_geometries(kind_k, kind_j, kind_i)
+        };
+        return (direct, reversed);
+    }
+
     /// Get the list of dihedral angles interaction acting between the particles
     /// at indexes `i`, `j`, `k` and `m`.
     pub fn dihedral_potentials(&self, i: usize, j: usize, k: usize, m: usize) -> &[Box<DihedralPotential>] {
@@ -258,6 +324,42 @@ impl System {
         return dihedrals;
     }
 
+    /// Get the list of improper dihedral angles interaction acting around the
+    /// particle at index `i`, with the particles at indexes `j`, `k` and `m`
+    /// in this order.
+    ///
+    /// Contrary to the other interactions, no warning is emitted if there is
+    /// no potential for this improper dihedral angle, since improper dihedral
+    /// angles are generated for all the particles with three bonds, and most
+    /// of them do not have an associated potential.
+    pub fn improper_potentials(&self, i: usize, j: usize, k: usize, m: usize) -> &[Box<DihedralPotential>] {
+        let kind_i = self.particle(i).kind;
+        let kind_j = self.particle(j).kind;
+        let kind_k = self.particle(k).kind;
+        let kind_m = self.particle(m).kind;
+        return self.interactions.impropers(kind_i, kind_j, kind_k, kind_m);
+    }
+
+    /// Get the particles in the `improper` dihedral angle, in the order
+    /// matching the improper potentials.
+    ///
+    /// The order of the particles in `improper` is used if there are
+    /// potentials for it. Else, the other orders of the three outer particles
+    /// are tried in turn, so that the potentials are matched on the particles
+    /// types for the improper angles generated around particles with three
+    /// bonds. Outer particles with the same type are kept in the order of
+    /// `improper`, i.e. by increasing index for generated improper angles.
+    pub fn improper_particles(&self, improper: &Improper) -> Improper {
+        let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+        let orders = [(j, k, m), (j, m, k), (k, j, m), (k, m, j), (m, j, k), (m, k, j)];
+        for &(j, k, m) in &orders {
+            if !self.improper_potentials(i, j, k, m).is_empty() {
+                return Improper::new(i, j, k, m);
+            }
+        }
+        return *improper;
+    }
+
     /// Get the coulombic interaction for the system
     pub fn coulomb_potential(&self) -> Option<&Box<CoulombicPotential>> {
         self.interactions.coulomb.as_ref()
@@ -272,10 +374,28 @@ impl System {
     pub fn maximum_cutoff(&self) -> Option<f64> {
         self.interactions.maximum_cutoff()
     }
+
+    /// Get the neighbor list used for pair interactions in this system
+    pub fn neighbor_list(&self) -> &NeighborList {
+        &self.neighbor_list
+    }
+
+    /// Get mutable access to the neighbor list used for pair interactions in
+    /// this system, for example to change the skin distance or to disable it.
+    pub fn neighbor_list_mut(&mut self) -> &mut NeighborList {
+        &mut self.neighbor_list
+    }
+
+    /// Get the neighbors of all the particles for the pair interactions,
+    /// rebuilding the neighbor list if needed.
+    pub fn neighbors(&self) -> Neighbors {
+        let cutoff = self.interactions.maximum_pairs_cutoff();
+        self.neighbor_list.neighbors(&self.configuration, cutoff)
+    }
 }
 
 use sys::compute::Compute;
-use sys::compute::{PotentialEnergy, KineticEnergy, TotalEnergy};
+use sys::compute::{PotentialEnergy, KineticEnergy, TotalEnergy, ConservedEnergy};
 use sys::compute::Forces;
 use sys::compute::Temperature;
 use sys::compute::Volume;
@@ -290,6 +410,9 @@ impl System {
     pub fn potential_energy(&self) -> f64 {PotentialEnergy.compute(self)}
     /// Get the total energy of the system.
     pub fn total_energy(&self) -> f64 {TotalEnergy.compute(self)}
+    /// Get the conserved energy of the system, *i.e.* the total energy plus
+    /// the energy of the extended degrees of freedom.
+    pub fn conserved_energy(&self) -> f64 {ConservedEnergy.compute(self)}
 
     /// Get the temperature of the system.
     pub fn temperature(&self) -> f64 {
@@ -361,6 +484,17 @@ mod tests {
         assert_eq!(system.step(), 3);
     }
 
+    #[test]
+    fn extended_energy() {
+        let mut system = System::new();
+        assert_eq!(system.extended_energy(), 0.0);
+
+        system.add_extended_energy(3.0);
+        system.add_extended_energy(-1.0);
+        assert_eq!(system.extended_energy(), 2.0);
+        assert_eq!(system.conserved_energy(), 2.0);
+    }
+
     #[test]
     #[should_panic]
     fn negative_external_temperature() {
@@ -444,5 +578,6 @@ mod tests {
         assert_eq!(system.bond_potentials(0, 0).len(), 0);
         assert_eq!(system.angle_potentials(0, 0, 0).len(), 0);
         assert_eq!(system.dihedral_potentials(0, 0, 0, 0).len(), 0);
+        assert_eq!(system.improper_potentials(0, 0, 0, 0).len(), 0);
     }
 }
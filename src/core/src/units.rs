@@ -70,6 +70,10 @@ lazy_static!(
         // Force unit.
         assert!(map.insert("N", 1e-20 / U_IN_KG).is_none());
 
+        // Electric potential unit. Charges are expressed in units of the
+        // elementary charge, so that 1 V corresponds to an energy of 1 eV.
+        assert!(map.insert("V", 1.60217653e-19 * 1e-10 / U_IN_KG).is_none());
+
         // Pressure units.
         assert!(map.insert("Pa", 1e-40 / U_IN_KG).is_none());
         assert!(map.insert("kPa", 1e-37 / U_IN_KG).is_none());
@@ -1,13 +1,16 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 use toml::value::{Value, Table};
+use std::path::Path;
 
 use lumol::sys::System;
 use lumol::energy::{Harmonic, CosineHarmonic, Torsion, MorsePotential, NullPotential};
-use lumol::energy::{AnglePotential, DihedralPotential};
+use lumol::energy::{RyckaertBellemans, Fourier};
+use lumol::energy::{AnglePotential, DihedralPotential, TablePotential};
+use lumol::energy::{AngleGeometryPotential, UreyBradley, BondBond, BondAngle};
 
 use error::{Error, Result};
-use FromToml;
+use {FromToml, FromTomlWithData};
 use extract;
 use super::InteractionsInput;
 
@@ -39,8 +42,14 @@ impl InteractionsInput {
             let b = try!(atoms[1].as_str().ok_or(Error::from("The second atom name is not a string in angle potential")));
             let c = try!(atoms[2].as_str().ok_or(Error::from("The third atom name is not a string in angle potential")));
 
-            let potential = try!(read_angle_potential(angle));
-            system.add_angle_potential(a, b, c, potential);
+            match try!(read_angle_potential(angle, self.path())) {
+                AngleInteraction::Angle(potential) => {
+                    system.add_angle_potential(a, b, c, potential);
+                }
+                AngleInteraction::Geometry(potential) => {
+                    system.add_angle_geometry_potential(a, b, c, potential);
+                }
+            }
         }
         Ok(())
     }
@@ -73,14 +82,56 @@ impl InteractionsInput {
             let c = try!(atoms[2].as_str().ok_or(Error::from("The third atom name is not a string in dihedral potential")));
             let d = try!(atoms[3].as_str().ok_or(Error::from("The fourth atom name is not a string in dihedral potential")));
 
-            let potential = try!(read_dihedral_potential(dihedral));
+            let potential = try!(read_dihedral_potential(dihedral, "dihedral potential"));
             system.add_dihedral_potential(a, b, c, d, potential);
         }
         Ok(())
     }
+
+    /// Read the "impropers" section from the potential configuration.
+    pub(crate) fn read_impropers(&self, system: &mut System) -> Result<()> {
+        let impropers = match self.config.get("impropers") {
+            Some(impropers) => impropers,
+            None => return Ok(())
+        };
+
+        let impropers = try!(impropers.as_array().ok_or(
+            Error::from("The 'impropers' section must be an array")
+        ));
+
+        for improper in impropers {
+            let improper = try!(improper.as_table().ok_or(
+                Error::from("improper potential entry must be a table")
+            ));
+
+            let atoms = try!(extract::slice("atoms", improper, "improper potential"));
+            if atoms.len() != 4 {
+                return Err(Error::from(
+                    format!("Wrong size for 'atoms' array in improper potential. Should be 4, is {}", atoms.len())
+                ));
+            }
+
+            let a = try!(atoms[0].as_str().ok_or(Error::from("The first atom name is not a string in improper potential")));
+            let b = try!(atoms[1].as_str().ok_or(Error::from("The second atom name is not a string in improper potential")));
+            let c = try!(atoms[2].as_str().ok_or(Error::from("The third atom name is not a string in improper potential")));
+            let d = try!(atoms[3].as_str().ok_or(Error::from("The fourth atom name is not a string in improper potential")));
+
+            let potential = try!(read_dihedral_potential(improper, "improper potential"));
+            system.add_improper_potential(a, b, c, d, potential);
+        }
+        Ok(())
+    }
 }
 
-fn read_angle_potential(angle: &Table) -> Result<Box<AnglePotential>> {
+/// Potentials that can be used in the "angles" section
+enum AngleInteraction {
+    /// Potential depending only on the angle value
+    Angle(Box<AnglePotential>),
+    /// Potential depending on the full geometry of the angle
+    Geometry(Box<AngleGeometryPotential>),
+}
+
+fn read_angle_potential(angle: &Table, root: &Path) -> Result<AngleInteraction> {
     let potentials = angle.keys().cloned()
                     .filter(|key| key != "atoms")
                     .collect::<Vec<_>>();
@@ -100,10 +151,14 @@ fn read_angle_potential(angle: &Table) -> Result<Box<AnglePotential>> {
     let key = &*potentials[0];
     if let Value::Table(ref table) = angle[key] {
         match key {
-            "null" => Ok(Box::new(try!(NullPotential::from_toml(table)))),
-            "harmonic" => Ok(Box::new(try!(Harmonic::from_toml(table)))),
-            "cosine-harmonic" => Ok(Box::new(try!(CosineHarmonic::from_toml(table)))),
-            "morse" => Ok(Box::new(try!(MorsePotential::from_toml(table)))),
+            "null" => Ok(AngleInteraction::Angle(Box::new(try!(NullPotential::from_toml(table))))),
+            "harmonic" => Ok(AngleInteraction::Angle(Box::new(try!(Harmonic::from_toml(table))))),
+            "cosine-harmonic" => Ok(AngleInteraction::Angle(Box::new(try!(CosineHarmonic::from_toml(table))))),
+            "morse" => Ok(AngleInteraction::Angle(Box::new(try!(MorsePotential::from_toml(table))))),
+            "table" => Ok(AngleInteraction::Angle(Box::new(try!(TablePotential::from_toml(table, root.to_path_buf()))))),
+            "urey-bradley" => Ok(AngleInteraction::Geometry(Box::new(try!(UreyBradley::from_toml(table))))),
+            "bond-bond" => Ok(AngleInteraction::Geometry(Box::new(try!(BondBond::from_toml(table))))),
+            "bond-angle" => Ok(AngleInteraction::Geometry(Box::new(try!(BondAngle::from_toml(table))))),
             other => Err(
                 Error::from(format!("Unknown potential type '{}'", other))
             ),
@@ -115,20 +170,22 @@ fn read_angle_potential(angle: &Table) -> Result<Box<AnglePotential>> {
     }
 }
 
-fn read_dihedral_potential(dihedral: &Table) -> Result<Box<DihedralPotential>> {
+/// Read a potential for dihedral angles. This is used for both proper and
+/// improper dihedral angles, `context` is used in error messages.
+fn read_dihedral_potential(dihedral: &Table, context: &str) -> Result<Box<DihedralPotential>> {
     let potentials = dihedral.keys().cloned()
                     .filter(|key| key != "atoms")
                     .collect::<Vec<_>>();
 
     if potentials.is_empty() {
         return Err(Error::from(
-            "Missing potential type in dihedral potential"
+            format!("Missing potential type in {}", context)
         ));
     }
 
     if potentials.len() > 1 {
         return Err(Error::from(
-            format!("Got more than one potential type in dihedral potential: {}", potentials.join(" and "))
+            format!("Got more than one potential type in {}: {}", context, potentials.join(" and "))
         ));
     }
 
@@ -139,6 +196,8 @@ fn read_dihedral_potential(dihedral: &Table) -> Result<Box<DihedralPotential>> {
             "harmonic" => Ok(Box::new(try!(Harmonic::from_toml(table)))),
             "cosine-harmonic" => Ok(Box::new(try!(CosineHarmonic::from_toml(table)))),
             "torsion" => Ok(Box::new(try!(Torsion::from_toml(table)))),
+            "ryckaert-bellemans" => Ok(Box::new(try!(RyckaertBellemans::from_toml(table)))),
+            "fourier" => Ok(Box::new(try!(Fourier::from_toml(table)))),
             "morse" => Ok(Box::new(try!(MorsePotential::from_toml(table)))),
             other => Err(
                 Error::from(format!("Unknown potential type '{}'", other))
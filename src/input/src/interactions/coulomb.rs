@@ -3,7 +3,8 @@
 use toml::Value;
 
 use lumol::sys::System;
-use lumol::energy::{Wolf, Ewald, SharedEwald, CoulombicPotential};
+use lumol::energy::{Wolf, ReactionField, DampedShiftedForce, CoulombicPotential};
+use lumol::energy::{Ewald, SharedEwald, SPME, SharedSPME};
 
 use error::{Error, Result};
 use FromToml;
@@ -36,10 +37,16 @@ impl InteractionsInput {
         if let Value::Table(ref table) = coulomb[key] {
             let mut potential: Box<CoulombicPotential> = match key {
                 "wolf" => Box::new(try!(Wolf::from_toml(table))),
+                "reaction-field" => Box::new(try!(ReactionField::from_toml(table))),
+                "dsf" => Box::new(try!(DampedShiftedForce::from_toml(table))),
                 "ewald" => {
                     let ewald = try!(Ewald::from_toml(table));
                     Box::new(SharedEwald::new(ewald))
                 }
+                "spme" => {
+                    let spme = try!(SPME::from_toml(table));
+                    Box::new(SharedSPME::new(spme))
+                }
                 other => {
                     return Err(Error::from(format!("Unknown coulomb solver '{}'", other)))
                 },
@@ -0,0 +1,247 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Read EAM potentials from `funcfl`, `setfl` and Finnis-Sinclair files.
+use toml::value::{Table, Value};
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+
+use lumol::energy::{EAM, EAMFunction};
+use lumol::units;
+
+use error::{Error, Result};
+use FromTomlWithData;
+use simulations::get_input_path;
+
+/// Conversion factor from `Hartree Bohr` to `eV A`, used for the effective
+/// charges in funcfl files
+const HARTREE_BOHR: f64 = 27.2 * 0.529;
+
+/// Available EAM file formats
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Format {
+    /// Single element `funcfl` file
+    Funcfl,
+    /// Multiple elements `setfl` file
+    Setfl,
+    /// Multiple elements Finnis-Sinclair file
+    FinnisSinclair,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Result<Format> {
+        match name {
+            "funcfl" => Ok(Format::Funcfl),
+            "setfl" => Ok(Format::Setfl),
+            "fs" => Ok(Format::FinnisSinclair),
+            other => Err(Error::from(format!("Unknown EAM file format '{}'", other))),
+        }
+    }
+
+    fn from_path(path: &Path) -> Result<Format> {
+        let name = path.to_string_lossy();
+        if name.ends_with(".eam.alloy") || name.ends_with(".setfl") {
+            Ok(Format::Setfl)
+        } else if name.ends_with(".eam.fs") {
+            Ok(Format::FinnisSinclair)
+        } else if name.ends_with(".eam") {
+            Ok(Format::Funcfl)
+        } else {
+            Err(Error::from(
+                "Could not guess the EAM file format from the file extension, use the 'format' key"
+            ))
+        }
+    }
+}
+
+/// Whitespace separated values in an EAM file
+struct Values<'a> {
+    values: SplitWhitespace<'a>,
+}
+
+impl<'a> Values<'a> {
+    fn new(content: &'a str) -> Values<'a> {
+        Values {
+            values: content.split_whitespace(),
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a str> {
+        self.values.next().ok_or(Error::from("Unexpected end of EAM file"))
+    }
+
+    fn number(&mut self) -> Result<f64> {
+        let value = try!(self.string());
+        value.parse().map_err(|_| Error::from(
+            format!("Invalid number '{}' in EAM file", value)
+        ))
+    }
+
+    fn uint(&mut self) -> Result<usize> {
+        let value = try!(self.string());
+        value.parse().map_err(|_| Error::from(
+            format!("Invalid integer '{}' in EAM file", value)
+        ))
+    }
+
+    /// Read `n` numbers, and multiply them by `factor`
+    fn numbers(&mut self, n: usize, factor: f64) -> Result<Vec<f64>> {
+        let mut values = Vec::with_capacity(n);
+        for _ in 0..n {
+            values.push(factor * try!(self.number()));
+        }
+        Ok(values)
+    }
+}
+
+/// Grids used for all the functions in an EAM file
+struct Grids {
+    nrho: usize,
+    drho: f64,
+    nr: usize,
+    dr: f64,
+    cutoff: f64,
+}
+
+impl Grids {
+    fn read(values: &mut Values) -> Result<Grids> {
+        let grids = Grids {
+            nrho: try!(values.uint()),
+            drho: try!(values.number()),
+            nr: try!(values.uint()),
+            dr: try!(values.number()),
+            cutoff: try!(values.number()),
+        };
+
+        if grids.nrho < 3 || grids.nr < 3 {
+            return Err(Error::from("EAM file must contain at least three points in each function"));
+        }
+        if grids.drho <= 0.0 || grids.dr <= 0.0 || grids.cutoff <= 0.0 {
+            return Err(Error::from("Grid spacing and cutoff must be positive in EAM file"));
+        }
+        Ok(grids)
+    }
+}
+
+/// Read a funcfl file. The first line is a comment, and the second line
+/// contains the atomic number, mass, lattice constant and lattice type.
+fn read_funcfl(content: &str, names: Vec<String>) -> Result<EAM> {
+    if names.len() != 1 {
+        return Err(Error::from(format!(
+            "'atoms' must contain a single name for funcfl EAM file, got {}", names.len()
+        )));
+    }
+    let ev = try!(units::from(1.0, "eV"));
+
+    let content = content.splitn(2, '\n').nth(1).unwrap_or("");
+    let mut values = Values::new(content);
+    // Atomic number, mass, lattice constant and lattice type
+    for _ in 0..4 {
+        let _ = try!(values.string());
+    }
+    let grids = try!(Grids::read(&mut values));
+
+    let embedding = try!(values.numbers(grids.nrho, ev));
+    let charges = try!(values.numbers(grids.nr, 1.0));
+    let density = try!(values.numbers(grids.nr, 1.0));
+    let pair = charges.iter().map(|z| HARTREE_BOHR * z * z * ev).collect();
+
+    Ok(EAM::new(
+        names,
+        vec![EAMFunction::new(grids.drho, embedding)],
+        vec![vec![EAMFunction::new(grids.dr, density)]],
+        vec![vec![EAMFunction::new(grids.dr, pair)]],
+        grids.cutoff,
+    ))
+}
+
+/// Read a setfl or Finnis-Sinclair file. The first three lines are comments,
+/// and the fourth line contains the number of elements and their names.
+fn read_setfl(content: &str, names: Vec<String>, format: Format) -> Result<EAM> {
+    let ev = try!(units::from(1.0, "eV"));
+
+    let content = content.splitn(4, '\n').nth(3).unwrap_or("");
+    let mut values = Values::new(content);
+    let n = try!(values.uint());
+    // Element names in the file
+    for _ in 0..n {
+        let _ = try!(values.string());
+    }
+    if names.len() != n {
+        return Err(Error::from(format!(
+            "EAM file contains {} elements, but 'atoms' contains {} names", n, names.len()
+        )));
+    }
+    let grids = try!(Grids::read(&mut values));
+
+    let mut embedding = Vec::new();
+    let mut density = vec![Vec::new(); n];
+    for a in 0..n {
+        // Atomic number, mass, lattice constant and lattice type
+        for _ in 0..4 {
+            let _ = try!(values.string());
+        }
+        embedding.push(EAMFunction::new(grids.drho, try!(values.numbers(grids.nrho, ev))));
+
+        if format == Format::FinnisSinclair {
+            // Densities created by this element at each element
+            for _ in 0..n {
+                let function = EAMFunction::new(grids.dr, try!(values.numbers(grids.nr, 1.0)));
+                density[a].push(function);
+            }
+        } else {
+            let function = EAMFunction::new(grids.dr, try!(values.numbers(grids.nr, 1.0)));
+            density[a] = vec![function; n];
+        }
+    }
+
+    // Lower triangle of the pair functions
+    let mut pair: Vec<Vec<Option<EAMFunction>>> = vec![vec![None; n]; n];
+    for a in 0..n {
+        for b in 0..(a + 1) {
+            let function = EAMFunction::new(grids.dr, try!(values.numbers(grids.nr, ev)));
+            pair[a][b] = Some(function.clone());
+            pair[b][a] = Some(function);
+        }
+    }
+    let pair = pair.into_iter().map(|functions| {
+        functions.into_iter().map(|f| f.expect("missing pair function")).collect()
+    }).collect();
+
+    Ok(EAM::new(names, embedding, density, pair, grids.cutoff))
+}
+
+impl FromTomlWithData for EAM {
+    /// Names of the particles for the elements in the file, and path of the
+    /// interactions input file, used to find the EAM file
+    type Data = (Vec<String>, PathBuf);
+
+    fn from_toml(table: &Table, data: (Vec<String>, PathBuf)) -> Result<EAM> {
+        let (names, root) = data;
+        let file = try!(table.get("file").ok_or(
+            Error::from("Missing 'file' in EAM potential")
+        ));
+        let file = try!(file.as_str().ok_or(
+            Error::from("'file' must be a string in EAM potential")
+        ));
+        let path = get_input_path(&root, file);
+
+        let format = match table.get("format") {
+            Some(&Value::String(ref format)) => try!(Format::from_name(format)),
+            Some(_) => return Err(Error::from("'format' must be a string in EAM potential")),
+            None => try!(Format::from_path(&path)),
+        };
+
+        let mut file = try_io!(File::open(&path), path);
+        let mut content = String::new();
+        let _ = try_io!(file.read_to_string(&mut content), path);
+
+        match format {
+            Format::Funcfl => read_funcfl(&content, names),
+            Format::Setfl | Format::FinnisSinclair => read_setfl(&content, names, format),
+        }
+    }
+}
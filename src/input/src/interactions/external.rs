@@ -0,0 +1,82 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use toml::value::Value;
+
+use lumol::sys::System;
+use lumol::types::{Vector3D, Zero};
+use lumol::energy::{ElectricField, ConstantForce};
+use lumol::units;
+
+use error::{Error, Result};
+use extract;
+use super::InteractionsInput;
+
+impl InteractionsInput {
+    /// Read the "external" section from the potential configuration.
+    pub(crate) fn read_external(&self, system: &mut System) -> Result<()> {
+        let external = match self.config.get("external") {
+            Some(external) => external,
+            None => return Ok(())
+        };
+
+        let external = try!(external.as_array().ok_or(
+            Error::from("The 'external' section must be an array")
+        ));
+
+        for field in external {
+            let field = try!(field.as_table().ok_or(
+                Error::from("External field entry must be a table")
+            ));
+
+            let types = field.keys().cloned()
+                             .filter(|key| key != "atoms")
+                             .collect::<Vec<_>>();
+
+            if types.is_empty() {
+                return Err(Error::from("Missing field type in external field"));
+            }
+
+            if types.len() > 1 {
+                return Err(Error::from(
+                    format!("Got more than one field type in external field: {}", types.join(" and "))
+                ));
+            }
+
+            let key = &*types[0];
+            match key {
+                "electric-field" => {
+                    let vector = try!(read_vector(key, &field[key]));
+                    system.add_global_potential(Box::new(ElectricField{field: vector}));
+                }
+                "constant-force" => {
+                    let atoms = try!(extract::str("atoms", field, "constant force"));
+                    let vector = try!(read_vector(key, &field[key]));
+                    system.add_global_potential(Box::new(ConstantForce{
+                        atoms: String::from(atoms),
+                        force: vector,
+                    }));
+                }
+                other => return Err(
+                    Error::from(format!("Unknown external field type '{}'", other))
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read a vector given as an array of three strings with units.
+fn read_vector(key: &str, value: &Value) -> Result<Vector3D> {
+    let error = || Error::from(format!("'{}' must be an array of three strings", key));
+    let values = try!(value.as_array().ok_or_else(&error));
+    if values.len() != 3 {
+        return Err(error());
+    }
+
+    let mut vector = Vector3D::zero();
+    for (i, value) in values.iter().enumerate() {
+        let value = try!(value.as_str().ok_or_else(&error));
+        vector[i] = try!(units::from_str(value));
+    }
+    return Ok(vector);
+}
@@ -0,0 +1,122 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use toml::value::{Value, Table};
+use std::path::Path;
+
+use lumol::sys::System;
+use lumol::energy::{ManyBodyPotential, ManyBodyInteraction};
+use lumol::energy::{StillingerWeber, Tersoff, EAM};
+
+use error::{Error, Result};
+use {FromToml, FromTomlWithData};
+use extract;
+use super::InteractionsInput;
+
+impl InteractionsInput {
+    /// Read the "manybody" section from the potential configuration.
+    pub(crate) fn read_manybody(&self, system: &mut System) -> Result<()> {
+        let manybody = match self.config.get("manybody") {
+            Some(manybody) => manybody,
+            None => return Ok(())
+        };
+
+        let manybody = try!(manybody.as_array().ok_or(
+            Error::from("The 'manybody' section must be an array")
+        ));
+
+        for interaction in manybody {
+            let interaction = try!(interaction.as_table().ok_or(
+                Error::from("Many-body potential entry must be a table")
+            ));
+
+            let potentials = interaction.keys().cloned()
+                                        .filter(|key| key != "atoms")
+                                        .collect::<Vec<_>>();
+
+            if potentials.is_empty() {
+                return Err(Error::from(
+                    "Missing potential type in many-body potential"
+                ));
+            }
+
+            if potentials.len() > 1 {
+                return Err(Error::from(
+                    format!("Got more than one potential type in many-body potential: {}", potentials.join(" and "))
+                ));
+            }
+
+            let key = &*potentials[0];
+            if key == "eam" {
+                let eam = try!(read_eam(interaction, self.path()));
+                system.add_global_potential(Box::new(eam));
+            } else {
+                let atoms = try!(extract::str("atoms", interaction, "many-body potential"));
+                let potential = try!(read_manybody_potential(key, &interaction[key]));
+                system.add_global_potential(Box::new(ManyBodyInteraction::new(potential, atoms)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read an EAM potential. The `atoms` key can contain a single name, or an
+/// array of names for the elements in the EAM file.
+fn read_eam(interaction: &Table, root: &Path) -> Result<EAM> {
+    let names = match interaction.get("atoms") {
+        Some(&Value::String(ref name)) => vec![name.clone()],
+        Some(&Value::Array(ref names)) => {
+            let mut result = Vec::new();
+            for name in names {
+                result.push(String::from(try!(name.as_str().ok_or(
+                    Error::from("'atoms' must be a string or an array of strings in EAM potential")
+                ))));
+            }
+            result
+        }
+        Some(_) => return Err(Error::from(
+            "'atoms' must be a string or an array of strings in EAM potential"
+        )),
+        None => return Err(Error::from("Missing 'atoms' key in EAM potential")),
+    };
+
+    if let Value::Table(ref table) = interaction["eam"] {
+        EAM::from_toml(table, (names, root.to_path_buf()))
+    } else {
+        Err(Error::from("'eam' potential must be a table"))
+    }
+}
+
+fn read_manybody_potential(key: &str, value: &Value) -> Result<Box<ManyBodyPotential>> {
+    match (key, value) {
+        ("stillinger-weber", &Value::String(ref name)) => {
+            match &**name {
+                "silicon" => Ok(Box::new(StillingerWeber::silicon())),
+                "mw-water" => Ok(Box::new(StillingerWeber::mw_water())),
+                other => Err(Error::from(
+                    format!("Unknown parameters '{}' for Stillinger-Weber potential", other)
+                )),
+            }
+        }
+        ("stillinger-weber", &Value::Table(ref table)) => {
+            Ok(Box::new(try!(StillingerWeber::from_toml(table))))
+        }
+        ("tersoff", &Value::String(ref name)) => {
+            match &**name {
+                "silicon" => Ok(Box::new(Tersoff::silicon())),
+                "carbon" => Ok(Box::new(Tersoff::carbon())),
+                other => Err(Error::from(
+                    format!("Unknown parameters '{}' for Tersoff potential", other)
+                )),
+            }
+        }
+        ("tersoff", &Value::Table(ref table)) => {
+            Ok(Box::new(try!(Tersoff::from_toml(table))))
+        }
+        ("stillinger-weber", _) | ("tersoff", _) => Err(Error::from(
+            format!("'{}' potential must be a string or a table", key)
+        )),
+        (other, _) => Err(Error::from(
+            format!("Unknown many-body potential type '{}'", other)
+        )),
+    }
+}
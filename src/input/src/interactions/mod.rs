@@ -5,7 +5,7 @@ use toml::value::{Table, Value};
 
 use std::io::prelude::*;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use lumol::sys::System;
 use lumol::energy::PairRestriction;
@@ -17,11 +17,19 @@ mod toml;
 mod pairs;
 mod angles;
 mod coulomb;
+mod manybody;
+mod eam;
+mod restraints;
+mod external;
+mod walls;
 
 /// An interaction input file for Lumol.
 pub struct InteractionsInput {
     /// The TOML configuration
     config: Table,
+    /// Path of the file containing the configuration, used to find the files
+    /// referenced in this configuration
+    path: PathBuf,
 }
 
 impl InteractionsInput {
@@ -31,31 +39,44 @@ impl InteractionsInput {
         let mut file = try_io!(File::open(&path), path);
         let mut buffer = String::new();
         let _ = try_io!(file.read_to_string(&mut buffer), path);
-        return InteractionsInput::from_string(&buffer);
+        return InteractionsInput::from_string(&buffer, path);
     }
 
-    /// Read the interactions from a TOML formatted string.
-    pub(crate) fn from_string(string: &str) -> Result<InteractionsInput> {
+    /// Read the interactions from a TOML formatted string, coming from the
+    /// file at `path`.
+    pub(crate) fn from_string(string: &str, path: PathBuf) -> Result<InteractionsInput> {
         let config = try!(parse(string).map_err(|err| {
             Error::TOML(Box::new(err))
         }));
         try!(validate(&config));
-        return InteractionsInput::from_toml(config.clone());
+        return InteractionsInput::from_toml(config.clone(), path);
     }
 
-    /// Read the interactions from a TOML table.
-    pub(crate) fn from_toml(config: Table) -> Result<InteractionsInput> {
+    /// Read the interactions from a TOML table, coming from the file at
+    /// `path`.
+    pub(crate) fn from_toml(config: Table, path: PathBuf) -> Result<InteractionsInput> {
         Ok(InteractionsInput{
-            config: config
+            config: config,
+            path: path,
         })
     }
 
+    /// Get the path of the file containing the configuration
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Read the interactions from this input into the `system`.
     pub fn read(&self, system: &mut System) -> Result<()> {
         try!(self.read_pairs(system));
         try!(self.read_bonds(system));
         try!(self.read_angles(system));
         try!(self.read_dihedrals(system));
+        try!(self.read_impropers(system));
+        try!(self.read_manybody(system));
+        try!(self.read_restraints(system));
+        try!(self.read_external(system));
+        try!(self.read_walls(system));
         try!(self.read_coulomb(system));
         try!(self.read_charges(system));
         Ok(())
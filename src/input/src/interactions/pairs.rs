@@ -8,7 +8,12 @@ use lumol::units;
 use lumol::energy::{PairPotential, PairInteraction, BondPotential};
 use lumol::energy::{Harmonic, LennardJones, NullPotential};
 use lumol::energy::{Buckingham, BornMayerHuggins, MorsePotential};
-use lumol::energy::TableComputation;
+use lumol::energy::{Mie, SoftCoreLennardJones};
+use lumol::energy::{Fene, WCA, Quartic};
+use lumol::energy::{TableComputation, TablePotential, Switching};
+
+use std::collections::BTreeSet;
+use std::path::Path;
 
 use error::{Error, Result};
 use {FromToml, FromTomlWithData};
@@ -16,10 +21,58 @@ use extract;
 use super::read_restriction;
 use super::InteractionsInput;
 
+/// Mixing rules used to generate Lennard-Jones cross interactions from
+/// per-atom parameters
+#[derive(Clone, Copy, Debug)]
+enum MixingRule {
+    /// Arithmetic mean for sigma, geometric mean for epsilon
+    LorentzBerthelot,
+    /// Geometric mean for both sigma and epsilon
+    Geometric,
+    /// Waldman-Hagler mixing, using sixth power means
+    WaldmanHagler,
+}
+
+impl MixingRule {
+    /// Get the mixing rule with the given `name`
+    fn from_name(name: &str) -> Result<MixingRule> {
+        match name {
+            "lorentz-berthelot" => Ok(MixingRule::LorentzBerthelot),
+            "geometric" => Ok(MixingRule::Geometric),
+            "waldman-hagler" => Ok(MixingRule::WaldmanHagler),
+            other => Err(Error::from(format!("Unknown mixing rule '{}'", other))),
+        }
+    }
+
+    /// Get the Lennard-Jones potential between two atoms with the potentials
+    /// `a` and `b`.
+    fn mix(&self, a: &LennardJones, b: &LennardJones) -> LennardJones {
+        match *self {
+            MixingRule::LorentzBerthelot => LennardJones {
+                sigma: 0.5 * (a.sigma + b.sigma),
+                epsilon: f64::sqrt(a.epsilon * b.epsilon),
+            },
+            MixingRule::Geometric => LennardJones {
+                sigma: f64::sqrt(a.sigma * b.sigma),
+                epsilon: f64::sqrt(a.epsilon * b.epsilon),
+            },
+            MixingRule::WaldmanHagler => {
+                let sigma3 = a.sigma.powi(3) * b.sigma.powi(3);
+                let sum6 = a.sigma.powi(6) + b.sigma.powi(6);
+                LennardJones {
+                    sigma: f64::powf(0.5 * sum6, 1.0 / 6.0),
+                    epsilon: 2.0 * f64::sqrt(a.epsilon * b.epsilon) * sigma3 / sum6,
+                }
+            }
+        }
+    }
+}
+
 /// Global settings for the pair interactions
 struct GlobalInformation<'a> {
     cutoff: Option<&'a Value>,
     tail: Option<bool>,
+    mixing: Option<MixingRule>,
 }
 
 impl<'a> GlobalInformation<'a> {
@@ -38,14 +91,24 @@ impl<'a> GlobalInformation<'a> {
                 } else {
                     None
                 };
+                let mixing = if let Some(mixing) = global.get("mixing") {
+                    let mixing = try!(mixing.as_str().ok_or(Error::from(
+                        "The 'mixing' section must be a string value"
+                    )));
+                    Some(try!(MixingRule::from_name(mixing)))
+                } else {
+                    None
+                };
                 Ok(GlobalInformation {
                     cutoff: cutoff,
                     tail: tail,
+                    mixing: mixing,
                 })
             }
             None => Ok(GlobalInformation {
                 cutoff: None,
                 tail: None,
+                mixing: None,
             })
         }
     }
@@ -56,7 +119,7 @@ impl InteractionsInput {
     pub(crate) fn read_pairs(&self, system: &mut System) -> Result<()> {
         let pairs = match self.config.get("pairs") {
             Some(pairs) => pairs,
-            None => return Ok(())
+            None => return self.read_mixed_pairs(system)
         };
 
         let pairs = try!(pairs.as_array().ok_or(
@@ -82,7 +145,7 @@ impl InteractionsInput {
                 "The second atom name is not a string in pair potential"
             )));
 
-            let potential = try!(read_pair_potential(pair));
+            let potential = try!(read_pair_potential(pair, self.path()));
             let potential = if let Some(computation) = pair.get("computation") {
                 let computation = try!(computation.as_table().ok_or(
                     Error::from("'computation' section must be a table")
@@ -93,53 +156,67 @@ impl InteractionsInput {
             };
 
             let global = try!(GlobalInformation::read(&self.config));
-            let cutoff = match pair.get("cutoff") {
-                Some(cutoff) => cutoff,
-                None => try!(global.cutoff.as_ref().ok_or(Error::from(
-                    "Missing 'cutoff' value for pair potential"
-                )))
-            };
+            let mut interaction = try!(read_interaction(pair, &global, potential));
 
-            let mut interaction = match *cutoff {
-                Value::String(ref cutoff) => {
-                    let cutoff = try!(units::from_str(cutoff));
-                    PairInteraction::new(potential, cutoff)
-                }
-                Value::Table(ref table) => {
-                    let shifted = try!(table.get("shifted").ok_or(Error::from(
-                        "'cutoff' table can only contain 'shifted' key"
-                    )));
-                    let cutoff = try!(shifted.as_str().ok_or(Error::from(
-                        "'cutoff.shifted' value must be a string"
-                    )));
-                    let cutoff = try!(units::from_str(cutoff));
-                    PairInteraction::shifted(potential, cutoff)
-                }
-                _ => return Err(Error::from(
-                    "'cutoff' must be a string or a table"
-                ))
-            };
+            if let Some(restriction) = try!(read_restriction(pair)) {
+                interaction.set_restriction(restriction);
+            }
 
-            let tail = match pair.get("tail_correction") {
-                Some(tail) => {
-                    Some(try!(tail.as_bool().ok_or(Error::from(
-                        "The 'tail_correction' section must be a boolean value"
-                    ))))
-                }
-                None => global.tail
-            };
+            system.add_pair_potential(a, b, interaction);
+        }
+        return self.read_mixed_pairs(system);
+    }
+
+    /// Read the "atoms" section from the potential configuration, and
+    /// generate the missing Lennard-Jones pair interactions using the mixing
+    /// rule from the "global" section.
+    fn read_mixed_pairs(&self, system: &mut System) -> Result<()> {
+        let atoms = match self.config.get("atoms") {
+            Some(atoms) => atoms,
+            None => return Ok(())
+        };
+
+        let atoms = try!(atoms.as_table().ok_or(
+            Error::from("The 'atoms' section must be a table")
+        ));
+
+        let global = try!(GlobalInformation::read(&self.config));
+        let mixing = try!(global.mixing.ok_or(Error::from(
+            "Missing 'mixing' rule in 'global' section for the 'atoms' parameters"
+        )));
 
-            if let Some(use_tail) = tail {
-                if use_tail {
-                    interaction.enable_tail_corrections()
+        let mut parameters = Vec::new();
+        for (name, atom) in atoms {
+            let atom = try!(atom.as_table().ok_or(Error::from(
+                format!("Parameters for atom '{}' must be a table", name)
+            )));
+            parameters.push((name, try!(LennardJones::from_toml(atom))));
+        }
+
+        // Pairs given explicitly in the "pairs" section take priority over
+        // the mixing rule
+        let mut explicit = BTreeSet::new();
+        if let Some(pairs) = self.config.get("pairs").and_then(|pairs| pairs.as_array()) {
+            for pair in pairs.iter().filter_map(|pair| pair.as_table()) {
+                let atoms = try!(extract::slice("atoms", pair, "pair potential"));
+                if let (Some(a), Some(b)) = (atoms[0].as_str(), atoms[1].as_str()) {
+                    let _ = explicit.insert((a, b));
+                    let _ = explicit.insert((b, a));
                 }
             }
+        }
 
-            if let Some(restriction) = try!(read_restriction(pair)) {
-                interaction.set_restriction(restriction);
-            }
+        let empty = Table::new();
+        for (i, &(a, ref lj_a)) in parameters.iter().enumerate() {
+            for &(b, ref lj_b) in &parameters[i..] {
+                if explicit.contains(&(a.as_str(), b.as_str())) {
+                    continue;
+                }
 
-            system.add_pair_potential(a, b, interaction);
+                let potential = Box::new(mixing.mix(lj_a, lj_b));
+                let interaction = try!(read_interaction(&empty, &global, potential));
+                system.add_pair_potential(a, b, interaction);
+            }
         }
         Ok(())
     }
@@ -170,14 +247,127 @@ impl InteractionsInput {
             let a = try!(atoms[0].as_str().ok_or(Error::from("The first atom name is not a string in pair potential")));
             let b = try!(atoms[1].as_str().ok_or(Error::from("The second atom name is not a string in pair potential")));
 
-            let potential = try!(read_bond_potential(bond));
+            let potential = try!(read_bond_potential(bond, self.path()));
             system.add_bond_potential(a, b, potential);
         }
         Ok(())
     }
 }
 
-fn read_pair_potential(pair: &Table) -> Result<Box<PairPotential>> {
+/// Create the pair interaction for the `potential`, using the cutoff and tail
+/// corrections from the `pair` table or from the `global` settings.
+fn read_interaction(pair: &Table, global: &GlobalInformation, potential: Box<PairPotential>) -> Result<PairInteraction> {
+    let cutoff = match pair.get("cutoff") {
+        Some(cutoff) => cutoff,
+        None => try!(global.cutoff.as_ref().ok_or(Error::from(
+            "Missing 'cutoff' value for pair potential"
+        )))
+    };
+
+    let mut interaction = match *cutoff {
+        Value::String(ref cutoff) => {
+            let cutoff = try!(units::from_str(cutoff));
+            PairInteraction::new(potential, cutoff)
+        }
+        Value::Table(ref table) => {
+            try!(read_cutoff_table(table, potential))
+        }
+        _ => return Err(Error::from(
+            "'cutoff' must be a string or a table"
+        ))
+    };
+
+    let tail = match pair.get("tail_correction") {
+        Some(tail) => {
+            Some(try!(tail.as_bool().ok_or(Error::from(
+                "The 'tail_correction' section must be a boolean value"
+            ))))
+        }
+        None => global.tail
+    };
+
+    if let Some(use_tail) = tail {
+        if use_tail {
+            if let Some(&Value::Table(ref table)) = pair.get("soft-core-lj") {
+                let potential = try!(SoftCoreLennardJones::from_toml(table));
+                if !potential.tail_converges(interaction.cutoff()) {
+                    return Err(Error::from(
+                        "The cutoff is too small to use tail corrections with soft-core Lennard-Jones potential"
+                    ));
+                }
+            }
+            interaction.enable_tail_corrections()
+        }
+    }
+
+    return Ok(interaction);
+}
+
+/// Read a pair interaction with a cutoff given as a table, either for a
+/// shifted cutoff or for a switching function.
+fn read_cutoff_table(table: &Table, potential: Box<PairPotential>) -> Result<PairInteraction> {
+    if let Some(shifted) = table.get("shifted") {
+        if table.len() != 1 {
+            return Err(Error::from("'cutoff' table with 'shifted' key can not contain other keys"));
+        }
+        let cutoff = try!(shifted.as_str().ok_or(Error::from(
+            "'cutoff.shifted' value must be a string"
+        )));
+        let cutoff = try!(units::from_str(cutoff));
+        return Ok(PairInteraction::shifted(potential, cutoff));
+    }
+
+    let switch = try!(table.get("switch").ok_or(Error::from(
+        "'cutoff' table can only contain 'shifted' or 'switch' keys"
+    )));
+    for key in table.keys() {
+        if key != "switch" && key != "function" {
+            return Err(Error::from(format!(
+                "unexpected '{}' key in 'cutoff' table", key
+            )));
+        }
+    }
+
+    let switch = try!(switch.as_array().ok_or(Error::from(
+        "'cutoff.switch' value must be an array of two strings"
+    )));
+    if switch.len() != 2 {
+        return Err(Error::from("'cutoff.switch' value must be an array of two strings"));
+    }
+    let on = try!(switch[0].as_str().ok_or(Error::from(
+        "'cutoff.switch' value must be an array of two strings"
+    )));
+    let off = try!(switch[1].as_str().ok_or(Error::from(
+        "'cutoff.switch' value must be an array of two strings"
+    )));
+    let on = try!(units::from_str(on));
+    let off = try!(units::from_str(off));
+    if on <= 0.0 || on >= off {
+        return Err(Error::from(
+            "the switching distance must be positive and smaller than the cutoff in 'cutoff.switch'"
+        ));
+    }
+
+    let switching = match table.get("function") {
+        None => Switching::Energy,
+        Some(function) => {
+            match function.as_str() {
+                Some("energy") => Switching::Energy,
+                Some("force") => Switching::Force,
+                Some("cubic") => Switching::Cubic,
+                Some("quintic") => Switching::Quintic,
+                Some(other) => return Err(Error::from(format!(
+                    "unknown switching function '{}' in 'cutoff.function'", other
+                ))),
+                None => return Err(Error::from("'cutoff.function' value must be a string")),
+            }
+        }
+    };
+
+    return Ok(PairInteraction::switched(potential, switching, on, off));
+}
+
+fn read_pair_potential(pair: &Table, root: &Path) -> Result<Box<PairPotential>> {
     const KEYWORDS: &'static[&'static str] = &[
         "restriction", "computation", "atoms", "cutoff", "tail_correction"
     ];
@@ -204,9 +394,13 @@ fn read_pair_potential(pair: &Table) -> Result<Box<PairPotential>> {
             "null" => Ok(Box::new(try!(NullPotential::from_toml(table)))),
             "harmonic" => Ok(Box::new(try!(Harmonic::from_toml(table)))),
             "lj" => Ok(Box::new(try!(LennardJones::from_toml(table)))),
+            "mie" => Ok(Box::new(try!(Mie::from_toml(table)))),
+            "soft-core-lj" => Ok(Box::new(try!(SoftCoreLennardJones::from_toml(table)))),
             "buckingham" => Ok(Box::new(try!(Buckingham::from_toml(table)))),
             "born" => Ok(Box::new(try!(BornMayerHuggins::from_toml(table)))),
             "morse" => Ok(Box::new(try!(MorsePotential::from_toml(table)))),
+            "wca" => Ok(Box::new(try!(WCA::from_toml(table)))),
+            "table" => Ok(Box::new(try!(TablePotential::from_toml(table, root.to_path_buf())))),
             other => Err(
                 Error::from(format!("Unknown potential type '{}'", other))
             ),
@@ -218,7 +412,7 @@ fn read_pair_potential(pair: &Table) -> Result<Box<PairPotential>> {
     }
 }
 
-fn read_bond_potential(pair: &Table) -> Result<Box<BondPotential>> {
+fn read_bond_potential(pair: &Table, root: &Path) -> Result<Box<BondPotential>> {
     let potentials = pair.keys().cloned()
                     .filter(|k| k != "atoms")
                     .collect::<Vec<_>>();
@@ -241,6 +435,10 @@ fn read_bond_potential(pair: &Table) -> Result<Box<BondPotential>> {
             "null" => Ok(Box::new(try!(NullPotential::from_toml(table)))),
             "harmonic" => Ok(Box::new(try!(Harmonic::from_toml(table)))),
             "morse" => Ok(Box::new(try!(MorsePotential::from_toml(table)))),
+            "fene" => Ok(Box::new(try!(Fene::from_toml(table)))),
+            "wca" => Ok(Box::new(try!(WCA::from_toml(table)))),
+            "quartic" => Ok(Box::new(try!(Quartic::from_toml(table)))),
+            "table" => Ok(Box::new(try!(TablePotential::from_toml(table, root.to_path_buf())))),
             other => Err(
                 Error::from(format!("Unknown potential type '{}'", other))
             ),
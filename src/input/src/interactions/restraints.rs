@@ -0,0 +1,228 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use toml::value::{Value, Table};
+use std::path::Path;
+
+use lumol::sys::{System, TrajectoryBuilder};
+use lumol::types::Vector3D;
+use lumol::energy::{PositionRestraint, DistanceRestraint};
+use lumol::energy::{AngleRestraint, DihedralRestraint};
+use lumol::units;
+
+use error::{Error, Result};
+use simulations::get_input_path;
+use extract;
+use super::InteractionsInput;
+
+impl InteractionsInput {
+    /// Read the "restraints" section from the potential configuration.
+    pub(crate) fn read_restraints(&self, system: &mut System) -> Result<()> {
+        let restraints = match self.config.get("restraints") {
+            Some(restraints) => restraints,
+            None => return Ok(())
+        };
+
+        let restraints = try!(restraints.as_array().ok_or(
+            Error::from("The 'restraints' section must be an array")
+        ));
+
+        for restraint in restraints {
+            let restraint = try!(restraint.as_table().ok_or(
+                Error::from("Restraint entry must be a table")
+            ));
+
+            let types = restraint.keys().cloned()
+                                 .filter(|key| key != "atoms" && key != "molecule")
+                                 .collect::<Vec<_>>();
+
+            if types.is_empty() {
+                return Err(Error::from("Missing restraint type in restraint"));
+            }
+
+            if types.len() > 1 {
+                return Err(Error::from(
+                    format!("Got more than one restraint type in restraint: {}", types.join(" and "))
+                ));
+            }
+
+            let key = &*types[0];
+            let table = match restraint[key] {
+                Value::Table(ref table) => table,
+                _ => return Err(Error::from(format!("'{}' restraint must be a table", key)))
+            };
+
+            match key {
+                "position" => {
+                    let restraint = try!(read_position_restraint(restraint, table, system, self.path()));
+                    system.add_global_potential(Box::new(restraint));
+                }
+                "distance" => {
+                    let (k, x0, width) = try!(read_parameters(table, "distance restraint"));
+                    let atoms = try!(select_n_atoms(restraint, system, 2, "distance"));
+                    system.add_global_potential(Box::new(DistanceRestraint{
+                        particles: [atoms[0], atoms[1]],
+                        k: k,
+                        x0: x0,
+                        width: width,
+                    }));
+                }
+                "angle" => {
+                    let (k, x0, width) = try!(read_parameters(table, "angle restraint"));
+                    let atoms = try!(select_n_atoms(restraint, system, 3, "angle"));
+                    system.add_global_potential(Box::new(AngleRestraint{
+                        particles: [atoms[0], atoms[1], atoms[2]],
+                        k: k,
+                        x0: x0,
+                        width: width,
+                    }));
+                }
+                "dihedral" => {
+                    let (k, x0, width) = try!(read_parameters(table, "dihedral restraint"));
+                    let atoms = try!(select_n_atoms(restraint, system, 4, "dihedral"));
+                    system.add_global_potential(Box::new(DihedralRestraint{
+                        particles: [atoms[0], atoms[1], atoms[2], atoms[3]],
+                        k: k,
+                        x0: x0,
+                        width: width,
+                    }));
+                }
+                other => return Err(
+                    Error::from(format!("Unknown restraint type '{}'", other))
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Select the atoms used in a restraint. The `atoms` key can contain an array
+/// of atomic indexes, or an atomic name, and the `molecule` key the index of a
+/// molecule. When both a name and a molecule are given, only the atoms with
+/// this name in this molecule are selected.
+fn select_atoms(restraint: &Table, system: &System) -> Result<Vec<usize>> {
+    let molecule = match restraint.get("molecule") {
+        Some(&Value::Integer(molid)) if molid >= 0 => {
+            let molid = molid as usize;
+            if molid >= system.molecules().len() {
+                return Err(Error::from(format!(
+                    "molecule index {} is out of bounds in restraint, the system contains {} molecules",
+                    molid, system.molecules().len()
+                )));
+            }
+            Some(system.molecule(molid))
+        }
+        Some(_) => return Err(Error::from(
+            "'molecule' must be a positive integer in restraint"
+        )),
+        None => None,
+    };
+
+    let atoms = match restraint.get("atoms") {
+        Some(&Value::Array(ref indexes)) => {
+            if molecule.is_some() {
+                return Err(Error::from(
+                    "atomic indexes can not be used together with 'molecule' in restraint"
+                ));
+            }
+            let mut atoms = Vec::new();
+            for index in indexes {
+                let index = match index.as_integer() {
+                    Some(index) if index >= 0 => index as usize,
+                    _ => return Err(Error::from(
+                        "'atoms' must be a string or an array of positive integers in restraint"
+                    )),
+                };
+                if index >= system.size() {
+                    return Err(Error::from(format!(
+                        "atom index {} is out of bounds in restraint, the system contains {} atoms",
+                        index, system.size()
+                    )));
+                }
+                atoms.push(index);
+            }
+            atoms
+        }
+        Some(&Value::String(ref name)) => {
+            let range = match molecule {
+                Some(molecule) => molecule.iter(),
+                None => 0..system.size(),
+            };
+            range.filter(|&i| system.particle(i).name() == name).collect()
+        }
+        Some(_) => return Err(Error::from(
+            "'atoms' must be a string or an array of positive integers in restraint"
+        )),
+        None => match molecule {
+            Some(molecule) => molecule.iter().collect(),
+            None => return Err(Error::from(
+                "Missing 'atoms' or 'molecule' key in restraint"
+            )),
+        },
+    };
+
+    if atoms.is_empty() {
+        return Err(Error::from("No atoms selected in restraint"));
+    }
+    return Ok(atoms);
+}
+
+/// Select exactly `count` atoms for the restraint of the given `kind`
+fn select_n_atoms(restraint: &Table, system: &System, count: usize, kind: &str) -> Result<Vec<usize>> {
+    let atoms = try!(select_atoms(restraint, system));
+    if atoms.len() != count {
+        return Err(Error::from(format!(
+            "{} restraint needs {} atoms, got {}", kind, count, atoms.len()
+        )));
+    }
+    return Ok(atoms);
+}
+
+/// Read an optional dimensioned parameter, defaulting to zero
+fn read_optional(table: &Table, key: &str, context: &str) -> Result<f64> {
+    if table.get(key).is_some() {
+        let value = try!(extract::str(key, table, context));
+        Ok(try!(units::from_str(value)))
+    } else {
+        Ok(0.0)
+    }
+}
+
+/// Read the `k`, `x0` and optional `width` parameters of a restraint
+fn read_parameters(table: &Table, context: &str) -> Result<(f64, f64, f64)> {
+    let k = try!(units::from_str(try!(extract::str("k", table, context))));
+    let x0 = try!(units::from_str(try!(extract::str("x0", table, context))));
+    let width = try!(read_optional(table, "width", context));
+    return Ok((k, x0, width));
+}
+
+/// Read a position restraint, using the `table` parameters and the atoms
+/// selected in the `restraint`. The reference positions are read from the
+/// file in the `reference` key if it is present, and are the current
+/// positions of the atoms in the `system` otherwise.
+fn read_position_restraint(restraint: &Table, table: &Table, system: &System, root: &Path) -> Result<PositionRestraint> {
+    let k = try!(units::from_str(try!(extract::str("k", table, "position restraint"))));
+    let width = try!(read_optional(table, "width", "position restraint"));
+    let atoms = try!(select_atoms(restraint, system));
+
+    let references: Vec<Vector3D> = if table.get("reference").is_some() {
+        let file = try!(extract::str("reference", table, "position restraint"));
+        let path = get_input_path(root, file);
+        let mut trajectory = try!(TrajectoryBuilder::new().open(&path));
+        let reference = try!(trajectory.read());
+        if reference.size() != system.size() {
+            return Err(Error::from(format!(
+                "reference file '{}' contains {} atoms, but the system contains {}",
+                file, reference.size(), system.size()
+            )));
+        }
+        reference.particles().map(|particle| particle.position).collect()
+    } else {
+        system.particles().map(|particle| particle.position).collect()
+    };
+
+    Ok(PositionRestraint {
+        references: atoms.into_iter().map(|i| (i, references[i])).collect(),
+        k: k,
+        width: width,
+    })
+}
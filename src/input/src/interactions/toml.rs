@@ -2,16 +2,29 @@
 // Copyright (C) Lumol's contributors — BSD license
 
 //! Convert TOML values to Lumol types.
+use toml::Value;
 use toml::value::Table;
 
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
 use error::{Error, Result};
 use FromToml;
 use FromTomlWithData;
+use simulations::get_input_path;
+use extract;
 
 use lumol::energy::{Harmonic, LennardJones, NullPotential, CosineHarmonic};
 use lumol::energy::{Torsion, Buckingham, BornMayerHuggins, MorsePotential};
-use lumol::energy::{Wolf, Ewald};
-use lumol::energy::{PairPotential, TableComputation};
+use lumol::energy::{Mie, SoftCoreLennardJones};
+use lumol::energy::{Wolf, ReactionField, DampedShiftedForce, Ewald, SPME};
+use lumol::energy::{PairPotential, TableComputation, TablePotential};
+use lumol::energy::{StillingerWeber, Tersoff};
+use lumol::energy::{UreyBradley, BondBond, BondAngle};
+use lumol::energy::{RyckaertBellemans, Fourier};
+use lumol::energy::{Fene, WCA, Quartic};
+use lumol::energy::{HardWall, LennardJones93, LennardJones1043};
 
 macro_rules! try_extract_parameter {
     ($table: expr, $key: expr, $context: expr) => (
@@ -30,6 +43,123 @@ impl FromToml for NullPotential {
     }
 }
 
+impl FromToml for Mie {
+    fn from_toml(table: &Table) -> Result<Mie> {
+        const CONTEXT: &'static str = "Mie potential";
+        let sigma = try!(::lumol::units::from_str(try!(extract::str("sigma", table, CONTEXT))));
+        let epsilon = try!(::lumol::units::from_str(try!(extract::str("epsilon", table, CONTEXT))));
+        let n = try!(extract::number("n", table, CONTEXT));
+        let m = try!(extract::number("m", table, CONTEXT));
+        if m <= 3.0 {
+            return Err(Error::from("'m' must be bigger than 3 in Mie potential"));
+        }
+        if n <= m {
+            return Err(Error::from("'n' must be bigger than 'm' in Mie potential"));
+        }
+        Ok(Mie::new(sigma, epsilon, n, m))
+    }
+}
+
+impl FromToml for SoftCoreLennardJones {
+    fn from_toml(table: &Table) -> Result<SoftCoreLennardJones> {
+        const CONTEXT: &'static str = "soft-core Lennard-Jones potential";
+        let sigma = try!(::lumol::units::from_str(try!(extract::str("sigma", table, CONTEXT))));
+        let epsilon = try!(::lumol::units::from_str(try!(extract::str("epsilon", table, CONTEXT))));
+        let lambda = try!(extract::number("lambda", table, CONTEXT));
+        if lambda < 0.0 || lambda > 1.0 {
+            return Err(Error::from(
+                "'lambda' must be between 0 and 1 in soft-core Lennard-Jones potential"
+            ));
+        }
+
+        let alpha = if table.contains_key("alpha") {
+            try!(extract::number("alpha", table, CONTEXT))
+        } else {
+            0.5
+        };
+        if alpha < 0.0 {
+            return Err(Error::from(
+                "'alpha' can not be negative in soft-core Lennard-Jones potential"
+            ));
+        }
+
+        Ok(SoftCoreLennardJones{sigma: sigma, epsilon: epsilon, lambda: lambda, alpha: alpha})
+    }
+}
+
+impl FromToml for StillingerWeber {
+    fn from_toml(table: &Table) -> Result<StillingerWeber> {
+        const CONTEXT: &'static str = "Stillinger-Weber potential";
+        let epsilon = try!(::lumol::units::from_str(try!(extract::str("epsilon", table, CONTEXT))));
+        let sigma = try!(::lumol::units::from_str(try!(extract::str("sigma", table, CONTEXT))));
+        let theta0 = try!(::lumol::units::from_str(try!(extract::str("theta0", table, CONTEXT))));
+        let a = try!(extract::number("a", table, CONTEXT));
+        let lambda = try!(extract::number("lambda", table, CONTEXT));
+        let gamma = try!(extract::number("gamma", table, CONTEXT));
+        let pair_a = try!(extract::number("A", table, CONTEXT));
+        let pair_b = try!(extract::number("B", table, CONTEXT));
+        let p = try!(extract::number("p", table, CONTEXT));
+        let q = try!(extract::number("q", table, CONTEXT));
+
+        Ok(StillingerWeber {
+            epsilon: epsilon,
+            sigma: sigma,
+            a: a,
+            lambda: lambda,
+            gamma: gamma,
+            cos_theta0: f64::cos(theta0),
+            pair_a: pair_a,
+            pair_b: pair_b,
+            p: p,
+            q: q,
+        })
+    }
+}
+
+impl FromToml for Tersoff {
+    fn from_toml(table: &Table) -> Result<Tersoff> {
+        const CONTEXT: &'static str = "Tersoff potential";
+        let a = try!(::lumol::units::from_str(try!(extract::str("A", table, CONTEXT))));
+        let b = try!(::lumol::units::from_str(try!(extract::str("B", table, CONTEXT))));
+        let lambda1 = try!(::lumol::units::from_str(try!(extract::str("lambda1", table, CONTEXT))));
+        let lambda2 = try!(::lumol::units::from_str(try!(extract::str("lambda2", table, CONTEXT))));
+        let lambda3 = try!(::lumol::units::from_str(try!(extract::str("lambda3", table, CONTEXT))));
+        let r = try!(::lumol::units::from_str(try!(extract::str("R", table, CONTEXT))));
+        let d = try!(::lumol::units::from_str(try!(extract::str("D", table, CONTEXT))));
+        let m = match try!(extract::uint("m", table, CONTEXT)) {
+            m @ 1 | m @ 3 => m as i32,
+            _ => return Err(Error::from("'m' must be 1 or 3 in Tersoff potential")),
+        };
+        let beta = try!(extract::number("beta", table, CONTEXT));
+        let n = try!(extract::number("n", table, CONTEXT));
+        let gamma = try!(extract::number("gamma", table, CONTEXT));
+        let c = try!(extract::number("c", table, CONTEXT));
+        let angular_d = try!(extract::number("d", table, CONTEXT));
+        let h = try!(extract::number("h", table, CONTEXT));
+
+        if d <= 0.0 || d >= r {
+            return Err(Error::from("'D' must be positive and smaller than 'R' in Tersoff potential"));
+        }
+
+        Ok(Tersoff {
+            a: a,
+            b: b,
+            lambda1: lambda1,
+            lambda2: lambda2,
+            lambda3: lambda3,
+            m: m,
+            beta: beta,
+            n: n,
+            gamma: gamma,
+            c: c,
+            d: angular_d,
+            h: h,
+            r: r,
+            cutoff_width: d,
+        })
+    }
+}
+
 impl FromToml for Harmonic {
     fn from_toml(table: &Table) -> Result<Harmonic> {
         let k = try_extract_parameter!(table, "k", "harmonic potential");
@@ -47,6 +177,67 @@ impl FromToml for Harmonic {
     }
 }
 
+impl FromToml for UreyBradley {
+    fn from_toml(table: &Table) -> Result<UreyBradley> {
+        let k = try_extract_parameter!(table, "k", "Urey-Bradley potential");
+        let r0 = try_extract_parameter!(table, "r0", "Urey-Bradley potential");
+
+        if let (Some(k), Some(r0)) = (k.as_str(), r0.as_str()) {
+            let k = try!(::lumol::units::from_str(k));
+            let r0 = try!(::lumol::units::from_str(r0));
+            Ok(UreyBradley{k: k, r0: r0})
+        } else {
+            Err(
+                Error::from("'k' and 'r0' must be strings in Urey-Bradley potential")
+            )
+        }
+    }
+}
+
+impl FromToml for BondBond {
+    fn from_toml(table: &Table) -> Result<BondBond> {
+        let k = try_extract_parameter!(table, "k", "bond-bond potential");
+        let r1 = try_extract_parameter!(table, "r1", "bond-bond potential");
+        let r2 = try_extract_parameter!(table, "r2", "bond-bond potential");
+
+        if let (Some(k), Some(r1), Some(r2)) = (k.as_str(), r1.as_str(), r2.as_str()) {
+            let k = try!(::lumol::units::from_str(k));
+            let r1 = try!(::lumol::units::from_str(r1));
+            let r2 = try!(::lumol::units::from_str(r2));
+            Ok(BondBond{k: k, r1: r1, r2: r2})
+        } else {
+            Err(
+                Error::from("'k', 'r1' and 'r2' must be strings in bond-bond potential")
+            )
+        }
+    }
+}
+
+impl FromToml for BondAngle {
+    fn from_toml(table: &Table) -> Result<BondAngle> {
+        let k1 = try_extract_parameter!(table, "k1", "bond-angle potential");
+        let k2 = try_extract_parameter!(table, "k2", "bond-angle potential");
+        let r1 = try_extract_parameter!(table, "r1", "bond-angle potential");
+        let r2 = try_extract_parameter!(table, "r2", "bond-angle potential");
+        let theta0 = try_extract_parameter!(table, "theta0", "bond-angle potential");
+
+        let values = (k1.as_str(), k2.as_str(), r1.as_str(), r2.as_str(), theta0.as_str());
+        if let (Some(k1), Some(k2), Some(r1), Some(r2), Some(theta0)) = values {
+            Ok(BondAngle{
+                k1: try!(::lumol::units::from_str(k1)),
+                k2: try!(::lumol::units::from_str(k2)),
+                r1: try!(::lumol::units::from_str(r1)),
+                r2: try!(::lumol::units::from_str(r2)),
+                theta0: try!(::lumol::units::from_str(theta0)),
+            })
+        } else {
+            Err(
+                Error::from("'k1', 'k2', 'r1', 'r2' and 'theta0' must be strings in bond-angle potential")
+            )
+        }
+    }
+}
+
 impl FromToml for LennardJones {
     fn from_toml(table: &Table) -> Result<LennardJones> {
         let sigma = try_extract_parameter!(table, "sigma", "Lennard-Jones potential");
@@ -102,6 +293,44 @@ impl FromToml for Torsion {
     }
 }
 
+/// Read an array of strings with units at `key` in `table`, for the potential
+/// in `context`.
+fn read_coefficients(table: &Table, key: &str, context: &str) -> Result<Vec<f64>> {
+    let values = try!(extract::slice(key, table, context));
+    let mut coefficients = Vec::with_capacity(values.len());
+    for value in values {
+        let value = try!(value.as_str().ok_or(Error::from(
+            format!("'{}' must be an array of strings in {}", key, context)
+        )));
+        coefficients.push(try!(::lumol::units::from_str(value)));
+    }
+    Ok(coefficients)
+}
+
+impl FromToml for RyckaertBellemans {
+    fn from_toml(table: &Table) -> Result<RyckaertBellemans> {
+        let values = try!(read_coefficients(table, "c", "Ryckaert-Bellemans potential"));
+        if values.len() != 6 {
+            return Err(Error::from(format!(
+                "'c' must contain 6 values in Ryckaert-Bellemans potential, got {}", values.len()
+            )));
+        }
+        let mut c = [0.0; 6];
+        c.copy_from_slice(&values);
+        Ok(RyckaertBellemans{c: c})
+    }
+}
+
+impl FromToml for Fourier {
+    fn from_toml(table: &Table) -> Result<Fourier> {
+        let k = try!(read_coefficients(table, "k", "Fourier potential"));
+        if k.is_empty() {
+            return Err(Error::from("'k' must contain at least one value in Fourier potential"));
+        }
+        Ok(Fourier::new(k))
+    }
+}
+
 impl FromToml for Buckingham {
     fn from_toml(table: &Table) -> Result<Buckingham> {
         let a = try_extract_parameter!(table, "A", "Buckingham potential");
@@ -168,6 +397,185 @@ impl FromToml for MorsePotential {
     }
 }
 
+impl FromToml for Fene {
+    fn from_toml(table: &Table) -> Result<Fene> {
+        let k = try_extract_parameter!(table, "k", "FENE potential");
+        let r_max = try_extract_parameter!(table, "r_max", "FENE potential");
+
+        if let (Some(k), Some(r_max)) = (k.as_str(), r_max.as_str()) {
+            let k = try!(::lumol::units::from_str(k));
+            let r_max = try!(::lumol::units::from_str(r_max));
+            if r_max <= 0.0 {
+                return Err(Error::from("'r_max' must be positive in FENE potential"));
+            }
+            Ok(Fene{k: k, r_max: r_max})
+        } else {
+            Err(Error::from(
+                "'k' and 'r_max' must be strings in FENE potential"
+            ))
+        }
+    }
+}
+
+impl FromToml for WCA {
+    fn from_toml(table: &Table) -> Result<WCA> {
+        let sigma = try_extract_parameter!(table, "sigma", "WCA potential");
+        let epsilon = try_extract_parameter!(table, "epsilon", "WCA potential");
+
+        if let (Some(sigma), Some(epsilon)) = (sigma.as_str(), epsilon.as_str()) {
+            let sigma = try!(::lumol::units::from_str(sigma));
+            let epsilon = try!(::lumol::units::from_str(epsilon));
+            Ok(WCA{sigma: sigma, epsilon: epsilon})
+        } else {
+            Err(Error::from(
+                "'sigma' and 'epsilon' must be strings in WCA potential"
+            ))
+        }
+    }
+}
+
+impl FromToml for Quartic {
+    fn from_toml(table: &Table) -> Result<Quartic> {
+        let k2 = try_extract_parameter!(table, "k2", "quartic potential");
+        let k3 = try_extract_parameter!(table, "k3", "quartic potential");
+        let k4 = try_extract_parameter!(table, "k4", "quartic potential");
+        let x0 = try_extract_parameter!(table, "x0", "quartic potential");
+
+        if let (Some(k2), Some(k3), Some(k4), Some(x0)) = (k2.as_str(), k3.as_str(), k4.as_str(), x0.as_str()) {
+            let k2 = try!(::lumol::units::from_str(k2));
+            let k3 = try!(::lumol::units::from_str(k3));
+            let k4 = try!(::lumol::units::from_str(k4));
+            let x0 = try!(::lumol::units::from_str(x0));
+            Ok(Quartic{k2: k2, k3: k3, k4: k4, x0: x0})
+        } else {
+            Err(Error::from(
+                "'k2', 'k3', 'k4' and 'x0' must be strings in quartic potential"
+            ))
+        }
+    }
+}
+
+impl FromToml for HardWall {
+    fn from_toml(table: &Table) -> Result<HardWall> {
+        let sigma = try_extract_parameter!(table, "sigma", "hard wall potential");
+        if let Some(sigma) = sigma.as_str() {
+            let sigma = try!(::lumol::units::from_str(sigma));
+            Ok(HardWall{sigma: sigma})
+        } else {
+            Err(Error::from("'sigma' must be a string in hard wall potential"))
+        }
+    }
+}
+
+impl FromToml for LennardJones93 {
+    fn from_toml(table: &Table) -> Result<LennardJones93> {
+        let sigma = try_extract_parameter!(table, "sigma", "Lennard-Jones 9-3 potential");
+        let epsilon = try_extract_parameter!(table, "epsilon", "Lennard-Jones 9-3 potential");
+
+        if let (Some(sigma), Some(epsilon)) = (sigma.as_str(), epsilon.as_str()) {
+            let sigma = try!(::lumol::units::from_str(sigma));
+            let epsilon = try!(::lumol::units::from_str(epsilon));
+            Ok(LennardJones93{sigma: sigma, epsilon: epsilon})
+        } else {
+            Err(Error::from(
+                "'sigma' and 'epsilon' must be strings in Lennard-Jones 9-3 potential"
+            ))
+        }
+    }
+}
+
+impl FromToml for LennardJones1043 {
+    fn from_toml(table: &Table) -> Result<LennardJones1043> {
+        let sigma = try_extract_parameter!(table, "sigma", "Lennard-Jones 10-4-3 potential");
+        let epsilon = try_extract_parameter!(table, "epsilon", "Lennard-Jones 10-4-3 potential");
+
+        if let (Some(sigma), Some(epsilon)) = (sigma.as_str(), epsilon.as_str()) {
+            let sigma = try!(::lumol::units::from_str(sigma));
+            let epsilon = try!(::lumol::units::from_str(epsilon));
+            Ok(LennardJones1043{sigma: sigma, epsilon: epsilon})
+        } else {
+            Err(Error::from(
+                "'sigma' and 'epsilon' must be strings in Lennard-Jones 10-4-3 potential"
+            ))
+        }
+    }
+}
+
+impl FromTomlWithData for TablePotential {
+    /// Path of the interactions input file, used to find the table file
+    type Data = PathBuf;
+
+    fn from_toml(table: &Table, root: PathBuf) -> Result<TablePotential> {
+        let file = try_extract_parameter!(table, "file", "tabulated potential");
+        let file = try!(file.as_str().ok_or(
+            Error::from("'file' must be a string in tabulated potential")
+        ));
+
+        // Use internal units for x by default
+        let x_factor = match table.get("x_unit") {
+            Some(unit) => {
+                let unit = try!(unit.as_str().ok_or(
+                    Error::from("'x_unit' must be a string in tabulated potential")
+                ));
+                try!(::lumol::units::from(1.0, unit))
+            }
+            None => 1.0,
+        };
+        let energy_unit = match table.get("energy_unit") {
+            Some(unit) => try!(unit.as_str().ok_or(
+                Error::from("'energy_unit' must be a string in tabulated potential")
+            )),
+            None => "kJ/mol",
+        };
+        let energy_factor = try!(::lumol::units::from(1.0, energy_unit));
+
+        let path = get_input_path(&root, file);
+        let mut file = try_io!(File::open(&path), path);
+        let mut content = String::new();
+        let _ = try_io!(file.read_to_string(&mut content), path);
+
+        let mut x = Vec::new();
+        let mut energy = Vec::new();
+        let mut force = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut values = Vec::new();
+            for value in line.split_whitespace() {
+                values.push(try!(value.parse::<f64>().map_err(|_| Error::from(format!(
+                    "invalid number '{}' at line {} in tabulated potential file", value, i + 1
+                )))));
+            }
+            if values.len() != 3 {
+                return Err(Error::from(format!(
+                    "expected 3 values at line {} in tabulated potential file, got {}", i + 1, values.len()
+                )));
+            }
+
+            if let Some(&previous) = x.last() {
+                if values[0] * x_factor <= previous {
+                    return Err(Error::from(format!(
+                        "x values must be strictly increasing in tabulated potential file, at line {}", i + 1
+                    )));
+                }
+            }
+
+            x.push(values[0] * x_factor);
+            energy.push(values[1] * energy_factor);
+            force.push(values[2] * energy_factor / x_factor);
+        }
+
+        if x.len() < 2 {
+            return Err(Error::from("tabulated potential file must contain at least two points"));
+        }
+
+        Ok(TablePotential::new(x, energy, force))
+    }
+}
+
 /******************************************************************************/
 
 impl FromTomlWithData for TableComputation {
@@ -204,8 +612,77 @@ impl FromToml for Wolf {
     }
 }
 
+impl FromToml for ReactionField {
+    fn from_toml(table: &Table) -> Result<ReactionField> {
+        let cutoff = try_extract_parameter!(table, "cutoff", "reaction field");
+        let dielectric = try_extract_parameter!(table, "dielectric", "reaction field");
+
+        let cutoff = try!(cutoff.as_str().ok_or(
+            Error::from("'cutoff' parameter must be a string in reaction field")
+        ));
+        let cutoff = try!(::lumol::units::from_str(cutoff));
+
+        let dielectric = match *dielectric {
+            Value::Integer(dielectric) => dielectric as f64,
+            Value::Float(dielectric) => dielectric,
+            Value::String(ref dielectric) if dielectric == "infinity" => ::std::f64::INFINITY,
+            _ => return Err(Error::from(
+                "'dielectric' parameter must be a number or \"infinity\" in reaction field"
+            ))
+        };
+
+        if dielectric < 1.0 {
+            return Err(Error::from("'dielectric' parameter must be bigger than 1 in reaction field"));
+        }
+
+        Ok(ReactionField::new(cutoff, dielectric))
+    }
+}
+
+impl FromToml for DampedShiftedForce {
+    fn from_toml(table: &Table) -> Result<DampedShiftedForce> {
+        let cutoff = try_extract_parameter!(table, "cutoff", "damped shifted force potential");
+        let alpha = try_extract_parameter!(table, "alpha", "damped shifted force potential");
+
+        if let (Some(cutoff), Some(alpha)) = (cutoff.as_str(), alpha.as_str()) {
+            let cutoff = try!(::lumol::units::from_str(cutoff));
+            let alpha = try!(::lumol::units::from_str(alpha));
+            if alpha < 0.0 {
+                Err(Error::from("'alpha' can not be negative in damped shifted force potential"))
+            } else {
+                Ok(DampedShiftedForce::new(cutoff, alpha))
+            }
+        } else {
+            Err(Error::from("'cutoff' and 'alpha' must be strings in damped shifted force potential"))
+        }
+    }
+}
+
 impl FromToml for Ewald {
     fn from_toml(table: &Table) -> Result<Ewald> {
+        if table.contains_key("accuracy") {
+            if table.contains_key("kmax") {
+                return Err(Error::from("Only one of 'kmax' and 'accuracy' can be given in Ewald potential"));
+            }
+
+            let cutoff = try_extract_parameter!(table, "cutoff", "ewald potential");
+            let accuracy = &table["accuracy"];
+            let accuracy = match *accuracy {
+                Value::Float(accuracy) => accuracy,
+                Value::Integer(accuracy) => accuracy as f64,
+                _ => return Err(Error::from("'cutoff' must be a string and 'accuracy' a number in Ewald potential")),
+            };
+
+            let cutoff = try!(cutoff.as_str().ok_or(
+                Error::from("'cutoff' must be a string and 'accuracy' a number in Ewald potential")
+            ));
+            let cutoff = try!(::lumol::units::from_str(cutoff));
+            if accuracy <= 0.0 {
+                return Err(Error::from("'accuracy' must be positive in Ewald potential"));
+            }
+            return Ok(Ewald::with_accuracy(cutoff, accuracy));
+        }
+
         let cutoff = try_extract_parameter!(table, "cutoff", "ewald potential");
         let kmax = try_extract_parameter!(table, "kmax", "ewald potential");
 
@@ -221,3 +698,50 @@ impl FromToml for Ewald {
         }
     }
 }
+
+impl FromToml for SPME {
+    fn from_toml(table: &Table) -> Result<SPME> {
+        let cutoff = try_extract_parameter!(table, "cutoff", "SPME potential");
+        let cutoff = try!(cutoff.as_str().ok_or(
+            Error::from("'cutoff' must be a string in SPME potential")
+        ));
+        let cutoff = try!(::lumol::units::from_str(cutoff));
+
+        let grid = try_extract_parameter!(table, "grid", "SPME potential");
+        let grid = match *grid {
+            Value::Integer(size) => vec![size; 3],
+            Value::Array(ref sizes) => {
+                let sizes = sizes.iter().map(|size| size.as_integer()).collect::<Option<Vec<_>>>();
+                match sizes {
+                    Some(ref sizes) if sizes.len() == 3 => sizes.clone(),
+                    _ => return Err(Error::from(
+                        "'grid' must be an integer or an array of 3 integers in SPME potential"
+                    ))
+                }
+            }
+            _ => return Err(Error::from(
+                "'grid' must be an integer or an array of 3 integers in SPME potential"
+            ))
+        };
+
+        let order = match table.get("order") {
+            Some(order) => try!(order.as_integer().ok_or(
+                Error::from("'order' must be an integer in SPME potential")
+            )),
+            None => 4,
+        };
+
+        if order < 3 {
+            return Err(Error::from("'order' must be at least 3 in SPME potential"));
+        }
+
+        if grid.iter().any(|&size| size < order) {
+            return Err(Error::from(
+                "'grid' must be at least as big as 'order' in SPME potential"
+            ));
+        }
+
+        let grid = [grid[0] as usize, grid[1] as usize, grid[2] as usize];
+        Ok(SPME::new(cutoff, grid, order as usize))
+    }
+}
@@ -0,0 +1,80 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use toml::value::{Value, Table};
+
+use lumol::sys::System;
+use lumol::energy::{Wall, CellFace, WallPotential};
+use lumol::energy::{HardWall, LennardJones93, LennardJones1043};
+
+use error::{Error, Result};
+use extract;
+use FromToml;
+use super::InteractionsInput;
+
+impl InteractionsInput {
+    /// Read the "walls" section from the potential configuration.
+    pub(crate) fn read_walls(&self, system: &mut System) -> Result<()> {
+        let walls = match self.config.get("walls") {
+            Some(walls) => walls,
+            None => return Ok(())
+        };
+
+        let walls = try!(walls.as_array().ok_or(
+            Error::from("The 'walls' section must be an array")
+        ));
+
+        for wall in walls {
+            let wall = try!(wall.as_table().ok_or(
+                Error::from("Wall entry must be a table")
+            ));
+
+            let face = try!(extract::str("face", wall, "wall"));
+            let face = match face {
+                "x-min" => CellFace::XMin,
+                "x-max" => CellFace::XMax,
+                "y-min" => CellFace::YMin,
+                "y-max" => CellFace::YMax,
+                "z-min" => CellFace::ZMin,
+                "z-max" => CellFace::ZMax,
+                other => return Err(Error::from(format!("Unknown face '{}' in wall", other))),
+            };
+            let atoms = try!(extract::str("atoms", wall, "wall"));
+            let potential = try!(read_wall_potential(wall));
+
+            system.add_global_potential(Box::new(Wall::new(face, atoms, potential)));
+        }
+        Ok(())
+    }
+}
+
+fn read_wall_potential(wall: &Table) -> Result<Box<WallPotential>> {
+    let potentials = wall.keys().cloned()
+                         .filter(|key| key != "face" && key != "atoms")
+                         .collect::<Vec<_>>();
+
+    if potentials.is_empty() {
+        return Err(Error::from("Missing potential type in wall"));
+    }
+
+    if potentials.len() > 1 {
+        return Err(Error::from(
+            format!("Got more than one potential type in wall: {}", potentials.join(" and "))
+        ));
+    }
+
+    let key = &*potentials[0];
+    if let Value::Table(ref table) = wall[key] {
+        match key {
+            "hard" => Ok(Box::new(try!(HardWall::from_toml(table)))),
+            "lj-9-3" => Ok(Box::new(try!(LennardJones93::from_toml(table)))),
+            "lj-10-4-3" => Ok(Box::new(try!(LennardJones1043::from_toml(table)))),
+            other => Err(
+                Error::from(format!("Unknown potential type '{}'", other))
+            ),
+        }
+    } else {
+        Err(
+            Error::from(format!("'{}' potential must be a table", key))
+        )
+    }
+}
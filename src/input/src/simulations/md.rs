@@ -29,6 +29,12 @@ impl FromToml for MolecularDynamics {
                 "AnisoBerendsenBarostat" => Box::new(try!(
                     AnisoBerendsenBarostat::from_toml(integrator, timestep)
                 )),
+                "MTKBarostat" => Box::new(try!(
+                    MTKBarostat::from_toml(integrator, timestep)
+                )),
+                "ParrinelloRahmanBarostat" => Box::new(try!(
+                    ParrinelloRahmanBarostat::from_toml(integrator, timestep)
+                )),
                 "Verlet" => Box::new(try!(
                     Verlet::from_toml(integrator, timestep)
                 )),
@@ -38,6 +44,9 @@ impl FromToml for MolecularDynamics {
                 "LeapFrog" => Box::new(try!(
                     LeapFrog::from_toml(integrator, timestep)
                 )),
+                "Langevin" => Box::new(try!(
+                    Langevin::from_toml(integrator, timestep)
+                )),
                 other => return Err(Error::from(
                     format!("Unknown integrator '{}'", other)
                 ))
@@ -60,6 +69,23 @@ impl FromToml for MolecularDynamics {
                 "Rescale" => Box::new(try!(
                     RescaleThermostat::from_toml(thermostat)
                 )),
+                "NoseHoover" => {
+                    let mut thermostat = try!(NoseHooverThermostat::from_toml(thermostat));
+                    if removes_translation(config) {
+                        thermostat.set_removed_dof(3);
+                    }
+                    Box::new(thermostat)
+                }
+                "Bussi" => {
+                    let mut thermostat = try!(BussiThermostat::from_toml(thermostat));
+                    if removes_translation(config) {
+                        thermostat.set_removed_dof(3);
+                    }
+                    Box::new(thermostat)
+                }
+                "Andersen" => Box::new(try!(
+                    AndersenThermostat::from_toml(thermostat)
+                )),
                 other => return Err(Error::from(
                     format!("Unknown thermostat type '{}'", other)
                 ))
@@ -84,6 +110,9 @@ impl FromToml for MolecularDynamics {
                     "RemoveRotation" => Box::new(try!(
                         Alternator::<RemoveRotation>::from_toml(control)
                     )),
+                    "MonteCarloBarostat" => Box::new(try!(
+                        Alternator::<MonteCarloBarostat>::from_toml(control)
+                    )),
                     other => return Err(Error::from(
                         format!("Unknown control '{}'", other)
                     ))
@@ -96,6 +125,20 @@ impl FromToml for MolecularDynamics {
     }
 }
 
+/// Check if the `RemoveTranslation` control is used in the molecular dynamics
+/// `config`.
+fn removes_translation(config: &Table) -> bool {
+    let controls = match config.get("controls").and_then(|c| c.as_array()) {
+        Some(controls) => controls,
+        None => return false,
+    };
+    controls.iter()
+            .filter_map(|control| control.as_table())
+            .any(|control| {
+                control.get("type").and_then(|t| t.as_str()) == Some("RemoveTranslation")
+            })
+}
+
 /******************************************************************************/
 
 impl FromTomlWithData for Verlet {
@@ -119,6 +162,48 @@ impl FromTomlWithData for LeapFrog {
     }
 }
 
+impl FromTomlWithData for Langevin {
+    type Data = f64;
+    fn from_toml(config: &Table, timestep: f64) -> Result<Langevin> {
+        let temperature = try!(extract::str("temperature", config, "Langevin integrator"));
+        let temperature = try!(units::from_str(temperature));
+        if temperature < 0.0 {
+            return Err(Error::from("'temperature' must be positive in Langevin integrator"));
+        }
+
+        let friction = try!(read_friction("friction", config));
+        let mut langevin = Langevin::new(timestep, temperature, friction);
+
+        if let Some(frictions) = config.get("frictions") {
+            let frictions = try!(frictions.as_table().ok_or(
+                Error::from("'frictions' must be a table in Langevin integrator")
+            ));
+            for name in frictions.keys() {
+                let friction = try!(read_friction(name, frictions));
+                langevin.set_friction(name.clone(), friction);
+            }
+        }
+
+        if config.get("seed").is_some() {
+            let seed = try!(extract::uint("seed", config, "Langevin integrator"));
+            langevin.seed(seed);
+        }
+
+        Ok(langevin)
+    }
+}
+
+/// Read a friction coefficient given as a string with units in the `key` of
+/// the `config` table.
+fn read_friction(key: &str, config: &Table) -> Result<f64> {
+    let friction = try!(extract::str(key, config, "Langevin integrator"));
+    let friction = try!(units::from_str(friction));
+    if friction < 0.0 {
+        return Err(Error::from(format!("'{}' must be positive in Langevin integrator", key)));
+    }
+    Ok(friction)
+}
+
 impl FromTomlWithData for BerendsenBarostat {
     type Data = f64;
     fn from_toml(config: &Table, timestep: f64) -> Result<BerendsenBarostat> {
@@ -139,6 +224,51 @@ impl FromTomlWithData for AnisoBerendsenBarostat {
     }
 }
 
+impl FromTomlWithData for MTKBarostat {
+    type Data = f64;
+    fn from_toml(config: &Table, timestep: f64) -> Result<MTKBarostat> {
+        let (pressure, temperature, tau, thermostat_tau) = try!(
+            read_extended_barostat(config, "MTK barostat")
+        );
+        Ok(MTKBarostat::new(timestep, pressure, temperature, tau, thermostat_tau))
+    }
+}
+
+impl FromTomlWithData for ParrinelloRahmanBarostat {
+    type Data = f64;
+    fn from_toml(config: &Table, timestep: f64) -> Result<ParrinelloRahmanBarostat> {
+        let (pressure, temperature, tau, thermostat_tau) = try!(
+            read_extended_barostat(config, "Parrinello-Rahman barostat")
+        );
+        Ok(ParrinelloRahmanBarostat::new(timestep, pressure, temperature, tau, thermostat_tau))
+    }
+}
+
+/// Read the pressure, temperature, barostat time constant and thermostat time
+/// constant of an extended system barostat in the `config` table.
+fn read_extended_barostat(config: &Table, context: &str) -> Result<(f64, f64, f64, f64)> {
+    let pressure = try!(extract::str("pressure", config, context));
+    let pressure = try!(units::from_str(pressure));
+
+    let temperature = try!(extract::str("temperature", config, context));
+    let temperature = try!(units::from_str(temperature));
+    if temperature <= 0.0 {
+        return Err(Error::from(format!("'temperature' must be positive in {}", context)));
+    }
+
+    let tau = try!(extract::number("timestep", config, context));
+    if tau <= 0.0 {
+        return Err(Error::from(format!("'timestep' must be positive in {}", context)));
+    }
+
+    let thermostat_tau = try!(extract::number("thermostat_timestep", config, context));
+    if thermostat_tau <= 0.0 {
+        return Err(Error::from(format!("'thermostat_timestep' must be positive in {}", context)));
+    }
+
+    Ok((pressure, temperature, tau, thermostat_tau))
+}
+
 /******************************************************************************/
 
 impl FromToml for BerendsenThermostat {
@@ -150,6 +280,84 @@ impl FromToml for BerendsenThermostat {
     }
 }
 
+impl FromToml for NoseHooverThermostat {
+    fn from_toml(config: &Table) -> Result<NoseHooverThermostat> {
+        let temperature = try!(extract::str("temperature", config, "Nose-Hoover thermostat"));
+        let temperature = try!(units::from_str(temperature));
+        if temperature <= 0.0 {
+            return Err(Error::from("'temperature' must be positive in Nose-Hoover thermostat"));
+        }
+        let tau = try!(extract::number("timestep", config, "Nose-Hoover thermostat"));
+        if tau <= 0.0 {
+            return Err(Error::from("'timestep' must be positive in Nose-Hoover thermostat"));
+        }
+
+        if config.get("chain").is_some() {
+            let length = try!(extract::uint("chain", config, "Nose-Hoover thermostat"));
+            if length == 0 {
+                return Err(Error::from("'chain' must be at least 1 in Nose-Hoover thermostat"));
+            }
+            Ok(NoseHooverThermostat::with_chain(temperature, tau, length as usize))
+        } else {
+            Ok(NoseHooverThermostat::new(temperature, tau))
+        }
+    }
+}
+
+impl FromToml for BussiThermostat {
+    fn from_toml(config: &Table) -> Result<BussiThermostat> {
+        let temperature = try!(extract::str("temperature", config, "Bussi thermostat"));
+        let temperature = try!(units::from_str(temperature));
+        if temperature < 0.0 {
+            return Err(Error::from("'temperature' must be positive in Bussi thermostat"));
+        }
+        let tau = try!(extract::number("timestep", config, "Bussi thermostat"));
+        if tau < 0.0 {
+            return Err(Error::from("'timestep' must be positive in Bussi thermostat"));
+        }
+
+        let mut thermostat = BussiThermostat::new(temperature, tau);
+        if config.get("seed").is_some() {
+            let seed = try!(extract::uint("seed", config, "Bussi thermostat"));
+            thermostat.seed(seed);
+        }
+        Ok(thermostat)
+    }
+}
+
+impl FromToml for AndersenThermostat {
+    fn from_toml(config: &Table) -> Result<AndersenThermostat> {
+        let temperature = try!(extract::str("temperature", config, "Andersen thermostat"));
+        let temperature = try!(units::from_str(temperature));
+        if temperature < 0.0 {
+            return Err(Error::from("'temperature' must be positive in Andersen thermostat"));
+        }
+        let tau = try!(extract::number("timestep", config, "Andersen thermostat"));
+        if tau < 1.0 {
+            return Err(Error::from("'timestep' must be larger than 1 in Andersen thermostat"));
+        }
+
+        let massive = match config.get("massive") {
+            Some(massive) => try!(massive.as_bool().ok_or(
+                Error::from("'massive' must be a boolean in Andersen thermostat")
+            )),
+            None => false,
+        };
+
+        let mut thermostat = if massive {
+            AndersenThermostat::massive(temperature, tau)
+        } else {
+            AndersenThermostat::new(temperature, tau)
+        };
+
+        if config.get("seed").is_some() {
+            let seed = try!(extract::uint("seed", config, "Andersen thermostat"));
+            thermostat.seed(seed);
+        }
+        Ok(thermostat)
+    }
+}
+
 impl FromToml for RescaleThermostat {
     fn from_toml(config: &Table) -> Result<RescaleThermostat> {
         let temperature = try!(extract::str("temperature", config, "Berendsen thermostat"));
@@ -189,3 +397,57 @@ impl FromToml for Alternator<RemoveRotation> {
         Ok(Alternator::new(every, RemoveRotation::new()))
     }
 }
+
+impl FromToml for Alternator<MonteCarloBarostat> {
+    fn from_toml(config: &Table) -> Result<Alternator<MonteCarloBarostat>> {
+        let every = if config.contains_key("every") {
+            try!(extract::uint("every", config, "Monte Carlo barostat"))
+        } else {
+           1
+        };
+
+        let pressure = try!(extract::str("pressure", config, "Monte Carlo barostat"));
+        let pressure = try!(units::from_str(pressure));
+
+        let temperature = try!(extract::str("temperature", config, "Monte Carlo barostat"));
+        let temperature = try!(units::from_str(temperature));
+        if temperature <= 0.0 {
+            return Err(Error::from("'temperature' must be positive in Monte Carlo barostat"));
+        }
+
+        let delta = try!(extract::str("delta", config, "Monte Carlo barostat"));
+        let delta = try!(units::from_str(delta));
+        if delta <= 0.0 {
+            return Err(Error::from("'delta' must be positive in Monte Carlo barostat"));
+        }
+
+        let mut barostat = MonteCarloBarostat::new(pressure, temperature, delta);
+
+        if config.get("target_acceptance").is_some() {
+            let acceptance = try!(extract::number("target_acceptance", config, "Monte Carlo barostat"));
+            if acceptance <= 0.0 || acceptance >= 1.0 {
+                return Err(Error::from(
+                    "'target_acceptance' has to be between 0.0 and 1.0 in Monte Carlo barostat"
+                ));
+            }
+            if config.get("update_frequency").is_none() {
+                return Err(Error::from(
+                    "No 'update_frequency' found. Please specify \
+                    'update_frequency' in combination with 'target_acceptance'"
+                ));
+            }
+            let frequency = try!(extract::uint("update_frequency", config, "Monte Carlo barostat"));
+            if frequency == 0 {
+                return Err(Error::from("'update_frequency' must be positive in Monte Carlo barostat"));
+            }
+            barostat.set_target_acceptance(acceptance, frequency);
+        }
+
+        if config.get("seed").is_some() {
+            let seed = try!(extract::uint("seed", config, "Monte Carlo barostat"));
+            barostat.seed(seed);
+        }
+
+        Ok(Alternator::new(every, barostat))
+    }
+}
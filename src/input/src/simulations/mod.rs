@@ -76,7 +76,7 @@ impl Input {
     }
 }
 
-fn get_input_path<P1: AsRef<Path>, P2: AsRef<Path>>(root: P1, path: P2) -> PathBuf {
+pub(crate) fn get_input_path<P1: AsRef<Path>, P2: AsRef<Path>>(root: P1, path: P2) -> PathBuf {
     let path = PathBuf::from(path.as_ref());
     if path.is_absolute() {
         path
@@ -5,10 +5,13 @@ use std::path::PathBuf;
 
 use lumol::out::Output;
 use lumol::out::{TrajectoryOutput, CellOutput, EnergyOutput, PropertiesOutput};
+use lumol::out::{DetailedEnergyOutput, GroupEnergyOutput, ConservedEnergyOutput};
+use lumol::sys::{Selection, read_molecule, molecule_type};
 
 use error::{Error, Result};
-use FromToml;
+use {FromToml, FromTomlWithData};
 use extract;
+use simulations::get_input_path;
 use super::Input;
 
 impl Input {
@@ -38,6 +41,9 @@ impl Input {
                 let output: Box<Output> = match try!(extract::typ(output, "output")) {
                     "Trajectory" | "trajectory" => Box::new(try!(TrajectoryOutput::from_toml(output))),
                     "Energy" | "energy" => Box::new(try!(EnergyOutput::from_toml(output))),
+                    "DetailedEnergy" | "detailed-energy" => Box::new(try!(DetailedEnergyOutput::from_toml(output))),
+                    "ConservedEnergy" | "conserved-energy" => Box::new(try!(ConservedEnergyOutput::from_toml(output))),
+                    "GroupEnergy" | "group-energy" => Box::new(try!(GroupEnergyOutput::from_toml(output, self.path.clone()))),
                     "Cell" | "cell" => Box::new(try!(CellOutput::from_toml(output))),
                     "Properties" | "properties" => Box::new(try!(PropertiesOutput::from_toml(output))),
                     other => {
@@ -90,6 +96,81 @@ impl FromToml for EnergyOutput {
     }
 }
 
+impl FromToml for DetailedEnergyOutput {
+    fn from_toml(config: &Table) -> Result<DetailedEnergyOutput> {
+        let path = try!(get_file(config));
+        let output = try_io!(DetailedEnergyOutput::new(path), PathBuf::from(path));
+        Ok(output)
+    }
+}
+
+impl FromToml for ConservedEnergyOutput {
+    fn from_toml(config: &Table) -> Result<ConservedEnergyOutput> {
+        let path = try!(get_file(config));
+        let output = try_io!(ConservedEnergyOutput::new(path), PathBuf::from(path));
+        Ok(output)
+    }
+}
+
+impl FromTomlWithData for GroupEnergyOutput {
+    type Data = PathBuf;
+    fn from_toml(config: &Table, root: PathBuf) -> Result<GroupEnergyOutput> {
+        let path = try!(get_file(config));
+        let first = try!(extract::table("first", config, "group energy output"));
+        let first = try!(read_selection(first, &root));
+        let second = try!(extract::table("second", config, "group energy output"));
+        let second = try!(read_selection(second, &root));
+        let output = try_io!(GroupEnergyOutput::new(path, first, second), PathBuf::from(path));
+        Ok(output)
+    }
+}
+
+/// Read a selection of particles from the `config` table, containing one of
+/// the `atoms`, `molecule` or `range` keys.
+fn read_selection(config: &Table, root: &PathBuf) -> Result<Selection> {
+    let keys = config.keys().cloned().collect::<Vec<_>>();
+    if keys.is_empty() {
+        return Err(Error::from("Missing selection in group energy output"));
+    }
+
+    if keys.len() > 1 {
+        return Err(Error::from(
+            format!("Got more than one selection in group energy output: {}", keys.join(" and "))
+        ));
+    }
+
+    match &*keys[0] {
+        "atoms" => {
+            let name = try!(extract::str("atoms", config, "group energy output"));
+            Ok(Selection::Name(String::from(name)))
+        }
+        "molecule" => {
+            let molfile = try!(extract::str("molecule", config, "group energy output"));
+            let molfile = get_input_path(root, molfile);
+            let (molecule, atoms) = try!(read_molecule(molfile));
+            Ok(Selection::MoleculeType(molecule_type(&molecule, &atoms)))
+        }
+        "range" => {
+            let error = || Error::from(
+                "'range' must be an array of two positive integers in group energy output"
+            );
+            let range = try!(extract::slice("range", config, "group energy output"));
+            if range.len() != 2 {
+                return Err(error());
+            }
+            let start = try!(range[0].as_integer().ok_or_else(&error));
+            let end = try!(range[1].as_integer().ok_or_else(&error));
+            if start < 0 || end < start {
+                return Err(error());
+            }
+            Ok(Selection::Range(start as usize..end as usize))
+        }
+        other => Err(Error::from(
+            format!("Unknown selection '{}' in group energy output", other)
+        )),
+    }
+}
+
 impl FromToml for PropertiesOutput {
     fn from_toml(config: &Table) -> Result<PropertiesOutput> {
         let path = try!(get_file(config));
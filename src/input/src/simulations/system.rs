@@ -44,8 +44,12 @@ impl Input {
             try!(trajectory.read())
         };
 
+        try!(self.read_slab(&mut system));
+        try!(self.read_impropers(&mut system));
+
         try!(self.read_potentials(&mut system));
         try!(self.init_velocities(&mut system));
+        try!(self.read_neighbor_list(&mut system));
 
         if !with_cell && system.cell.is_infinite() {
             warn!(
@@ -116,6 +120,73 @@ impl Input {
         }
     }
 
+    fn read_slab(&self, system: &mut System) -> Result<()> {
+        let config = try!(self.system_table());
+        if let Some(slab) = config.get("slab") {
+            let slab = try!(slab.as_bool().ok_or(
+                Error::from("'slab' should be a boolean value in system")
+            ));
+            if slab {
+                if system.cell.shape() != CellShape::Orthorhombic {
+                    return Err(Error::from(
+                        "'slab' can only be used with orthorhombic unit cells in system"
+                    ));
+                }
+                let (a, b, c) = (system.cell.a(), system.cell.b(), system.cell.c());
+                system.cell = UnitCell::slab(a, b, c);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_impropers(&self, system: &mut System) -> Result<()> {
+        let config = try!(self.system_table());
+        let impropers = match config.get("impropers") {
+            Some(impropers) => impropers,
+            None => return Ok(()),
+        };
+
+        let error = "'impropers' must be an array of arrays of four atomic indexes in system";
+        let impropers = try!(impropers.as_array().ok_or(Error::from(error)));
+        for improper in impropers {
+            let improper = try!(improper.as_array().ok_or(Error::from(error)));
+            if improper.len() != 4 {
+                return Err(Error::from(error));
+            }
+
+            let mut atoms = [0; 4];
+            for (atom, index) in atoms.iter_mut().zip(improper) {
+                *atom = match index.as_integer() {
+                    Some(index) if index >= 0 => index as usize,
+                    _ => return Err(Error::from(error)),
+                };
+                if *atom >= system.size() {
+                    return Err(Error::from(format!(
+                        "atom index {} is out of bounds in improper, the system contains {} atoms",
+                        atom, system.size()
+                    )));
+                }
+            }
+
+            for i in 0..4 {
+                for j in (i + 1)..4 {
+                    if atoms[i] == atoms[j] {
+                        return Err(Error::from("the atoms in an improper must be different"));
+                    }
+                }
+            }
+
+            let molecule = system.molid(atoms[0]);
+            if atoms.iter().any(|&atom| system.molid(atom) != molecule) {
+                return Err(Error::from("all the atoms in an improper must be in the same molecule"));
+            }
+
+            system.add_improper(atoms[0], atoms[1], atoms[2], atoms[3]);
+        }
+
+        Ok(())
+    }
+
     fn init_velocities(&self, system: &mut System) -> Result<()> {
         let config = try!(self.system_table());
 
@@ -137,6 +208,31 @@ impl Input {
         Ok(())
     }
 
+    fn read_neighbor_list(&self, system: &mut System) -> Result<()> {
+        let config = try!(self.system_table());
+
+        if let Some(neighbor_list) = config.get("neighbor_list") {
+            if let Some(enabled) = neighbor_list.as_bool() {
+                if !enabled {
+                    system.neighbor_list_mut().disable();
+                }
+            } else if let Some(neighbor_list) = neighbor_list.as_table() {
+                if neighbor_list.get("skin").is_some() {
+                    let skin = try!(extract::str("skin", neighbor_list, "neighbor list"));
+                    let skin = try!(units::from_str(skin));
+                    if skin < 0.0 {
+                        return Err(Error::from("'skin' must be positive in neighbor list"));
+                    }
+                    system.neighbor_list_mut().set_skin(skin);
+                }
+            } else {
+                return Err(Error::from("'neighbor_list' must be a boolean or a table in system"));
+            }
+        }
+
+        Ok(())
+    }
+
     fn read_potentials(&self, system: &mut System) -> Result<()> {
         let config = try!(self.system_table());
         if let Some(potentials) = config.get("potentials") {
@@ -145,7 +241,7 @@ impl Input {
                 let input = try!(InteractionsInput::new(path));
                 try!(input.read(system));
             } else if let Some(potentials) = potentials.as_table() {
-                let input = try!(InteractionsInput::from_toml(potentials.clone()));
+                let input = try!(InteractionsInput::from_toml(potentials.clone(), self.path.clone()));
                 try!(input.read(system));
             } else {
                 return Err(Error::from("'potentials' must be a string or a table in system"))
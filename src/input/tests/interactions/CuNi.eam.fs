@@ -0,0 +1,157 @@
+Test potential
+for lumol
+input tests
+2 Cu Ni
+200 1.000000e-02 50 1.000000e-01 4.900000e+00
+29 63.550 3.615 fcc
+-0.00000000e+00 -1.00000000e-01 -1.41421356e-01 -1.73205081e-01 -2.00000000e-01
+-2.23606798e-01 -2.44948974e-01 -2.64575131e-01 -2.82842712e-01 -3.00000000e-01
+-3.16227766e-01 -3.31662479e-01 -3.46410162e-01 -3.60555128e-01 -3.74165739e-01
+-3.87298335e-01 -4.00000000e-01 -4.12310563e-01 -4.24264069e-01 -4.35889894e-01
+-4.47213595e-01 -4.58257569e-01 -4.69041576e-01 -4.79583152e-01 -4.89897949e-01
+-5.00000000e-01 -5.09901951e-01 -5.19615242e-01 -5.29150262e-01 -5.38516481e-01
+-5.47722558e-01 -5.56776436e-01 -5.65685425e-01 -5.74456265e-01 -5.83095189e-01
+-5.91607978e-01 -6.00000000e-01 -6.08276253e-01 -6.16441400e-01 -6.24499800e-01
+-6.32455532e-01 -6.40312424e-01 -6.48074070e-01 -6.55743852e-01 -6.63324958e-01
+-6.70820393e-01 -6.78232998e-01 -6.85565460e-01 -6.92820323e-01 -7.00000000e-01
+-7.07106781e-01 -7.14142843e-01 -7.21110255e-01 -7.28010989e-01 -7.34846923e-01
+-7.41619849e-01 -7.48331477e-01 -7.54983444e-01 -7.61577311e-01 -7.68114575e-01
+-7.74596669e-01 -7.81024968e-01 -7.87400787e-01 -7.93725393e-01 -8.00000000e-01
+-8.06225775e-01 -8.12403840e-01 -8.18535277e-01 -8.24621125e-01 -8.30662386e-01
+-8.36660027e-01 -8.42614977e-01 -8.48528137e-01 -8.54400375e-01 -8.60232527e-01
+-8.66025404e-01 -8.71779789e-01 -8.77496439e-01 -8.83176087e-01 -8.88819442e-01
+-8.94427191e-01 -9.00000000e-01 -9.05538514e-01 -9.11043358e-01 -9.16515139e-01
+-9.21954446e-01 -9.27361850e-01 -9.32737905e-01 -9.38083152e-01 -9.43398113e-01
+-9.48683298e-01 -9.53939201e-01 -9.59166305e-01 -9.64365076e-01 -9.69535971e-01
+-9.74679434e-01 -9.79795897e-01 -9.84885780e-01 -9.89949494e-01 -9.94987437e-01
+-1.00000000e+00 -1.00498756e+00 -1.00995049e+00 -1.01488916e+00 -1.01980390e+00
+-1.02469508e+00 -1.02956301e+00 -1.03440804e+00 -1.03923048e+00 -1.04403065e+00
+-1.04880885e+00 -1.05356538e+00 -1.05830052e+00 -1.06301458e+00 -1.06770783e+00
+-1.07238053e+00 -1.07703296e+00 -1.08166538e+00 -1.08627805e+00 -1.09087121e+00
+-1.09544512e+00 -1.10000000e+00 -1.10453610e+00 -1.10905365e+00 -1.11355287e+00
+-1.11803399e+00 -1.12249722e+00 -1.12694277e+00 -1.13137085e+00 -1.13578167e+00
+-1.14017543e+00 -1.14455231e+00 -1.14891253e+00 -1.15325626e+00 -1.15758369e+00
+-1.16189500e+00 -1.16619038e+00 -1.17046999e+00 -1.17473401e+00 -1.17898261e+00
+-1.18321596e+00 -1.18743421e+00 -1.19163753e+00 -1.19582607e+00 -1.20000000e+00
+-1.20415946e+00 -1.20830460e+00 -1.21243557e+00 -1.21655251e+00 -1.22065556e+00
+-1.22474487e+00 -1.22882057e+00 -1.23288280e+00 -1.23693169e+00 -1.24096736e+00
+-1.24498996e+00 -1.24899960e+00 -1.25299641e+00 -1.25698051e+00 -1.26095202e+00
+-1.26491106e+00 -1.26885775e+00 -1.27279221e+00 -1.27671453e+00 -1.28062485e+00
+-1.28452326e+00 -1.28840987e+00 -1.29228480e+00 -1.29614814e+00 -1.30000000e+00
+-1.30384048e+00 -1.30766968e+00 -1.31148770e+00 -1.31529464e+00 -1.31909060e+00
+-1.32287566e+00 -1.32664992e+00 -1.33041347e+00 -1.33416641e+00 -1.33790882e+00
+-1.34164079e+00 -1.34536240e+00 -1.34907376e+00 -1.35277493e+00 -1.35646600e+00
+-1.36014705e+00 -1.36381817e+00 -1.36747943e+00 -1.37113092e+00 -1.37477271e+00
+-1.37840488e+00 -1.38202750e+00 -1.38564065e+00 -1.38924440e+00 -1.39283883e+00
+-1.39642400e+00 -1.40000000e+00 -1.40356688e+00 -1.40712473e+00 -1.41067360e+00
+2.00000000e+01 1.66640737e+01 1.38604781e+01 1.15077033e+01 9.53625681e+00
+7.88695096e+00 6.50942772e+00 5.36088758e+00 4.40499599e+00 3.61094279e+00
+2.95263404e+00 2.40799806e+00 1.95839005e+00 1.58808174e+00 1.28382426e+00
+1.03447410e+00 8.30673324e-01 6.64576484e-01 5.29617569e-01 4.20311409e-01
+3.32084575e-01 2.61131575e-01 2.04292711e-01 1.58950449e-01 1.22941626e-01
+9.44831660e-02 7.21093144e-02 5.46186923e-02 4.10296966e-02 3.05429978e-02
+2.25100590e-02 1.64067591e-02 1.18113359e-02 8.38597988e-03 5.86150950e-03
+4.02464086e-03 2.70744068e-03 1.77861142e-03 1.13631146e-03 7.02258810e-04
+4.16905655e-04 2.35503978e-04 1.24911094e-04 6.10076652e-05 2.66213437e-05
+9.86643984e-06 2.82472438e-06 5.04872358e-07 2.85517125e-08 0.00000000e+00
+2.10000000e+01 1.74972774e+01 1.45535020e+01 1.20830885e+01 1.00130696e+01
+8.28129851e+00 6.83489910e+00 5.62893196e+00 4.62524578e+00 3.79148992e+00
+3.10026574e+00 2.52839796e+00 2.05630955e+00 1.66748583e+00 1.34801547e+00
+1.08619780e+00 8.72206990e-01 6.97805308e-01 5.56098447e-01 4.41326980e-01
+3.48688804e-01 2.74188154e-01 2.14507347e-01 1.66897971e-01 1.29088708e-01
+9.92073243e-02 7.57147802e-02 5.73496269e-02 4.30811814e-02 3.20701477e-02
+2.36355620e-02 1.72270971e-02 1.24019027e-02 8.80527887e-03 6.15458498e-03
+4.22587290e-03 2.84281271e-03 1.86754199e-03 1.19312703e-03 7.37371750e-04
+4.37750937e-04 2.47279177e-04 1.31156649e-04 6.40580485e-05 2.79524109e-05
+1.03597618e-05 2.96596059e-06 5.30115976e-07 2.99792982e-08 0.00000000e+00
+28 58.690 3.520 fcc
+-0.00000000e+00 -1.20000000e-01 -1.69705627e-01 -2.07846097e-01 -2.40000000e-01
+-2.68328157e-01 -2.93938769e-01 -3.17490157e-01 -3.39411255e-01 -3.60000000e-01
+-3.79473319e-01 -3.97994975e-01 -4.15692194e-01 -4.32666153e-01 -4.48998886e-01
+-4.64758002e-01 -4.80000000e-01 -4.94772675e-01 -5.09116882e-01 -5.23067873e-01
+-5.36656315e-01 -5.49909083e-01 -5.62849891e-01 -5.75499783e-01 -5.87877538e-01
+-6.00000000e-01 -6.11882342e-01 -6.23538291e-01 -6.34980315e-01 -6.46219777e-01
+-6.57267069e-01 -6.68131724e-01 -6.78822510e-01 -6.89347518e-01 -6.99714227e-01
+-7.09929574e-01 -7.20000000e-01 -7.29931504e-01 -7.39729680e-01 -7.49399760e-01
+-7.58946638e-01 -7.68374908e-01 -7.77688884e-01 -7.86892623e-01 -7.95989950e-01
+-8.04984472e-01 -8.13879598e-01 -8.22678552e-01 -8.31384388e-01 -8.40000000e-01
+-8.48528137e-01 -8.56971411e-01 -8.65332306e-01 -8.73613187e-01 -8.81816307e-01
+-8.89943818e-01 -8.97997773e-01 -9.05980132e-01 -9.13892773e-01 -9.21737490e-01
+-9.29516003e-01 -9.37229961e-01 -9.44880945e-01 -9.52470472e-01 -9.60000000e-01
+-9.67470930e-01 -9.74884609e-01 -9.82242333e-01 -9.89545350e-01 -9.96794864e-01
+-1.00399203e+00 -1.01113797e+00 -1.01823376e+00 -1.02528045e+00 -1.03227903e+00
+-1.03923048e+00 -1.04613575e+00 -1.05299573e+00 -1.05981130e+00 -1.06658333e+00
+-1.07331263e+00 -1.08000000e+00 -1.08664622e+00 -1.09325203e+00 -1.09981817e+00
+-1.10634533e+00 -1.11283422e+00 -1.11928549e+00 -1.12569978e+00 -1.13207774e+00
+-1.13841996e+00 -1.14472704e+00 -1.15099957e+00 -1.15723809e+00 -1.16344317e+00
+-1.16961532e+00 -1.17575508e+00 -1.18186294e+00 -1.18793939e+00 -1.19398492e+00
+-1.20000000e+00 -1.20598507e+00 -1.21194059e+00 -1.21786699e+00 -1.22376468e+00
+-1.22963409e+00 -1.23547562e+00 -1.24128965e+00 -1.24707658e+00 -1.25283678e+00
+-1.25857062e+00 -1.26427845e+00 -1.26996063e+00 -1.27561750e+00 -1.28124939e+00
+-1.28685664e+00 -1.29243955e+00 -1.29799846e+00 -1.30353366e+00 -1.30904545e+00
+-1.31453414e+00 -1.32000000e+00 -1.32544332e+00 -1.33086438e+00 -1.33626345e+00
+-1.34164079e+00 -1.34699666e+00 -1.35233132e+00 -1.35764502e+00 -1.36293800e+00
+-1.36821051e+00 -1.37346278e+00 -1.37869504e+00 -1.38390751e+00 -1.38910043e+00
+-1.39427400e+00 -1.39942845e+00 -1.40456399e+00 -1.40968081e+00 -1.41477913e+00
+-1.41985915e+00 -1.42492105e+00 -1.42996503e+00 -1.43499129e+00 -1.44000000e+00
+-1.44499135e+00 -1.44996552e+00 -1.45492268e+00 -1.45986301e+00 -1.46478667e+00
+-1.46969385e+00 -1.47458469e+00 -1.47945936e+00 -1.48431803e+00 -1.48916084e+00
+-1.49398795e+00 -1.49879952e+00 -1.50359569e+00 -1.50837661e+00 -1.51314243e+00
+-1.51789328e+00 -1.52262930e+00 -1.52735065e+00 -1.53205744e+00 -1.53674982e+00
+-1.54142791e+00 -1.54609185e+00 -1.55074176e+00 -1.55537777e+00 -1.56000000e+00
+-1.56460858e+00 -1.56920362e+00 -1.57378525e+00 -1.57835357e+00 -1.58290871e+00
+-1.58745079e+00 -1.59197990e+00 -1.59649616e+00 -1.60099969e+00 -1.60549058e+00
+-1.60996894e+00 -1.61443489e+00 -1.61888851e+00 -1.62332991e+00 -1.62775920e+00
+-1.63217646e+00 -1.63658180e+00 -1.64097532e+00 -1.64535710e+00 -1.64972725e+00
+-1.65408585e+00 -1.65843300e+00 -1.66276878e+00 -1.66709328e+00 -1.67140659e+00
+-1.67570881e+00 -1.68000000e+00 -1.68428026e+00 -1.68854967e+00 -1.69280832e+00
+2.20000000e+01 1.83304810e+01 1.52465259e+01 1.26584737e+01 1.04898825e+01
+8.67564606e+00 7.16037049e+00 5.89697634e+00 4.84549558e+00 3.97203706e+00
+3.24789744e+00 2.64879786e+00 2.15422905e+00 1.74688991e+00 1.41220669e+00
+1.13792151e+00 9.13740657e-01 7.31034132e-01 5.82579326e-01 4.62342550e-01
+3.65293032e-01 2.87244733e-01 2.24721982e-01 1.74845494e-01 1.35235789e-01
+1.03931483e-01 7.93202459e-02 6.00805615e-02 4.51326662e-02 3.35972976e-02
+2.47610649e-02 1.80474350e-02 1.29924694e-02 9.22457786e-03 6.44766045e-03
+4.42710494e-03 2.97818475e-03 1.95647256e-03 1.24994260e-03 7.72484691e-04
+4.58596220e-04 2.59054376e-04 1.37402203e-04 6.71084318e-05 2.92834781e-05
+1.08530838e-05 3.10719681e-06 5.55359593e-07 3.14068838e-08 0.00000000e+00
+2.30000000e+01 1.91636847e+01 1.59395498e+01 1.32338588e+01 1.09666953e+01
+9.06999361e+00 7.48584187e+00 6.16502071e+00 5.06574538e+00 4.15258420e+00
+3.39552914e+00 2.76919776e+00 2.25214856e+00 1.82629400e+00 1.47639790e+00
+1.18964521e+00 9.55274323e-01 7.64262956e-01 6.09060204e-01 4.83358120e-01
+3.81897261e-01 3.00301312e-01 2.34936618e-01 1.82793016e-01 1.41382870e-01
+1.08655641e-01 8.29257116e-02 6.28114961e-02 4.71841510e-02 3.51244475e-02
+2.58865679e-02 1.88677730e-02 1.35830362e-02 9.64387686e-03 6.74073593e-03
+4.62833698e-03 3.11355678e-03 2.04540313e-03 1.30675817e-03 8.07597631e-04
+4.79441503e-04 2.70829575e-04 1.43647758e-04 7.01588150e-05 3.06145453e-05
+1.13464058e-05 3.24843303e-06 5.80603211e-07 3.28344694e-08 0.00000000e+00
+0.00000000e+00 3.92827084e-01 6.16716777e-01 7.25501097e-01 7.57926824e-01
+7.41581214e-01 6.95845187e-01 6.34106479e-01 5.65411157e-01 4.95690127e-01
+4.28665068e-01 3.66513404e-01 3.10352881e-01 2.60591680e-01 2.17178795e-01
+1.79780876e-01 1.47905173e-01 1.20983316e-01 9.84268536e-02 7.96626820e-02
+6.41543203e-02 5.14134062e-02 4.10045767e-02 3.25460104e-02 2.57072482e-02
+2.02054221e-02 1.58006720e-02 1.22912703e-02 9.50879241e-03 7.31354332e-03
+5.59035528e-03 4.24481493e-03 3.19993371e-03 2.39325056e-03 1.77433983e-03
+1.30268852e-03 9.45903386e-04 6.78207978e-04 4.79191106e-04 3.32771016e-04
+2.26342964e-04 1.50081418e-04 9.63717104e-05 5.93493895e-05 3.45286095e-05
+1.85037607e-05 8.71101882e-06 3.23867645e-06 6.77005279e-07 0.00000000e+00
+0.00000000e+00 4.71392500e-01 7.40060132e-01 8.70601316e-01 9.09512189e-01
+8.89897457e-01 8.35014224e-01 7.60927774e-01 6.78493388e-01 5.94828153e-01
+5.14398082e-01 4.39816085e-01 3.72423458e-01 3.12710016e-01 2.60614554e-01
+2.15737051e-01 1.77486208e-01 1.45179979e-01 1.18112224e-01 9.55952183e-02
+7.69851844e-02 6.16960875e-02 4.92054921e-02 3.90552125e-02 3.08486978e-02
+2.42465065e-02 1.89608064e-02 1.47495243e-02 1.14105509e-02 8.77625199e-03
+6.70842634e-03 5.09377792e-03 3.83992045e-03 2.87190067e-03 2.12920780e-03
+1.56322623e-03 1.13508406e-03 8.13849574e-04 5.75029327e-04 3.99325219e-04
+2.71611557e-04 1.80097701e-04 1.15646052e-04 7.12192675e-05 4.14343313e-05
+2.22045129e-05 1.04532226e-05 3.88641174e-06 8.12406335e-07 0.00000000e+00
+0.00000000e+00 5.49957917e-01 8.63403488e-01 1.01570154e+00 1.06109755e+00
+1.03821370e+00 9.74183262e-01 8.87749070e-01 7.91575619e-01 6.93966178e-01
+6.00131096e-01 5.13118766e-01 4.34494034e-01 3.64828352e-01 3.04050313e-01
+2.51693226e-01 2.07067243e-01 1.69376642e-01 1.37797595e-01 1.11527755e-01
+8.98160484e-02 7.19787687e-02 5.74064074e-02 4.55644146e-02 3.59901474e-02
+2.82875909e-02 2.21209408e-02 1.72077784e-02 1.33123094e-02 1.02389607e-02
+7.82649740e-03 5.94274091e-03 4.47990719e-03 3.35055078e-03 2.48407577e-03
+1.82376393e-03 1.32426474e-03 9.49491169e-04 6.70867548e-04 4.65879422e-04
+3.16880150e-04 2.10113985e-04 1.34920395e-04 8.30891454e-05 4.83400532e-05
+2.59052650e-05 1.21954263e-05 4.53414703e-06 9.47807391e-07 0.00000000e+00
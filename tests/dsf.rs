@@ -0,0 +1,97 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Comparing the damped shifted force summation to the Ewald summation for
+//! sodium chloride and water configurations
+extern crate lumol;
+
+use lumol::sys::{System, UnitCell};
+use lumol::sys::TrajectoryBuilder;
+use lumol::energy::{GlobalPotential, DampedShiftedForce, Ewald, SharedEwald};
+use lumol::types::{Vector3D, Zero};
+
+use std::path::Path;
+
+fn get_system(directory: &str, file: &str, cell: f64) -> System {
+    let path = Path::new(file!()).parent().unwrap()
+                                 .join("data")
+                                 .join(directory)
+                                 .join(file);
+    let mut system = TrajectoryBuilder::new()
+                                       .open(&path)
+                                       .and_then(|mut traj| traj.read())
+                                       .unwrap();
+    system.cell = UnitCell::cubic(cell);
+    return system;
+}
+
+fn ewald(cutoff: f64) -> SharedEwald {
+    let mut ewald = Ewald::new(cutoff, 10);
+    ewald.set_alpha(3.0 / cutoff);
+    return SharedEwald::new(ewald);
+}
+
+fn compute_forces(potential: &GlobalPotential, system: &System) -> Vec<Vector3D> {
+    let mut forces = vec![Vector3D::zero(); system.size()];
+    potential.forces(system, &mut forces);
+    return forces;
+}
+
+#[test]
+fn nacl() {
+    let mut system = get_system("md-nacl", "big.xyz", 22.5608);
+    for (i, particle) in system.particles_mut().enumerate() {
+        particle.charge = match particle.name() {
+            "Na" => 1.0,
+            "Cl" => -1.0,
+            other => panic!("Unknown particle name: {}", other)
+        };
+        // Move the particles away from the crystal positions, where all
+        // the forces are zero
+        for k in 0..3 {
+            particle.position[k] += 0.3 * f64::sin(1.7 * i as f64 + 2.3 * k as f64);
+        }
+    }
+
+    let dsf = DampedShiftedForce::new(11.0, 0.2);
+    let ewald = ewald(11.0);
+
+    let energy = dsf.energy(&system);
+    let expected = ewald.energy(&system);
+    assert!(f64::abs((energy - expected) / expected) < 5e-3);
+
+    let forces = compute_forces(&dsf, &system);
+    let expected = compute_forces(&ewald, &system);
+    let mut error = 0.0;
+    let mut norm = 0.0;
+    for (force, expected) in forces.iter().zip(&expected) {
+        error += (*force - *expected).norm2();
+        norm += expected.norm2();
+    }
+    assert!(f64::sqrt(error / norm) < 0.1);
+}
+
+#[test]
+fn water() {
+    let mut system = get_system("mc-water", "water.xyz", 18.0);
+    for particle in system.particles_mut() {
+        particle.charge = match particle.name() {
+            "H" => 0.41,
+            "O" => -0.82,
+            other => panic!("Unknown particle name: {}", other)
+        };
+    }
+
+    let dsf = DampedShiftedForce::new(9.0, 0.2);
+    let ewald = ewald(9.0);
+
+    let energy = dsf.energy(&system);
+    let expected = ewald.energy(&system);
+    assert!(f64::abs((energy - expected) / expected) < 1e-2);
+
+    let forces = compute_forces(&dsf, &system);
+    let expected = compute_forces(&ewald, &system);
+    for (force, expected) in forces.iter().zip(&expected) {
+        assert!((*force - *expected).norm() < 3e-2 * expected.norm());
+    }
+}
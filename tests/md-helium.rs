@@ -109,6 +109,57 @@ fn berendsen_barostat() {
     assert!(f64::abs(temperature - expected) / expected < 1e-2);
 }
 
+#[test]
+fn mtk_barostat() {
+    START.call_once(|| {env_logger::init().unwrap();});
+    let path = Path::new(file!()).parent().unwrap()
+                                 .join("data")
+                                 .join("md-helium")
+                                 .join("npt-mtk-barostat.toml");
+    let mut config = Input::new(path).unwrap().read().unwrap();
+
+    let collecter = utils::Collecter::new(1000);
+    let temperatures = collecter.temperatures();
+    let pressures = collecter.pressures();
+
+    let e_initial = config.system.conserved_energy();
+    config.simulation.add_output(Box::new(collecter));
+    config.simulation.run(&mut config.system, config.nsteps);
+    let e_final = config.system.conserved_energy();
+    assert!(f64::abs((e_initial - e_final) / e_final) < 1e-3);
+
+    let expected = units::from(5000.0, "bar").unwrap();
+    let pressure = ::utils::mean(pressures.clone());
+    assert!(f64::abs(pressure - expected) / expected < 1e-1);
+
+    let expected = units::from(273.0, "K").unwrap();
+    let temperature = ::utils::mean(temperatures.clone());
+    assert!(f64::abs(temperature - expected) / expected < 3e-2);
+}
+
+#[test]
+fn nose_hoover_thermostat() {
+    START.call_once(|| {env_logger::init().unwrap();});
+    let path = Path::new(file!()).parent().unwrap()
+                                 .join("data")
+                                 .join("md-helium")
+                                 .join("nvt-nose-hoover.toml");
+    let mut config = Input::new(path).unwrap().read().unwrap();
+
+    let collecter = utils::Collecter::new(1000);
+    let temperatures = collecter.temperatures();
+
+    let e_initial = config.system.conserved_energy();
+    config.simulation.add_output(Box::new(collecter));
+    config.simulation.run(&mut config.system, config.nsteps);
+    let e_final = config.system.conserved_energy();
+    assert!(f64::abs((e_initial - e_final) / e_final) < 1e-3);
+
+    let expected = units::from(250.0, "K").unwrap();
+    let temperature = ::utils::mean(temperatures.clone());
+    assert!(f64::abs(temperature - expected) / expected < 2e-2);
+}
+
 #[test]
 fn shifted() {
     START.call_once(|| {env_logger::init().unwrap();});
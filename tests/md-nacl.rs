@@ -95,3 +95,32 @@ mod ewald {
         assert!(f64::abs((energy - LAMMPS_ENERGY) / LAMMPS_ENERGY) < 1e-3);
     }
 }
+
+mod field {
+    use START;
+    use lumol::units;
+    use input::Input;
+    use std::path::Path;
+
+    #[test]
+    fn ions_drift() {
+        START.call_once(|| {::env_logger::init().unwrap();});
+        let path = Path::new(file!()).parent().unwrap()
+                                     .join("data")
+                                     .join("md-nacl")
+                                     .join("nve-field.toml");
+        let mut config = Input::new(path).unwrap().read().unwrap();
+        config.simulation.run(&mut config.system, config.nsteps);
+
+        // The ions are too far away from each other to interact, so they are
+        // uniformly accelerated by the field, in opposite directions.
+        let field = units::from(0.05, "V/A").unwrap();
+        let time = units::from(config.nsteps as f64, "fs").unwrap();
+        for particle in config.system.particles() {
+            let expected = particle.charge * field * time / particle.mass;
+            assert!(f64::abs((particle.velocity[2] - expected) / expected) < 1e-3);
+            assert!(f64::abs(particle.velocity[0]) < 1e-12);
+            assert!(f64::abs(particle.velocity[1]) < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Testing the dihedral energy of butane with the TraPPE-UA torsion, using the
+//! parameters from Martin, M. G. and Siepmann, J. I. J. Phys. Chem. B 102,
+//! 2569 (1998). The configuration contains ten butane molecules with dihedral
+//! angles of 180, 60, -60, 0, 120, 90, -150, 30, -100 and 45 degrees.
+extern crate lumol;
+
+use lumol::sys::{System, UnitCell};
+use lumol::sys::TrajectoryBuilder;
+use lumol::energy::{DihedralPotential, RyckaertBellemans, Fourier};
+use lumol::consts::K_BOLTZMANN;
+
+use std::path::Path;
+
+/// TraPPE-UA torsion parameters for CHx-CH2-CH2-CHy, in Kelvin
+const C1: f64 = 355.03;
+const C2: f64 = -68.19;
+const C3: f64 = 791.32;
+
+/// Total dihedral energy of the configuration, in Kelvin, computed from the
+/// TraPPE-UA functional form at the dihedral angles used to build the
+/// configuration.
+const REFERENCE: f64 = 10163.31843010764;
+
+fn get_system(potential: Box<DihedralPotential>) -> System {
+    let path = Path::new(file!()).parent().unwrap()
+                                 .join("data")
+                                 .join("trappe-butane")
+                                 .join("butane.xyz");
+    let mut system = TrajectoryBuilder::new()
+                                       .open(&path)
+                                       .and_then(|mut traj| traj.read())
+                                       .unwrap();
+    system.cell = UnitCell::cubic(30.0);
+
+    for i in 0..system.size() {
+        if i % 4 != 3 {
+            system.add_bond(i, i + 1);
+        }
+    }
+    assert_eq!(system.molecules().len(), 10);
+    for molecule in system.molecules() {
+        assert_eq!(molecule.dihedrals().len(), 1);
+    }
+
+    system.add_dihedral_potential("C", "C", "C", "C", potential);
+    return system;
+}
+
+fn ryckaert_bellemans() -> Box<DihedralPotential> {
+    let c = [
+        C1 + 2.0 * C2 + C3, -C1 + 3.0 * C3, -2.0 * C2, -4.0 * C3, 0.0, 0.0
+    ];
+    Box::new(RyckaertBellemans{
+        c: [
+            c[0] * K_BOLTZMANN, c[1] * K_BOLTZMANN, c[2] * K_BOLTZMANN,
+            c[3] * K_BOLTZMANN, c[4] * K_BOLTZMANN, c[5] * K_BOLTZMANN
+        ]
+    })
+}
+
+fn fourier() -> Box<DihedralPotential> {
+    Box::new(Fourier::new(vec![
+        2.0 * C1 * K_BOLTZMANN, 2.0 * C2 * K_BOLTZMANN, 2.0 * C3 * K_BOLTZMANN
+    ]))
+}
+
+#[test]
+fn ryckaert_bellemans_energy() {
+    let system = get_system(ryckaert_bellemans());
+    let energy = system.energy_evaluator().dihedrals() / K_BOLTZMANN;
+    assert!(f64::abs((energy - REFERENCE) / REFERENCE) < 1e-8);
+}
+
+#[test]
+fn fourier_energy() {
+    let system = get_system(fourier());
+    let energy = system.energy_evaluator().dihedrals() / K_BOLTZMANN;
+    assert!(f64::abs((energy - REFERENCE) / REFERENCE) < 1e-8);
+}
+
+#[test]
+fn same_energy_and_forces() {
+    let rb = get_system(ryckaert_bellemans());
+    let fourier = get_system(fourier());
+
+    let e_rb = rb.energy_evaluator().dihedrals();
+    let e_fourier = fourier.energy_evaluator().dihedrals();
+    assert!(f64::abs((e_rb - e_fourier) / e_fourier) < 1e-12);
+
+    for (f_rb, f_fourier) in rb.forces().iter().zip(&fourier.forces()) {
+        assert!((f_rb - f_fourier).norm() < 1e-12);
+    }
+}